@@ -346,4 +346,57 @@ mod test_timeout_executor {
             }
         }
     }
+
+    #[derive(Clone)]
+    /// Custom [`Task`] for testing,
+    /// [`SleepTask`] will sleep for a while before returning the result.
+    struct SleepTask {
+        id: usize,
+        duration: Duration,
+    }
+
+    impl Task for SleepTask {
+        fn run(&self, ch: std::sync::mpsc::Sender<FinishedTask>) {
+            thread::sleep(self.duration);
+            ch.send(FinishedTask::new(
+                self.info(),
+                vec![],
+                vec![],
+                TaskStatus::Finished,
+            ))
+            .unwrap();
+        }
+
+        fn info(&self) -> TaskInfo {
+            TaskInfo::new(self.id.into(), "SleepTask".to_string().into())
+        }
+    }
+
+    #[test]
+    /// Each task has its own deadline calculated from the time it is started,
+    /// only the task running longer than the task timeout is reported.
+    fn test_task_timeout_executor() {
+        let tasks = vec![
+            SleepTask {
+                id: 0,
+                duration: Duration::from_millis(10),
+            },
+            SleepTask {
+                id: 1,
+                duration: Duration::from_millis(1500),
+            },
+        ];
+        let executor =
+            TimeoutExecutor::new_with_thread_count_and_task_timeout(1, Duration::from_millis(500));
+        let mut events_collector = Arc::new(Mutex::new(EventsCollector::default()));
+        executor
+            .run_all_tasks(&tasks, |e| {
+                capture_events(e, &mut Arc::clone(&events_collector))
+            })
+            .unwrap();
+        let events_str = events_collector.lock().unwrap().events_str.clone();
+        assert!(!events_str.contains("tname:SleepTask tid:0 event:timeout"));
+        assert!(events_str.contains("tname:SleepTask tid:1 event:timeout"));
+        assert!(events_str.contains("tname:SleepTask tid:1 event:finished"));
+    }
 }
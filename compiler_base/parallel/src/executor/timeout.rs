@@ -26,6 +26,7 @@ pub struct TimeoutExecutor {
     timeout_queue: VecDeque<TimeoutSituation>,
     capacity: usize,
     timeout: Option<Instant>,
+    task_timeout: Option<Duration>,
 }
 
 impl TimeoutExecutor {
@@ -39,6 +40,7 @@ impl TimeoutExecutor {
             timeout_queue: VecDeque::default(),
             capacity: thread_count,
             timeout: Some(default_deadline_60_seconds()),
+            task_timeout: None,
         }
     }
 
@@ -52,6 +54,26 @@ impl TimeoutExecutor {
             timeout_queue: VecDeque::default(),
             capacity: thread_count,
             timeout: Some(timeout),
+            task_timeout: None,
+        }
+    }
+
+    /// New a [`TimeoutExecutor`] with [`thread_count`] and the [`task_timeout`] for each [`Task`].
+    /// Unlike [`TimeoutExecutor::new_with_thread_count_and_timeout`], the deadline of each
+    /// [`Task`] is calculated from the time the [`Task`] is loaded into the thread.
+    pub fn new_with_thread_count_and_task_timeout(
+        thread_count: usize,
+        task_timeout: Duration,
+    ) -> Self {
+        debug_assert!(
+            thread_count > 0,
+            "At least one thread is required to execute the task."
+        );
+        TimeoutExecutor {
+            timeout_queue: VecDeque::default(),
+            capacity: thread_count,
+            timeout: None,
+            task_timeout: Some(task_timeout),
         }
     }
 
@@ -113,7 +135,9 @@ impl Executor for TimeoutExecutor {
                 let tinfo = task.info();
 
                 // Calculate the deadline.
-                let deadline = if let Some(timeout) = self.timeout {
+                let deadline = if let Some(task_timeout) = self.task_timeout {
+                    Instant::now() + task_timeout
+                } else if let Some(timeout) = self.timeout {
                     timeout
                 } else {
                     default_deadline_60_seconds()
@@ -23,9 +23,18 @@ fn main() {
         .extern_path(".google.protobuf.Any", "::prost_wkt_types::Any")
         .extern_path(".google.protobuf.Timestamp", "::prost_wkt_types::Timestamp")
         .extern_path(".google.protobuf.Value", "::prost_wkt_types::Value")
-        .file_descriptor_set_path(&descriptor_file)
-        .compile_protos(&["spec.proto"], &["."])
-        .expect("Running prost build failed.");
+        .file_descriptor_set_path(&descriptor_file);
+    // The gRPC services are generated for the native targets, which serve the KCL APIs
+    // over gRPC with the tonic transport.
+    if env::var("CARGO_CFG_TARGET_ARCH").as_deref() == Ok("wasm32") {
+        prost_build
+            .compile_protos(&["spec.proto"], &["."])
+            .expect("Running prost build failed.");
+    } else {
+        tonic_prost_build::configure()
+            .compile_with_config(prost_build, &["spec.proto"], &["."])
+            .expect("Running tonic build failed.");
+    }
 
     let descriptor_bytes = std::fs::read(descriptor_file).unwrap();
 
@@ -126,6 +126,25 @@ fn test_c_api_format_code() {
     );
 }
 
+#[test]
+fn test_c_api_list_dep_files() {
+    test_c_api::<ListDepFilesArgs, ListDepFilesResult, _>(
+        "KclService.ListDepFiles",
+        "list-dep-files.json",
+        "list-dep-files.response.json",
+        |r| {
+            let root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+            let pkgroot = r.pkgroot.adjust_canonicalization();
+            let pkgroot = pkgroot
+                .strip_prefix(root.to_str().unwrap())
+                .unwrap_or(&pkgroot);
+            r.pkgroot = pkgroot.trim_start_matches(['/', '\\']).replace('\\', "/");
+            r.files = r.files.iter().map(|f| f.replace('\\', "/")).collect();
+            r.files.sort();
+        },
+    );
+}
+
 #[test]
 fn test_c_api_format_path() {
     test_c_api_without_wrapper::<FormatPathArgs, FormatPathResult>(
@@ -265,6 +284,12 @@ fn test_c_api_testing() {
         |r| {
             for i in &mut r.info {
                 i.duration = 0;
+                if let Some(position) = &mut i.position {
+                    position.filename = Path::new(&position.filename)
+                        .file_name()
+                        .map(|f| f.to_string_lossy().to_string())
+                        .unwrap_or_default();
+                }
             }
         },
     );
@@ -0,0 +1,86 @@
+use crate::gpyrpc::builtin_service_client::BuiltinServiceClient;
+use crate::gpyrpc::kcl_service_client::KclServiceClient;
+use crate::gpyrpc::*;
+use crate::service::grpc::grpc_router;
+use std::fs;
+use std::path::Path;
+use tokio::net::TcpListener;
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::transport::Channel;
+use tonic_health::pb::health_check_response::ServingStatus;
+use tonic_health::pb::{HealthCheckRequest, health_client::HealthClient};
+const TEST_DATA_PATH: &str = "./src/testdata";
+
+/// Serve the gRPC services on a random local port and return the server url.
+async fn start_grpc_server() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let router = grpc_router().await.unwrap();
+    tokio::spawn(router.serve_with_incoming(TcpListenerStream::new(listener)));
+    format!("http://{}", addr)
+}
+
+#[tokio::test]
+async fn test_grpc_exec_program() {
+    let url = start_grpc_server().await;
+    let mut client = KclServiceClient::connect(url).await.unwrap();
+    let result = client
+        .exec_program(ExecProgramArgs {
+            work_dir: TEST_DATA_PATH.to_string(),
+            k_filename_list: vec!["hello.k".to_string()],
+            ..Default::default()
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(result.yaml_result, "a: 1");
+    assert_eq!(result.err_message, "");
+
+    let input =
+        fs::read_to_string(Path::new(TEST_DATA_PATH).join("exec-program-with-compile-only.json"))
+            .unwrap();
+    let args: ExecProgramArgs = serde_json::from_str(&input).unwrap();
+    let err = client.exec_program(args).await.unwrap_err();
+    assert!(err.message().contains("Module 'ext' imported but unused"));
+}
+
+#[tokio::test]
+async fn test_grpc_builtin_service() {
+    let url = start_grpc_server().await;
+    let mut client = BuiltinServiceClient::connect(url).await.unwrap();
+    let result = client
+        .ping(PingArgs {
+            value: "hello".to_string(),
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(result.value, "hello");
+    let result = client
+        .list_method(ListMethodArgs::default())
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(
+        result
+            .method_name_list
+            .contains(&"KclService.ExecProgram".to_string())
+    );
+}
+
+#[tokio::test]
+async fn test_grpc_health_check() {
+    let url = start_grpc_server().await;
+    let channel = Channel::from_shared(url).unwrap().connect().await.unwrap();
+    let mut client = HealthClient::new(channel);
+    for service in ["gpyrpc.KclService", "gpyrpc.BuiltinService"] {
+        let result = client
+            .check(HealthCheckRequest {
+                service: service.to_string(),
+            })
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(result.status, ServingStatus::Serving as i32);
+    }
+}
@@ -0,0 +1,78 @@
+use crate::service::jsonrpc::{CancelFlags, handle_request, new_io_handler};
+use serde_json::{Value, json};
+use std::sync::Arc;
+use std::time::Duration;
+const TEST_DATA_PATH: &str = "./src/testdata";
+
+/// The program keeps iterating the comprehension loop in a single statement until it is
+/// cancelled.
+const LONG_RUNNING_CODE: &str = r#"data = [i * j for i in range(10000) for j in range(10000)]
+"#;
+
+fn request(id: u64, method: &str, params: Value) -> String {
+    json!({"jsonrpc": "2.0", "id": id, "method": method, "params": params}).to_string()
+}
+
+#[tokio::test]
+async fn test_jsonrpc_concurrent_requests_and_cancellation() {
+    let cancel_flags = CancelFlags::default();
+    let io = Arc::new(new_io_handler(&cancel_flags));
+    let long_running = {
+        let io = io.clone();
+        let cancel_flags = cancel_flags.clone();
+        tokio::spawn(async move {
+            let line = request(
+                1,
+                "KclService.ExecProgram",
+                json!({"k_filename_list": ["main.k"], "k_code_list": [LONG_RUNNING_CODE]}),
+            );
+            handle_request(&io, &cancel_flags, &line).await
+        })
+    };
+
+    // The quick request is not blocked by the long running one.
+    let line = request(
+        2,
+        "KclService.ExecProgram",
+        json!({"work_dir": TEST_DATA_PATH, "k_filename_list": ["hello.k"]}),
+    );
+    let response = handle_request(&io, &cancel_flags, &line).await.unwrap();
+    let response: Value = serde_json::from_str(&response).unwrap();
+    assert_eq!(response["id"], 2);
+    assert_eq!(response["result"]["yaml_result"], "a: 1");
+    assert!(!long_running.is_finished());
+
+    // The cancellation is retried until the long running request is registered.
+    let cancel = json!({"jsonrpc": "2.0", "method": "$/cancelRequest", "params": {"id": 1}});
+    while !long_running.is_finished() {
+        assert!(
+            handle_request(&io, &cancel_flags, &cancel.to_string())
+                .await
+                .is_none()
+        );
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    let response = long_running.await.unwrap().unwrap();
+    let response: Value = serde_json::from_str(&response).unwrap();
+    assert_eq!(response["id"], 1);
+    assert_eq!(response["error"]["code"], -32800);
+    assert!(cancel_flags.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_jsonrpc_errors() {
+    let cancel_flags = CancelFlags::default();
+    let io = new_io_handler(&cancel_flags);
+    let line = request(
+        1,
+        "KclService.ExecProgram",
+        json!({"work_dir": TEST_DATA_PATH, "k_filename_list": ["not_found.k"]}),
+    );
+    let response = handle_request(&io, &cancel_flags, &line).await.unwrap();
+    let response: Value = serde_json::from_str(&response).unwrap();
+    assert_eq!(response["error"]["code"], 0x4B434C);
+
+    let response = handle_request(&io, &cancel_flags, "{").await.unwrap();
+    let response: Value = serde_json::from_str(&response).unwrap();
+    assert_eq!(response["error"]["code"], -32700);
+}
@@ -27,10 +27,18 @@
 //! ```
 #[cfg(test)]
 pub mod capi_test;
+#[cfg(all(test, not(target_arch = "wasm32")))]
+pub mod grpc_test;
+#[cfg(all(test, not(target_arch = "wasm32")))]
+pub mod jsonrpc_test;
 pub mod service;
 
 pub mod gpyrpc {
     include!(concat!(env!("OUT_DIR"), "/gpyrpc.rs"));
+
+    /// The encoded file descriptor set of `spec.proto`, e.g., for the gRPC reflection.
+    pub const FILE_DESCRIPTOR_SET: &[u8] =
+        include_bytes!(concat!(env!("OUT_DIR"), "/kcl_service_descriptor.bin"));
 }
 
 pub use crate::gpyrpc::*;
@@ -25,7 +25,10 @@ fn c_char_to_vec(args: *const c_char, args_len: usize) -> Vec<u8> {
 /// The caller must ensure that the returned pointer is properly managed and eventually freed using `kcl_service_delete`.
 #[unsafe(no_mangle)]
 pub unsafe extern "C-unwind" fn kcl_service_new(plugin_agent: u64) -> *mut kcl_service {
-    let serv = kcl_service { plugin_agent };
+    let serv = kcl_service {
+        plugin_agent,
+        ..Default::default()
+    };
     Box::into_raw(Box::new(serv))
 }
 
@@ -174,7 +177,11 @@ pub(crate) fn kcl_get_service_fn_ptr_by_name(name: &str) -> u64 {
         "KclService.ListOptions" => list_options as *const () as u64,
         "KclService.ListVariables" => list_variables as *const () as u64,
         "KclService.ExecProgram" => exec_program as *const () as u64,
+        "KclService.BuildProgram" => build_program as *const () as u64,
+        "KclService.ExecArtifact" => exec_artifact as *const () as u64,
         "KclService.OverrideFile" => override_file as *const () as u64,
+        "KclService.GetSchemaType" => get_schema_type as *const () as u64,
+        "KclService.GetFullSchemaType" => get_full_schema_type as *const () as u64,
         "KclService.GetSchemaTypeMapping" => get_schema_type_mapping as *const () as u64,
         "KclService.GetSchemaTypeMappingUnderPath" => {
             get_schema_type_mapping_under_path as *const () as u64
@@ -183,6 +190,7 @@ pub(crate) fn kcl_get_service_fn_ptr_by_name(name: &str) -> u64 {
         "KclService.FormatPath" => format_path as *const () as u64,
         "KclService.LintPath" => lint_path as *const () as u64,
         "KclService.ValidateCode" => validate_code as *const () as u64,
+        "KclService.ListDepFiles" => list_dep_files as *const () as u64,
         "KclService.LoadSettingsFiles" => load_settings_files as *const () as u64,
         "KclService.Rename" => rename as *const () as u64,
         "KclService.RenameCode" => rename_code as *const () as u64,
@@ -409,6 +417,70 @@ pub(crate) fn exec_program(
     )
 }
 
+/// build_program provides users with the ability to build the KCL program to an artifact.
+///
+/// # Parameters
+///
+/// `serv`: [*mut kcl_service]
+///     The pointer of &\[[KclServiceImpl]]
+///
+///
+/// `args`: [*const c_char]
+///     the items and compile parameters selected by the user in the KCL CLI
+///     serialized as protobuf byte sequence
+///
+/// # Returns
+///
+/// result: [*const c_char]
+///     Result of the call serialized as protobuf byte sequence
+pub(crate) fn build_program(
+    serv: *mut kcl_service,
+    args: *const c_char,
+    args_len: usize,
+    result_len: *mut usize,
+) -> *const c_char {
+    call!(
+        serv,
+        args,
+        args_len,
+        result_len,
+        BuildProgramArgs,
+        build_program
+    )
+}
+
+/// exec_artifact provides users with the ability to execute the KCL artifact.
+///
+/// # Parameters
+///
+/// `serv`: [*mut kcl_service]
+///     The pointer of &\[[KclServiceImpl]]
+///
+///
+/// `args`: [*const c_char]
+///     the artifact path and the items selected by the user in the KCL CLI
+///     serialized as protobuf byte sequence
+///
+/// # Returns
+///
+/// result: [*const c_char]
+///     Result of the call serialized as protobuf byte sequence
+pub(crate) fn exec_artifact(
+    serv: *mut kcl_service,
+    args: *const c_char,
+    args_len: usize,
+    result_len: *mut usize,
+) -> *const c_char {
+    call!(
+        serv,
+        args,
+        args_len,
+        result_len,
+        ExecArtifactArgs,
+        exec_artifact
+    )
+}
+
 /// override_file enable users override existing KCL file with specific KCl code
 ///
 /// # Parameters
@@ -441,6 +513,52 @@ pub(crate) fn override_file(
     )
 }
 
+/// Get schema types from a kcl file or code.
+///
+/// # Parameters
+/// file: [&str]. The kcl filename.
+///
+/// code: [Option<&str>]. The kcl code string
+///
+/// schema_name: [Option<&str>]. The schema name, when the schema name is empty, all schemas are returned.
+pub(crate) fn get_schema_type(
+    serv: *mut kcl_service,
+    args: *const c_char,
+    args_len: usize,
+    result_len: *mut usize,
+) -> *const c_char {
+    call!(
+        serv,
+        args,
+        args_len,
+        result_len,
+        GetSchemaTypeArgs,
+        get_schema_type
+    )
+}
+
+/// Get full schema types of the program including the imported packages.
+///
+/// # Parameters
+/// exec_args: [Option<ExecProgramArgs>]. The arguments of the program.
+///
+/// schema_name: [Option<&str>]. The schema name, when the schema name is empty, all schemas are returned.
+pub(crate) fn get_full_schema_type(
+    serv: *mut kcl_service,
+    args: *const c_char,
+    args_len: usize,
+    result_len: *mut usize,
+) -> *const c_char {
+    call!(
+        serv,
+        args,
+        args_len,
+        result_len,
+        GetFullSchemaTypeArgs,
+        get_full_schema_type
+    )
+}
+
 /// Get schema types from a kcl file or code.
 ///
 /// # Parameters
@@ -554,6 +672,23 @@ pub(crate) fn validate_code(
     )
 }
 
+/// Service for listing the files which the package depends on transitively.
+pub(crate) fn list_dep_files(
+    serv: *mut kcl_service,
+    args: *const c_char,
+    args_len: usize,
+    result_len: *mut usize,
+) -> *const c_char {
+    call!(
+        serv,
+        args,
+        args_len,
+        result_len,
+        ListDepFilesArgs,
+        list_dep_files
+    )
+}
+
 /// Service for building setting file config from args.
 pub(crate) fn load_settings_files(
     serv: *mut kcl_service,
@@ -0,0 +1,191 @@
+//! The gRPC server of the `KclService` and the `BuiltinService` defined in `spec.proto`,
+//! which also serves the gRPC health checking and reflection services.
+//!
+//! Each request runs on the blocking thread pool, so the requests are handled concurrently.
+//! The deadlines set by the `grpc-timeout` header are enforced by the server, and the
+//! evaluation of a request which exceeds its deadline or is cancelled by the client is
+//! interrupted.
+
+use std::panic::AssertUnwindSafe;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use kcl_runner::runner::catch_runtime_panic;
+use tonic::transport::Server;
+use tonic::transport::server::Router;
+use tonic::{Request, Response, Status};
+
+use crate::gpyrpc::builtin_service_server::{BuiltinService, BuiltinServiceServer};
+use crate::gpyrpc::kcl_service_server::{KclService, KclServiceServer};
+use crate::gpyrpc::*;
+use crate::service::service_impl::KclServiceImpl;
+
+/// The address prefix of the Unix domain sockets, e.g., `unix:/tmp/kcl.sock`.
+pub const UNIX_ADDR_PREFIX: &str = "unix:";
+
+/// Start a gRPC server on the TCP address, e.g., `127.0.0.1:50051`, or on the Unix domain
+/// socket path prefixed with `unix:`, until the process receives Ctrl-C.
+#[tokio::main]
+pub async fn start_grpc_server(addr: &str) -> anyhow::Result<()> {
+    let router = grpc_router().await?;
+    #[cfg(unix)]
+    if let Some(path) = addr.strip_prefix(UNIX_ADDR_PREFIX) {
+        // Remove the socket file left by a previous server.
+        let _ = std::fs::remove_file(path);
+        let listener = tokio::net::UnixListener::bind(path)?;
+        router
+            .serve_with_incoming_shutdown(
+                tokio_stream::wrappers::UnixListenerStream::new(listener),
+                shutdown_signal(),
+            )
+            .await?;
+        let _ = std::fs::remove_file(path);
+        return Ok(());
+    }
+    router
+        .serve_with_shutdown(addr.parse()?, shutdown_signal())
+        .await?;
+    Ok(())
+}
+
+/// Returns the router of the KCL services, the health checking service and the reflection
+/// service, which can be served on any incoming connections.
+pub async fn grpc_router() -> anyhow::Result<Router> {
+    let (health_reporter, health_service) = tonic_health::server::health_reporter();
+    health_reporter
+        .set_serving::<KclServiceServer<KclGrpcService>>()
+        .await;
+    health_reporter
+        .set_serving::<BuiltinServiceServer<KclGrpcService>>()
+        .await;
+    let reflection_service = tonic_reflection::server::Builder::configure()
+        .register_encoded_file_descriptor_set(FILE_DESCRIPTOR_SET)
+        .register_encoded_file_descriptor_set(tonic_health::pb::FILE_DESCRIPTOR_SET)
+        .build_v1()?;
+    Ok(Server::builder()
+        .add_service(health_service)
+        .add_service(reflection_service)
+        .add_service(KclServiceServer::new(KclGrpcService::default()))
+        .add_service(BuiltinServiceServer::new(KclGrpcService::default())))
+}
+
+async fn shutdown_signal() {
+    let _ = tokio::signal::ctrl_c().await;
+}
+
+/// The gRPC service which calls the [`KclServiceImpl`].
+#[derive(Debug, Clone, Default)]
+pub struct KclGrpcService {
+    pub plugin_agent: u64,
+}
+
+impl KclGrpcService {
+    /// Calls the service method on the blocking thread pool, where the panics are caught
+    /// without changing the process panic hook and returned as the `INTERNAL` status.
+    async fn call<A, R>(
+        &self,
+        request: Request<A>,
+        method: fn(&KclServiceImpl, &A) -> anyhow::Result<R>,
+    ) -> Result<Response<R>, Status>
+    where
+        A: Send + 'static,
+        R: Send + 'static,
+    {
+        let cancel_flag = CancelOnDrop(Arc::new(AtomicBool::new(false)));
+        let serv = KclServiceImpl {
+            plugin_agent: self.plugin_agent,
+            cancel_flag: Some(cancel_flag.0.clone()),
+        };
+        let args = request.into_inner();
+        let result = tokio::task::spawn_blocking(move || {
+            catch_runtime_panic(AssertUnwindSafe(|| method(&serv, &args)))
+        })
+        .await
+        .map_err(|err| Status::internal(err.to_string()))?;
+        match result {
+            Ok(Ok(result)) => Ok(Response::new(result)),
+            Ok(Err(err)) => Err(Status::unknown(err.to_string())),
+            Err(panic_err) => Err(Status::internal(kcl_error::err_to_str(panic_err))),
+        }
+    }
+}
+
+/// Sets the cancellation flag when the request future is dropped, e.g., the request is
+/// cancelled by the client or exceeds its deadline.
+struct CancelOnDrop(Arc<AtomicBool>);
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Implements the `KclService` methods by the [`KclServiceImpl`] methods of the same names,
+/// and lists the method names for the `BuiltinService.ListMethod`.
+macro_rules! impl_kcl_service {
+    ($($method:ident($name:literal, $args:ty) -> $result:ty;)*) => {
+        /// The `KclService` method names in the form of `KclService.Ping`.
+        const KCL_SERVICE_METHODS: &[&str] = &[$(concat!("KclService.", $name)),*];
+
+        #[tonic::async_trait]
+        impl KclService for KclGrpcService {
+            $(
+                async fn $method(
+                    &self,
+                    request: Request<$args>,
+                ) -> Result<Response<$result>, Status> {
+                    self.call(request, KclServiceImpl::$method).await
+                }
+            )*
+        }
+    };
+}
+
+impl_kcl_service! {
+    ping("Ping", PingArgs) -> PingResult;
+    get_version("GetVersion", GetVersionArgs) -> GetVersionResult;
+    parse_program("ParseProgram", ParseProgramArgs) -> ParseProgramResult;
+    parse_file("ParseFile", ParseFileArgs) -> ParseFileResult;
+    load_package("LoadPackage", LoadPackageArgs) -> LoadPackageResult;
+    list_options("ListOptions", ParseProgramArgs) -> ListOptionsResult;
+    list_variables("ListVariables", ListVariablesArgs) -> ListVariablesResult;
+    exec_program("ExecProgram", ExecProgramArgs) -> ExecProgramResult;
+    build_program("BuildProgram", BuildProgramArgs) -> BuildProgramResult;
+    exec_artifact("ExecArtifact", ExecArtifactArgs) -> ExecProgramResult;
+    override_file("OverrideFile", OverrideFileArgs) -> OverrideFileResult;
+    get_schema_type("GetSchemaType", GetSchemaTypeArgs) -> GetSchemaTypeResult;
+    get_full_schema_type("GetFullSchemaType", GetFullSchemaTypeArgs) -> GetSchemaTypeResult;
+    get_schema_type_mapping("GetSchemaTypeMapping", GetSchemaTypeMappingArgs) -> GetSchemaTypeMappingResult;
+    format_code("FormatCode", FormatCodeArgs) -> FormatCodeResult;
+    format_path("FormatPath", FormatPathArgs) -> FormatPathResult;
+    lint_path("LintPath", LintPathArgs) -> LintPathResult;
+    validate_code("ValidateCode", ValidateCodeArgs) -> ValidateCodeResult;
+    list_dep_files("ListDepFiles", ListDepFilesArgs) -> ListDepFilesResult;
+    load_settings_files("LoadSettingsFiles", LoadSettingsFilesArgs) -> LoadSettingsFilesResult;
+    rename("Rename", RenameArgs) -> RenameResult;
+    rename_code("RenameCode", RenameCodeArgs) -> RenameCodeResult;
+    test("Test", TestArgs) -> TestResult;
+    update_dependencies("UpdateDependencies", UpdateDependenciesArgs) -> UpdateDependenciesResult;
+}
+
+#[tonic::async_trait]
+impl BuiltinService for KclGrpcService {
+    async fn ping(&self, request: Request<PingArgs>) -> Result<Response<PingResult>, Status> {
+        Ok(Response::new(PingResult {
+            value: request.into_inner().value,
+        }))
+    }
+
+    async fn list_method(
+        &self,
+        _request: Request<ListMethodArgs>,
+    ) -> Result<Response<ListMethodResult>, Status> {
+        Ok(Response::new(ListMethodResult {
+            method_name_list: KCL_SERVICE_METHODS
+                .iter()
+                .chain(&["BuiltinService.Ping", "BuiltinService.ListMethod"])
+                .map(|name| name.to_string())
+                .collect(),
+        }))
+    }
+}
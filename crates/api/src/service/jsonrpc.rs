@@ -1,44 +1,159 @@
+//! The JSON-RPC server of the `KclService` and the `BuiltinService` via Stdin/Stdout, where
+//! each line of the input is a request and each line of the output is a response.
+//!
+//! The `KclService` requests are handled by a pool of workers at the same time, and the
+//! responses are written in the order of completion. A request can be cancelled by the
+//! `$/cancelRequest` notification with its id, e.g., `{"jsonrpc": "2.0", "method":
+//! "$/cancelRequest", "params": {"id": 1}}`, which interrupts the running evaluation, and
+//! the request responds with the `RequestCancelled` error.
+
 use crate::gpyrpc::*;
 use crate::service::service_impl::KclServiceImpl;
 use core::fmt::Display;
-use jsonrpc_stdio_server::ServerBuilder;
-use jsonrpc_stdio_server::jsonrpc_core::{Error, ErrorCode, IoHandler, Params};
+use jsonrpc_core::{
+    Call, Error, ErrorCode, Id, MetaIoHandler, Metadata, Output, Params, Request, Response, Version,
+};
+use kcl_runner::runner::catch_runtime_panic;
 use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::collections::HashMap;
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::sync::{Semaphore, mpsc};
 const KCL_SERVER_ERROR_CODE: i64 = 0x4B434C; // the ASCII code of "KCL"
+const REQUEST_CANCELLED_ERROR_CODE: i64 = -32800; // the same as the LSP
+const CANCEL_REQUEST_METHOD: &str = "$/cancelRequest";
+const MIN_WORKERS: usize = 2;
+
+/// The metadata of a request, which holds the cancellation flag of the request.
+#[derive(Debug, Clone, Default)]
+pub struct RequestMeta {
+    cancel_flag: Arc<AtomicBool>,
+}
+
+impl Metadata for RequestMeta {}
+
+/// The cancellation flags of the running requests by the request ids.
+pub type CancelFlags = Arc<Mutex<HashMap<Id, Arc<AtomicBool>>>>;
 
 /// Start a json rpc server via Stdin/Stdout
 #[tokio::main]
 pub async fn start_stdio_server() -> Result<(), anyhow::Error> {
-    let mut io = IoHandler::default();
+    let cancel_flags = CancelFlags::default();
+    let io = Arc::new(new_io_handler(&cancel_flags));
+    let (sender, mut receiver) = mpsc::unbounded_channel::<String>();
+    let writer = tokio::spawn(async move {
+        let mut stdout = tokio::io::stdout();
+        while let Some(response) = receiver.recv().await {
+            stdout.write_all(response.as_bytes()).await?;
+            stdout.write_all(b"\n").await?;
+            stdout.flush().await?;
+        }
+        anyhow::Ok(())
+    });
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    while let Some(line) = lines.next_line().await? {
+        let io = io.clone();
+        let cancel_flags = cancel_flags.clone();
+        let sender = sender.clone();
+        tokio::spawn(async move {
+            if let Some(response) = handle_request(&io, &cancel_flags, &line).await {
+                let _ = sender.send(response);
+            }
+        });
+    }
+    // Wait for the running requests to respond before exiting.
+    drop(sender);
+    writer.await?
+}
+
+/// New the handler of the `KclService` and the `BuiltinService` methods, the `KclService`
+/// methods run in a pool of workers whose size is the available parallelism, and there are
+/// at least two workers so that a long running request doesn't block the others.
+pub fn new_io_handler(cancel_flags: &CancelFlags) -> MetaIoHandler<RequestMeta> {
+    let workers = Arc::new(Semaphore::new(
+        std::thread::available_parallelism().map_or(MIN_WORKERS, |n| n.get().max(MIN_WORKERS)),
+    ));
+    let mut io = MetaIoHandler::default();
     // KclService
-    register_kcl_service(&mut io);
+    register_kcl_service(&mut io, &workers);
     // BuiltinService
     register_builtin_service(&mut io);
-    let server = ServerBuilder::new(io).build();
-    server.await;
-    Ok(())
+    // Cancellation
+    let cancel_flags = cancel_flags.clone();
+    io.add_notification(CANCEL_REQUEST_METHOD, move |params: Params| {
+        #[derive(serde::Deserialize)]
+        struct CancelParams {
+            id: Id,
+        }
+        if let Ok(params) = params.parse::<CancelParams>()
+            && let Some(cancel_flag) = cancel_flags.lock().unwrap().get(&params.id)
+        {
+            cancel_flag.store(true, Ordering::Relaxed);
+        }
+    });
+    io
 }
 
-macro_rules! catch {
-    ($serv:expr, $args:expr, $serv_name:ident) => {{
-        let prev_hook = std::panic::take_hook();
-
-        // disable print panic info
-        std::panic::set_hook(Box::new(|_info| {}));
-        let result = std::panic::catch_unwind(|| to_json_result(&$serv.$serv_name(&$args)));
-        std::panic::set_hook(prev_hook);
-        match result {
-            Ok(result) => result,
-            Err(panic_err) => {
-                let err_message = kcl_error::err_to_str(panic_err);
-                Err(Error {
-                    code: ErrorCode::from(KCL_SERVER_ERROR_CODE),
-                    message: err_message,
-                    data: None,
-                })
-            }
+/// Handle a request line, which is a single call or a batch of calls, and return the
+/// response line if any.
+pub async fn handle_request(
+    io: &MetaIoHandler<RequestMeta>,
+    cancel_flags: &CancelFlags,
+    line: &str,
+) -> Option<String> {
+    if line.trim().is_empty() {
+        return None;
+    }
+    let response = match serde_json::from_str::<Request>(line) {
+        Ok(Request::Single(call)) => handle_call(io, cancel_flags, call)
+            .await
+            .map(Response::Single),
+        Ok(Request::Batch(calls)) => {
+            let outputs: Vec<Output> = futures::future::join_all(
+                calls
+                    .into_iter()
+                    .map(|call| handle_call(io, cancel_flags, call)),
+            )
+            .await
+            .into_iter()
+            .flatten()
+            .collect();
+            (!outputs.is_empty()).then_some(Response::Batch(outputs))
         }
-    }};
+        Err(_) => Some(Response::from(
+            Error::new(ErrorCode::ParseError),
+            Some(Version::V2),
+        )),
+    };
+    response.and_then(|response| serde_json::to_string(&response).ok())
+}
+
+/// Handle a call, the cancellation flag of the call is registered by its id until the call
+/// returns.
+async fn handle_call(
+    io: &MetaIoHandler<RequestMeta>,
+    cancel_flags: &CancelFlags,
+    call: Call,
+) -> Option<Output> {
+    let meta = RequestMeta::default();
+    let id = match &call {
+        Call::MethodCall(method_call) => Some(method_call.id.clone()),
+        _ => None,
+    };
+    if let Some(id) = &id {
+        cancel_flags
+            .lock()
+            .unwrap()
+            .insert(id.clone(), meta.cancel_flag.clone());
+    }
+    let output = io.handle_call(call, meta).await;
+    if let Some(id) = &id {
+        cancel_flags.lock().unwrap().remove(id);
+    }
+    output
 }
 
 /// Transform the [`Result<V, E>`]  into [`Result<serde_json::Value,jsonrpc_core::Error>`]
@@ -50,185 +165,213 @@ where
 {
     match val {
         Ok(val) => Ok(serde_json::to_value(val).unwrap()),
-        Err(err) => Err(Error {
-            code: ErrorCode::from(KCL_SERVER_ERROR_CODE),
-            message: err.to_string(),
-            data: None,
-        }),
+        Err(err) => Err(kcl_server_error(err.to_string())),
     }
 }
 
-fn register_kcl_service(io: &mut IoHandler) {
-    io.add_method("KclService.Ping", |params: Params| {
-        let kcl_service_impl = KclServiceImpl::default();
-        let args: PingArgs = match params.parse() {
-            Ok(val) => val,
-            Err(err) => return futures::future::ready(Err(err)),
-        };
-        futures::future::ready(catch!(kcl_service_impl, args, ping))
-    });
-    io.add_method("KclService.GetVersion", |params: Params| {
-        let kcl_service_impl = KclServiceImpl::default();
-        let args: GetVersionArgs = match params.parse() {
-            Ok(val) => val,
-            Err(err) => return futures::future::ready(Err(err)),
-        };
-        futures::future::ready(catch!(kcl_service_impl, args, get_version))
-    });
-    io.add_method("KclService.ParseFile", |params: Params| {
-        let kcl_service_impl = KclServiceImpl::default();
-        let args: ParseFileArgs = match params.parse() {
-            Ok(val) => val,
-            Err(err) => return futures::future::ready(Err(err)),
-        };
-        futures::future::ready(catch!(kcl_service_impl, args, parse_file))
-    });
-    io.add_method("KclService.ParseProgram", |params: Params| {
-        let kcl_service_impl = KclServiceImpl::default();
-        let args: ParseProgramArgs = match params.parse() {
-            Ok(val) => val,
-            Err(err) => return futures::future::ready(Err(err)),
-        };
-        futures::future::ready(catch!(kcl_service_impl, args, parse_program))
-    });
-    io.add_method("KclService.LoadPackage", |params: Params| {
-        let kcl_service_impl = KclServiceImpl::default();
-        let args: LoadPackageArgs = match params.parse() {
-            Ok(val) => val,
-            Err(err) => return futures::future::ready(Err(err)),
-        };
-        futures::future::ready(catch!(kcl_service_impl, args, load_package))
-    });
-    io.add_method("KclService.ListOptions", |params: Params| {
-        let kcl_service_impl = KclServiceImpl::default();
-        let args: ParseProgramArgs = match params.parse() {
-            Ok(val) => val,
-            Err(err) => return futures::future::ready(Err(err)),
-        };
-        futures::future::ready(catch!(kcl_service_impl, args, list_options))
-    });
-    io.add_method("KclService.ListVariables", |params: Params| {
-        let kcl_service_impl = KclServiceImpl::default();
-        let args: ListVariablesArgs = match params.parse() {
-            Ok(val) => val,
-            Err(err) => return futures::future::ready(Err(err)),
-        };
-        futures::future::ready(catch!(kcl_service_impl, args, list_variables))
-    });
-    io.add_method("KclService.ExecProgram", |params: Params| {
-        let kcl_service_impl = KclServiceImpl::default();
-        let args: ExecProgramArgs = match params.parse() {
-            Ok(val) => val,
-            Err(err) => return futures::future::ready(Err(err)),
-        };
-        futures::future::ready(catch!(kcl_service_impl, args, exec_program))
-    });
-    io.add_method("KclService.OverrideFile", |params: Params| {
-        let kcl_service_impl = KclServiceImpl::default();
-        let args: OverrideFileArgs = match params.parse() {
-            Ok(val) => val,
-            Err(err) => return futures::future::ready(Err(err)),
-        };
-        futures::future::ready(catch!(kcl_service_impl, args, override_file))
-    });
-    io.add_method("KclService.GetSchemaTypeMapping", |params: Params| {
-        let kcl_service_impl = KclServiceImpl::default();
-        let args: GetSchemaTypeMappingArgs = match params.parse() {
-            Ok(val) => val,
-            Err(err) => return futures::future::ready(Err(err)),
-        };
-        futures::future::ready(catch!(kcl_service_impl, args, get_schema_type_mapping))
+#[inline]
+fn kcl_server_error(message: String) -> Error {
+    Error {
+        code: ErrorCode::from(KCL_SERVER_ERROR_CODE),
+        message,
+        data: None,
+    }
+}
+
+#[inline]
+fn request_cancelled_error() -> Error {
+    Error {
+        code: ErrorCode::from(REQUEST_CANCELLED_ERROR_CODE),
+        message: "the request is cancelled".to_string(),
+        data: None,
+    }
+}
+
+/// Register a `KclService` method which runs in the worker pool, where the panics are
+/// caught without changing the process panic hook.
+fn add_kcl_service_method<A, R>(
+    io: &mut MetaIoHandler<RequestMeta>,
+    workers: &Arc<Semaphore>,
+    name: &str,
+    method: fn(&KclServiceImpl, &A) -> anyhow::Result<R>,
+) where
+    A: DeserializeOwned + Send + 'static,
+    R: Serialize + Send + 'static,
+{
+    let workers = workers.clone();
+    io.add_method_with_meta(name, move |params: Params, meta: RequestMeta| {
+        let workers = workers.clone();
+        async move {
+            let args: A = params.parse()?;
+            let _permit = workers
+                .acquire_owned()
+                .await
+                .map_err(|err| kcl_server_error(err.to_string()))?;
+            // The request may be cancelled while waiting for an idle worker.
+            if meta.cancel_flag.load(Ordering::Relaxed) {
+                return Err(request_cancelled_error());
+            }
+            let kcl_service_impl = KclServiceImpl {
+                cancel_flag: Some(meta.cancel_flag.clone()),
+                ..Default::default()
+            };
+            let result = tokio::task::spawn_blocking(move || {
+                catch_runtime_panic(AssertUnwindSafe(|| {
+                    to_json_result(&method(&kcl_service_impl, &args))
+                }))
+            })
+            .await;
+            if meta.cancel_flag.load(Ordering::Relaxed) {
+                return Err(request_cancelled_error());
+            }
+            match result {
+                Ok(Ok(result)) => result,
+                Ok(Err(panic_err)) => Err(kcl_server_error(kcl_error::err_to_str(panic_err))),
+                Err(err) => Err(kcl_server_error(err.to_string())),
+            }
+        }
     });
-    io.add_method(
+}
+
+fn register_kcl_service(io: &mut MetaIoHandler<RequestMeta>, workers: &Arc<Semaphore>) {
+    add_kcl_service_method(io, workers, "KclService.Ping", KclServiceImpl::ping);
+    add_kcl_service_method(
+        io,
+        workers,
+        "KclService.GetVersion",
+        KclServiceImpl::get_version,
+    );
+    add_kcl_service_method(
+        io,
+        workers,
+        "KclService.ParseFile",
+        KclServiceImpl::parse_file,
+    );
+    add_kcl_service_method(
+        io,
+        workers,
+        "KclService.ParseProgram",
+        KclServiceImpl::parse_program,
+    );
+    add_kcl_service_method(
+        io,
+        workers,
+        "KclService.LoadPackage",
+        KclServiceImpl::load_package,
+    );
+    add_kcl_service_method(
+        io,
+        workers,
+        "KclService.ListOptions",
+        KclServiceImpl::list_options,
+    );
+    add_kcl_service_method(
+        io,
+        workers,
+        "KclService.ListVariables",
+        KclServiceImpl::list_variables,
+    );
+    add_kcl_service_method(
+        io,
+        workers,
+        "KclService.ExecProgram",
+        KclServiceImpl::exec_program,
+    );
+    add_kcl_service_method(
+        io,
+        workers,
+        "KclService.BuildProgram",
+        KclServiceImpl::build_program,
+    );
+    add_kcl_service_method(
+        io,
+        workers,
+        "KclService.ExecArtifact",
+        KclServiceImpl::exec_artifact,
+    );
+    add_kcl_service_method(
+        io,
+        workers,
+        "KclService.OverrideFile",
+        KclServiceImpl::override_file,
+    );
+    add_kcl_service_method(
+        io,
+        workers,
+        "KclService.GetSchemaType",
+        KclServiceImpl::get_schema_type,
+    );
+    add_kcl_service_method(
+        io,
+        workers,
+        "KclService.GetFullSchemaType",
+        KclServiceImpl::get_full_schema_type,
+    );
+    add_kcl_service_method(
+        io,
+        workers,
+        "KclService.GetSchemaTypeMapping",
+        KclServiceImpl::get_schema_type_mapping,
+    );
+    add_kcl_service_method(
+        io,
+        workers,
         "KclService.GetSchemaTypeMappingUnderPath",
-        |params: Params| {
-            let kcl_service_impl = KclServiceImpl::default();
-            let args: GetSchemaTypeMappingArgs = match params.parse() {
-                Ok(val) => val,
-                Err(err) => return futures::future::ready(Err(err)),
-            };
-            futures::future::ready(catch!(
-                kcl_service_impl,
-                args,
-                get_schema_type_mapping_under_path
-            ))
-        },
+        KclServiceImpl::get_schema_type_mapping_under_path,
+    );
+    add_kcl_service_method(
+        io,
+        workers,
+        "KclService.FormatCode",
+        KclServiceImpl::format_code,
+    );
+    add_kcl_service_method(
+        io,
+        workers,
+        "KclService.FormatPath",
+        KclServiceImpl::format_path,
+    );
+    add_kcl_service_method(
+        io,
+        workers,
+        "KclService.LintPath",
+        KclServiceImpl::lint_path,
+    );
+    add_kcl_service_method(
+        io,
+        workers,
+        "KclService.ValidateCode",
+        KclServiceImpl::validate_code,
+    );
+    add_kcl_service_method(
+        io,
+        workers,
+        "KclService.ListDepFiles",
+        KclServiceImpl::list_dep_files,
+    );
+    add_kcl_service_method(
+        io,
+        workers,
+        "KclService.LoadSettingsFiles",
+        KclServiceImpl::load_settings_files,
+    );
+    add_kcl_service_method(io, workers, "KclService.Rename", KclServiceImpl::rename);
+    add_kcl_service_method(
+        io,
+        workers,
+        "KclService.RenameCode",
+        KclServiceImpl::rename_code,
+    );
+    add_kcl_service_method(io, workers, "KclService.Test", KclServiceImpl::test);
+    add_kcl_service_method(
+        io,
+        workers,
+        "KclService.UpdateDependencies",
+        KclServiceImpl::update_dependencies,
     );
-    io.add_method("KclService.FormatCode", |params: Params| {
-        let kcl_service_impl = KclServiceImpl::default();
-        let args: FormatCodeArgs = match params.parse() {
-            Ok(val) => val,
-            Err(err) => return futures::future::ready(Err(err)),
-        };
-        futures::future::ready(catch!(kcl_service_impl, args, format_code))
-    });
-    io.add_method("KclService.FormatPath", |params: Params| {
-        let kcl_service_impl = KclServiceImpl::default();
-        let args: FormatPathArgs = match params.parse() {
-            Ok(val) => val,
-            Err(err) => return futures::future::ready(Err(err)),
-        };
-        futures::future::ready(catch!(kcl_service_impl, args, format_path))
-    });
-    io.add_method("KclService.LintPath", |params: Params| {
-        let kcl_service_impl = KclServiceImpl::default();
-        let args: LintPathArgs = match params.parse() {
-            Ok(val) => val,
-            Err(err) => return futures::future::ready(Err(err)),
-        };
-        futures::future::ready(catch!(kcl_service_impl, args, lint_path))
-    });
-    io.add_method("KclService.ValidateCode", |params: Params| {
-        let kcl_service_impl = KclServiceImpl::default();
-        let args: ValidateCodeArgs = match params.parse() {
-            Ok(val) => val,
-            Err(err) => return futures::future::ready(Err(err)),
-        };
-        futures::future::ready(catch!(kcl_service_impl, args, validate_code))
-    });
-    io.add_method("KclService.LoadSettingsFiles", |params: Params| {
-        let kcl_service_impl = KclServiceImpl::default();
-        let args: LoadSettingsFilesArgs = match params.parse() {
-            Ok(val) => val,
-            Err(err) => return futures::future::ready(Err(err)),
-        };
-        futures::future::ready(catch!(kcl_service_impl, args, load_settings_files))
-    });
-    io.add_method("KclService.Rename", |params: Params| {
-        let kcl_service_impl = KclServiceImpl::default();
-        let args: RenameArgs = match params.parse() {
-            Ok(val) => val,
-            Err(err) => return futures::future::ready(Err(err)),
-        };
-        futures::future::ready(catch!(kcl_service_impl, args, rename))
-    });
-    io.add_method("KclService.RenameCode", |params: Params| {
-        let kcl_service_impl = KclServiceImpl::default();
-        let args: RenameCodeArgs = match params.parse() {
-            Ok(val) => val,
-            Err(err) => return futures::future::ready(Err(err)),
-        };
-        futures::future::ready(catch!(kcl_service_impl, args, rename_code))
-    });
-    io.add_method("KclService.Test", |params: Params| {
-        let kcl_service_impl = KclServiceImpl::default();
-        let args: TestArgs = match params.parse() {
-            Ok(val) => val,
-            Err(err) => return futures::future::ready(Err(err)),
-        };
-        futures::future::ready(catch!(kcl_service_impl, args, test))
-    });
-    io.add_method("KclService.UpdateDependencies", |params: Params| {
-        let kcl_service_impl = KclServiceImpl::default();
-        let args: UpdateDependenciesArgs = match params.parse() {
-            Ok(val) => val,
-            Err(err) => return futures::future::ready(Err(err)),
-        };
-        futures::future::ready(catch!(kcl_service_impl, args, update_dependencies))
-    });
 }
 
-fn register_builtin_service(io: &mut IoHandler) {
+fn register_builtin_service(io: &mut MetaIoHandler<RequestMeta>) {
     io.add_sync_method("BuiltinService.Ping", |params: Params| {
         let args: PingArgs = params.parse()?;
         let result = PingResult { value: args.value };
@@ -241,6 +384,9 @@ fn register_builtin_service(io: &mut IoHandler) {
                 "KclService.GetVersion".to_owned(),
                 "KclService.ParseFile".to_owned(),
                 "KclService.ParseProgram".to_owned(),
+                "KclService.LoadPackage".to_owned(),
+                "KclService.ListOptions".to_owned(),
+                "KclService.ListVariables".to_owned(),
                 "KclService.ExecProgram".to_owned(),
                 "KclService.BuildProgram".to_owned(),
                 "KclService.ExecArtifact".to_owned(),
@@ -248,17 +394,19 @@ fn register_builtin_service(io: &mut IoHandler) {
                 "KclService.GetSchemaType".to_owned(),
                 "KclService.GetFullSchemaType".to_owned(),
                 "KclService.GetSchemaTypeMapping".to_owned(),
+                "KclService.GetSchemaTypeMappingUnderPath".to_owned(),
                 "KclService.FormatCode".to_owned(),
                 "KclService.FormatPath".to_owned(),
                 "KclService.LintPath".to_owned(),
                 "KclService.ValidateCode".to_owned(),
+                "KclService.ListDepFiles".to_owned(),
                 "KclService.LoadSettingsFiles".to_owned(),
                 "KclService.Rename".to_owned(),
                 "KclService.RenameCode".to_owned(),
                 "KclService.Test".to_owned(),
                 "KclService.UpdateDependencies".to_owned(),
                 "BuiltinService.Ping".to_owned(),
-                "BuiltinService.PingListMethod".to_owned(),
+                "BuiltinService.ListMethod".to_owned(),
             ],
         };
         Ok(serde_json::to_value(result).unwrap())
@@ -1,4 +1,6 @@
 pub mod capi;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod grpc;
 pub(crate) mod into;
 #[cfg(not(target_arch = "wasm32"))]
 pub mod jsonrpc;
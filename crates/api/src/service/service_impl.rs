@@ -2,26 +2,31 @@ use std::collections::HashMap;
 use std::io::Write;
 use std::path::PathBuf;
 use std::string::String;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
 
 use crate::gpyrpc::{self, *};
 
 use kcl_ast::ast::SerializeProgram;
+use kcl_config::modfile::get_pkg_root;
 use kcl_config::settings::build_settings_pathbuf;
+use kcl_error::sarif;
 use kcl_language_server::rename;
 use kcl_loader::option::list_options;
 use kcl_loader::{LoadPackageOptions, load_packages_with_cache};
 use kcl_parser::KCLModuleCache;
 use kcl_parser::LoadProgramOptions;
 use kcl_parser::ParseSessionRef;
+use kcl_parser::deps::{ListDepFilesOptions, list_dep_files};
 use kcl_parser::entry::{canonicalize_input_file, get_normalized_k_files_from_paths};
 use kcl_parser::load_program;
 use kcl_parser::parse_single_file;
-use kcl_query::GetSchemaOption;
 use kcl_query::override_file;
 use kcl_query::query::CompilationOptions;
 use kcl_query::query::{get_full_schema_type, get_full_schema_type_under_path};
 use kcl_query::selector::{ListOptions, list_variables};
-use kcl_runner::exec_program;
+use kcl_query::{GetSchemaOption, get_schema_type};
+use kcl_runner::{build_program, exec_artifact, exec_program};
 use kcl_sema::core::global_state::GlobalState;
 use kcl_sema::resolver::Options;
 use kcl_sema::resolver::scope::KCLScopeCache;
@@ -31,7 +36,7 @@ use kcl_tools::testing;
 use kcl_tools::testing::TestRun;
 use kcl_tools::vet::validator::LoaderKind;
 use kcl_tools::vet::validator::ValidateOption;
-use kcl_tools::vet::validator::validate;
+use kcl_tools::vet::validator::{validate, validate_all};
 use tempfile::NamedTempFile;
 
 use super::into::*;
@@ -42,6 +47,9 @@ use super::util::{transform_exec_para, transform_str_para};
 #[derive(Debug, Clone, Default)]
 pub struct KclServiceImpl {
     pub plugin_agent: u64,
+    /// The flag to interrupt the running evaluation of the service, e.g., when the request
+    /// is cancelled by the client.
+    pub cancel_flag: Option<Arc<AtomicBool>>,
 }
 
 impl From<&kcl_query::selector::Variable> for Variable {
@@ -476,7 +484,7 @@ impl KclServiceImpl {
     /// ```
     pub fn exec_program(&self, args: &ExecProgramArgs) -> anyhow::Result<ExecProgramResult> {
         // transform args to json
-        let exec_args = transform_exec_para(&Some(args.clone()), self.plugin_agent)?;
+        let exec_args = self.transform_exec_args(&Some(args.clone()))?;
         let sess = ParseSessionRef::default();
         let result = exec_program(sess, &exec_args)?;
 
@@ -485,6 +493,72 @@ impl KclServiceImpl {
             yaml_result: result.yaml_result,
             log_message: result.log_message,
             err_message: result.err_message,
+            output_result: result.output_result,
+            provenance_result: result.provenance_result,
+        })
+    }
+
+    /// Build the KCL program to an artifact that can be executed many times with
+    /// [`KclServiceImpl::exec_artifact`] without parsing and resolving it again.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kcl_api::service::service_impl::KclServiceImpl;
+    /// use kcl_api::gpyrpc::*;
+    /// use std::path::Path;
+    ///
+    /// let serv = KclServiceImpl::default();
+    /// let exec_args = ExecProgramArgs {
+    ///     work_dir: Path::new(".").join("src").join("testdata").canonicalize().unwrap().display().to_string(),
+    ///     k_filename_list: vec!["test.k".to_string()],
+    ///     ..Default::default()
+    /// };
+    /// let artifact = serv.build_program(&BuildProgramArgs {
+    ///     exec_args: Some(exec_args.clone()),
+    ///     output: "".to_string(),
+    /// }).unwrap();
+    /// assert!(!artifact.path.is_empty());
+    /// let exec_result = serv.exec_artifact(&ExecArtifactArgs {
+    ///     path: artifact.path,
+    ///     exec_args: Some(exec_args),
+    /// }).unwrap();
+    /// assert_eq!(exec_result.yaml_result, "alice:\n  age: 18");
+    /// ```
+    pub fn build_program(&self, args: &BuildProgramArgs) -> anyhow::Result<BuildProgramResult> {
+        let exec_args = self.transform_exec_args(&args.exec_args)?;
+        let sess = ParseSessionRef::default();
+        let artifact = build_program(sess, &exec_args, transform_str_para(&args.output))?;
+        Ok(BuildProgramResult {
+            path: artifact.to_string_lossy().to_string(),
+        })
+    }
+
+    /// Execute the KCL artifact with args. **Note that it is not thread safe.**
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kcl_api::service::service_impl::KclServiceImpl;
+    /// use kcl_api::gpyrpc::*;
+    ///
+    /// let serv = KclServiceImpl::default();
+    /// let error = serv.exec_artifact(&ExecArtifactArgs {
+    ///     path: "invalid_artifact_path".to_string(),
+    ///     ..Default::default()
+    /// }).unwrap_err();
+    /// assert!(error.to_string().contains("Cannot find the kcl artifact"), "{error}");
+    /// ```
+    pub fn exec_artifact(&self, args: &ExecArtifactArgs) -> anyhow::Result<ExecProgramResult> {
+        let exec_args = self.transform_exec_args(&args.exec_args)?;
+        let result = exec_artifact(&args.path, &exec_args)?;
+        Ok(ExecProgramResult {
+            json_result: result.json_result,
+            yaml_result: result.yaml_result,
+            log_message: result.log_message,
+            err_message: result.err_message,
+            output_result: result.output_result,
+            provenance_result: result.provenance_result,
         })
     }
 
@@ -530,6 +604,104 @@ impl KclServiceImpl {
         })
     }
 
+    /// Service for getting the schema types of a file, including the schema definitions
+    /// and the schema instances.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kcl_api::service::service_impl::KclServiceImpl;
+    /// use kcl_api::gpyrpc::*;
+    ///
+    /// let serv = KclServiceImpl::default();
+    /// let result = serv.get_schema_type(&GetSchemaTypeArgs {
+    ///     file: "schema.k".to_string(),
+    ///     code: "schema Person:\n    age: int\n\nalice = Person {age = 18}\n".to_string(),
+    ///     ..Default::default()
+    /// }).unwrap();
+    /// assert_eq!(result.schema_type_list.len(), 2);
+    ///
+    /// let result = serv.get_schema_type(&GetSchemaTypeArgs {
+    ///     file: "schema.k".to_string(),
+    ///     code: "schema Person:\n    age: int\n\nalice = Person {age = 18}\n".to_string(),
+    ///     schema_name: "alice".to_string(),
+    /// }).unwrap();
+    /// assert_eq!(result.schema_type_list.len(), 1);
+    /// assert_eq!(result.schema_type_list[0].schema_name, "Person");
+    /// ```
+    pub fn get_schema_type(&self, args: &GetSchemaTypeArgs) -> anyhow::Result<GetSchemaTypeResult> {
+        let schema_type_list = get_schema_type(
+            &args.file,
+            (!args.code.is_empty()).then_some(args.code.as_str()),
+            (!args.schema_name.is_empty()).then_some(args.schema_name.as_str()),
+            GetSchemaOption::default(),
+        )?
+        .values()
+        .map(kcl_schema_ty_to_pb_ty)
+        .collect();
+        Ok(GetSchemaTypeResult { schema_type_list })
+    }
+
+    /// Service for getting the full schema types of the program, including the schemas
+    /// of the imported packages.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kcl_api::service::service_impl::KclServiceImpl;
+    /// use kcl_api::gpyrpc::*;
+    /// use std::path::Path;
+    ///
+    /// let serv = KclServiceImpl::default();
+    /// let work_dir_parent = Path::new(".").join("src").join("testdata").join("get_schema_ty");
+    /// let args = ExecProgramArgs {
+    ///     work_dir: work_dir_parent.join("aaa").canonicalize().unwrap().display().to_string(),
+    ///     k_filename_list: vec![
+    ///         work_dir_parent.join("aaa").join("main.k").canonicalize().unwrap().display().to_string()
+    ///     ],
+    ///     external_pkgs: vec![
+    ///         ExternalPkg {
+    ///             pkg_name:"bbb".to_string(),
+    ///             pkg_path: work_dir_parent.join("bbb").canonicalize().unwrap().display().to_string()
+    ///         },
+    ///         ExternalPkg {
+    ///             pkg_name:"ccc".to_string(),
+    ///             pkg_path: work_dir_parent.join("ccc").canonicalize().unwrap().display().to_string()
+    ///         }
+    ///     ],
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let result = serv.get_full_schema_type(&GetFullSchemaTypeArgs {
+    ///     exec_args: Some(args),
+    ///     schema_name: "a".to_string(),
+    /// }).unwrap();
+    /// assert_eq!(result.schema_type_list.len(), 1);
+    /// assert_eq!(result.schema_type_list[0].schema_name, "B");
+    /// ```
+    pub fn get_full_schema_type(
+        &self,
+        args: &GetFullSchemaTypeArgs,
+    ) -> anyhow::Result<GetSchemaTypeResult> {
+        let exec_args = self.transform_exec_args(&args.exec_args)?;
+        let schema_type_list = get_full_schema_type(
+            Some(&args.schema_name),
+            CompilationOptions {
+                paths: exec_args.clone().k_filename_list,
+                loader_opts: Some(exec_args.get_load_program_options()),
+                resolve_opts: Options {
+                    resolve_val: true,
+                    ..Default::default()
+                },
+                get_schema_opts: GetSchemaOption::default(),
+            },
+        )?
+        .values()
+        .map(kcl_schema_ty_to_pb_ty)
+        .collect();
+        Ok(GetSchemaTypeResult { schema_type_list })
+    }
+
     /// Service for getting the schema mapping.
     ///
     /// # Examples
@@ -566,7 +738,7 @@ impl KclServiceImpl {
         args: &GetSchemaTypeMappingArgs,
     ) -> anyhow::Result<GetSchemaTypeMappingResult> {
         let mut type_mapping = HashMap::new();
-        let exec_args = transform_exec_para(&args.exec_args, self.plugin_agent)?;
+        let exec_args = self.transform_exec_args(&args.exec_args)?;
         for (k, schema_ty) in get_full_schema_type(
             Some(&args.schema_name),
             CompilationOptions {
@@ -630,7 +802,7 @@ impl KclServiceImpl {
         args: &GetSchemaTypeMappingArgs,
     ) -> anyhow::Result<GetSchemaTypeMappingUnderPathResult> {
         let mut type_mapping = HashMap::new();
-        let exec_args = transform_exec_para(&args.exec_args, self.plugin_agent)?;
+        let exec_args = self.transform_exec_args(&args.exec_args)?;
         for (k, schema_tys) in get_full_schema_type_under_path(
             Some(&args.schema_name),
             CompilationOptions {
@@ -745,12 +917,26 @@ impl KclServiceImpl {
     ///     ..Default::default()
     /// }).unwrap();
     /// assert_eq!(result.results, vec!["Module 'math' imported but unused".to_string()]);
+    ///
+    /// // Lint the files and return the SARIF report.
+    /// let result = serv.lint_path(&LintPathArgs {
+    ///     paths: vec!["./src/testdata/test-lint.k".to_string()],
+    ///     format: "sarif".to_string(),
+    /// }).unwrap();
+    /// let report: serde_json::Value = serde_json::from_str(&result.report).unwrap();
+    /// assert_eq!(report["version"], "2.1.0");
+    /// assert_eq!(report["runs"][0]["results"][0]["level"], "warning");
     /// ```
     pub fn lint_path(&self, args: &LintPathArgs) -> anyhow::Result<LintPathResult> {
         let (errs, warnings) = lint_files(
             &args.paths.iter().map(|p| p.as_str()).collect::<Vec<&str>>(),
             None,
         );
+        let report = match args.format.to_lowercase().as_str() {
+            "" => String::new(),
+            "sarif" => sarif::to_sarif_string(errs.iter().chain(warnings.iter()))?,
+            format => anyhow::bail!("unknown lint report format '{format}', expected sarif"),
+        };
         let mut results = vec![];
         // Append errors.
         for err in errs {
@@ -764,7 +950,7 @@ impl KclServiceImpl {
                 results.push(msg.message)
             }
         }
-        Ok(LintPathResult { results })
+        Ok(LintPathResult { results, report })
     }
 
     /// Service for validating the data string using the schema code string, when the parameter
@@ -816,25 +1002,158 @@ impl KclServiceImpl {
             .map(|pkg| (pkg.pkg_name.clone(), pkg.pkg_path.clone()))
             .collect();
 
-        let (success, err_message) = match validate(ValidateOption::new(
+        let opt = ValidateOption::new(
             transform_str_para(&args.schema),
             args.attribute_name.clone(),
             file_path,
             match args.format.to_lowercase().as_str() {
                 "yaml" | "yml" => LoaderKind::YAML,
                 "json" => LoaderKind::JSON,
+                "toml" => LoaderKind::TOML,
+                "jsonl" | "ndjson" => LoaderKind::JSONL,
                 _ => LoaderKind::JSON,
             },
             transform_str_para(&args.file),
             transform_str_para(&args.code),
             dep_pkgs_map,
-        )) {
+        )
+        .with_discriminator(transform_str_para(&args.discriminator));
+        if args.collect_all {
+            return Ok(match validate_all(opt) {
+                Ok(violations) => ValidateCodeResult {
+                    success: violations.is_empty(),
+                    err_message: violations
+                        .iter()
+                        .map(|v| {
+                            format!(
+                                "{}:{}:{}: {}: {}",
+                                v.filename, v.line, v.column, v.path, v.message
+                            )
+                        })
+                        .collect::<Vec<String>>()
+                        .join("\n"),
+                    violations: violations
+                        .into_iter()
+                        .map(|v| ValidationViolation {
+                            document: v.document as i64,
+                            path: v.path,
+                            message: v.message,
+                            position: Some(Position {
+                                line: v.line as i64,
+                                column: v.column as i64,
+                                filename: v.filename,
+                            }),
+                            kcl_position: Some(Position {
+                                line: v.kcl_line as i64,
+                                column: 0,
+                                filename: v.kcl_filename,
+                            }),
+                        })
+                        .collect(),
+                },
+                Err(err) => ValidateCodeResult {
+                    success: false,
+                    err_message: err.to_string(),
+                    ..Default::default()
+                },
+            });
+        }
+        let (success, err_message) = match validate(opt) {
             Ok(success) => (success, "".to_string()),
             Err(err) => (false, err.to_string()),
         };
         Ok(ValidateCodeResult {
             success,
             err_message,
+            ..Default::default()
+        })
+    }
+
+    /// Service for listing the files which the package in `work_dir` depends on
+    /// transitively, including the KCL files of the imported packages and the files read by
+    /// `file.read` with string literal paths. The external packages of the `kcl.mod`
+    /// dependencies are included when `include_all` is set, and the files of the main package
+    /// are excluded when `exclude_main_pkg` is set. The external packages are resolved from
+    /// the `kcl.mod.lock` file and the vendor directory without downloading them, so the
+    /// packages which are not locked yet, e.g., before `kcl mod update`, are not listed.
+    ///
+    /// The file paths are relative to the package root unless `use_abs_path` is set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use kcl_api::service::service_impl::KclServiceImpl;
+    /// use kcl_api::gpyrpc::*;
+    ///
+    /// let serv = KclServiceImpl::default();
+    /// let mut result = serv.list_dep_files(&ListDepFilesArgs {
+    ///     work_dir: "./src/testdata/list_dep_files".to_string(),
+    ///     ..Default::default()
+    /// }).unwrap();
+    /// result.files.sort();
+    /// assert_eq!(result.pkgpath, "");
+    /// assert_eq!(result.files, vec!["data.yaml", "main.k", "sub/sub.k"]);
+    ///
+    /// let result = serv.list_dep_files(&ListDepFilesArgs {
+    ///     work_dir: "./src/testdata/list_dep_files".to_string(),
+    ///     exclude_main_pkg: true,
+    ///     ..Default::default()
+    /// }).unwrap();
+    /// assert_eq!(result.files, vec!["sub/sub.k"]);
+    /// ```
+    pub fn list_dep_files(&self, args: &ListDepFilesArgs) -> anyhow::Result<ListDepFilesResult> {
+        let work_dir = PathBuf::from(&args.work_dir).canonicalize()?;
+        let work_dir_str = work_dir.to_string_lossy().to_string();
+        let pkgroot = PathBuf::from(get_pkg_root(&work_dir_str).unwrap_or(work_dir_str.clone()));
+        #[allow(unused_mut)]
+        let mut package_maps = HashMap::new();
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            use kcl_config::modfile::KCL_MOD_FILE;
+            use kcl_driver::client::ModClient;
+            if args.include_all && pkgroot.join(KCL_MOD_FILE).is_file() {
+                let client = ModClient::new(&pkgroot)?;
+                if let Some(metadata) = client.get_metadata_from_mod_lock_file() {
+                    for (name, pkg) in metadata.packages {
+                        package_maps.insert(name, pkg.manifest_path.to_string_lossy().to_string());
+                    }
+                }
+            }
+        }
+        let files = list_dep_files(
+            ParseSessionRef::default(),
+            &[&work_dir_str],
+            Some(LoadProgramOptions {
+                work_dir: work_dir_str.clone(),
+                package_maps,
+                ..Default::default()
+            }),
+            &ListDepFilesOptions {
+                include_external_pkgs: args.include_all,
+                exclude_main_pkg: args.exclude_main_pkg,
+            },
+        )?;
+        let files = files
+            .into_iter()
+            .map(|file| match file.strip_prefix(&pkgroot) {
+                Ok(rel_path) if !args.use_abs_path => rel_path.to_string_lossy().to_string(),
+                _ => file.to_string_lossy().to_string(),
+            })
+            .collect();
+        let pkgpath = work_dir
+            .strip_prefix(&pkgroot)
+            .map(|rel_path| {
+                rel_path
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy().to_string())
+                    .collect::<Vec<String>>()
+                    .join(".")
+            })
+            .unwrap_or_default();
+        Ok(ListDepFilesResult {
+            pkgroot: pkgroot.to_string_lossy().to_string(),
+            pkgpath,
+            files,
         })
     }
 
@@ -978,12 +1297,20 @@ impl KclServiceImpl {
     /// ```
     pub fn test(&self, args: &TestArgs) -> anyhow::Result<TestResult> {
         let mut result = TestResult::default();
-        let exec_args = transform_exec_para(&args.exec_args, self.plugin_agent)?;
+        let exec_args = self.transform_exec_args(&args.exec_args)?;
         let opts = testing::TestOptions {
             exec_args,
             run_regexp: args.run_regexp.clone(),
             fail_fast: args.fail_fast,
+            report_format: if args.report_format.is_empty() {
+                None
+            } else {
+                Some(args.report_format.parse()?)
+            },
+            report_output: transform_str_para(&args.report_output),
+            ..Default::default()
         };
+        let mut suite_results = vec![];
         for pkg in &args.pkg_list {
             let suites = testing::load_test_suites(pkg, &opts)?;
             for suite in &suites {
@@ -998,10 +1325,29 @@ impl KclServiceImpl {
                             .unwrap_or_default(),
                         duration: info.duration.as_micros() as u64,
                         log_message: info.log_message.clone(),
+                        position: Some(Position {
+                            line: info.line as i64,
+                            column: info.column as i64,
+                            filename: info.filename.clone(),
+                        }),
+                        failure: info.failure.as_ref().map(|failure| TestFailure {
+                            message: failure.message.clone(),
+                            position: Some(Position {
+                                line: failure.line as i64,
+                                column: failure.column as i64,
+                                filename: failure.filename.clone(),
+                            }),
+                        }),
                     })
                 }
+                suite_results.push((suite.pkg.clone(), suite_result));
             }
         }
+        let suite_results = suite_results
+            .iter()
+            .map(|(pkg, result)| (pkg.as_str(), result))
+            .collect::<Vec<_>>();
+        result.report = testing::write_report(&opts, &suite_results)?.unwrap_or_default();
         Ok(result)
     }
 
@@ -1054,4 +1400,15 @@ impl KclServiceImpl {
                 .collect(),
         })
     }
+
+    /// Transform the exec arguments with the plugin agent and the cancellation flag of the
+    /// service.
+    fn transform_exec_args(
+        &self,
+        exec_args: &Option<ExecProgramArgs>,
+    ) -> anyhow::Result<kcl_runner::ExecProgramArgs> {
+        let mut args = transform_exec_para(exec_args, self.plugin_agent)?;
+        args.cancel_flag = self.cancel_flag.clone();
+        Ok(args)
+    }
 }
@@ -416,6 +416,28 @@ impl From<Program> for SerializeProgram {
     }
 }
 
+impl From<SerializeProgram> for Program {
+    fn from(val: SerializeProgram) -> Self {
+        let mut pkgs = HashMap::new();
+        let mut modules = HashMap::new();
+        for (name, pkg_modules) in val.pkgs {
+            let mut filenames = Vec::with_capacity(pkg_modules.len());
+            for module in pkg_modules {
+                filenames.push(module.filename.clone());
+                modules.insert(module.filename.clone(), Arc::new(RwLock::new(module)));
+            }
+            pkgs.insert(name, filenames);
+        }
+        Program {
+            root: val.root,
+            pkgs,
+            modules,
+            pkgs_not_imported: HashMap::new(),
+            modules_not_imported: HashMap::new(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct Program {
     pub root: String,
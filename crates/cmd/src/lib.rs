@@ -3,6 +3,7 @@
 #[macro_use]
 extern crate clap;
 
+pub mod lint;
 pub mod run;
 pub mod settings;
 pub(crate) mod util;
@@ -15,6 +16,7 @@ use clap::{ArgAction, Command};
 use std::io;
 
 use anyhow::Result;
+use lint::lint_command;
 use run::run_command;
 
 /// Run the KCL main command.
@@ -23,12 +25,16 @@ pub fn main(args: &[&str]) -> Result<()> {
     // Sub commands
     match matches.subcommand() {
         Some(("run", sub_matches)) => run_command(sub_matches, &mut io::stdout()),
+        Some(("lint", sub_matches)) => lint_command(sub_matches, &mut io::stdout()),
         Some(("version", _)) => {
             println!("{}", kcl_version::get_version_info());
             Ok(())
         }
         #[cfg(not(target_arch = "wasm32"))]
-        Some(("server", _)) => kcl_api::service::jsonrpc::start_stdio_server(),
+        Some(("server", sub_matches)) => match sub_matches.get_one::<String>("grpc") {
+            Some(addr) => kcl_api::service::grpc::start_grpc_server(addr),
+            None => kcl_api::service::jsonrpc::start_stdio_server(),
+        },
         _ => Ok(()),
     }
 }
@@ -44,7 +50,7 @@ pub fn app() -> Command {
             .about("run")
             .arg(arg!([input] ... "Specify the input files to run").num_args(0..))
             .arg(arg!(output: -o --output <output> "Specify the YAML output file path"))
-            .arg(arg!(format: -f --format <format> "Specify the output format (yaml or json)"))
+            .arg(arg!(format: -f --format <format> "Specify the output format (yaml, json, toml, env, properties or tfjson)"))
             .arg(arg!(setting: -Y --setting <setting> ... "Specify the input setting file").num_args(1..))
             .arg(arg!(verbose: -v --verbose "Print test information verbosely").action(ArgAction::Count))
             .arg(arg!(disable_none: -n --disable_none "Disable dumping None values"))
@@ -60,6 +66,16 @@ pub fn app() -> Command {
             .arg(arg!(recursive: -R --recursive "Compile the files directory recursively"))
             .arg(arg!(package_map: -E --external <package_map> ... "Mapping of package name and path where the package is located").num_args(1..)),
         )
-    .subcommand(Command::new("server").about("Start a rpc server for APIs"))
+    .subcommand(
+        Command::new("lint")
+            .about("lint")
+            .arg(arg!([input] ... "Specify the input files to lint").num_args(0..))
+            .arg(arg!(setting: -Y --setting <setting> ... "Specify the input setting file").num_args(1..)),
+    )
+    .subcommand(
+        Command::new("server")
+            .about("Start a rpc server for APIs")
+            .arg(arg!(grpc: --grpc <addr> "Serve gRPC on the TCP address or the unix:<path> socket instead of the stdio JSON-RPC")),
+    )
     .subcommand(Command::new("version").about("Show the KCL version"))
 }
@@ -0,0 +1,35 @@
+use anyhow::{Result, bail};
+use clap::ArgMatches;
+use kcl_error::Handler;
+use kcl_tools::lint::{lint_files, lint_files_with_config, load_lint_config};
+use std::io::Write;
+
+use crate::util::strings_from_matches;
+
+/// Run the KCL lint command, which fails when any lint is reported as an error, e.g.,
+/// the lints configured as `deny`.
+pub fn lint_command<W: Write>(matches: &ArgMatches, writer: &mut W) -> Result<()> {
+    let files = strings_from_matches(matches, "input").unwrap_or_default();
+    let files: Vec<&str> = files.iter().map(|file| file.as_str()).collect();
+    let (errors, warnings) = match strings_from_matches(matches, "setting") {
+        Some(settings_files) => {
+            let settings_files: Vec<&str> =
+                settings_files.iter().map(|file| file.as_str()).collect();
+            let config = load_lint_config(&files, &settings_files)?;
+            lint_files_with_config(&files, None, config)
+        }
+        None => lint_files(&files, None),
+    };
+    let error_count = errors.len();
+    let mut handler = Handler::default();
+    handler.diagnostics.extend(errors);
+    handler.diagnostics.extend(warnings);
+    let output = handler.emit_to_string()?;
+    if !output.is_empty() {
+        writeln!(writer, "{output}")?;
+    }
+    if error_count > 0 {
+        bail!("lint failed with {error_count} error(s)");
+    }
+    Ok(())
+}
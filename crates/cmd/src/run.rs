@@ -4,7 +4,8 @@ use anyhow::Result;
 use clap::ArgMatches;
 use kcl_error::StringError;
 use kcl_parser::ParseSession;
-use kcl_runner::exec_program;
+use kcl_runner::{ExecProgramArgs, exec_program};
+use kcl_runtime::OutputFormat;
 use std::io::Write;
 use std::sync::Arc;
 
@@ -15,9 +16,16 @@ pub fn run_command<W: Write>(matches: &ArgMatches, writer: &mut W) -> Result<()>
     // Config settings building
     let settings = must_build_settings(matches);
     let output = settings.output();
-    let format_opt = matches.get_one::<String>("format").map(|s| s.as_str());
+    let format = match matches.get_one::<String>("format") {
+        Some(format) => format.parse().map_err(anyhow::Error::msg)?,
+        None => OutputFormat::Yaml,
+    };
+    let mut args: ExecProgramArgs = settings.try_into()?;
+    if !matches!(format, OutputFormat::Yaml | OutputFormat::Json) {
+        args.format = Some(format);
+    }
     let sess = Arc::new(ParseSession::default());
-    match exec_program(sess.clone(), &settings.try_into()?) {
+    match exec_program(sess.clone(), &args) {
         Ok(result) => {
             // Output log message
             if !result.log_message.is_empty() {
@@ -31,15 +39,10 @@ pub fn run_command<W: Write>(matches: &ArgMatches, writer: &mut W) -> Result<()>
                 sess.0.emit_stashed_diagnostics_and_abort()?;
             }
             // Select output based on format option
-            let output_str = match format_opt {
-                Some("json") => &result.json_result,
-                Some("yaml") | None => &result.yaml_result,
-                Some(f) => {
-                    return Err(anyhow::anyhow!(
-                        "Invalid format '{}', expected 'yaml' or 'json'",
-                        f
-                    ));
-                }
+            let output_str = match format {
+                OutputFormat::Yaml => &result.yaml_result,
+                OutputFormat::Json => &result.json_result,
+                _ => &result.output_result,
             };
             if !output_str.is_empty() {
                 match output {
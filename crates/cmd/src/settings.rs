@@ -68,6 +68,7 @@ pub(crate) fn build_settings(matches: &ArgMatches) -> Result<SettingsPathBuf> {
             } else {
                 None
             },
+            kcl_lint_configs: None,
         }),
     )
 }
@@ -0,0 +1,7 @@
+[package]
+name = "lint_deny"
+edition = "v0.9.0"
+version = "0.0.1"
+
+[lint]
+unused_import = "deny"
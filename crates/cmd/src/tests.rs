@@ -9,10 +9,12 @@ use std::{
 
 use kcl_config::modfile::KCL_PKG_PATH;
 use kcl_parser::ParseSession;
-use kcl_runner::{MapErrorResult, exec_program};
+use kcl_runner::{ExecProgramArgs, MapErrorResult, exec_program};
+use kcl_runtime::OutputFormat;
 
 use crate::{
     app,
+    lint::lint_command,
     run::run_command,
     settings::{build_settings, must_build_settings},
     util::hashmaps_from_matches,
@@ -173,6 +175,73 @@ fn test_external_cmd_invalid() {
     }
 }
 
+#[test]
+fn test_run_command_with_format() {
+    let test_case_path = "./src/test_data/format/main.k";
+    let run = |format: &str| {
+        let matches = app().arg_required_else_help(true).get_matches_from([
+            ROOT_CMD,
+            "run",
+            test_case_path,
+            "--format",
+            format,
+        ]);
+        let mut buf = Vec::new();
+        run_command(matches.subcommand_matches("run").unwrap(), &mut buf)
+            .map(|_| String::from_utf8(buf).unwrap())
+    };
+    assert_eq!(
+        run("toml").unwrap(),
+        "name = \"app\"\nport = 8080\n\n[db]\nhost = \"localhost\"\n"
+    );
+    assert_eq!(
+        run("properties").unwrap(),
+        "name=app\nport=8080\ndb.host=localhost\n"
+    );
+    assert!(
+        run("xml")
+            .unwrap_err()
+            .to_string()
+            .contains("invalid output format 'xml'")
+    );
+
+    let matches =
+        app()
+            .arg_required_else_help(true)
+            .get_matches_from([ROOT_CMD, "run", test_case_path]);
+    let settings = must_build_settings(matches.subcommand_matches("run").unwrap());
+    let mut args: ExecProgramArgs = settings.try_into().unwrap();
+    args.format = Some(OutputFormat::Env);
+    let msg = exec_program(Arc::new(ParseSession::default()), &args)
+        .map_err_to_result()
+        .unwrap_err()
+        .to_string();
+    assert!(msg.contains("the dict value of 'db' can't be represented in the env output format"));
+}
+
+#[test]
+fn test_lint_command() {
+    let lint = |args: &[&str]| {
+        let matches = app()
+            .arg_required_else_help(true)
+            .get_matches_from([&[ROOT_CMD, "lint"], args].concat());
+        let mut buf = Vec::new();
+        lint_command(matches.subcommand_matches("lint").unwrap(), &mut buf)
+            .map(|_| String::from_utf8(buf).unwrap())
+    };
+    // The unused import is denied in the kcl.mod file.
+    let err = lint(&["./src/test_data/lint_deny/main.k"]).unwrap_err();
+    assert!(err.to_string().contains("lint failed with 1 error(s)"));
+    // The settings file re-levels the lint as a warning.
+    let output = lint(&[
+        "./src/test_data/lint_deny/main.k",
+        "-Y",
+        "./src/test_data/lint_deny/settings.yaml",
+    ])
+    .unwrap();
+    assert!(output.contains("Module 'math' imported but unused"));
+}
+
 #[test]
 #[cfg(not(windows))]
 // All the unit test cases in [`test_run_command`] can not be executed concurrently.
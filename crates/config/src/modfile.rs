@@ -4,7 +4,7 @@ use anyhow::Result;
 use kcl_utils::path::PathPrefix;
 use serde::{Deserialize, Serialize};
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     env, fs,
     io::Read,
     path::{Path, PathBuf},
@@ -30,8 +30,13 @@ pub struct ModFile {
     pub package: Option<Package>,
     pub profile: Option<Profile>,
     pub dependencies: Option<Dependencies>,
+    pub lint: Option<LintRules>,
 }
 
+/// The lint rule levels in the `[lint]` section of 'kcl.mod', keyed by the
+/// lint code or name, e.g., `W0411 = "allow"` and `reimport = "deny"`.
+pub type LintRules = BTreeMap<String, String>;
+
 /// ModLockFile is kcl package file 'kc.mod.lock'.
 #[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq)]
 pub struct ModLockFile {
@@ -104,6 +109,8 @@ pub struct LockDependency {
     pub reg: Option<String>,
     pub repo: Option<String>,
     pub oci_tag: Option<String>,
+    /// The OCI manifest digest of the locked tag, e.g., `sha256:...`.
+    pub digest: Option<String>,
 
     /* Git Source */
     pub url: Option<String>,
@@ -195,6 +202,24 @@ pub fn load_mod_lock_file<P: AsRef<Path>>(path: P) -> Result<ModLockFile> {
     toml::from_slice(buffer.as_slice()).map_err(|e| anyhow::anyhow!(e))
 }
 
+/// Save kcl mod lock file into path. Dependencies are sorted by their names
+/// to make the lock file content stable.
+pub fn save_mod_lock_file<P: AsRef<Path>>(path: P, mod_lock_file: &ModLockFile) -> Result<()> {
+    #[derive(Serialize)]
+    struct SortedModLockFile<'a> {
+        dependencies: Option<BTreeMap<&'a String, &'a LockDependency>>,
+    }
+    let sorted = SortedModLockFile {
+        dependencies: mod_lock_file
+            .dependencies
+            .as_ref()
+            .map(|deps| deps.iter().collect()),
+    };
+    let file_path = path.as_ref().join(KCL_MOD_LOCK_FILE);
+    fs::write(file_path, toml::to_string(&sorted)?)?;
+    Ok(())
+}
+
 /// Get the path holding the external kcl package.
 /// From the environment variable KCL_PKG_PATH.
 /// If `KCL_PKG_PATH` is not present, then the user root string is returned.
@@ -335,6 +360,37 @@ mod modfile_test {
         assert_eq!(root.unwrap().as_str(), expected);
     }
 
+    #[test]
+    fn test_save_and_load_mod_lock_file() {
+        let dir = std::env::temp_dir().join("kcl_test_save_mod_lock_file");
+        fs::create_dir_all(&dir).unwrap();
+        let mut dependencies = LockDependencies::new();
+        dependencies.insert(
+            "helloworld".to_string(),
+            LockDependency {
+                name: "helloworld".to_string(),
+                full_name: Some("helloworld_0.1.0".to_string()),
+                version: Some("0.1.0".to_string()),
+                sum: Some("sum".to_string()),
+                reg: Some("ghcr.io".to_string()),
+                repo: Some("kcl-lang/helloworld".to_string()),
+                oci_tag: Some("0.1.0".to_string()),
+                digest: Some("sha256:digest".to_string()),
+                url: None,
+                branch: None,
+                commit: None,
+                git_tag: None,
+                path: None,
+            },
+        );
+        let mod_lock_file = ModLockFile {
+            dependencies: Some(dependencies),
+        };
+        save_mod_lock_file(&dir, &mod_lock_file).unwrap();
+        assert_eq!(load_mod_lock_file(&dir).unwrap(), mod_lock_file);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
     #[test]
     fn test_load_mod_file() {
         let kcl_mod = load_mod_file(TEST_ROOT).unwrap();
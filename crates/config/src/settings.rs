@@ -6,6 +6,8 @@ use serde::{
 };
 use std::{collections::HashMap, ops::Deref, path::PathBuf};
 
+use crate::modfile::LintRules;
+
 /// Default settings file `kcl.yaml`
 pub const DEFAULT_SETTING_FILE: &str = "kcl.yaml";
 
@@ -46,6 +48,10 @@ impl SettingsPathBuf {
 pub struct SettingsFile {
     pub kcl_cli_configs: Option<Config>,
     pub kcl_options: Option<Vec<KeyValuePair>>,
+    /// The lint rule levels keyed by the lint code or name, which override
+    /// the `[lint]` section of 'kcl.mod'.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kcl_lint_configs: Option<LintRules>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Default)]
@@ -90,6 +96,7 @@ impl SettingsFile {
                 package_maps: Some(HashMap::default()),
             }),
             kcl_options: Some(vec![]),
+            kcl_lint_configs: None,
         }
     }
 
@@ -396,6 +403,12 @@ pub fn merge_settings(settings: &[SettingsFile]) -> SettingsFile {
                 set_if!(result_kcl_cli_configs, package_maps, kcl_cli_configs);
             }
         }
+        if let Some(kcl_lint_configs) = &setting.kcl_lint_configs {
+            result
+                .kcl_lint_configs
+                .get_or_insert_with(Default::default)
+                .extend(kcl_lint_configs.clone());
+        }
         if let Some(kcl_options) = &setting.kcl_options {
             if result.kcl_options.is_none() {
                 result.kcl_options = Some(vec![])
@@ -1,4 +1,13 @@
+use anyhow::Result;
+use sha2::{Digest, Sha256};
 use std::path::Path;
+use walkdir::WalkDir;
+
+/// The git metadata directory which is ignored in the package checksum.
+const GIT_DIR: &str = ".git";
+/// The prefix of the package checksum, which distinguishes the checksum from
+/// the ones calculated by other tools using different algorithms.
+pub(crate) const CHECKSUM_PREFIX: &str = "sha256:";
 
 #[inline]
 pub(crate) fn directory_is_not_empty<P: AsRef<Path>>(path: P) -> bool {
@@ -6,3 +15,34 @@ pub(crate) fn directory_is_not_empty<P: AsRef<Path>>(path: P) -> bool {
         .map(|mut entries| entries.next().is_some())
         .is_ok()
 }
+
+/// Calculate the content hash of all the files in the directory. Files are
+/// visited in the file name order, and each file contributes its relative
+/// path and content to the hash, so the hash is stable across machines.
+pub(crate) fn hash_dir<P: AsRef<Path>>(path: P) -> Result<String> {
+    let path = path.as_ref();
+    let mut hasher = Sha256::new();
+    let entries = WalkDir::new(path)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|e| e.file_name() != GIT_DIR);
+    for entry in entries {
+        let entry = entry?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let rel_path = entry
+            .path()
+            .strip_prefix(path)?
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        let content = std::fs::read(entry.path())?;
+        hasher.update(rel_path.as_bytes());
+        hasher.update([0]);
+        hasher.update((content.len() as u64).to_le_bytes());
+        hasher.update(&content);
+    }
+    Ok(format!("{}{:x}", CHECKSUM_PREFIX, hasher.finalize()))
+}
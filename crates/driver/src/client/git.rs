@@ -63,3 +63,19 @@ pub(crate) fn cmd_clone_git_repo_to(
 
     Ok(path.into())
 }
+
+/// Get the commit hash of the HEAD in the git repository.
+pub(crate) fn cmd_get_git_head_commit(path: &Path) -> Result<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "HEAD"])
+        .current_dir(path)
+        .output()?;
+    if !output.status.success() {
+        bail!(
+            "Failed to get the Git commit of {}: {}",
+            path.display(),
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
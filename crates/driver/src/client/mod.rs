@@ -2,11 +2,12 @@ mod fs;
 mod git;
 mod oci;
 
-use anyhow::Result;
-use git::cmd_clone_git_repo_to;
+use anyhow::{Context, Result, bail};
+use git::{cmd_clone_git_repo_to, cmd_get_git_head_commit};
 use kcl_config::modfile::{Dependency, GitSource, ModFile, OciSource};
 use kcl_config::modfile::{
-    LockDependency, ModLockFile, get_vendor_home, load_mod_file, load_mod_lock_file,
+    LockDependencies, LockDependency, ModLockFile, get_vendor_home, load_mod_file,
+    load_mod_lock_file, save_mod_lock_file,
 };
 use kcl_primitives::IndexSet;
 use kcl_utils::fslock::open_lock_file;
@@ -35,6 +36,8 @@ pub struct ModClient {
     vendor: Option<PathBuf>,
     /// A lazy OCI client.
     oci_client: Arc<Client>,
+    /// The vendored package paths which are already verified against the lock file.
+    verified: IndexSet<PathBuf>,
 }
 
 unsafe impl Send for ModClient {}
@@ -58,6 +61,7 @@ impl ModClient {
             mod_lock_file: load_mod_lock_file(&work_dir).ok(),
             vendor: None,
             oci_client,
+            verified: Default::default(),
         })
     }
 
@@ -119,7 +123,34 @@ impl ModClient {
 
     /// Resolve package deps to metadata, note this function will download deps from remote sources.
     /// If the dependency is downloaded to the local path, calculate the package metadata.
+    ///
+    /// When `update` is true, the exact git commit or OCI digest and the content checksum of every
+    /// transitive dependency are written into the kcl.mod.lock file of the current module, and the
+    /// dependencies already in the lock file are verified against it.
     pub fn resolve_all_deps(&mut self, update: bool) -> Result<Metadata> {
+        let previous = self
+            .mod_lock_file
+            .as_ref()
+            .and_then(|mod_lock_file| mod_lock_file.dependencies.clone())
+            .unwrap_or_default();
+        let mut locked = LockDependencies::new();
+        let metadata = self.resolve_deps(update, &previous, &mut locked)?;
+        if update && !locked.is_empty() {
+            let mod_lock_file = ModLockFile {
+                dependencies: Some(locked),
+            };
+            save_mod_lock_file(&self.work_dir, &mod_lock_file)?;
+            self.mod_lock_file = Some(mod_lock_file);
+        }
+        Ok(metadata)
+    }
+
+    fn resolve_deps(
+        &self,
+        update: bool,
+        previous: &LockDependencies,
+        locked: &mut LockDependencies,
+    ) -> Result<Metadata> {
         let mut metadata = Metadata::default();
         match &self.mod_file.dependencies {
             Some(dependencies) if !dependencies.is_empty() => {
@@ -127,7 +158,27 @@ impl ModClient {
                 let mut paths: IndexSet<PathBuf> = IndexSet::default();
                 for (name, dep) in dependencies {
                     let path = if update {
-                        let path = self.download_dep_to_vendor(name, dep, &vendor)?;
+                        let (path, mut lock_dep) =
+                            self.download_and_lock_dep(name, dep, &vendor)?;
+                        if !locked.contains_key(name) {
+                            if let Some(previous_dep) = previous.get(name) {
+                                verify_lock_dep(dep, previous_dep, &mut lock_dep)?;
+                            }
+                            if let (None, Some(oci_tag)) = (&lock_dep.digest, &lock_dep.oci_tag)
+                                && let (Some(reg), Some(repo)) = (&lock_dep.reg, &lock_dep.repo)
+                            {
+                                // The cached OCI package is not pulled again, and the digest
+                                // is fetched from the registry. The lock file is not written
+                                // without the digest, which pins the package.
+                                let image = oci::oci_reg_repo_join(reg, repo);
+                                let digest =
+                                    self.fetch_oci_digest(&image, oci_tag).with_context(|| {
+                                        format!("failed to fetch the digest of {image}:{oci_tag}")
+                                    })?;
+                                lock_dep.digest = Some(digest);
+                            }
+                            locked.insert(name.to_string(), lock_dep);
+                        }
                         paths.insert(path.clone());
                         path
                     } else {
@@ -142,10 +193,10 @@ impl ModClient {
                     );
                 }
                 for path in paths {
-                    if let Ok(mut client) =
+                    if let Ok(client) =
                         ModClient::new_with_oci_client(path, self.oci_client.clone())
                     {
-                        let new_metadata = client.resolve_all_deps(update)?;
+                        let new_metadata = client.resolve_deps(update, previous, locked)?;
                         for (name, package) in new_metadata.packages {
                             metadata.packages.entry(name).or_insert(package);
                         }
@@ -184,6 +235,85 @@ impl ModClient {
         }
     }
 
+    /// Download a dependency to the local path and return the lock dependency which records
+    /// the exact source and the content checksum of the downloaded package.
+    fn download_and_lock_dep(
+        &self,
+        name: &str,
+        dep: &Dependency,
+        vendor: &Path,
+    ) -> Result<(PathBuf, LockDependency)> {
+        let mut lock_dep = LockDependency {
+            name: name.to_string(),
+            full_name: None,
+            version: None,
+            sum: None,
+            reg: None,
+            repo: None,
+            oci_tag: None,
+            digest: None,
+            url: None,
+            branch: None,
+            commit: None,
+            git_tag: None,
+            path: None,
+        };
+        let path = match dep {
+            Dependency::Version(version) => self.download_and_lock_oci_source(
+                name,
+                &OciSource {
+                    oci: oci::oci_reg_repo_join(&self.default_oci_registry(), name),
+                    tag: Some(version.to_string()),
+                },
+                vendor,
+                &mut lock_dep,
+            )?,
+            Dependency::Oci(oci_source) => {
+                self.download_and_lock_oci_source(name, oci_source, vendor, &mut lock_dep)?
+            }
+            Dependency::Git(git_source) => {
+                let full_name = self.get_local_path_from_dep(name, dep);
+                let path = self.download_git_source_to(git_source, &vendor.join(&full_name))?;
+                lock_dep.full_name = Some(full_name);
+                lock_dep.version = git_source.version.clone();
+                lock_dep.url = Some(git_source.git.clone());
+                lock_dep.branch = git_source.branch.clone();
+                lock_dep.git_tag = git_source.tag.clone();
+                lock_dep.commit = Some(cmd_get_git_head_commit(&path)?);
+                path
+            }
+            Dependency::Local(local_source) => {
+                // Local packages are not locked by the checksum.
+                lock_dep.path = Some(local_source.path.clone());
+                return Ok((
+                    vendor.join(self.get_local_path_from_dep(name, dep)),
+                    lock_dep,
+                ));
+            }
+        };
+        lock_dep.sum = Some(fs::hash_dir(&path)?);
+        Ok((path, lock_dep))
+    }
+
+    fn download_and_lock_oci_source(
+        &self,
+        name: &str,
+        oci_source: &OciSource,
+        vendor: &Path,
+        lock_dep: &mut LockDependency,
+    ) -> Result<PathBuf> {
+        let package = self.pull_oci_source_to(name, oci_source, vendor)?;
+        let image = oci::strip_oci_scheme_prefix(&oci_source.oci);
+        let (reg, repo) = image.split_once('/').unwrap_or(("", image));
+        lock_dep.full_name = Some(format!("{}_{}", name, package.tag));
+        lock_dep.version = Some(package.tag.clone());
+        lock_dep.reg = Some(reg.to_string());
+        lock_dep.repo = Some(repo.to_string());
+        lock_dep.oci_tag = Some(package.tag);
+        lock_dep.digest = package.digest;
+        Ok(package.path)
+    }
+
     /// Get the vendor path.
     pub fn get_vendor_path(&self) -> Result<PathBuf> {
         Ok(match &self.vendor {
@@ -212,8 +342,17 @@ impl ModClient {
         oci_source: &OciSource,
         path: &Path,
     ) -> Result<PathBuf> {
+        Ok(self.pull_oci_source_to(name, oci_source, path)?.path)
+    }
+
+    fn pull_oci_source_to(
+        &self,
+        name: &str,
+        oci_source: &OciSource,
+        path: &Path,
+    ) -> Result<oci::OciPackage> {
         let rt = tokio::runtime::Runtime::new()?;
-        let path = rt.block_on(async {
+        rt.block_on(async {
             oci::pull_oci_and_extract_layer(
                 &self.oci_client,
                 name,
@@ -222,8 +361,12 @@ impl ModClient {
                 path,
             )
             .await
-        })?;
-        Ok(path)
+        })
+    }
+
+    fn fetch_oci_digest(&self, image: &str, tag: &str) -> Result<String> {
+        let rt = tokio::runtime::Runtime::new()?;
+        rt.block_on(async { oci::fetch_oci_manifest_digest(&self.oci_client, image, tag).await })
     }
 
     /// Get the dependency store path
@@ -259,29 +402,34 @@ impl ModClient {
         }
     }
 
-    /// Get the lock dependency store path
+    /// Get the lock dependency store path. The dependencies declared in the kcl.mod file are
+    /// stored where they are downloaded, e.g., a git dependency without the commit in the kcl.mod
+    /// file is not stored in the path of the locked commit.
     pub fn get_local_path_from_lock_dep(&self, lock_dep: &LockDependency) -> Option<String> {
         if lock_dep.reg.is_some() {
             lock_dep.full_name.clone()
+        } else if let Some(dep) = self
+            .mod_file
+            .dependencies
+            .as_ref()
+            .and_then(|dependencies| dependencies.get(&lock_dep.name))
+        {
+            Some(self.get_local_path_from_dep(&lock_dep.name, dep))
         } else if let Some(git_url) = &lock_dep.url {
-            Some(self.get_local_path_from_dep(
-                &lock_dep.gen_filename(),
-                &Dependency::Git(GitSource {
-                    git: git_url.to_string(),
-                    branch: lock_dep.branch.clone(),
-                    commit: lock_dep.commit.clone(),
-                    tag: lock_dep.git_tag.clone(),
-                    version: lock_dep.version.clone(),
-                }),
-            ))
+            lock_dep.full_name.clone().or_else(|| {
+                Some(self.get_local_path_from_dep(
+                    &lock_dep.gen_filename(),
+                    &Dependency::Git(GitSource {
+                        git: git_url.to_string(),
+                        branch: lock_dep.branch.clone(),
+                        commit: lock_dep.commit.clone(),
+                        tag: lock_dep.git_tag.clone(),
+                        version: lock_dep.version.clone(),
+                    }),
+                ))
+            })
         } else {
-            match &self.mod_file.dependencies {
-                Some(dependencies) => dependencies
-                    .get(&lock_dep.name)
-                    .as_ref()
-                    .map(|dep| self.get_local_path_from_dep(&lock_dep.name, dep)),
-                None => None,
-            }
+            None
         }
     }
 
@@ -308,4 +456,108 @@ impl ModClient {
         }
         None
     }
+
+    /// Verify the vendored packages against the content checksums in the kcl.mod.lock file.
+    /// Packages which are not vendored yet are skipped, and an error is returned when the
+    /// content of a vendored package does not match its checksum.
+    pub fn verify_locked_deps(&mut self) -> Result<()> {
+        let dependencies = match self
+            .mod_lock_file
+            .as_ref()
+            .and_then(|mod_lock_file| mod_lock_file.dependencies.clone())
+        {
+            Some(dependencies) => dependencies,
+            None => return Ok(()),
+        };
+        let vendor = self.get_vendor_path()?;
+        for (name, dep) in &dependencies {
+            // Checksums calculated by other tools using different algorithms are skipped.
+            let sum = match &dep.sum {
+                Some(sum) if sum.starts_with(fs::CHECKSUM_PREFIX) => sum,
+                _ => continue,
+            };
+            let path = match self.get_local_path_from_lock_dep(dep) {
+                Some(path) if !path.is_empty() => vendor.join(path),
+                _ => continue,
+            };
+            if !path.is_dir() || self.verified.contains(&path) {
+                continue;
+            }
+            let actual = fs::hash_dir(&path)?;
+            if &actual != sum {
+                bail!(
+                    "checksum mismatch for the dependency '{}' in {}: expected {}, got {}",
+                    name,
+                    path.display(),
+                    sum,
+                    actual
+                );
+            }
+            self.verified.insert(path);
+        }
+        Ok(())
+    }
+}
+
+/// Returns the content checksum of the package directory which is recorded in the kcl.mod.lock file.
+#[inline]
+pub fn package_checksum<P: AsRef<Path>>(path: P) -> Result<String> {
+    fs::hash_dir(path)
+}
+
+/// Verify the downloaded dependency against the previously locked one. A dependency whose
+/// source is changed in the kcl.mod file is locked again, otherwise the git commit, the OCI
+/// digest and the content checksum must be the same as the locked ones.
+fn verify_lock_dep(
+    dep: &Dependency,
+    locked: &LockDependency,
+    actual: &mut LockDependency,
+) -> Result<()> {
+    let same_source = locked.reg == actual.reg
+        && locked.repo == actual.repo
+        && locked.oci_tag == actual.oci_tag
+        && locked.url == actual.url
+        && locked.branch == actual.branch
+        && locked.git_tag == actual.git_tag
+        && locked.path == actual.path
+        && match dep {
+            Dependency::Git(git_source) if git_source.commit.is_some() => {
+                git_source.commit == locked.commit
+            }
+            _ => true,
+        };
+    if !same_source {
+        return Ok(());
+    }
+    if locked.commit != actual.commit {
+        bail!(
+            "the dependency '{}' is locked to the git commit {}, but got {}",
+            actual.name,
+            locked.commit.as_deref().unwrap_or_default(),
+            actual.commit.as_deref().unwrap_or_default()
+        );
+    }
+    match (&locked.digest, &actual.digest) {
+        (Some(expected), Some(digest)) if expected != digest => bail!(
+            "the dependency '{}' is locked to the OCI digest {}, but got {}",
+            actual.name,
+            expected,
+            digest
+        ),
+        // The cached OCI package keeps the locked digest.
+        (Some(expected), None) => actual.digest = Some(expected.clone()),
+        _ => {}
+    }
+    if let (Some(expected), Some(sum)) = (&locked.sum, &actual.sum)
+        && expected.starts_with(fs::CHECKSUM_PREFIX)
+        && expected != sum
+    {
+        bail!(
+            "checksum mismatch for the dependency '{}': expected {}, got {}",
+            actual.name,
+            expected,
+            sum
+        );
+    }
+    Ok(())
 }
@@ -27,20 +27,35 @@ pub(crate) fn oci_reg_repo_join(reg: &str, repo: &str) -> String {
     format!("{reg}/{repo}")
 }
 
+/// The OCI package pulled into the local path.
+pub(crate) struct OciPackage {
+    /// The local path of the package.
+    pub(crate) path: PathBuf,
+    /// The resolved tag of the package.
+    pub(crate) tag: String,
+    /// The manifest digest of the package, which is [None] when the package
+    /// already exists in the local path and is not pulled again.
+    pub(crate) digest: Option<String>,
+}
+
 pub(crate) async fn pull_oci_and_extract_layer(
     client: &Client,
     name: &str,
     image: &str,
     tag: &Option<String>,
     save_dir: &Path,
-) -> Result<PathBuf> {
+) -> Result<OciPackage> {
     let image = strip_oci_scheme_prefix(image);
     let auth = RegistryAuth::Anonymous;
-    let (img_data, path) = match &tag {
+    let (img_data, path, tag) = match &tag {
         Some(tag) => {
             let path = save_dir.join(format!("{name}_{tag}"));
             if directory_is_not_empty(&path) {
-                return Ok(path);
+                return Ok(OciPackage {
+                    path,
+                    tag: tag.to_string(),
+                    digest: None,
+                });
             }
             let img_ref = Reference::try_from(format!("{image}:{tag}"))?;
             (
@@ -48,6 +63,7 @@ pub(crate) async fn pull_oci_and_extract_layer(
                     .pull(&img_ref, &auth, vec![IMAGE_LAYER_MEDIA_TYPE])
                     .await?,
                 path,
+                tag.to_string(),
             )
         }
         None => {
@@ -67,13 +83,18 @@ pub(crate) async fn pull_oci_and_extract_layer(
             };
             let path = save_dir.join(format!("{name}_{tag}"));
             if directory_is_not_empty(&path) {
-                return Ok(path);
+                return Ok(OciPackage {
+                    path,
+                    tag: tag.to_string(),
+                    digest: None,
+                });
             }
             (
                 client
                     .pull(&img_tag_ref, &auth, vec![IMAGE_LAYER_MEDIA_TYPE])
                     .await?,
                 path,
+                tag.to_string(),
             )
         }
     };
@@ -81,5 +102,22 @@ pub(crate) async fn pull_oci_and_extract_layer(
         let buf = layer.data.as_slice();
         tar::Archive::new(buf).unpack(&path)?;
     }
-    Ok(path)
+    Ok(OciPackage {
+        path,
+        tag,
+        digest: img_data.digest,
+    })
+}
+
+/// Fetch the manifest digest of the OCI package tag from the registry.
+pub(crate) async fn fetch_oci_manifest_digest(
+    client: &Client,
+    image: &str,
+    tag: &str,
+) -> Result<String> {
+    let image = strip_oci_scheme_prefix(image);
+    let img_ref = Reference::try_from(format!("{image}:{tag}"))?;
+    Ok(client
+        .fetch_manifest_digest(&img_ref, &RegistryAuth::Anonymous)
+        .await?)
 }
@@ -127,10 +127,16 @@ pub fn lookup_compile_workspaces(
                     workfile.canonicalize(root.to_path_buf());
                     for work in workfile.workspaces {
                         if let Ok(workspace) = lookup_workspace(&work.abs_path) {
-                            workspaces.insert(
-                                workspace.clone(),
-                                lookup_compile_workspace(tool, &work.abs_path, load_pkg),
-                            );
+                            let mut opts = lookup_compile_workspace(tool, &work.abs_path, load_pkg);
+                            // The workspace directory without the entries in `kcl.mod`
+                            // compiles the kcl files in the directory.
+                            if opts.0.is_empty()
+                                && load_pkg
+                                && let Ok(files) = get_kcl_files(&work.abs_path, false)
+                            {
+                                opts.0 = files;
+                            }
+                            workspaces.insert(workspace.clone(), opts);
                         }
                     }
                     return (workspaces, Some(workfile.failed.clone()));
@@ -1,9 +1,12 @@
 use std::panic;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::process::Command;
 
+use kcl_config::modfile::load_mod_lock_file;
 use kcl_config::settings::KeyValuePair;
 
 use crate::arguments::parse_key_value_pair;
+use crate::client::{ModClient, package_checksum};
 use crate::toolchain::NativeToolchain;
 use crate::toolchain::Toolchain;
 use crate::{get_pkg_list, lookup_the_nearest_file_dir, toolchain};
@@ -170,3 +173,153 @@ fn test_get_pkg_list() {
         3
     );
 }
+
+#[test]
+fn test_verify_locked_deps() {
+    let work_dir = std::env::temp_dir().join("kcl_test_verify_locked_deps");
+    std::fs::create_dir_all(&work_dir).unwrap();
+    std::fs::write(
+        work_dir.join("kcl.mod"),
+        "[package]\nname = \"test\"\nversion = \"0.0.1\"\n\n[dependencies]\nhelloworld = \"0.1.0\"\n",
+    )
+    .unwrap();
+    let vendor = PathBuf::from("./src/test_data/test_vendor")
+        .canonicalize()
+        .unwrap();
+    let sum = package_checksum(vendor.join("helloworld_0.1.0")).unwrap();
+    let write_lock_file = |sum: &str| {
+        std::fs::write(
+            work_dir.join("kcl.mod.lock"),
+            format!(
+                "[dependencies.helloworld]\nname = \"helloworld\"\nfull_name = \"helloworld_0.1.0\"\nversion = \"0.1.0\"\nsum = \"{sum}\"\nreg = \"ghcr.io\"\nrepo = \"kcl-lang/helloworld\"\noci_tag = \"0.1.0\"\n"
+            ),
+        )
+        .unwrap();
+    };
+
+    write_lock_file(&sum);
+    let mut client = ModClient::new(&work_dir).unwrap();
+    client.set_vendor(&vendor);
+    assert!(client.verify_locked_deps().is_ok());
+
+    write_lock_file(&format!("{}0000", &sum[..sum.len() - 4]));
+    let mut client = ModClient::new(&work_dir).unwrap();
+    client.set_vendor(&vendor);
+    let err = client.verify_locked_deps().unwrap_err();
+    assert!(err.to_string().contains("checksum mismatch"), "{err}");
+
+    std::fs::remove_dir_all(&work_dir).unwrap();
+}
+
+#[test]
+fn test_resolve_all_deps_lock_file() {
+    let root = std::env::temp_dir().join("kcl_test_resolve_all_deps_lock_file");
+    let _ = std::fs::remove_dir_all(&root);
+    let write_package = |path: &Path, name: &str| {
+        std::fs::create_dir_all(path).unwrap();
+        std::fs::write(
+            path.join("kcl.mod"),
+            format!("[package]\nname = \"{name}\"\nversion = \"0.0.1\"\n"),
+        )
+        .unwrap();
+        std::fs::write(path.join("main.k"), format!("name = \"{name}\"\n")).unwrap();
+    };
+    let git = |args: &[&str], dir: &Path| {
+        let output = Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    };
+    // The dependencies are a local package, a local git repository and a vendored OCI package,
+    // so the test does not need the network.
+    write_package(&root.join("local_dep"), "local_dep");
+    let repo = root.join("git_dep");
+    write_package(&repo, "git_dep");
+    git(&["init", "-q"], &repo);
+    git(&["add", "."], &repo);
+    git(
+        &[
+            "-c",
+            "user.name=kcl",
+            "-c",
+            "user.email=kcl@kcl-lang.io",
+            "commit",
+            "-q",
+            "-m",
+            "init",
+        ],
+        &repo,
+    );
+    let commit = git(&["rev-parse", "HEAD"], &repo);
+    let vendor = root.join("vendor");
+    write_package(&vendor.join("helloworld_0.1.0"), "helloworld");
+    let work_dir = root.join("work");
+    std::fs::create_dir_all(&work_dir).unwrap();
+    std::fs::write(
+        work_dir.join("kcl.mod"),
+        format!(
+            "[package]\nname = \"work\"\nversion = \"0.0.1\"\n\n[dependencies]\nhelloworld = \"0.1.0\"\ngit_dep = {{ git = \"file://{}\" }}\nlocal_dep = {{ path = \"../local_dep\" }}\n",
+            repo.display()
+        ),
+    )
+    .unwrap();
+    // The cached OCI package is not pulled again, and keeps the digest locked before.
+    let digest = "sha256:5f4dcc3b5aa765d61d8327deb882cf992b95990a9151374abd8ff8c5a7a0fe08";
+    std::fs::write(
+        work_dir.join("kcl.mod.lock"),
+        format!(
+            "[dependencies.helloworld]\nname = \"helloworld\"\nfull_name = \"helloworld_0.1.0\"\nversion = \"0.1.0\"\nreg = \"ghcr.io\"\nrepo = \"kcl-lang/helloworld\"\noci_tag = \"0.1.0\"\ndigest = \"{digest}\"\n"
+        ),
+    )
+    .unwrap();
+
+    let mut client = ModClient::new(&work_dir).unwrap();
+    client.set_vendor(&vendor);
+    client.resolve_all_deps(true).unwrap();
+    let dependencies = load_mod_lock_file(&work_dir).unwrap().dependencies.unwrap();
+    let helloworld = &dependencies["helloworld"];
+    assert_eq!(helloworld.digest.as_deref(), Some(digest));
+    assert_eq!(
+        helloworld.sum,
+        Some(package_checksum(vendor.join("helloworld_0.1.0")).unwrap())
+    );
+    let git_dep = &dependencies["git_dep"];
+    assert_eq!(git_dep.commit.as_deref(), Some(commit.as_str()));
+    assert_eq!(
+        git_dep.sum,
+        Some(package_checksum(vendor.join("git_dep_latest")).unwrap())
+    );
+    let local_dep = &dependencies["local_dep"];
+    assert_eq!(local_dep.path.as_deref(), Some("../local_dep"));
+    assert_eq!(local_dep.sum, None);
+
+    let native_toolchain = || {
+        let mut client = ModClient::new(&work_dir).unwrap();
+        client.set_vendor(&vendor);
+        NativeToolchain::new(client)
+    };
+    assert!(native_toolchain().fetch_metadata(work_dir.clone()).is_ok());
+    // The tampered vendored package is rejected on the next fetch.
+    std::fs::write(
+        vendor.join("git_dep_latest").join("main.k"),
+        "name = \"tampered\"\n",
+    )
+    .unwrap();
+    let err = native_toolchain()
+        .fetch_metadata(work_dir.clone())
+        .unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("checksum mismatch for the dependency 'git_dep'"),
+        "{err}"
+    );
+
+    std::fs::remove_dir_all(&root).unwrap();
+}
@@ -105,13 +105,26 @@ pub struct NativeToolchain {
     client: Arc<Mutex<ModClient>>,
 }
 
+#[cfg(not(target_arch = "wasm32"))]
+impl NativeToolchain {
+    /// New a native toolchain with the mod client, e.g., which uses a custom vendor path.
+    pub fn new(client: ModClient) -> Self {
+        Self {
+            client: Arc::new(Mutex::new(client)),
+        }
+    }
+}
+
 #[cfg(not(target_arch = "wasm32"))]
 impl Toolchain for NativeToolchain {
     fn fetch_metadata(&self, manifest_path: PathBuf) -> Result<Metadata> {
         let mut client = self.client.lock();
         client.change_work_dir(manifest_path)?;
         match client.get_metadata_from_mod_lock_file() {
-            Some(metadata) => Ok(metadata),
+            Some(metadata) => {
+                client.verify_locked_deps()?;
+                Ok(metadata)
+            }
             None => client.resolve_all_deps(false),
         }
     }
@@ -5,6 +5,7 @@
 
 pub mod diagnostic;
 mod error;
+pub mod sarif;
 
 use annotate_snippets::{
     display_list::DisplayList,
@@ -82,6 +83,12 @@ impl Handler {
         Ok(error_strings.join("\n"))
     }
 
+    /// Emit all diagnostics to a SARIF 2.1.0 log string.
+    #[inline]
+    pub fn emit_to_sarif(&self) -> Result<String> {
+        sarif::to_sarif_string(&self.diagnostics)
+    }
+
     /// Emit all diagnostics and abort if has any errors.
     pub fn abort_if_any_errors(&mut self) {
         match self.emit() {
@@ -0,0 +1,393 @@
+//! SARIF 2.1.0 emitter of the KCL diagnostics.
+//!
+//! Each diagnostic code (e.g., E1001 and W1001) is mapped to a SARIF rule whose help is
+//! the markdown documentation of the code, and each diagnostic is mapped to a SARIF result
+//! with its source regions and suggested replacements, which can be uploaded to the code
+//! scanning services, e.g., GitHub code scanning.
+
+use std::path::Path;
+
+use anyhow::Result;
+use kcl_primitives::IndexMap;
+use serde::Serialize;
+
+use crate::{Diagnostic, DiagnosticId, ERRORS, ErrorKind, Level, Position, WARNINGS, WarningKind};
+
+pub const SARIF_VERSION: &str = "2.1.0";
+pub const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+const TOOL_NAME: &str = "kcl";
+const TOOL_INFORMATION_URI: &str = "https://kcl-lang.io";
+
+/// The SARIF log which contains one run of the KCL tool.
+#[derive(Debug, Clone, Serialize)]
+pub struct SarifLog {
+    #[serde(rename = "$schema")]
+    pub schema: String,
+    pub version: String,
+    pub runs: Vec<Run>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Run {
+    pub tool: Tool,
+    pub results: Vec<SarifResult>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Tool {
+    pub driver: Driver,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Driver {
+    pub name: String,
+    pub information_uri: String,
+    pub version: String,
+    pub rules: Vec<Rule>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Rule {
+    /// The diagnostic code, e.g., E1001.
+    pub id: String,
+    /// The diagnostic kind name, e.g., InvalidSyntax.
+    pub name: String,
+    pub short_description: Text,
+    pub help: Help,
+    pub default_configuration: Configuration,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Help {
+    pub text: String,
+    pub markdown: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Configuration {
+    pub level: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Text {
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SarifResult {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rule_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rule_index: Option<usize>,
+    pub level: String,
+    pub message: Text,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub locations: Vec<Location>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub related_locations: Vec<Location>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fixes: Vec<Fix>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Location {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<usize>,
+    pub physical_location: PhysicalLocation,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<Text>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PhysicalLocation {
+    pub artifact_location: ArtifactLocation,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub region: Option<Region>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ArtifactLocation {
+    pub uri: String,
+}
+
+/// The SARIF region whose lines and columns are 1-based, and the end column is exclusive.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Region {
+    pub start_line: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_column: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_line: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_column: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Fix {
+    pub description: Text,
+    pub artifact_changes: Vec<ArtifactChange>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArtifactChange {
+    pub artifact_location: ArtifactLocation,
+    pub replacements: Vec<Replacement>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Replacement {
+    pub deleted_region: Region,
+    pub inserted_content: Text,
+}
+
+/// Convert the diagnostics into a SARIF log.
+///
+/// # Examples
+///
+/// ```
+/// use kcl_error::*;
+///
+/// let mut handler = Handler::default();
+/// handler.add_syntex_error("expected expression", (Position::dummy_pos(), Position::dummy_pos()));
+/// let log = sarif::to_sarif(&handler.diagnostics);
+/// assert_eq!(log.runs[0].tool.driver.rules[0].id, "E1001");
+/// assert_eq!(log.runs[0].results[0].rule_id.as_deref(), Some("E1001"));
+/// ```
+pub fn to_sarif<'a>(diags: impl IntoIterator<Item = &'a Diagnostic>) -> SarifLog {
+    let mut rules: IndexMap<String, Rule> = IndexMap::default();
+    let mut results = vec![];
+    for diag in diags {
+        let (rule_id, rule_index) = match rule(diag) {
+            Some(rule) => {
+                let id = rule.id.clone();
+                let (index, _) = rules.insert_full(id.clone(), rule);
+                (Some(id), Some(index))
+            }
+            None => (None, None),
+        };
+        results.push(result(diag, rule_id, rule_index));
+    }
+    SarifLog {
+        schema: SARIF_SCHEMA.to_string(),
+        version: SARIF_VERSION.to_string(),
+        runs: vec![Run {
+            tool: Tool {
+                driver: Driver {
+                    name: TOOL_NAME.to_string(),
+                    information_uri: TOOL_INFORMATION_URI.to_string(),
+                    version: env!("CARGO_PKG_VERSION").to_string(),
+                    rules: rules.into_values().collect(),
+                },
+            },
+            results,
+        }],
+    }
+}
+
+/// Convert the diagnostics into a pretty SARIF JSON string.
+pub fn to_sarif_string<'a>(diags: impl IntoIterator<Item = &'a Diagnostic>) -> Result<String> {
+    Ok(serde_json::to_string_pretty(&to_sarif(diags))?)
+}
+
+/// Get the rule of the diagnostic code. Diagnostics without the code are
+/// regarded as evaluation errors and compiler warnings like the text emitter.
+fn rule(diag: &Diagnostic) -> Option<Rule> {
+    let (code, name, help, level) = match (&diag.code, diag.level) {
+        (Some(DiagnosticId::Error(kind)), _) => error_rule(kind),
+        (None, Level::Error) => error_rule(&ErrorKind::EvaluationError),
+        (Some(DiagnosticId::Warning(kind)), _) => warning_rule(kind),
+        (None, Level::Warning) => warning_rule(&WarningKind::CompilerWarning),
+        _ => return None,
+    };
+    Some(Rule {
+        id: code.clone(),
+        name: name.clone(),
+        short_description: Text { text: name },
+        help: Help {
+            text: help.trim().to_string(),
+            markdown: help.trim().to_string(),
+        },
+        default_configuration: Configuration {
+            level: level.to_string(),
+        },
+    })
+}
+
+fn error_rule(kind: &ErrorKind) -> (String, String, &'static str, &'static str) {
+    let code = kind.code();
+    match ERRORS.iter().find(|(c, _)| *c == code) {
+        Some((_, error)) => (
+            code,
+            error.kind.name(),
+            error.message.unwrap_or_default(),
+            "error",
+        ),
+        None => (code, kind.name(), "", "error"),
+    }
+}
+
+fn warning_rule(kind: &WarningKind) -> (String, String, &'static str, &'static str) {
+    let code = kind.code();
+    match WARNINGS.iter().find(|(c, _)| *c == code) {
+        Some((_, warning)) => (
+            code,
+            warning.kind.name(),
+            warning.message.unwrap_or_default(),
+            "warning",
+        ),
+        None => (code, kind.name(), "", "warning"),
+    }
+}
+
+/// Convert the diagnostic into a SARIF result. The first message is the result message
+/// and location, and the other messages are the related locations.
+fn result(diag: &Diagnostic, rule_id: Option<String>, rule_index: Option<usize>) -> SarifResult {
+    let level = match diag.level {
+        Level::Error => "error",
+        Level::Warning => "warning",
+        Level::Note | Level::Suggestions => "note",
+    };
+    let mut messages = diag.messages.iter();
+    let (message, locations) = match messages.next() {
+        Some(msg) => {
+            let mut text = msg.message.clone();
+            if let Some(note) = &msg.note {
+                text.push_str(&format!("\nnote: {note}"));
+            }
+            (text, location(&msg.range, None, None).into_iter().collect())
+        }
+        None => (String::new(), vec![]),
+    };
+    let related_locations = messages
+        .enumerate()
+        .filter_map(|(i, msg)| {
+            location(
+                &msg.range,
+                Some(i + 1),
+                Some(Text {
+                    text: msg.message.clone(),
+                }),
+            )
+        })
+        .collect();
+    let fixes = diag
+        .messages
+        .iter()
+        .flat_map(|msg| {
+            let region = region(&msg.range);
+            msg.suggested_replacement
+                .iter()
+                .flatten()
+                .filter_map(move |replacement| {
+                    Some(Fix {
+                        description: Text {
+                            text: if replacement.is_empty() {
+                                "Remove the code".to_string()
+                            } else {
+                                format!("Replace with '{replacement}'")
+                            },
+                        },
+                        artifact_changes: vec![ArtifactChange {
+                            artifact_location: ArtifactLocation {
+                                uri: artifact_uri(&msg.range.0.filename),
+                            },
+                            replacements: vec![Replacement {
+                                deleted_region: region.clone()?,
+                                inserted_content: Text {
+                                    text: replacement.clone(),
+                                },
+                            }],
+                        }],
+                    })
+                })
+        })
+        .collect();
+    SarifResult {
+        rule_id,
+        rule_index,
+        level: level.to_string(),
+        message: Text {
+            // The SARIF message text must not be empty.
+            text: if message.is_empty() {
+                diag.level.to_string()
+            } else {
+                message
+            },
+        },
+        locations,
+        related_locations,
+        fixes,
+    }
+}
+
+fn location(
+    range: &(Position, Position),
+    id: Option<usize>,
+    message: Option<Text>,
+) -> Option<Location> {
+    if range.0.filename.is_empty() {
+        return None;
+    }
+    Some(Location {
+        id,
+        physical_location: PhysicalLocation {
+            artifact_location: ArtifactLocation {
+                uri: artifact_uri(&range.0.filename),
+            },
+            region: region(range),
+        },
+        message,
+    })
+}
+
+/// Convert the diagnostic range into a SARIF region. The diagnostic lines are 1-based and
+/// the columns are 0-based, and the end position is omitted when it is the same as the start.
+fn region((start, end): &(Position, Position)) -> Option<Region> {
+    if start.filename.is_empty() || !start.is_valid() {
+        return None;
+    }
+    let mut region = Region {
+        start_line: start.line,
+        start_column: start.column.map(|column| column + 1),
+        end_line: None,
+        end_column: None,
+    };
+    if end.is_valid() && end.filename == start.filename && start.less(end) {
+        region.end_line = Some(end.line);
+        region.end_column = end.column.map(|column| column + 1);
+    }
+    Some(region)
+}
+
+/// Returns the artifact URI of the file. The files in the working directory use the relative
+/// paths, which are resolved against the repository root by the code scanning services.
+fn artifact_uri(filename: &str) -> String {
+    let path = Path::new(filename);
+    let relative = std::env::current_dir()
+        .ok()
+        .and_then(|cwd| path.strip_prefix(cwd).ok().map(|p| p.to_path_buf()));
+    match relative {
+        Some(relative) => relative.to_string_lossy().replace('\\', "/"),
+        None if path.is_absolute() => {
+            let path = filename.replace('\\', "/");
+            if path.starts_with('/') {
+                format!("file://{path}")
+            } else {
+                format!("file:///{path}")
+            }
+        }
+        None => filename
+            .trim_start_matches("./")
+            .trim_start_matches(".\\")
+            .replace('\\', "/"),
+    }
+}
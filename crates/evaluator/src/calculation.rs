@@ -1,7 +1,7 @@
 /* Calculation methods */
 
 use kcl_ast::ast;
-use kcl_runtime::{ConfigEntryOperationKind, DictValue, UnionOptions, Value, ValueRef};
+use kcl_runtime::{ConfigEntryOperationKind, DictValue, Provenance, UnionOptions, Value, ValueRef};
 
 use crate::Evaluator;
 use crate::ty::{resolve_schema, type_pack_and_check};
@@ -219,6 +219,30 @@ impl<'ctx> Evaluator<'ctx> {
         self.dict_merge_key_value_pair(dict, key, value, op, insert_index, true);
     }
 
+    /// Record the source position of `node` and the operation `op` as the provenance of
+    /// the dict entry, only when the provenance map is enabled in the plan options.
+    pub(crate) fn dict_add_provenance<T>(
+        &self,
+        dict: &mut ValueRef,
+        key: &str,
+        node: &ast::Node<T>,
+        op: &ast::ConfigEntryOperation,
+    ) {
+        if !self.provenance {
+            return;
+        }
+        let op = match op {
+            ast::ConfigEntryOperation::Union => ConfigEntryOperationKind::Union,
+            ast::ConfigEntryOperation::Override => ConfigEntryOperationKind::Override,
+            ast::ConfigEntryOperation::Insert => ConfigEntryOperationKind::Insert,
+        };
+        dict.dict_add_provenance(
+            key,
+            Provenance::new(&node.filename, node.line, node.column, &op),
+            &op,
+        );
+    }
+
     /// Insert a dict entry including key, value, op and insert_index into the dict,
     /// and the type of key is `&str`
     #[inline]
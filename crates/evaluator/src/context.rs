@@ -284,6 +284,9 @@ impl<'ctx> Evaluator<'ctx> {
             let backtrace_frame = BacktraceFrame::from_panic_info(&ctx.panic_info);
             ctx.backtrace.push(backtrace_frame);
             ctx.panic_info.kcl_func = frame.proxy.get_name();
+            let mut count = self.debug_frame_count.borrow_mut();
+            *count += 1;
+            self.debug_frame_ids.borrow_mut().push(*count);
         }
     }
 
@@ -292,6 +295,7 @@ impl<'ctx> Evaluator<'ctx> {
         if ctx.cfg.debug_mode
             && let Some(backtrace_frame) = ctx.backtrace.pop()
         {
+            self.debug_frame_ids.borrow_mut().pop();
             ctx.panic_info.kcl_func = backtrace_frame.func;
             ctx.panic_info.kcl_line = backtrace_frame.line;
             ctx.panic_info.kcl_file = backtrace_frame.file;
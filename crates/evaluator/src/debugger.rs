@@ -0,0 +1,226 @@
+//! Debugger support of the evaluator.
+//!
+//! The evaluator calls the [`DebugHook`] set on it before evaluating each statement,
+//! including the statements in schema bodies and lambda bodies. A debugger such as a
+//! Debug Adapter Protocol (DAP) server can block in the hook to pause the evaluation,
+//! and inspect the stack frames, scope variables and the schema config and value being
+//! evaluated through the methods on the [`Evaluator`]. [`DebugState`] implements the
+//! breakpoint and stepping rules shared by the debuggers.
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use kcl_ast::ast;
+use kcl_primitives::{DefaultHashBuilder, IndexMap};
+use kcl_runtime::ValueRef;
+use kcl_sema::builtin::BUILTIN_FUNCTION_NAMES;
+
+use crate::Evaluator;
+
+/// The hook called by the evaluator before evaluating each statement.
+pub trait DebugHook {
+    /// Called before the evaluator evaluates the statement `stmt`, and the evaluation
+    /// is paused until the method returns.
+    fn on_stmt(&self, evaluator: &Evaluator, stmt: &ast::Node<ast::Stmt>);
+
+    /// Called before each iteration of the comprehension and quantifier loops, which may
+    /// run for a long time within a single statement. Does nothing by default.
+    fn on_loop(&self, _evaluator: &Evaluator) {}
+}
+
+/// A stack frame of the evaluation.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DebugFrame {
+    /// The function, schema or rule name of the frame, empty for the package body.
+    pub name: String,
+    /// The file name of the current position in the frame.
+    pub filename: String,
+    /// The line of the current position in the frame.
+    pub line: u64,
+}
+
+/// The step mode of the debugger.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StepMode {
+    /// Run until a breakpoint is hit.
+    #[default]
+    Continue,
+    /// Stop at the next statement.
+    StepIn,
+    /// Stop at the next statement in the same or an outer frame.
+    StepOver,
+    /// Stop at the next statement in an outer frame.
+    StepOut,
+    /// Stop at the next statement as soon as possible.
+    Pause,
+}
+
+/// The reason why the debugger stops.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopReason {
+    Breakpoint,
+    Step,
+    Pause,
+}
+
+/// Breakpoints and stepping state of a debugger.
+#[derive(Debug, Default)]
+pub struct DebugState {
+    /// Breakpoint lines keyed by the normalized file path.
+    breakpoints: HashMap<String, HashSet<u64>>,
+    /// Normalized file path cache.
+    paths: HashMap<String, String>,
+    /// Current step mode.
+    mode: StepMode,
+    /// The stack depth where the step starts.
+    depth: usize,
+    /// The last stopped position with the frame id, which prevents stopping at the same
+    /// line of the same frame twice.
+    last: Option<(usize, String, u64)>,
+}
+
+impl DebugState {
+    /// Set the breakpoint lines of the file, which replaces the previous breakpoints of the file.
+    pub fn set_breakpoints(&mut self, filename: &str, lines: &[u64]) {
+        let filename = self.normalize(filename);
+        if lines.is_empty() {
+            self.breakpoints.remove(&filename);
+        } else {
+            self.breakpoints
+                .insert(filename, lines.iter().cloned().collect());
+        }
+    }
+
+    /// Resume the evaluation with the step mode at the stack depth.
+    pub fn resume(&mut self, mode: StepMode, depth: usize) {
+        self.mode = mode;
+        self.depth = depth;
+    }
+
+    /// Returns the stop reason if the evaluation should stop at the position in the frame
+    /// `frame_id` at the stack depth, see [`Evaluator::debug_frame_id`].
+    pub fn should_stop(
+        &mut self,
+        filename: &str,
+        line: u64,
+        depth: usize,
+        frame_id: usize,
+    ) -> Option<StopReason> {
+        let filename = self.normalize(filename);
+        let position = (frame_id, filename, line);
+        // Statements on the same line of a frame are regarded as one stop position, and
+        // the repeated calls of a function on the same line stop in each call.
+        if self.last.as_ref() == Some(&position) {
+            return None;
+        }
+        let reason = if self
+            .breakpoints
+            .get(&position.1)
+            .map(|lines| lines.contains(&line))
+            .unwrap_or_default()
+        {
+            Some(StopReason::Breakpoint)
+        } else {
+            match self.mode {
+                StepMode::Continue => None,
+                StepMode::StepIn => Some(StopReason::Step),
+                StepMode::StepOver if depth <= self.depth => Some(StopReason::Step),
+                StepMode::StepOut if depth < self.depth => Some(StopReason::Step),
+                StepMode::StepOver | StepMode::StepOut => None,
+                StepMode::Pause => Some(StopReason::Pause),
+            }
+        };
+        self.last = Some(position);
+        if reason.is_some() {
+            self.mode = StepMode::Continue;
+        }
+        reason
+    }
+
+    fn normalize(&mut self, filename: &str) -> String {
+        if let Some(path) = self.paths.get(filename) {
+            return path.clone();
+        }
+        let path = Path::new(filename)
+            .canonicalize()
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or_else(|_| filename.to_string());
+        self.paths.insert(filename.to_string(), path.clone());
+        path
+    }
+}
+
+impl<'ctx> Evaluator<'ctx> {
+    /// Get the stack frames of the evaluation, the innermost frame is the first.
+    /// Note that the caller frames are only recorded in the runtime debug mode.
+    pub fn debug_stack_frames(&self) -> Vec<DebugFrame> {
+        let ctx = self.runtime_ctx.borrow();
+        let mut frames = vec![DebugFrame {
+            name: ctx.panic_info.kcl_func.clone(),
+            filename: ctx.panic_info.kcl_file.clone(),
+            line: ctx.panic_info.kcl_line.max(0) as u64,
+        }];
+        frames.extend(ctx.backtrace.iter().rev().map(|frame| DebugFrame {
+            name: frame.func.clone(),
+            filename: frame.file.clone(),
+            line: frame.line.max(0) as u64,
+        }));
+        frames
+    }
+
+    /// Get the stack depth of the evaluation.
+    #[inline]
+    pub fn debug_stack_depth(&self) -> usize {
+        self.runtime_ctx.borrow().backtrace.len()
+    }
+
+    /// Get the id of the innermost stack frame, which is unique for each call of the
+    /// functions, schemas and rules, and 0 for the package body.
+    #[inline]
+    pub fn debug_frame_id(&self) -> usize {
+        self.debug_frame_ids
+            .borrow()
+            .last()
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Get the local variables in the current package scopes, the inner scope
+    /// variables shadow the outer ones.
+    pub fn debug_local_variables(&self) -> IndexMap<String, ValueRef> {
+        let mut variables = IndexMap::with_hasher(DefaultHashBuilder::default());
+        let pkg_scopes = self.pkg_scopes.borrow();
+        if let Some(scopes) = pkg_scopes.get(&self.current_pkgpath()) {
+            for scope in scopes.iter().skip(1) {
+                for (name, value) in &scope.variables {
+                    variables.insert(name.clone(), value.clone());
+                }
+            }
+        }
+        variables
+    }
+
+    /// Get the global variables in the current package without the builtin functions.
+    /// The lazy global variables which are not evaluated yet are undefined.
+    pub fn debug_global_variables(&self) -> IndexMap<String, ValueRef> {
+        let mut variables = IndexMap::with_hasher(DefaultHashBuilder::default());
+        let pkg_scopes = self.pkg_scopes.borrow();
+        if let Some(scope) = pkg_scopes
+            .get(&self.current_pkgpath())
+            .and_then(|scopes| scopes.first())
+        {
+            for (name, value) in &scope.variables {
+                if !BUILTIN_FUNCTION_NAMES.contains(&name.as_str()) {
+                    variables.insert(name.clone(), value.clone());
+                }
+            }
+        }
+        variables
+    }
+
+    /// Get the config and value of the schema or rule being evaluated.
+    pub fn debug_schema_config_and_value(&self) -> Option<(ValueRef, ValueRef)> {
+        self.get_schema_or_rule_config_info()
+            .map(|(value, config, _)| (config, value))
+    }
+}
@@ -2,6 +2,7 @@
 
 pub(crate) const RUNTIME_ERROR_MSG: &str = "Runtime error";
 pub(crate) const INTERNAL_ERROR_MSG: &str = "Internal error, please report a bug to us";
+pub(crate) const CANCELLED_ERROR_MSG: &str = "The evaluation is cancelled";
 pub(crate) const INVALID_OPERATOR_MSG: &str = "Invalid operator";
 pub(crate) const INVALID_JOINED_STR_MSG: &str = "Invalid AST JoinedString value";
 pub(crate) const INVALID_STR_INTERPOLATION_SPEC_MSG: &str =
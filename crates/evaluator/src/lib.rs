@@ -5,6 +5,7 @@ mod tests;
 
 mod calculation;
 mod context;
+pub mod debugger;
 mod error;
 mod func;
 #[macro_use]
@@ -22,6 +23,7 @@ mod value;
 
 extern crate kcl_error;
 
+use debugger::DebugHook;
 use func::FunctionEvalContextRef;
 use generational_arena::{Arena, Index};
 use kcl_primitives::IndexMap;
@@ -35,12 +37,16 @@ use std::collections::{HashMap, HashSet};
 use std::panic::RefUnwindSafe;
 use std::rc::Rc;
 use std::str;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
 use std::{cell::RefCell, panic::UnwindSafe};
 
 use crate::error as eval_error;
 use anyhow::Result;
 use kcl_ast::ast::{self, AstIndex};
-use kcl_runtime::{Context, ValueRef};
+use kcl_runtime::{
+    ConfigEntryOperationKind, Context, OutputFormat, Provenance, ProvenanceMap, ValueRef,
+};
 
 /// SCALAR_KEY denotes the temp scalar key for the global variable json plan process.
 const SCALAR_KEY: &str = "";
@@ -97,6 +103,18 @@ pub struct Evaluator<'ctx> {
     pub backtrack_meta: RefCell<Vec<BacktrackMeta>>,
     /// Current AST id for the evaluator walker.
     pub ast_id: RefCell<AstIndex>,
+    /// Debugger hook called before evaluating each statement.
+    pub debug_hook: Option<Rc<dyn DebugHook>>,
+    /// Cancellation flag checked before evaluating each statement.
+    pub cancel_flag: Option<Arc<AtomicBool>>,
+    /// The ids of the function, schema and rule frames pushed in the debug mode, the
+    /// innermost frame is the last.
+    pub debug_frame_ids: RefCell<Vec<usize>>,
+    /// The number of the frames pushed in the debug mode, which gives the next frame id.
+    pub debug_frame_count: RefCell<usize>,
+    /// Whether to record the source provenances of the config entries, which is cached
+    /// from the plan options to skip all the bookkeeping when it is disabled.
+    pub provenance: bool,
 }
 
 #[derive(Clone)]
@@ -142,6 +160,7 @@ impl<'ctx> Evaluator<'ctx> {
         program: &'ctx ast::Program,
         runtime_ctx: Rc<RefCell<Context>>,
     ) -> Evaluator<'ctx> {
+        let provenance = runtime_ctx.borrow().plan_opts.provenance;
         Evaluator {
             runtime_ctx,
             program,
@@ -164,9 +183,29 @@ impl<'ctx> Evaluator<'ctx> {
             backtrack_meta: RefCell::new(Default::default()),
             ast_id: RefCell::new(AstIndex::default()),
             ctx_stack: RefCell::new(Default::default()),
+            debug_hook: None,
+            cancel_flag: None,
+            debug_frame_ids: RefCell::new(Default::default()),
+            debug_frame_count: RefCell::new(0),
+            provenance,
         }
     }
 
+    /// Set the debugger hook called before evaluating each statement.
+    #[inline]
+    pub fn with_debug_hook(mut self, hook: Rc<dyn DebugHook>) -> Self {
+        self.debug_hook = Some(hook);
+        self
+    }
+
+    /// Set the cancellation flag, the evaluation stops with a runtime error at the next
+    /// statement or loop iteration once the flag is set, e.g., by another thread.
+    #[inline]
+    pub fn with_cancel_flag(mut self, cancel_flag: Arc<AtomicBool>) -> Self {
+        self.cancel_flag = Some(cancel_flag);
+        self
+    }
+
     /// Evaluate the program and return the JSON and YAML result.
     pub fn run(self: &Evaluator<'ctx>) -> Result<(String, String)> {
         let modules = self.program.get_modules_for_pkg(kcl_ast::MAIN_PKG);
@@ -189,6 +228,25 @@ impl<'ctx> Evaluator<'ctx> {
         }
     }
 
+    /// Invoke the global function named `name` in the main package with the arguments and
+    /// return the function result. The main package should be evaluated first using
+    /// [`Evaluator::run`] or [`Evaluator::run_as_function`].
+    pub fn invoke_global_function(
+        &self,
+        name: &str,
+        args: &ValueRef,
+        kwargs: &ValueRef,
+    ) -> EvalResult {
+        let func = self.get_variable_in_pkgpath(name, kcl_ast::MAIN_PKG);
+        match func.try_get_proxy() {
+            Some(proxy) => Ok(self.invoke_proxy_function(proxy, args, kwargs)),
+            None => Err(anyhow::anyhow!(
+                "'{}' is not a function in the main package",
+                name
+            )),
+        }
+    }
+
     /// Plan globals to a planed json and yaml string.
     pub(crate) fn plan_globals_to_string(&self) -> (String, String) {
         let current_pkgpath = self.current_pkgpath();
@@ -236,24 +294,113 @@ impl<'ctx> Evaluator<'ctx> {
 
     pub fn plan_value(&self, value: &ValueRef) -> (String, String) {
         let mut ctx = self.runtime_ctx.borrow_mut();
-        // If custom_manifests_output is set (e.g., from yaml_stream), use it directly for YAML
-        match ctx.buffer.custom_manifests_output.take() {
-            Some(output) => {
-                // Use the pre-formatted YAML stream directly
-                let yaml_string = output.clone();
-                // For JSON, parse the YAML stream and format as JSON stream
-                let value = ValueRef::from_yaml_stream(&mut ctx, &yaml_string).unwrap();
-                let (json_string, _) = value.plan(&ctx);
-                ctx.json_result = json_string.clone();
-                ctx.yaml_result = yaml_string.clone();
-                (json_string, yaml_string)
+        // If custom_manifests_output is set (e.g., from yaml_stream), use it directly for YAML,
+        // and parse the YAML stream for the JSON stream and the other output formats.
+        let custom_yaml = ctx.buffer.custom_manifests_output.take();
+        let is_custom_yaml = custom_yaml.is_some();
+        let value = match &custom_yaml {
+            Some(output) => ValueRef::from_yaml_stream(&mut ctx, output).unwrap(),
+            None => value.clone(),
+        };
+        // Plan the documents once for JSON, YAML and the output format besides them.
+        let mut formats = vec![OutputFormat::Json];
+        if custom_yaml.is_none() {
+            formats.push(OutputFormat::Yaml);
+        }
+        formats.extend(ctx.plan_opts.format);
+        let mut outputs = value
+            .plan_formats(&ctx, &formats)
+            .unwrap_or_else(|e| panic!("{e}"))
+            .into_iter();
+        let json_string = outputs.next().unwrap_or_default();
+        let yaml_string = match custom_yaml {
+            Some(output) => output,
+            None => outputs.next().unwrap_or_default(),
+        };
+        if let Some(output) = outputs.next() {
+            ctx.output_result = output;
+        }
+        // Plan the source provenance map of the output paths. The values parsed from the
+        // custom YAML stream have no source positions, so the map is empty.
+        if self.provenance {
+            let provenance = if is_custom_yaml {
+                ProvenanceMap::default()
+            } else {
+                value.provenance_map(&ctx, &self.top_level_provenances())
+            };
+            ctx.provenance_result = provenance.to_json_string();
+        }
+        ctx.json_result = json_string.clone();
+        ctx.yaml_result = yaml_string.clone();
+        (json_string, yaml_string)
+    }
+
+    /// Returns the provenances of the top-level variables in the main package, which are
+    /// the positions of their assignment and unification statements.
+    fn top_level_provenances(&self) -> IndexMap<String, Vec<Provenance>> {
+        let mut provenances = IndexMap::default();
+        for module in self.program.get_modules_for_pkg(kcl_ast::MAIN_PKG) {
+            let module = module.read().expect("Failed to acquire module lock");
+            collect_top_level_provenances(&module.body, &mut provenances);
+        }
+        provenances
+    }
+}
+
+fn collect_top_level_provenances(
+    stmts: &[ast::NodeRef<ast::Stmt>],
+    provenances: &mut IndexMap<String, Vec<Provenance>>,
+) {
+    for stmt in stmts {
+        match &stmt.node {
+            ast::Stmt::Assign(assign_stmt) => {
+                for target in &assign_stmt.targets {
+                    if target.node.paths.is_empty() {
+                        let op = ConfigEntryOperationKind::Override;
+                        provenances.insert(
+                            target.node.get_name().to_string(),
+                            vec![Provenance::new(
+                                &target.filename,
+                                target.line,
+                                target.column,
+                                &op,
+                            )],
+                        );
+                    }
+                }
+            }
+            ast::Stmt::AugAssign(aug_assign_stmt) => {
+                let target = &aug_assign_stmt.target;
+                if target.node.paths.is_empty() {
+                    provenances
+                        .entry(target.node.get_name().to_string())
+                        .or_default()
+                        .push(Provenance {
+                            filename: target.filename.clone(),
+                            line: target.line,
+                            column: target.column,
+                            op: aug_assign_stmt.op.symbol().to_string(),
+                        });
+                }
+            }
+            ast::Stmt::Unification(unification_stmt) => {
+                let target = &unification_stmt.target;
+                let op = ConfigEntryOperationKind::Union;
+                provenances
+                    .entry(target.node.get_name())
+                    .or_default()
+                    .push(Provenance::new(
+                        &target.filename,
+                        target.line,
+                        target.column,
+                        &op,
+                    ));
             }
-            None => {
-                let (json_string, yaml_string) = value.plan(&ctx);
-                ctx.json_result = json_string.clone();
-                ctx.yaml_result = yaml_string.clone();
-                (json_string, yaml_string)
+            ast::Stmt::If(if_stmt) => {
+                collect_top_level_provenances(&if_stmt.body, provenances);
+                collect_top_level_provenances(&if_stmt.orelse, provenances);
             }
+            _ => {}
         }
     }
 }
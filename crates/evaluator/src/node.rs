@@ -2,6 +2,7 @@
 
 use std::cell::RefCell;
 use std::rc::Rc;
+use std::sync::atomic::Ordering;
 use std::sync::{Arc, RwLock};
 
 use anyhow::Ok;
@@ -15,7 +16,7 @@ use kcl_runtime::{
 use kcl_sema::{builtin, pkgpath_without_prefix, plugin};
 use scopeguard::defer;
 
-use crate::error::INTERNAL_ERROR_MSG;
+use crate::error::{CANCELLED_ERROR_MSG, INTERNAL_ERROR_MSG};
 use crate::func::{FunctionCaller, FunctionEvalContext, FunctionEvalThis, func_body};
 use crate::lazy::Setter;
 use crate::proxy::Proxy;
@@ -57,6 +58,10 @@ impl<'ctx> TypedResultWalker<'ctx> for Evaluator<'ctx> {
         backtrack_break_here!(self, stmt);
         self.update_ctx_panic_info(stmt);
         self.update_ast_id(stmt);
+        if let Some(hook) = &self.debug_hook {
+            hook.on_stmt(self, stmt);
+        }
+        self.check_cancelled();
         let value = match &stmt.node {
             ast::Stmt::TypeAlias(type_alias) => self.walk_type_alias_stmt(type_alias),
             ast::Stmt::Expr(expr_stmt) => self.walk_expr_stmt(expr_stmt),
@@ -417,6 +422,7 @@ impl<'ctx> TypedResultWalker<'ctx> for Evaluator<'ctx> {
         // Start block
         while let Some((next_value, key, value)) = iter_value.next_with_key_value(&iter_host_value)
         {
+            self.on_loop_iteration();
             // Next value block
             let variables = &quant_expr.variables;
             for v in variables {
@@ -541,6 +547,12 @@ impl<'ctx> TypedResultWalker<'ctx> for Evaluator<'ctx> {
                             None,
                         ),
                     }
+                    self.dict_add_provenance(
+                        &mut schema_value,
+                        name,
+                        &schema_attr.name,
+                        &ast::ConfigEntryOperation::Override,
+                    );
                 }
             }
             self.value_union(&mut schema_value, &entry);
@@ -584,6 +596,12 @@ impl<'ctx> TypedResultWalker<'ctx> for Evaluator<'ctx> {
                         None,
                     ),
                 }
+                self.dict_add_provenance(
+                    &mut schema_value,
+                    name,
+                    &schema_attr.name,
+                    &ast::ConfigEntryOperation::Override,
+                );
             }
         }
         // Set config cache for the schema eval context.
@@ -1615,6 +1633,24 @@ impl<'ctx> Evaluator<'ctx> {
         }
     }
 
+    /// Call the debugger hook and check the cancellation before each iteration of the
+    /// comprehension and quantifier loops, which may run for a long time in a statement.
+    fn on_loop_iteration(&self) {
+        if let Some(hook) = &self.debug_hook {
+            hook.on_loop(self);
+        }
+        self.check_cancelled();
+    }
+
+    /// Stop the evaluation with a runtime error if the cancellation flag is set.
+    fn check_cancelled(&self) {
+        if let Some(cancel_flag) = &self.cancel_flag
+            && cancel_flag.load(Ordering::Relaxed)
+        {
+            panic!("{}", CANCELLED_ERROR_MSG);
+        }
+    }
+
     #[allow(clippy::too_many_arguments)]
     pub fn walk_generator(
         &self,
@@ -1636,6 +1672,7 @@ impl<'ctx> Evaluator<'ctx> {
 
         while let Some((next_value, key, value)) = iter_value.next_with_key_value(&iter_host_value)
         {
+            self.on_loop_iteration();
             for v in targets {
                 self.add_loop_var(&v.node.names[0].node)
             }
@@ -1764,6 +1801,14 @@ impl<'ctx> Evaluator<'ctx> {
                     &item.node.operation,
                     insert_index,
                 );
+                if self.provenance {
+                    self.dict_add_provenance(
+                        &mut config_value,
+                        &key.as_str(),
+                        key_node,
+                        &item.node.operation,
+                    );
+                }
                 if let Some(name) = &optional_name {
                     let value = self.dict_get_value(&config_value, name);
                     self.add_or_update_local_variable_within_scope(name, value);
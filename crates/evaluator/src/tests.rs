@@ -871,3 +871,144 @@ sum = testing.add(1, 1)
     let evaluator = Evaluator::new_with_runtime_ctx(&p.program, context_with_plugin());
     insta::assert_snapshot!(format!("{}", evaluator.run().unwrap().1));
 }
+
+#[test]
+fn test_invoke_global_function() {
+    let src = r#"
+base = 1
+add = lambda x: int {
+    x + base
+}
+"#;
+    let p = load_packages(&LoadPackageOptions {
+        paths: vec!["test.k".to_string()],
+        load_opts: Some(LoadProgramOptions {
+            k_code_list: vec![src.to_string()],
+            ..Default::default()
+        }),
+        load_builtin: false,
+        ..Default::default()
+    })
+    .unwrap();
+    let evaluator = Evaluator::new(&p.program);
+    evaluator.run_as_function();
+    let args = ValueRef::list_int(&[2]);
+    let result = evaluator
+        .invoke_global_function("add", &args, &ValueRef::dict(None))
+        .unwrap();
+    assert_eq!(result.as_int(), 3);
+    assert!(
+        evaluator
+            .invoke_global_function("base", &ValueRef::list(None), &ValueRef::dict(None))
+            .is_err()
+    );
+}
+
+#[test]
+fn test_debug_hook() {
+    use crate::debugger::{DebugHook, DebugState, StepMode, StopReason};
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    struct Stop {
+        line: u64,
+        depth: usize,
+        locals: Vec<String>,
+        in_schema: bool,
+    }
+
+    #[derive(Default)]
+    struct RecordHook {
+        stops: RefCell<Vec<Stop>>,
+    }
+
+    impl DebugHook for RecordHook {
+        fn on_stmt(&self, evaluator: &Evaluator, stmt: &kcl_ast::ast::Node<kcl_ast::ast::Stmt>) {
+            let frames = evaluator.debug_stack_frames();
+            assert_eq!(frames[0].line, stmt.line);
+            self.stops.borrow_mut().push(Stop {
+                line: stmt.line,
+                depth: evaluator.debug_stack_depth(),
+                locals: evaluator.debug_local_variables().keys().cloned().collect(),
+                in_schema: evaluator.debug_schema_config_and_value().is_some(),
+            });
+        }
+    }
+
+    let src = r#"schema Person:
+    name: str
+    age: int = 1
+
+add = lambda x {
+    y = x + 1
+    y
+}
+a = add(1)
+p = Person {name = "Alice"}
+"#;
+    let p = load_packages(&LoadPackageOptions {
+        paths: vec!["test.k".to_string()],
+        load_opts: Some(LoadProgramOptions {
+            k_code_list: vec![src.to_string()],
+            ..Default::default()
+        }),
+        load_builtin: false,
+        ..Default::default()
+    })
+    .unwrap();
+    let mut ctx = Context::new();
+    ctx.cfg.debug_mode = true;
+    let hook = Rc::new(RecordHook::default());
+    let evaluator = Evaluator::new_with_runtime_ctx(&p.program, Rc::new(RefCell::new(ctx)))
+        .with_debug_hook(hook.clone());
+    evaluator.run().unwrap();
+    let stops = hook.stops.borrow();
+    // Lambda body
+    let stop = stops.iter().find(|s| s.line == 6).unwrap();
+    assert!(stop.depth > 0);
+    assert!(stop.locals.contains(&"x".to_string()));
+    // Schema body
+    let stop = stops.iter().find(|s| s.line == 3).unwrap();
+    assert!(stop.depth > 0);
+    assert!(stop.in_schema);
+    // Package body
+    let stop = stops.iter().find(|s| s.line == 9).unwrap();
+    assert_eq!(stop.depth, 0);
+    assert!(!stop.in_schema);
+
+    // Breakpoints and stepping.
+    let mut state = DebugState::default();
+    state.set_breakpoints("test.k", &[9]);
+    assert_eq!(state.should_stop("test.k", 5, 0, 0), None);
+    assert_eq!(
+        state.should_stop("test.k", 9, 0, 0),
+        Some(StopReason::Breakpoint)
+    );
+    state.resume(StepMode::StepOver, 0);
+    assert_eq!(state.should_stop("test.k", 6, 1, 1), None);
+    assert_eq!(
+        state.should_stop("test.k", 10, 0, 0),
+        Some(StopReason::Step)
+    );
+    state.resume(StepMode::StepIn, 0);
+    assert_eq!(state.should_stop("test.k", 3, 1, 2), Some(StopReason::Step));
+    state.resume(StepMode::StepOut, 1);
+    assert_eq!(state.should_stop("test.k", 2, 1, 2), None);
+    assert_eq!(
+        state.should_stop("test.k", 10, 0, 0),
+        Some(StopReason::Step)
+    );
+    state.resume(StepMode::Continue, 0);
+    assert_eq!(state.should_stop("test.k", 11, 0, 0), None);
+    // The repeated calls of a function stop at the same line in each call frame.
+    state.set_breakpoints("test.k", &[6]);
+    assert_eq!(
+        state.should_stop("test.k", 6, 1, 3),
+        Some(StopReason::Breakpoint)
+    );
+    assert_eq!(state.should_stop("test.k", 6, 1, 3), None);
+    assert_eq!(
+        state.should_stop("test.k", 6, 1, 4),
+        Some(StopReason::Breakpoint)
+    );
+}
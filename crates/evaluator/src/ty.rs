@@ -224,7 +224,10 @@ pub fn convert_collection_value(s: &Evaluator, value: &ValueRef, tpe: &str) -> V
                 .get(k)
                 .unwrap_or(&ConfigEntryOperationKind::Union);
             let index = dict_ref.insert_indexs.get(k);
-            expected_dict.dict_update_entry(k, &expected_value, op, index)
+            expected_dict.dict_update_entry(k, &expected_value, op, index);
+            expected_dict
+                .as_dict_mut_ref()
+                .copy_provenances(&dict_ref, k);
         }
         expected_dict
     } else if is_list_type(tpe) {
@@ -196,6 +196,10 @@ fn do_union(
                 }
             }
         }
+        // Update provenances
+        if s.provenance {
+            obj.merge_provenances_from(delta);
+        }
     };
 
     // Whether to union schema vars and resolve it and do the check of schema.
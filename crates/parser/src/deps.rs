@@ -0,0 +1,168 @@
+//! Lists the files which a compile unit depends on, e.g., to compute the cache keys and the
+//! watch paths of the build systems.
+//!
+//! The KCL files are collected from the file graph of the imports built by the loader, and
+//! the other files are the string literal paths read by `file.read`, e.g.,
+//! `yaml.decode(file.read("config.yaml"))`. The paths computed at runtime can't be listed.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use kcl_ast::walker::MutSelfWalker;
+use kcl_ast::{MAIN_PKG, ast};
+
+use crate::{LoadProgramOptions, Loader, ParseSessionRef};
+
+/// The system module of the file functions.
+const FILE_MODULE: &str = "file";
+/// The file functions whose first argument is a path to read.
+const FILE_READ_FUNCTIONS: [&str; 1] = ["read"];
+
+/// The options of [`list_dep_files`].
+#[derive(Debug, Clone, Default)]
+pub struct ListDepFilesOptions {
+    /// Whether to include the files of the external packages, e.g., the `kcl.mod` dependencies.
+    pub include_external_pkgs: bool,
+    /// Whether to exclude the files of the main package.
+    pub exclude_main_pkg: bool,
+}
+
+/// List the files which the compile unit of `paths` depends on transitively. The KCL files
+/// are in the topological order of the file graph, followed by the files read by `file.read`
+/// in them. The relative paths read by `file.read` are joined with the `work_dir` of the
+/// options where the program runs, or the root of the program if it is empty.
+///
+/// The packages which are not found are not listed, and the parse errors are ignored.
+pub fn list_dep_files(
+    sess: ParseSessionRef,
+    paths: &[&str],
+    opts: Option<LoadProgramOptions>,
+    dep_opts: &ListDepFilesOptions,
+) -> Result<Vec<PathBuf>> {
+    let mut loader = Loader::new(sess, paths, opts, None);
+    let result = loader.load_main()?;
+    // The packages out of the package roots of the main package are external packages.
+    let main_roots: HashSet<&str> = loader
+        .pkgmap
+        .iter()
+        .filter(|(file, _)| file.pkg_path == MAIN_PKG)
+        .map(|(_, pkg)| pkg.pkg_root.as_str())
+        .collect();
+    let included: HashSet<&PathBuf> = loader
+        .pkgmap
+        .iter()
+        .filter(|(file, pkg)| {
+            if file.pkg_path == MAIN_PKG {
+                !dep_opts.exclude_main_pkg
+            } else {
+                dep_opts.include_external_pkgs || main_roots.contains(pkg.pkg_root.as_str())
+            }
+        })
+        .map(|(file, _)| file.get_path())
+        .collect();
+
+    let work_dir = if loader.opts.work_dir.is_empty() {
+        &result.program.root
+    } else {
+        &loader.opts.work_dir
+    };
+    let mut seen = HashSet::new();
+    let mut files = vec![];
+    let mut read_files = vec![];
+    for path in &result.paths {
+        if !included.contains(path) || !seen.insert(path.clone()) {
+            continue;
+        }
+        files.push(path.clone());
+        if let Some(filename) = path.to_str()
+            && let Ok(Some(module)) = result.program.get_module(filename)
+        {
+            let mut collector = FileReadCollector::new(&module);
+            collector.walk_module(&module);
+            for read_path in collector.paths {
+                let read_path = resolve_read_path(work_dir, &read_path);
+                if seen.insert(read_path.clone()) {
+                    read_files.push(read_path);
+                }
+            }
+        }
+    }
+    files.extend(read_files);
+    Ok(files)
+}
+
+/// Resolves a path read at runtime, which is relative to the working directory.
+fn resolve_read_path(work_dir: &str, path: &str) -> PathBuf {
+    let path = Path::new(path);
+    if path.is_absolute() || work_dir.is_empty() {
+        path.to_path_buf()
+    } else {
+        Path::new(work_dir).join(path)
+    }
+}
+
+/// Collects the string literals passed to the file functions as the path argument.
+struct FileReadCollector {
+    /// The import names in the module, e.g., `f` in `import file as f`.
+    imports: HashMap<String, String>,
+    paths: Vec<String>,
+}
+
+impl FileReadCollector {
+    fn new(module: &ast::Module) -> Self {
+        let imports = module
+            .body
+            .iter()
+            .filter_map(|stmt| match &stmt.node {
+                ast::Stmt::Import(import_stmt) => {
+                    Some((import_stmt.name.clone(), import_stmt.path.node.clone()))
+                }
+                _ => None,
+            })
+            .collect();
+        Self {
+            imports,
+            paths: vec![],
+        }
+    }
+
+    fn is_file_read_function(&self, func: &ast::Expr) -> bool {
+        let ast::Expr::Identifier(identifier) = func else {
+            return false;
+        };
+        match identifier.names.as_slice() {
+            [module, name] => {
+                self.imports.get(&module.node).map(|path| path.as_str()) == Some(FILE_MODULE)
+                    && FILE_READ_FUNCTIONS.contains(&name.node.as_str())
+            }
+            _ => false,
+        }
+    }
+}
+
+impl MutSelfWalker for FileReadCollector {
+    fn walk_call_expr(&mut self, call_expr: &ast::CallExpr) {
+        if self.is_file_read_function(&call_expr.func.node) {
+            let path = call_expr.args.first().map(|arg| arg.as_ref()).or_else(|| {
+                call_expr
+                    .keywords
+                    .iter()
+                    .find(|keyword| keyword.node.arg.node.get_name() == "filepath")
+                    .and_then(|keyword| keyword.node.value.as_deref())
+            });
+            if let Some(path) = path
+                && let ast::Expr::StringLit(string_lit) = &path.node
+            {
+                self.paths.push(string_lit.value.clone());
+            }
+        }
+        self.walk_expr(&call_expr.func.node);
+        for arg in &call_expr.args {
+            self.walk_expr(&arg.node);
+        }
+        for keyword in &call_expr.keywords {
+            self.walk_keyword(&keyword.node);
+        }
+    }
+}
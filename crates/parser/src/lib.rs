@@ -2,6 +2,7 @@
 #![allow(clippy::type_complexity)]
 //! Copyright The KCL Authors. All rights reserved.
 
+pub mod deps;
 pub mod entry;
 pub mod file_graph;
 mod lexer;
@@ -872,3 +872,57 @@ fn parse_all_file_under_path() {
 
     assert_eq!(res.paths.len(), 1);
 }
+
+#[test]
+fn test_list_dep_files() {
+    let testpath = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("testdata")
+        .join("list_dep_files")
+        .canonicalize()
+        .unwrap();
+    let assign = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("testdata")
+        .join("test_vendor")
+        .join("assign")
+        .canonicalize()
+        .unwrap();
+    let main = testpath.join("main.k");
+    let mut opts = LoadProgramOptions {
+        work_dir: testpath.display().to_string(),
+        ..Default::default()
+    };
+    opts.package_maps
+        .insert("assign".to_string(), assign.display().to_string());
+    let list = |dep_opts: deps::ListDepFilesOptions| {
+        let mut files = deps::list_dep_files(
+            ParseSessionRef::default(),
+            &[main.to_str().unwrap()],
+            Some(opts.clone()),
+            &dep_opts,
+        )
+        .unwrap();
+        files.sort();
+        files
+    };
+
+    assert_eq!(
+        list(Default::default()),
+        vec![
+            testpath.join("data.yaml"),
+            testpath.join("main.k"),
+            testpath.join("sub").join("sub.json"),
+            testpath.join("sub").join("sub.k"),
+        ]
+    );
+    assert_eq!(
+        list(deps::ListDepFilesOptions {
+            include_external_pkgs: true,
+            exclude_main_pkg: true,
+        }),
+        vec![
+            testpath.join("sub").join("sub.json"),
+            testpath.join("sub").join("sub.k"),
+            assign.join("assign.k"),
+        ]
+    );
+}
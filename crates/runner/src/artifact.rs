@@ -0,0 +1,167 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+
+use anyhow::{Context, Result};
+use kcl_ast::ast::{Program, SerializeProgram};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// The file extension of the KCL artifact.
+pub const ARTIFACT_FILE_EXT: &str = "kart";
+/// The default artifact output directory name under the system temp directory.
+const DEFAULT_ARTIFACT_DIR: &str = "kcl_artifacts";
+
+/// In-process artifact cache keyed by the canonical artifact path, which avoids reading,
+/// deserializing and verifying the same artifact for each execution. The cached artifact
+/// is used only when the file is not modified since it was loaded.
+static ARTIFACT_CACHE: Lazy<Mutex<ArtifactCache>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// The loaded artifacts and the stamps of their files keyed by the canonical artifact path.
+type ArtifactCache = HashMap<PathBuf, (FileStamp, Arc<Artifact>)>;
+
+/// The modification time and size of the artifact file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct FileStamp {
+    modified: Option<SystemTime>,
+    len: u64,
+}
+
+impl FileStamp {
+    fn new(path: &Path) -> std::io::Result<Self> {
+        let metadata = fs::metadata(path)?;
+        Ok(Self {
+            modified: metadata.modified().ok(),
+            len: metadata.len(),
+        })
+    }
+}
+
+/// Artifact denotes a KCL program that has been parsed and resolved once and
+/// can be executed many times with different arguments.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Artifact {
+    /// Content hash of the parsed and the resolved program modules.
+    pub checksum: String,
+    /// The parsed program AST before resolving, which is used to apply
+    /// the overrides at the execution time.
+    pub parsed: SerializeProgram,
+    /// The resolved program AST which can be directly evaluated.
+    pub resolved: SerializeProgram,
+}
+
+impl Artifact {
+    /// New an artifact from the parsed and resolved program.
+    pub fn new(parsed: &Program, resolved: &Program) -> Result<Self> {
+        let parsed: SerializeProgram = parsed.clone().into();
+        let resolved: SerializeProgram = resolved.clone().into();
+        let checksum = artifact_checksum(&parsed, &resolved)?;
+        Ok(Self {
+            checksum,
+            parsed,
+            resolved,
+        })
+    }
+
+    /// Get the deep copied parsed program.
+    #[inline]
+    pub fn parsed_program(&self) -> Program {
+        self.parsed.clone().into()
+    }
+
+    /// Get the deep copied resolved program.
+    #[inline]
+    pub fn resolved_program(&self) -> Program {
+        self.resolved.clone().into()
+    }
+
+    /// Save the artifact into the output directory and return the artifact path.
+    /// When the output is empty, the system temp directory is used.
+    pub fn save<P: AsRef<Path>>(&self, output: Option<P>) -> Result<PathBuf> {
+        let dir = match output {
+            Some(output) if !output.as_ref().as_os_str().is_empty() => {
+                output.as_ref().to_path_buf()
+            }
+            _ => std::env::temp_dir().join(DEFAULT_ARTIFACT_DIR),
+        };
+        fs::create_dir_all(&dir)?;
+        let path = dir.join(format!("{}.{}", self.checksum, ARTIFACT_FILE_EXT));
+        // The artifact is content addressed, so the same file needn't be written twice.
+        // The existing file is not cached here, it is verified when it is loaded.
+        if !path.exists() {
+            fs::write(&path, serde_json::to_vec(self)?)?;
+            let stamp = FileStamp::new(&path)?;
+            ARTIFACT_CACHE
+                .lock()
+                .unwrap()
+                .insert(path.canonicalize()?, (stamp, Arc::new(self.clone())));
+        }
+        Ok(path)
+    }
+
+    /// Load the artifact from the path and verify its checksum. The artifact is cached in
+    /// the current process using its canonical path, and it is loaded and verified again
+    /// when the modification time or the size of the file changes.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Arc<Self>> {
+        let path = path.as_ref();
+        let (key, stamp) = path
+            .canonicalize()
+            .and_then(|key| FileStamp::new(&key).map(|stamp| (key, stamp)))
+            .with_context(|| format!("Cannot find the kcl artifact {}", path.display()))?;
+        if let Some((cached_stamp, artifact)) = ARTIFACT_CACHE.lock().unwrap().get(&key)
+            && cached_stamp == &stamp
+        {
+            return Ok(artifact.clone());
+        }
+        let content = fs::read(path)
+            .with_context(|| format!("Cannot find the kcl artifact {}", path.display()))?;
+        let artifact: Artifact = serde_json::from_slice(&content)
+            .with_context(|| format!("Invalid kcl artifact {}", path.display()))?;
+        let checksum = artifact_checksum(&artifact.parsed, &artifact.resolved)?;
+        if checksum != artifact.checksum {
+            anyhow::bail!(
+                "kcl artifact {} checksum mismatch, expected {}, got {}",
+                path.display(),
+                artifact.checksum,
+                checksum
+            );
+        }
+        let artifact = Arc::new(artifact);
+        ARTIFACT_CACHE
+            .lock()
+            .unwrap()
+            .insert(key, (stamp, artifact.clone()));
+        Ok(artifact)
+    }
+}
+
+/// Calculate the content hash of the parsed and the resolved program, so the artifact
+/// is rejected when any of them is modified.
+fn artifact_checksum(parsed: &SerializeProgram, resolved: &SerializeProgram) -> Result<String> {
+    let mut hasher = Sha256::new();
+    update_program_hash(&mut hasher, parsed)?;
+    update_program_hash(&mut hasher, resolved)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Hash the program into the hasher. Packages and modules are sorted by their names to
+/// make the hash stable.
+fn update_program_hash(hasher: &mut Sha256, program: &SerializeProgram) -> Result<()> {
+    hasher.update(program.root.as_bytes());
+    let mut pkgs: Vec<_> = program.pkgs.iter().collect();
+    pkgs.sort_by(|a, b| a.0.cmp(b.0));
+    for (name, modules) in pkgs {
+        hasher.update(name.as_bytes());
+        let mut modules: Vec<_> = modules.iter().collect();
+        modules.sort_by(|a, b| a.filename.cmp(&b.filename));
+        for module in modules {
+            hasher.update(serde_json::to_vec(module)?);
+        }
+    }
+    Ok(())
+}
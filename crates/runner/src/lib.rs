@@ -1,21 +1,25 @@
 use std::{
     collections::HashMap,
+    path::{Path, PathBuf},
     sync::{Arc, RwLock},
 };
 
 use anyhow::{Result, bail};
+pub use artifact::Artifact;
 use kcl_ast::{
     MAIN_PKG,
-    ast::{Module, Program},
+    ast::{Module, Program, SerializeProgram},
 };
 use kcl_parser::{KCLModuleCache, ParseSessionRef, load_program};
 use kcl_query::apply_overrides;
+use kcl_runtime::PanicInfo;
 use kcl_sema::resolver::{
     Options, resolve_program, resolve_program_with_opts, scope::ProgramScope,
 };
 pub use runner::{ExecProgramArgs, ExecProgramResult, MapErrorResult};
 use runner::{FastRunner, RunnerOptions};
 
+pub mod artifact;
 pub mod runner;
 
 #[cfg(test)]
@@ -91,6 +95,102 @@ pub fn exec_program(sess: ParseSessionRef, args: &ExecProgramArgs) -> Result<Exe
     execute(sess, program, args)
 }
 
+/// Parse and resolve the KCL program once, then save it as an [`Artifact`] into
+/// the `output` directory and return the artifact path. The artifact file is named
+/// with the content hash of the program, so building an unchanged program again
+/// returns the same path. When `output` is `None`, the system temp directory is used.
+///
+/// The overrides in `args` are applied before resolving and become a part of the
+/// artifact, other execution arguments such as `-D` options are ignored at the
+/// build time and can be set by [`exec_artifact`].
+///
+/// # Examples
+///
+/// ```
+/// use kcl_runner::{build_program, exec_artifact, ExecProgramArgs};
+/// use kcl_parser::ParseSession;
+/// use std::sync::Arc;
+///
+/// let mut args = ExecProgramArgs::default();
+/// args.k_filename_list = vec!["./src/test_datas/init_check_order_0/main.k".to_string()];
+/// let path = build_program::<&str>(Arc::new(ParseSession::default()), &args, None).unwrap();
+/// let result = exec_artifact(&path, &args).unwrap();
+/// assert!(result.err_message.is_empty());
+/// ```
+pub fn build_program<P: AsRef<Path>>(
+    sess: ParseSessionRef,
+    args: &ExecProgramArgs,
+    output: Option<P>,
+) -> Result<PathBuf> {
+    let mut program = load_program_with_overrides(sess.clone(), args)?;
+    // Keep a deep copy of the parsed program to apply overrides at the execution time.
+    let parsed: Program = SerializeProgram::from(program.clone()).into();
+    // Resolve ast
+    let scope = resolve_program(&mut program);
+    // Emit parse and resolve errors if exists.
+    emit_compile_diag_to_string(sess, &scope, false)?;
+    Artifact::new(&parsed, &program)?.save(output)
+}
+
+/// Parse the files in `args`, apply the overrides and resolve the program, then return
+/// the resolved program which can be evaluated many times without compiling it again.
+/// Parse and resolve errors are returned as an error.
+pub fn compile_program(sess: ParseSessionRef, args: &ExecProgramArgs) -> Result<Program> {
+    let mut program = load_program_with_overrides(sess.clone(), args)?;
+    // Resolve ast
+    let scope = resolve_program(&mut program);
+    // Emit parse and resolve errors if exists.
+    emit_compile_diag_to_string(sess, &scope, false)?;
+    Ok(program)
+}
+
+/// Load the program from the files in `args` and apply the overrides on it.
+fn load_program_with_overrides(sess: ParseSessionRef, args: &ExecProgramArgs) -> Result<Program> {
+    let opts = args.get_load_program_options();
+    let kcl_paths_str = args
+        .k_filename_list
+        .iter()
+        .map(|s| s.as_str())
+        .collect::<Vec<&str>>();
+    let mut program = load_program(
+        sess,
+        kcl_paths_str.as_slice(),
+        Some(opts),
+        Some(KCLModuleCache::default()),
+    )?
+    .program;
+    apply_overrides(
+        &mut program,
+        &args.overrides,
+        &[],
+        args.print_override_ast || args.debug > 0,
+    )?;
+    Ok(program)
+}
+
+/// Execute the [`Artifact`] built by [`build_program`] with the arguments, which
+/// skips parsing and resolving the program. When the arguments contain overrides,
+/// they are applied on a copy of the parsed program which is then resolved again.
+///
+/// **Note that it is not thread safe.**
+pub fn exec_artifact<P: AsRef<Path>>(path: P, args: &ExecProgramArgs) -> Result<ExecProgramResult> {
+    let artifact = Artifact::load(path)?;
+    let program = if args.overrides.is_empty() {
+        artifact.resolved_program()
+    } else {
+        let sess = ParseSessionRef::default();
+        let mut program = artifact.parsed_program();
+        apply_overrides(&mut program, &args.overrides, &[], false)?;
+        let scope = resolve_program(&mut program);
+        emit_compile_diag_to_string(sess, &scope, false)?;
+        program
+    };
+    FastRunner::new(Some(RunnerOptions {
+        plugin_agent_ptr: args.plugin_agent,
+    }))
+    .run(&program, args)
+}
+
 /// After the kcl program passed through kcl-parser in the compiler frontend,
 /// KCL needs to resolve ast, generate corresponding LLVM IR, dynamic link library or
 /// executable file for kcl program in the compiler backend.
@@ -167,6 +267,26 @@ pub fn execute(
     .run(&program, args)
 }
 
+/// Resolve and execute the program like [`execute`], but record all the schema check failures
+/// instead of stopping at the first one. The evaluation stops at the first error which is not a
+/// check failure, and the error follows the recorded check failures in the returned list.
+///
+/// **Note that it is not thread safe.**
+pub fn execute_and_collect_check_failures(
+    sess: ParseSessionRef,
+    mut program: Program,
+    args: &ExecProgramArgs,
+) -> Result<(ExecProgramResult, Vec<PanicInfo>)> {
+    // Resolve ast
+    let scope = resolve_program(&mut program);
+    // Emit parse and resolve errors if exists.
+    emit_compile_diag_to_string(sess, &scope, false)?;
+    FastRunner::new(Some(RunnerOptions {
+        plugin_agent_ptr: args.plugin_agent,
+    }))
+    .run_and_collect_check_failures(&program, args)
+}
+
 /// `execute_module` can directly execute the ast `Module`.
 /// `execute_module` constructs `Program` with default pkg name `MAIN_PKG`,
 /// and calls method `execute` with default `plugin_agent` and `ExecProgramArgs`.
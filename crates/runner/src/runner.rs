@@ -1,7 +1,14 @@
 use anyhow::{Result, anyhow};
-use kcl_evaluator::Evaluator;
+use kcl_evaluator::{Evaluator, debugger::DebugHook};
+use std::any::Any;
 use std::collections::HashMap;
-use std::{cell::RefCell, rc::Rc};
+use std::panic::UnwindSafe;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
 
 use kcl_ast::ast;
 use kcl_config::{
@@ -9,12 +16,12 @@ use kcl_config::{
     settings::{SettingsFile, SettingsPathBuf},
 };
 use kcl_error::{Diagnostic, Handler};
+use kcl_query::r#override::parse_override_spec;
 #[cfg(not(target_arch = "wasm32"))]
 use kcl_runtime::kcl_plugin_init;
-use kcl_runtime::{Context, PanicInfo, RuntimePanicRecord};
-#[cfg(target_arch = "wasm32")]
+use kcl_runtime::{Context, OutputFormat, PanicInfo, RuntimePanicRecord, ValueRef};
 use once_cell::sync::Lazy;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use std::os::raw::c_char;
 
 const RESULT_SIZE: usize = 2048 * 2048;
@@ -60,6 +67,19 @@ pub struct ExecProgramArgs {
     pub show_hidden: bool,
     /// Whether including schema type in JSON/YAML result
     pub include_schema_type_path: bool,
+    /// --format: plan the result to the output format besides JSON and YAML,
+    /// e.g., toml, env, properties and tfjson.
+    #[serde(
+        default,
+        deserialize_with = "deserialize_format",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub format: Option<OutputFormat>,
+    /// Whether to emit the source provenance map, which maps each output path to the
+    /// source positions and the operations which produced its final value. The map is
+    /// empty when the output is a custom YAML stream, e.g., from `manifests.yaml_stream`.
+    #[serde(default)]
+    pub provenance: bool,
     /// Whether to compile only.
     pub compile_only: bool,
     /// plugin_agent is the address of plugin.
@@ -69,6 +89,10 @@ pub struct ExecProgramArgs {
     /// the result without any form of compilation.
     #[serde(skip)]
     pub fast_eval: bool,
+    /// The flag to interrupt the evaluation, which is checked before evaluating each
+    /// statement, e.g., when the request is cancelled by the client.
+    #[serde(skip)]
+    pub cancel_flag: Option<Arc<AtomicBool>>,
 }
 
 impl ExecProgramArgs {
@@ -98,10 +122,29 @@ impl ExecProgramArgs {
 pub struct ExecProgramResult {
     pub json_result: String,
     pub yaml_result: String,
+    /// The result in the output format of the arguments, which is empty if it is not set.
+    #[serde(default)]
+    pub output_result: String,
+    /// The JSON provenance map of the output paths, which is empty if it is not enabled.
+    #[serde(default)]
+    pub provenance_result: String,
     pub log_message: String,
     pub err_message: String,
 }
 
+/// Deserialize the output format from its name, e.g., `toml`, where the empty name denotes
+/// the default JSON and YAML results.
+fn deserialize_format<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<Option<OutputFormat>, D::Error> {
+    match Option::<String>::deserialize(deserializer)? {
+        Some(format) if !format.is_empty() => {
+            format.parse().map(Some).map_err(serde::de::Error::custom)
+        }
+        _ => Ok(None),
+    }
+}
+
 pub trait MapErrorResult {
     /// Map execute error message into the [`Result::Err`]
     fn map_err_to_result(self) -> Result<ExecProgramResult>
@@ -231,29 +274,74 @@ thread_local! {
     pub static KCL_RUNTIME_PANIC_RECORD: RefCell<RuntimePanicRecord> = RefCell::new(RuntimePanicRecord::default())
 }
 
-#[cfg(target_arch = "wasm32")]
-static ONCE_PANIC_HOOK: Lazy<()> = Lazy::new(|| {
-    std::panic::set_hook(Box::new(|info: &std::panic::PanicHookInfo| {
+/// Record the runtime panic information into the [`KCL_RUNTIME_PANIC_RECORD`] of the
+/// panicking thread, which is used as the panic hook during the evaluation.
+pub fn record_runtime_panic(info: &std::panic::PanicHookInfo) {
+    KCL_RUNTIME_PANIC_RECORD.with(|record| {
+        let mut record = record.borrow_mut();
+        record.kcl_panic_info = true;
+        record.message = panic_message(info.payload());
+        if let Some(location) = info.location() {
+            record.rust_file = location.file().to_string();
+            record.rust_line = location.line() as i32;
+            record.rust_col = location.column() as i32;
+        }
+    })
+}
+
+thread_local! {
+    static CATCHING_RUNTIME_PANIC: Cell<bool> = const { Cell::new(false) };
+}
+
+/// The process panic hook which records the panics caught by [`catch_runtime_panic`] and
+/// forwards the other panics to the previous hook. It is set only once, so the evaluations
+/// in multiple threads don't race on setting and restoring the process panic hook.
+static RUNTIME_PANIC_HOOK: Lazy<()> = Lazy::new(|| {
+    let prev_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        if CATCHING_RUNTIME_PANIC.with(Cell::get) {
+            record_runtime_panic(info);
+        } else {
+            prev_hook(info);
+        }
+    }));
+});
+
+/// Invoke the closure and catch its panic like [`std::panic::catch_unwind`], where the panic
+/// is recorded into the [`KCL_RUNTIME_PANIC_RECORD`] of the current thread instead of being
+/// printed. Unlike setting [`record_runtime_panic`] as the process panic hook, it can be
+/// called in multiple threads at the same time.
+pub fn catch_runtime_panic<F: FnOnce() -> R + UnwindSafe, R>(f: F) -> std::thread::Result<R> {
+    Lazy::force(&RUNTIME_PANIC_HOOK);
+    let catching = CATCHING_RUNTIME_PANIC.with(|catching| catching.replace(true));
+    KCL_RUNTIME_PANIC_RECORD.with(|record| record.borrow_mut().kcl_panic_info = false);
+    let result = std::panic::catch_unwind(f);
+    CATCHING_RUNTIME_PANIC.with(|c| c.set(catching));
+    if let Err(err) = &result {
+        // The process panic hook may be replaced after it is set, e.g., by the tests which
+        // disable printing the panic info, then the message is recorded from the payload.
         KCL_RUNTIME_PANIC_RECORD.with(|record| {
             let mut record = record.borrow_mut();
-            record.kcl_panic_info = true;
-            record.message = if let Some(s) = info.payload().downcast_ref::<&str>() {
-                s.to_string()
-            } else if let Some(s) = info.payload().downcast_ref::<&String>() {
-                (*s).clone()
-            } else if let Some(s) = info.payload().downcast_ref::<String>() {
-                (*s).clone()
-            } else {
-                "unknown runtime error".to_string()
-            };
-            if let Some(location) = info.location() {
-                record.rust_file = location.file().to_string();
-                record.rust_line = location.line() as i32;
-                record.rust_col = location.column() as i32;
+            if !record.kcl_panic_info {
+                record.kcl_panic_info = true;
+                record.message = panic_message(err.as_ref());
             }
-        })
-    }));
-});
+        });
+    }
+    result
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = payload.downcast_ref::<&String>() {
+        (*s).clone()
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "unknown runtime error".to_string()
+    }
+}
 
 pub struct FastRunner {
     opts: RunnerOptions,
@@ -268,52 +356,141 @@ impl FastRunner {
     }
 
     /// Run kcl library with exec arguments.
+    #[inline]
     pub fn run(&self, program: &ast::Program, args: &ExecProgramArgs) -> Result<ExecProgramResult> {
-        let ctx = Rc::new(RefCell::new(args_to_ctx(program, args)));
-        let evaluator = Evaluator::new_with_runtime_ctx(program, ctx.clone());
-        #[cfg(target_arch = "wasm32")]
-        // Ensure the panic hook is set (this will only happen once) for the WASM target,
-        // because it is single threaded.
-        Lazy::force(&ONCE_PANIC_HOOK);
-        #[cfg(not(target_arch = "wasm32"))]
-        let prev_hook = std::panic::take_hook();
-        #[cfg(not(target_arch = "wasm32"))]
-        std::panic::set_hook(Box::new(|info: &std::panic::PanicHookInfo| {
-            KCL_RUNTIME_PANIC_RECORD.with(|record| {
-                let mut record = record.borrow_mut();
-                record.kcl_panic_info = true;
-                record.message = if let Some(s) = info.payload().downcast_ref::<&str>() {
-                    s.to_string()
-                } else if let Some(s) = info.payload().downcast_ref::<&String>() {
-                    (*s).clone()
-                } else if let Some(s) = info.payload().downcast_ref::<String>() {
-                    (*s).clone()
-                } else {
-                    "unknown runtime error".to_string()
-                };
-                if let Some(location) = info.location() {
-                    record.rust_file = location.file().to_string();
-                    record.rust_line = location.line() as i32;
-                    record.rust_col = location.column() as i32;
-                }
-            })
-        }));
-        let evaluator_result = std::panic::catch_unwind(|| {
-            if self.opts.plugin_agent_ptr > 0 {
-                #[cfg(not(target_arch = "wasm32"))]
-                unsafe {
-                    let plugin_method: extern "C-unwind" fn(
-                        method: *const c_char,
-                        args: *const c_char,
-                        kwargs: *const c_char,
-                    ) -> *const c_char = std::mem::transmute(self.opts.plugin_agent_ptr);
-                    kcl_plugin_init(plugin_method);
-                }
-            }
+        self.run_with_hook(program, args, None)
+    }
+
+    /// Run kcl library with exec arguments and the debugger hook, which is called before
+    /// evaluating each statement. The stack frames are only recorded in the runtime debug
+    /// mode, e.g., when `args.debug` is set for the debuggers inspecting the frames.
+    #[inline]
+    pub fn run_with_debug_hook(
+        &self,
+        program: &ast::Program,
+        args: &ExecProgramArgs,
+        hook: Rc<dyn DebugHook>,
+    ) -> Result<ExecProgramResult> {
+        self.run_with_hook(program, args, Some(hook))
+    }
+
+    /// Run kcl library with exec arguments and record all the schema check failures instead
+    /// of stopping at the first one. The evaluation still stops at the first error which is
+    /// not a check failure, and the error follows the check failures in the returned list.
+    pub fn run_and_collect_check_failures(
+        &self,
+        program: &ast::Program,
+        args: &ExecProgramArgs,
+    ) -> Result<(ExecProgramResult, Vec<PanicInfo>)> {
+        let mut ctx = args_to_ctx(program, args);
+        ctx.cfg.collect_check_failures = true;
+        let ctx = Rc::new(RefCell::new(ctx));
+        let (result, panic_info) = self.run_with_ctx(program, args, ctx.clone(), None);
+        let mut failures = std::mem::take(&mut ctx.borrow_mut().check_failures);
+        failures.extend(panic_info);
+        Ok((result, failures))
+    }
+
+    fn run_with_hook(
+        &self,
+        program: &ast::Program,
+        args: &ExecProgramArgs,
+        hook: Option<Rc<dyn DebugHook>>,
+    ) -> Result<ExecProgramResult> {
+        let ctx = args_to_ctx(program, args);
+        Ok(self
+            .run_with_ctx(program, args, Rc::new(RefCell::new(ctx)), hook)
+            .0)
+    }
+
+    fn run_with_ctx(
+        &self,
+        program: &ast::Program,
+        args: &ExecProgramArgs,
+        ctx: Rc<RefCell<Context>>,
+        hook: Option<Rc<dyn DebugHook>>,
+    ) -> (ExecProgramResult, Option<PanicInfo>) {
+        let evaluator = new_evaluator(program, args, ctx.clone(), hook);
+        let evaluator_result = catch_runtime_panic(|| {
+            self.init_plugin();
             evaluator.run()
         });
-        #[cfg(not(target_arch = "wasm32"))]
-        std::panic::set_hook(prev_hook);
+        self.collect_result(&ctx, evaluator_result)
+    }
+
+    /// Evaluate the main package of the resolved program and invoke the global function
+    /// named `name` without any arguments, e.g., the `test_*` lambdas of the testing tool.
+    /// The function result is not planned, and the result only contains the log message
+    /// and the error message if any, the structured runtime error is also returned when
+    /// the function fails.
+    ///
+    /// Like [`FastRunner::run`], the runtime errors are caught by [`catch_runtime_panic`]
+    /// without changing the process panic hook, which makes it possible to be called in
+    /// multiple threads at the same time.
+    pub fn run_function(
+        &self,
+        program: &ast::Program,
+        args: &ExecProgramArgs,
+        name: &str,
+    ) -> Result<(ExecProgramResult, Option<PanicInfo>)> {
+        self.run_function_with_hook(program, args, name, None)
+    }
+
+    /// Invoke the global function named `name` like [`FastRunner::run_function`] with the
+    /// debugger hook, e.g., to debug a test case lambda.
+    #[inline]
+    pub fn run_function_with_debug_hook(
+        &self,
+        program: &ast::Program,
+        args: &ExecProgramArgs,
+        name: &str,
+        hook: Rc<dyn DebugHook>,
+    ) -> Result<(ExecProgramResult, Option<PanicInfo>)> {
+        self.run_function_with_hook(program, args, name, Some(hook))
+    }
+
+    fn run_function_with_hook(
+        &self,
+        program: &ast::Program,
+        args: &ExecProgramArgs,
+        name: &str,
+        hook: Option<Rc<dyn DebugHook>>,
+    ) -> Result<(ExecProgramResult, Option<PanicInfo>)> {
+        let ctx = Rc::new(RefCell::new(args_to_ctx(program, args)));
+        let evaluator = new_evaluator(program, args, ctx.clone(), hook);
+        KCL_RUNTIME_PANIC_RECORD.with(|record| *record.borrow_mut() = Default::default());
+        let evaluator_result = catch_runtime_panic(|| {
+            self.init_plugin();
+            evaluator.run_as_function();
+            evaluator
+                .invoke_global_function(name, &ValueRef::list(None), &ValueRef::dict(None))
+                .map(|_| (String::new(), String::new()))
+        });
+        Ok(self.collect_result(&ctx, evaluator_result))
+    }
+
+    /// Init the plugin agent function if set.
+    fn init_plugin(&self) {
+        if self.opts.plugin_agent_ptr > 0 {
+            #[cfg(not(target_arch = "wasm32"))]
+            unsafe {
+                let plugin_method: extern "C-unwind" fn(
+                    method: *const c_char,
+                    args: *const c_char,
+                    kwargs: *const c_char,
+                ) -> *const c_char = std::mem::transmute(self.opts.plugin_agent_ptr);
+                kcl_plugin_init(plugin_method);
+            }
+        }
+    }
+
+    /// Collect the evaluator result and runtime errors into the [`ExecProgramResult`],
+    /// and return the [`PanicInfo`] of the runtime error if any.
+    fn collect_result(
+        &self,
+        ctx: &Rc<RefCell<Context>>,
+        evaluator_result: std::thread::Result<Result<(String, String)>>,
+    ) -> (ExecProgramResult, Option<PanicInfo>) {
         KCL_RUNTIME_PANIC_RECORD.with(|record| {
             let record = record.borrow();
             ctx.borrow_mut().set_panic_info(&record);
@@ -328,6 +505,8 @@ impl FastRunner {
                 Ok((json, yaml)) => {
                     result.json_result = json;
                     result.yaml_result = yaml;
+                    result.output_result = ctx.borrow().output_result.clone();
+                    result.provenance_result = ctx.borrow().provenance_result.clone();
                 }
                 Err(err) => {
                     result.err_message = err.to_string();
@@ -343,12 +522,17 @@ impl FastRunner {
                 };
             }
         }
+        let panic_info = if result.err_message.is_empty() {
+            None
+        } else {
+            Some(PanicInfo::from(result.err_message.as_str()))
+        };
         // Wrap runtime JSON Panic error string into diagnostic style string.
-        if !result.err_message.is_empty() && std::env::var(KCL_DEBUG_ERROR_ENV_VAR).is_err() {
+        if let Some(panic_info) = &panic_info
+            && std::env::var(KCL_DEBUG_ERROR_ENV_VAR).is_err()
+        {
             result.err_message = match Handler::default()
-                .add_diagnostic(<PanicInfo as Into<Diagnostic>>::into(PanicInfo::from(
-                    result.err_message.as_str(),
-                )))
+                .add_diagnostic(<PanicInfo as Into<Diagnostic>>::into(panic_info.clone()))
                 .emit_to_string()
             {
                 Ok(msg) => msg,
@@ -358,8 +542,25 @@ impl FastRunner {
         // Free all value references at runtime. This is because the runtime context marks
         // all KCL objects and holds their copies, so it is necessary to actively GC them.
         ctx.borrow().gc();
-        Ok(result)
+        (result, panic_info)
+    }
+}
+
+/// New an evaluator with the debugger hook and the cancellation flag in the arguments.
+fn new_evaluator<'ctx>(
+    program: &'ctx ast::Program,
+    args: &ExecProgramArgs,
+    ctx: Rc<RefCell<Context>>,
+    hook: Option<Rc<dyn DebugHook>>,
+) -> Evaluator<'ctx> {
+    let mut evaluator = Evaluator::new_with_runtime_ctx(program, ctx);
+    if let Some(hook) = hook {
+        evaluator = evaluator.with_debug_hook(hook);
     }
+    if let Some(cancel_flag) = &args.cancel_flag {
+        evaluator = evaluator.with_cancel_flag(cancel_flag.clone());
+    }
+    evaluator
 }
 
 pub(crate) fn args_to_ctx(program: &ast::Program, args: &ExecProgramArgs) -> Context {
@@ -371,6 +572,18 @@ pub(crate) fn args_to_ctx(program: &ast::Program, args: &ExecProgramArgs) -> Con
     ctx.plan_opts.sort_keys = args.sort_keys;
     ctx.plan_opts.include_schema_type_path = args.include_schema_type_path;
     ctx.plan_opts.query_paths = args.path_selector.clone();
+    ctx.plan_opts.format = args.format;
+    ctx.plan_opts.provenance = args.provenance;
+    if args.provenance {
+        // Mark the values set by the CLI overrides in the provenance map.
+        ctx.plan_opts.override_paths = args
+            .overrides
+            .iter()
+            .filter_map(|spec| parse_override_spec(spec).ok())
+            .filter(|spec| spec.action == ast::OverrideAction::CreateOrUpdate)
+            .map(|spec| spec.field_path)
+            .collect();
+    }
     for arg in &args.args {
         ctx.builtin_option_init(&arg.name, &arg.value);
     }
@@ -1,6 +1,7 @@
 #![allow(clippy::arc_with_non_send_sync)]
 
 use crate::exec_program;
+use crate::{build_program, exec_artifact};
 use crate::{execute, runner::ExecProgramArgs};
 use anyhow::Result;
 use kcl_ast::ast::{Module, Program};
@@ -171,6 +172,23 @@ fn test_from_str_program_arg() {
     }
 }
 
+#[test]
+fn test_program_arg_format() {
+    let with_format = |format: &str| {
+        ExecProgramArgs::default().to_json().replace(
+            "\"provenance\"",
+            &format!("\"format\":\"{format}\",\"provenance\""),
+        )
+    };
+    let args = ExecProgramArgs::from_json(&with_format("dotenv"));
+    assert_eq!(args.format, Some(kcl_runtime::OutputFormat::Env));
+    assert_eq!(args.to_json(), with_format("env"));
+    // The empty format denotes the default JSON and YAML results.
+    let args = ExecProgramArgs::from_json(&with_format(""));
+    assert_eq!(args.format, None);
+    assert!(serde_json::from_str::<ExecProgramArgs>(&with_format("xml")).is_err());
+}
+
 #[test]
 fn test_from_setting_file_program_arg() {
     for (case_yaml, case_json) in settings_file_test_case() {
@@ -447,3 +465,129 @@ fn test_kcl_issue_1799() {
         )
     );
 }
+
+#[test]
+fn test_build_program_and_exec_artifact() {
+    let main_test_path = PathBuf::from("./src/test_datas/artifact/main.k");
+    let output = tempfile::tempdir().unwrap();
+    let mut args = ExecProgramArgs::default();
+    args.k_filename_list
+        .push(main_test_path.display().to_string());
+    let path = build_program(
+        Arc::new(ParseSession::default()),
+        &args,
+        Some(output.path()),
+    )
+    .unwrap();
+    // Building the unchanged program returns the same content addressed artifact.
+    let path_again = build_program(
+        Arc::new(ParseSession::default()),
+        &args,
+        Some(output.path()),
+    )
+    .unwrap();
+    assert_eq!(path, path_again);
+
+    let res = exec_artifact(&path, &args).unwrap();
+    assert_eq!(
+        res.yaml_result,
+        "env: dev\napp:\n  name: app\n  env: dev\n  replicas: 1"
+    );
+    // Execute the same artifact with different options and overrides.
+    args.args.push(kcl_ast::ast::Argument {
+        name: "env".to_string(),
+        value: "prod".to_string(),
+    });
+    args.overrides.push("app.replicas=3".to_string());
+    let res = exec_artifact(&path, &args).unwrap();
+    assert_eq!(
+        res.yaml_result,
+        "env: prod\napp:\n  name: app\n  env: prod\n  replicas: 3"
+    );
+}
+
+#[test]
+fn test_exec_with_provenance() {
+    let main_test_path = PathBuf::from("./src/test_datas/provenance/main.k");
+    let mut args = ExecProgramArgs::default();
+    args.k_filename_list
+        .push(main_test_path.display().to_string());
+    args.provenance = true;
+    let res = exec_program(Arc::new(ParseSession::default()), &args).unwrap();
+    let provenance: Value = serde_json::from_str(&res.provenance_result).unwrap();
+    let positions = |path: &str| -> Vec<(u64, String)> {
+        provenance[path]
+            .as_array()
+            .unwrap_or_else(|| panic!("path {path} is not found"))
+            .iter()
+            .map(|p| {
+                assert!(p["filename"].as_str().unwrap().ends_with("main.k"));
+                (
+                    p["line"].as_u64().unwrap(),
+                    p["op"].as_str().unwrap().to_string(),
+                )
+            })
+            .collect()
+    };
+    assert_eq!(positions("base"), vec![(6, "=".to_string())]);
+    assert_eq!(positions("app"), vec![(9, ":".to_string())]);
+    assert_eq!(positions("app.name"), vec![(10, "=".to_string())]);
+    assert_eq!(positions("app.replicas"), vec![(3, "=".to_string())]);
+    assert_eq!(
+        positions("app.labels"),
+        vec![(11, ":".to_string()), (12, ":".to_string())]
+    );
+    assert_eq!(positions("app.labels.env"), vec![(7, "=".to_string())]);
+    assert_eq!(positions("app.labels.tier"), vec![(12, "=".to_string())]);
+
+    // The values set by the CLI overrides are marked with `-O`.
+    args.overrides.push("app.replicas=3".to_string());
+    let res = exec_program(Arc::new(ParseSession::default()), &args).unwrap();
+    let provenance: Value = serde_json::from_str(&res.provenance_result).unwrap();
+    assert_eq!(provenance["app.replicas"][0]["op"], "-O");
+    assert_eq!(provenance["app.name"][0]["op"], "=");
+
+    // The values of the custom YAML stream output have no provenances.
+    let mut args = ExecProgramArgs::default();
+    args.k_filename_list
+        .push("./src/test_datas/provenance/yaml_stream.k".to_string());
+    args.provenance = true;
+    let res = exec_program(Arc::new(ParseSession::default()), &args).unwrap();
+    assert_eq!(res.provenance_result, "{}");
+}
+
+#[test]
+fn test_exec_tampered_artifact() {
+    let output = tempfile::tempdir().unwrap();
+    let mut args = ExecProgramArgs::default();
+    args.k_filename_list
+        .push("./src/test_datas/artifact/main.k".to_string());
+    let path = build_program(
+        Arc::new(ParseSession::default()),
+        &args,
+        Some(output.path()),
+    )
+    .unwrap();
+    assert!(exec_artifact(&path, &args).is_ok());
+    let mut artifact: Value = serde_json::from_slice(&fs::read(&path).unwrap()).unwrap();
+    artifact["checksum"] = Value::from("tampered");
+    let tampered = serde_json::to_vec(&artifact).unwrap();
+    // The artifact with the same file name in another directory is not the cached one.
+    let other_output = tempfile::tempdir().unwrap();
+    let other_path = other_output.path().join(path.file_name().unwrap());
+    fs::write(&other_path, &tampered).unwrap();
+    let err = exec_artifact(&other_path, &args).unwrap_err();
+    assert!(err.to_string().contains("checksum mismatch"), "{err}");
+    // The resolved program is covered by the checksum as well.
+    let mut artifact: Value = serde_json::from_slice(&fs::read(&path).unwrap()).unwrap();
+    artifact["resolved"]["root"] = Value::from("tampered");
+    let resolved_output = tempfile::tempdir().unwrap();
+    let resolved_path = resolved_output.path().join(path.file_name().unwrap());
+    fs::write(&resolved_path, serde_json::to_vec(&artifact).unwrap()).unwrap();
+    let err = exec_artifact(&resolved_path, &args).unwrap_err();
+    assert!(err.to_string().contains("checksum mismatch"), "{err}");
+    // The modified artifact is verified again.
+    fs::write(&path, &tampered).unwrap();
+    let err = exec_artifact(&path, &args).unwrap_err();
+    assert!(err.to_string().contains("checksum mismatch"), "{err}");
+}
@@ -1,6 +1,6 @@
 //! Copyright The KCL Authors. All rights reserved.
 
-use crate::{new_mut_ptr, val_plan::PlanOptions};
+use crate::{Provenance, new_mut_ptr, val_plan::PlanOptions};
 use generational_arena::Index;
 use kcl_primitives::{IndexMap, IndexSet};
 use serde::{Deserialize, Serialize};
@@ -233,6 +233,9 @@ pub struct DictValue {
     /// Stored schema arguments (args, kwargs) for instances() to use
     /// This is None for non-schema dicts, Some(args, kwargs) for schema instances
     pub schema_args: Option<(ValueRef, ValueRef)>,
+    /// The source provenances of the entries, which are only recorded when
+    /// [`PlanOptions::provenance`] is set.
+    pub provenances: IndexMap<SmolStr, Vec<Provenance>>,
 }
 
 #[derive(PartialEq, Clone, Default, Debug)]
@@ -311,6 +314,9 @@ pub struct ContextConfig {
     pub debug_mode: bool,
     pub strict_range_check: bool,
     pub disable_schema_check: bool,
+    /// Record the schema check failures into [`Context::check_failures`] and continue
+    /// the evaluation instead of raising the first failure.
+    pub collect_check_failures: bool,
 }
 
 #[derive(PartialEq, Eq, Clone, Debug)]
@@ -364,12 +370,18 @@ pub struct Context {
     pub json_result: String,
     /// Planned YAML result
     pub yaml_result: String,
+    /// Planned result in the output format of the plan options
+    pub output_result: String,
+    /// Planned provenance map JSON result of the output paths
+    pub provenance_result: String,
     /// Panic information at runtime
     pub panic_info: PanicInfo,
     /// Planning options
     pub plan_opts: PlanOptions,
     /// Builtin plugin functions, the key of the map is the form <module_name>.<module_func> e.g., `hello.say_hello`
     pub plugin_functions: IndexMap<String, PluginFunction>,
+    /// Schema check failures recorded when [`ContextConfig::collect_check_failures`] is set.
+    pub check_failures: Vec<PanicInfo>,
 }
 
 impl UnwindSafe for Context {}
@@ -2112,29 +2112,7 @@ pub unsafe extern "C-unwind" fn kcl_schema_assert(
     let config_meta = unsafe { ptr_as_ref(config_meta) };
     if !value.is_truthy() {
         let ctx = unsafe { mut_ptr_as_ref(ctx) };
-        ctx.set_err_type(&RuntimeErrorType::SchemaCheckFailure);
-        if let Some(config_meta_file) = config_meta.get_by_key(CONFIG_META_FILENAME) {
-            let config_meta_line = config_meta.get_by_key(CONFIG_META_LINE).unwrap();
-            let config_meta_column = config_meta.get_by_key(CONFIG_META_COLUMN).unwrap();
-            ctx.set_kcl_config_meta_location_info(
-                Some("Instance check failed"),
-                Some(config_meta_file.as_str().as_str()),
-                Some(config_meta_line.as_int() as i32),
-                Some(config_meta_column.as_int() as i32),
-            );
-        }
-
-        let arg_msg = format!(
-            "Check failed on the condition{}",
-            if msg.is_empty() {
-                "".to_string()
-            } else {
-                format!(": {msg}")
-            }
-        );
-        ctx.set_kcl_location_info(Some(arg_msg.as_str()), None, None, None);
-
-        panic!("{}", msg.as_str());
+        schema_assert(ctx, value, &msg.as_str(), config_meta);
     }
 }
 
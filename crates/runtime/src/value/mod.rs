@@ -59,6 +59,12 @@ pub mod val_bin;
 
 pub mod val_plan;
 
+pub mod val_encode;
+pub use val_encode::*;
+
+pub mod val_provenance;
+pub use val_provenance::*;
+
 pub mod val_str;
 
 pub mod val_attr;
@@ -148,6 +148,7 @@ impl ValueRef {
                     };
                     let index = dict.insert_indexs.get(key);
                     d.dict_update_entry(key, value, op, index);
+                    d.as_dict_mut_ref().copy_provenances(dict, key);
                     d.set_potential_schema_type(&dict.potential_schema.clone().unwrap_or_default());
                     Some(d)
                 } else {
@@ -165,6 +166,7 @@ impl ValueRef {
                     };
                     let index = schema.config.insert_indexs.get(key);
                     d.dict_update_entry(key, value, op, index);
+                    d.as_dict_mut_ref().copy_provenances(&schema.config, key);
                     d.set_potential_schema_type(
                         &schema.config.potential_schema.clone().unwrap_or_default(),
                     );
@@ -192,6 +194,7 @@ impl ValueRef {
                             .unwrap_or(&ConfigEntryOperationKind::Union);
                         let index = dict.insert_indexs.get(key);
                         d.dict_update_entry(key, value, op, index);
+                        d.as_dict_mut_ref().copy_provenances(dict, key);
                     }
                 }
                 d.set_potential_schema_type(&dict.potential_schema.clone().unwrap_or_default());
@@ -209,6 +212,7 @@ impl ValueRef {
                             .unwrap_or(&ConfigEntryOperationKind::Union);
                         let index = schema.config.insert_indexs.get(key);
                         d.dict_update_entry(key, value, op, index);
+                        d.as_dict_mut_ref().copy_provenances(&schema.config, key);
                     }
                 }
                 d.set_potential_schema_type(
@@ -240,6 +244,7 @@ impl ValueRef {
                         let value = dict.values.get(key).unwrap();
                         let index = dict.insert_indexs.get(key);
                         d.dict_update_entry(key, value, op, index);
+                        d.as_dict_mut_ref().copy_provenances(dict, key);
                     }
                 }
                 d.set_potential_schema_type(&dict.potential_schema.clone().unwrap_or_default());
@@ -252,6 +257,7 @@ impl ValueRef {
                         let value = schema.config.values.get(key).unwrap();
                         let index = schema.config.insert_indexs.get(key);
                         d.dict_update_entry(key, value, op, index);
+                        d.as_dict_mut_ref().copy_provenances(&schema.config, key);
                     }
                 }
                 d.set_potential_schema_type(
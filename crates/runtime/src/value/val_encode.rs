@@ -0,0 +1,493 @@
+//! Copyright The KCL Authors. All rights reserved.
+//!
+//! The output encoders which encode the planned documents into the output formats, e.g.,
+//! `toml`, `.env`, Java `.properties` and the Terraform JSON syntax `*.tf.json`, in
+//! addition to the JSON and YAML results.
+
+use std::fmt;
+use std::str::FromStr;
+
+use kcl_primitives::IndexMap;
+use serde::{Deserialize, Serialize};
+
+use crate::val_plan::PlanOptions;
+use crate::value::val_json::JsonValue;
+use crate::{JSON_STREAM_SEP, JsonEncodeOptions, ValueRef, YamlEncodeOptions};
+
+/// The top-level block types of the Terraform JSON configuration syntax.
+const TERRAFORM_BLOCK_TYPES: [&str; 12] = [
+    "terraform",
+    "provider",
+    "variable",
+    "output",
+    "locals",
+    "module",
+    "resource",
+    "data",
+    "check",
+    "import",
+    "moved",
+    "removed",
+];
+
+/// OutputFormat denotes the format of the planned result.
+#[derive(PartialEq, Eq, Clone, Copy, Default, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    #[default]
+    Yaml,
+    Json,
+    Toml,
+    /// The `KEY=value` lines of the env files, e.g., for docker-compose.
+    Env,
+    /// The Java properties files.
+    Properties,
+    /// The Terraform JSON configuration syntax, e.g., `main.tf.json`.
+    TfJson,
+}
+
+impl OutputFormat {
+    /// All the output formats.
+    pub const ALL: [OutputFormat; 6] = [
+        OutputFormat::Yaml,
+        OutputFormat::Json,
+        OutputFormat::Toml,
+        OutputFormat::Env,
+        OutputFormat::Properties,
+        OutputFormat::TfJson,
+    ];
+
+    /// Returns the name of the output format.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OutputFormat::Yaml => "yaml",
+            OutputFormat::Json => "json",
+            OutputFormat::Toml => "toml",
+            OutputFormat::Env => "env",
+            OutputFormat::Properties => "properties",
+            OutputFormat::TfJson => "tfjson",
+        }
+    }
+
+    /// Returns the encoder of the output format.
+    pub fn encoder(&self) -> &'static dyn OutputEncoder {
+        match self {
+            OutputFormat::Yaml => &YamlEncoder,
+            OutputFormat::Json => &JsonEncoder,
+            OutputFormat::Toml => &TomlEncoder,
+            OutputFormat::Env => &EnvEncoder,
+            OutputFormat::Properties => &PropertiesEncoder,
+            OutputFormat::TfJson => &TfJsonEncoder,
+        }
+    }
+
+    /// Encode the planned documents into the output format, and join them with the stream
+    /// separator of the format. Returns the error message if a value can't be represented
+    /// in the format.
+    pub fn encode_documents(
+        &self,
+        documents: &[ValueRef],
+        opts: &PlanOptions,
+    ) -> Result<String, String> {
+        let encoder = self.encoder();
+        let sep = match encoder.stream_separator(opts) {
+            Some(sep) => sep,
+            None if documents.len() > 1 => {
+                return Err(format!(
+                    "the {} output format doesn't support multiple documents, got {}",
+                    self,
+                    documents.len()
+                ));
+            }
+            None => String::new(),
+        };
+        let documents = documents
+            .iter()
+            .map(|document| encoder.encode(document, opts))
+            .collect::<Result<Vec<String>, String>>()?;
+        Ok(documents.join(&sep))
+    }
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "yaml" | "yml" => Ok(OutputFormat::Yaml),
+            "json" => Ok(OutputFormat::Json),
+            "toml" => Ok(OutputFormat::Toml),
+            "env" | "dotenv" => Ok(OutputFormat::Env),
+            "properties" => Ok(OutputFormat::Properties),
+            "tfjson" | "tf.json" => Ok(OutputFormat::TfJson),
+            _ => Err(format!(
+                "invalid output format '{}', expected one of {}",
+                s,
+                OutputFormat::ALL
+                    .iter()
+                    .map(|format| format!("'{format}'"))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            )),
+        }
+    }
+}
+
+/// OutputEncoder encodes a planned document into the text of an output format.
+pub trait OutputEncoder {
+    /// Encode a planned document, or return the error message if a value can't be
+    /// represented in the format.
+    fn encode(&self, value: &ValueRef, opts: &PlanOptions) -> Result<String, String>;
+
+    /// Returns the separator between the documents, or `None` if the format supports only
+    /// a single document.
+    fn stream_separator(&self, _opts: &PlanOptions) -> Option<String> {
+        None
+    }
+}
+
+struct YamlEncoder;
+
+impl OutputEncoder for YamlEncoder {
+    fn encode(&self, value: &ValueRef, opts: &PlanOptions) -> Result<String, String> {
+        let yaml_opts = YamlEncodeOptions {
+            sort_keys: opts.sort_keys,
+            ..Default::default()
+        };
+        let yaml = value.to_yaml_string_with_options(&yaml_opts);
+        Ok(yaml.strip_suffix('\n').unwrap_or(&yaml).to_string())
+    }
+
+    fn stream_separator(&self, opts: &PlanOptions) -> Option<String> {
+        Some(format!("\n{}\n", opts.sep.as_deref().unwrap_or("---")))
+    }
+}
+
+struct JsonEncoder;
+
+impl OutputEncoder for JsonEncoder {
+    fn encode(&self, value: &ValueRef, opts: &PlanOptions) -> Result<String, String> {
+        Ok(value.to_json_string_with_options(&json_encode_options(opts)))
+    }
+
+    fn stream_separator(&self, _opts: &PlanOptions) -> Option<String> {
+        Some(JSON_STREAM_SEP.to_string())
+    }
+}
+
+struct TomlEncoder;
+
+impl OutputEncoder for TomlEncoder {
+    fn encode(&self, value: &ValueRef, opts: &PlanOptions) -> Result<String, String> {
+        let JsonValue::Object(table) = value.build_json(&json_encode_options(opts)) else {
+            return Err(top_level_error(OutputFormat::Toml, value));
+        };
+        let mut out = String::new();
+        write_toml_table(&mut out, &[], &table, false)?;
+        Ok(out.trim_matches('\n').to_string())
+    }
+}
+
+/// Writes the key-values of the table, then the sub-tables and the arrays of tables with
+/// the full key paths, because the key-values after a header belong to that table, and the
+/// sub-tables after an array of tables header belong to the last table of the array.
+fn write_toml_table(
+    out: &mut String,
+    path: &[String],
+    table: &IndexMap<String, JsonValue>,
+    is_array_element: bool,
+) -> Result<(), String> {
+    let (tables, values): (Vec<_>, Vec<_>) =
+        table.iter().partition(|(_, value)| is_toml_table(value));
+    if is_array_element {
+        out.push_str(&format!("\n[[{}]]\n", toml_key_path(path)));
+    } else if !path.is_empty() && (!values.is_empty() || tables.is_empty()) {
+        out.push_str(&format!("\n[{}]\n", toml_key_path(path)));
+    }
+    for (key, value) in values {
+        let value = toml_value(value).map_err(|kind| toml_error(path, key, kind))?;
+        out.push_str(&format!("{} = {}\n", toml_key(key), value));
+    }
+    for (key, value) in tables {
+        let mut path = path.to_vec();
+        path.push(key.clone());
+        match value {
+            JsonValue::Object(table) => write_toml_table(out, &path, table, false)?,
+            JsonValue::Array(tables) => {
+                for table in tables {
+                    if let JsonValue::Object(table) = table {
+                        write_toml_table(out, &path, table, true)?;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Returns whether the value is written as a table or an array of tables with the headers.
+fn is_toml_table(value: &JsonValue) -> bool {
+    match value {
+        JsonValue::Object(_) => true,
+        JsonValue::Array(values) => {
+            !values.is_empty() && values.iter().all(|v| matches!(v, JsonValue::Object(_)))
+        }
+        _ => false,
+    }
+}
+
+/// Returns the inline TOML value, or the description of the value which can't be represented.
+fn toml_value(value: &JsonValue) -> Result<String, &'static str> {
+    match value {
+        JsonValue::Null => Err("None"),
+        JsonValue::Bool(v) => Ok(v.to_string()),
+        JsonValue::Number(v) => Ok(v.to_string()),
+        JsonValue::String(v) => Ok(quote_string(v)),
+        JsonValue::Array(values) => Ok(format!(
+            "[{}]",
+            values
+                .iter()
+                .map(toml_value)
+                .collect::<Result<Vec<String>, &'static str>>()?
+                .join(", ")
+        )),
+        JsonValue::Object(table) => Ok(format!(
+            "{{ {} }}",
+            table
+                .iter()
+                .map(|(k, v)| Ok(format!("{} = {}", toml_key(k), toml_value(v)?)))
+                .collect::<Result<Vec<String>, &'static str>>()?
+                .join(", ")
+        )),
+    }
+}
+
+fn toml_error(path: &[String], key: &str, kind: &str) -> String {
+    let mut path = path.to_vec();
+    path.push(key.to_string());
+    format!(
+        "the {} value of '{}' can't be represented in the toml output format",
+        kind,
+        path.join(".")
+    )
+}
+
+fn toml_key_path(path: &[String]) -> String {
+    path.iter()
+        .map(|key| toml_key(key))
+        .collect::<Vec<String>>()
+        .join(".")
+}
+
+/// Returns the bare key if possible, otherwise the quoted key.
+fn toml_key(key: &str) -> String {
+    if !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        key.to_string()
+    } else {
+        quote_string(key)
+    }
+}
+
+/// Returns the double-quoted string with the escapes shared by TOML and the env files.
+fn quote_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04X}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+struct EnvEncoder;
+
+impl OutputEncoder for EnvEncoder {
+    fn encode(&self, value: &ValueRef, opts: &PlanOptions) -> Result<String, String> {
+        let JsonValue::Object(table) = value.build_json(&json_encode_options(opts)) else {
+            return Err(top_level_error(OutputFormat::Env, value));
+        };
+        let mut lines = vec![];
+        for (key, value) in &table {
+            if key.is_empty()
+                || key.starts_with(|c: char| c.is_ascii_digit())
+                || !key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+            {
+                return Err(format!(
+                    "'{key}' is not a valid variable name in the env output format"
+                ));
+            }
+            let value = match value {
+                JsonValue::Null => String::new(),
+                JsonValue::Bool(v) => v.to_string(),
+                JsonValue::Number(v) => v.to_string(),
+                JsonValue::String(v) => env_string(v),
+                JsonValue::Array(_) | JsonValue::Object(_) => {
+                    return Err(format!(
+                        "the {} value of '{}' can't be represented in the env output format, only the scalar values are supported",
+                        json_kind(value),
+                        key
+                    ));
+                }
+            };
+            lines.push(format!("{key}={value}"));
+        }
+        Ok(lines.join("\n"))
+    }
+}
+
+/// Returns the env file value of the string. The strings with `$` are single-quoted to
+/// avoid the variable interpolation, e.g., in docker-compose, and `$` is escaped as `$$`
+/// when the string can't be single-quoted.
+fn env_string(s: &str) -> String {
+    if s.is_empty()
+        || s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || "_-.,:/@+%".contains(c) || !c.is_ascii())
+    {
+        s.to_string()
+    } else if !s.contains('$') {
+        quote_string(s)
+    } else if !s.contains('\'') && !s.chars().any(|c| c.is_control()) {
+        format!("'{s}'")
+    } else {
+        quote_string(s).replace('$', "$$")
+    }
+}
+
+struct PropertiesEncoder;
+
+impl OutputEncoder for PropertiesEncoder {
+    fn encode(&self, value: &ValueRef, opts: &PlanOptions) -> Result<String, String> {
+        let JsonValue::Object(table) = value.build_json(&json_encode_options(opts)) else {
+            return Err(top_level_error(OutputFormat::Properties, value));
+        };
+        let mut lines = vec![];
+        for (key, value) in &table {
+            write_properties(&mut lines, key.clone(), value);
+        }
+        Ok(lines.join("\n"))
+    }
+}
+
+/// Flattens the nested configs with the dotted keys and the lists with the indexed keys,
+/// e.g., `server.hosts[0]=localhost`. The `None` values and the empty collections are
+/// written as the empty strings.
+fn write_properties(lines: &mut Vec<String>, key: String, value: &JsonValue) {
+    let value = match value {
+        JsonValue::Object(table) if !table.is_empty() => {
+            for (k, v) in table {
+                write_properties(lines, format!("{key}.{k}"), v);
+            }
+            return;
+        }
+        JsonValue::Array(values) if !values.is_empty() => {
+            for (i, v) in values.iter().enumerate() {
+                write_properties(lines, format!("{key}[{i}]"), v);
+            }
+            return;
+        }
+        JsonValue::Null | JsonValue::Object(_) | JsonValue::Array(_) => String::new(),
+        JsonValue::Bool(v) => v.to_string(),
+        JsonValue::Number(v) => v.to_string(),
+        JsonValue::String(v) => escape_properties(v, false),
+    };
+    lines.push(format!("{}={}", escape_properties(&key, true), value));
+}
+
+/// Escapes the key or the value of the Java properties, where the non-ASCII characters are
+/// written as the unicode escapes for the ISO 8859-1 encoding.
+fn escape_properties(s: &str, is_key: bool) -> String {
+    let mut out = String::with_capacity(s.len());
+    for (i, c) in s.chars().enumerate() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '\u{c}' => out.push_str("\\f"),
+            ' ' if is_key || i == 0 => out.push_str("\\ "),
+            '=' | ':' | '#' | '!' if is_key || i == 0 => {
+                out.push('\\');
+                out.push(c);
+            }
+            c if !(' '..='~').contains(&c) => {
+                let mut buf = [0u16; 2];
+                for unit in c.encode_utf16(&mut buf) {
+                    out.push_str(&format!("\\u{unit:04X}"));
+                }
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+struct TfJsonEncoder;
+
+impl OutputEncoder for TfJsonEncoder {
+    fn encode(&self, value: &ValueRef, opts: &PlanOptions) -> Result<String, String> {
+        if !value.is_config() {
+            return Err(top_level_error(OutputFormat::TfJson, value));
+        }
+        if let Some(key) = value
+            .as_dict_ref()
+            .values
+            .keys()
+            .find(|key| !TERRAFORM_BLOCK_TYPES.contains(&key.as_str()))
+        {
+            return Err(format!(
+                "'{}' is not a Terraform block type, expected one of {}",
+                key,
+                TERRAFORM_BLOCK_TYPES.join(", ")
+            ));
+        }
+        Ok(value.to_json_string_with_options(&JsonEncodeOptions {
+            indent: 2,
+            ..json_encode_options(opts)
+        }))
+    }
+}
+
+fn json_encode_options(opts: &PlanOptions) -> JsonEncodeOptions {
+    JsonEncodeOptions {
+        sort_keys: opts.sort_keys,
+        ..Default::default()
+    }
+}
+
+fn top_level_error(format: OutputFormat, value: &ValueRef) -> String {
+    format!(
+        "the {} output format requires a config at the top level, got {}",
+        format,
+        value.type_str()
+    )
+}
+
+fn json_kind(value: &JsonValue) -> &'static str {
+    match value {
+        JsonValue::Null => "None",
+        JsonValue::Bool(_) => "bool",
+        JsonValue::Number(_) => "number",
+        JsonValue::String(_) => "str",
+        JsonValue::Array(_) => "list",
+        JsonValue::Object(_) => "dict",
+    }
+}
@@ -24,6 +24,12 @@ pub struct PlanOptions {
     pub query_paths: Vec<String>,
     /// YAML plan separator string, default is `---`.
     pub sep: Option<String>,
+    /// Plan the value to the output format besides JSON and YAML.
+    pub format: Option<OutputFormat>,
+    /// Emit the source provenance map of the output paths.
+    pub provenance: bool,
+    /// The paths set by the CLI overrides, which are marked in the provenance map.
+    pub override_paths: Vec<String>,
 }
 
 /// Filter list or config results with context options.
@@ -232,16 +238,38 @@ pub fn type_of(v: &ValueRef, full_name: bool) -> String {
 impl ValueRef {
     /// Plan the value to JSON and YAML strings.
     pub fn plan(&self, ctx: &Context) -> (String, String) {
-        // Encoding options
-        let json_opts = JsonEncodeOptions {
-            sort_keys: ctx.plan_opts.sort_keys,
-            ..Default::default()
-        };
-        let yaml_opts = YamlEncodeOptions {
-            sort_keys: ctx.plan_opts.sort_keys,
-            ..Default::default()
-        };
-        // Filter values with query paths
+        let mut outputs = self
+            .plan_formats(ctx, &[OutputFormat::Json, OutputFormat::Yaml])
+            .unwrap_or_else(|e| panic!("{e}"))
+            .into_iter();
+        let json_string = outputs.next().unwrap_or_default();
+        let yaml_string = outputs.next().unwrap_or_default();
+        (json_string, yaml_string)
+    }
+
+    /// Plan the value to the string of the output format, or return the error message if a
+    /// value can't be represented in the format.
+    pub fn plan_with_format(&self, ctx: &Context, format: OutputFormat) -> Result<String, String> {
+        format.encode_documents(&self.plan_documents(ctx), &ctx.plan_opts)
+    }
+
+    /// Plan the value to the strings of the output formats in order, where the value is
+    /// filtered into the documents only once for all the formats.
+    pub fn plan_formats(
+        &self,
+        ctx: &Context,
+        formats: &[OutputFormat],
+    ) -> Result<Vec<String>, String> {
+        let documents = self.plan_documents(ctx);
+        formats
+            .iter()
+            .map(|format| format.encode_documents(&documents, &ctx.plan_opts))
+            .collect()
+    }
+
+    /// Filter the value with the query paths and the plan options into the documents, where
+    /// the list of configs is planned as a stream of documents.
+    fn plan_documents(&self, ctx: &Context) -> Vec<ValueRef> {
         let value = if ctx.plan_opts.query_paths.is_empty() {
             self.clone()
         } else {
@@ -249,55 +277,9 @@ impl ValueRef {
                 .unwrap_or_else(|e| panic!("{e}"))
         };
         if value.is_list_or_config() {
-            let results = filter_results(ctx, &value);
-
-            // Plan result using the same approach for both JSON and YAML
-            // Serialize the whole filtered value instead of using stream format
-            if results.len() == 1 {
-                // For config/dict, use the filtered result
-                (
-                    results[0].to_json_string_with_options(&json_opts),
-                    results[0]
-                        .to_yaml_string_with_options(&yaml_opts)
-                        .strip_suffix('\n')
-                        .unwrap()
-                        .to_string(),
-                )
-            } else {
-                // Fallback to original value (shouldn't happen normally)
-                let sep = ctx
-                    .plan_opts
-                    .sep
-                    .clone()
-                    .unwrap_or_else(|| "---".to_string());
-                // Plan YAML array results
-                let yaml_result = results
-                    .iter()
-                    .map(|r| {
-                        r.to_yaml_string_with_options(&yaml_opts)
-                            .strip_suffix('\n')
-                            .unwrap()
-                            .to_string()
-                    })
-                    .collect::<Vec<String>>()
-                    .join(&format!("\n{}\n", sep));
-                // Plan JSON array results
-                let json_result = results
-                    .iter()
-                    .map(|r| r.to_json_string_with_options(&json_opts))
-                    .collect::<Vec<String>>()
-                    .join(JSON_STREAM_SEP);
-                (json_result, yaml_result)
-            }
+            filter_results(ctx, &value)
         } else {
-            (
-                value.to_json_string_with_options(&json_opts),
-                value
-                    .to_yaml_string_with_options(&yaml_opts)
-                    .strip_suffix('\n')
-                    .unwrap()
-                    .to_string(),
-            )
+            vec![value]
         }
     }
 
@@ -335,7 +317,9 @@ impl ValueRef {
 
 #[cfg(test)]
 mod test_value_plan {
-    use crate::{Context, MAIN_PKG_PATH, ValueRef, schema_runtime_type, val_plan::PlanOptions};
+    use crate::{
+        Context, MAIN_PKG_PATH, OutputFormat, ValueRef, schema_runtime_type, val_plan::PlanOptions,
+    };
 
     use super::filter_results;
 
@@ -494,4 +478,66 @@ mod test_value_plan {
         assert_eq!(json_string, "{}");
         assert_eq!(yaml_string, "{}");
     }
+
+    #[test]
+    fn test_value_plan_with_format() {
+        let ctx = Context::new();
+        let mut db = ValueRef::dict(None);
+        db.dict_update_key_value("host", ValueRef::str("localhost"));
+        db.dict_update_key_value("ports", ValueRef::list_int(&[5432, 5433]));
+        let mut config = ValueRef::dict(None);
+        config.dict_update_key_value("name", ValueRef::str("my app"));
+        config.dict_update_key_value("port", ValueRef::int(8080));
+        config.dict_update_key_value("debug", ValueRef::bool(true));
+        let env = config.plan_with_format(&ctx, OutputFormat::Env).unwrap();
+        assert_eq!(env, "name=\"my app\"\nport=8080\ndebug=true");
+        let vars = ValueRef::dict_str(&[("price", "$5"), ("home", "it's $HOME")]);
+        let env = vars.plan_with_format(&ctx, OutputFormat::Env).unwrap();
+        assert_eq!(env, "price='$5'\nhome=\"it's $$HOME\"");
+        let outputs = vars
+            .plan_formats(&ctx, &[OutputFormat::Json, OutputFormat::Env])
+            .unwrap();
+        assert_eq!(outputs.len(), 2);
+        assert_eq!(outputs[1], env);
+
+        config.dict_update_key_value("db", db);
+        let toml = config.plan_with_format(&ctx, OutputFormat::Toml).unwrap();
+        assert_eq!(
+            toml,
+            "name = \"my app\"\nport = 8080\ndebug = true\n\n[db]\nhost = \"localhost\"\nports = [5432, 5433]"
+        );
+        let properties = config
+            .plan_with_format(&ctx, OutputFormat::Properties)
+            .unwrap();
+        assert_eq!(
+            properties,
+            "name=my app\nport=8080\ndebug=true\ndb.host=localhost\ndb.ports[0]=5432\ndb.ports[1]=5433"
+        );
+        assert_eq!(
+            config
+                .plan_with_format(&ctx, OutputFormat::Env)
+                .unwrap_err(),
+            "the dict value of 'db' can't be represented in the env output format, only the scalar values are supported"
+        );
+        assert_eq!(
+            config
+                .plan_with_format(&ctx, OutputFormat::TfJson)
+                .unwrap_err(),
+            "'name' is not a Terraform block type, expected one of terraform, provider, variable, output, locals, module, resource, data, check, import, moved, removed"
+        );
+
+        let mut tf = ValueRef::dict(None);
+        tf.dict_update_key_value("locals", ValueRef::dict_str(&[("region", "us-east-1")]));
+        let tf_json = tf.plan_with_format(&ctx, OutputFormat::TfJson).unwrap();
+        assert_eq!(
+            tf_json,
+            "{\n  \"locals\": {\n    \"region\": \"us-east-1\"\n  }\n}"
+        );
+        assert_eq!(
+            ValueRef::int(1)
+                .plan_with_format(&ctx, OutputFormat::Toml)
+                .unwrap_err(),
+            "the toml output format requires a config at the top level, got int"
+        );
+    }
 }
@@ -0,0 +1,302 @@
+//! Copyright The KCL Authors. All rights reserved.
+//!
+//! The source provenance of the planned values, which maps each output path, e.g.,
+//! `app.spec.replicas`, to the source positions and the operations which produced its
+//! final value.
+
+use kcl_primitives::IndexMap;
+use serde::{Deserialize, Serialize, Serializer};
+
+use crate::val_plan::KCL_PRIVATE_VAR_PREFIX;
+use crate::*;
+
+/// The operation symbol of the values set by the CLI overrides, e.g., `-O app.replicas=3`.
+pub const OVERRIDE_PROVENANCE_OP: &str = "-O";
+
+/// Provenance denotes a source position and the operation which produced a config value.
+#[derive(PartialEq, Eq, Clone, Default, Debug, Serialize, Deserialize)]
+pub struct Provenance {
+    pub filename: String,
+    pub line: u64,
+    pub column: u64,
+    /// The operation symbol, `=` for override, `:` for union, `+=` for insert and `-O`
+    /// for the CLI overrides, whose position is empty.
+    pub op: String,
+}
+
+impl Provenance {
+    /// New a provenance with the config entry operation at the source position.
+    pub fn new(filename: &str, line: u64, column: u64, op: &ConfigEntryOperationKind) -> Self {
+        Self {
+            filename: filename.to_string(),
+            line,
+            column,
+            op: op.symbol().to_string(),
+        }
+    }
+
+    /// New a provenance of the value set by the CLI overrides.
+    pub fn cli_override() -> Self {
+        Self {
+            op: OVERRIDE_PROVENANCE_OP.to_string(),
+            ..Default::default()
+        }
+    }
+}
+
+impl ConfigEntryOperationKind {
+    /// Returns the operation symbol in the config entries.
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            ConfigEntryOperationKind::Union => ":",
+            ConfigEntryOperationKind::Override => "=",
+            ConfigEntryOperationKind::Insert => "+=",
+        }
+    }
+}
+
+/// The provenance map from the output paths to the provenances in the order of the paths
+/// in the planned value.
+#[derive(PartialEq, Clone, Default, Debug)]
+pub struct ProvenanceMap(pub IndexMap<String, Vec<Provenance>>);
+
+impl ProvenanceMap {
+    /// Returns the JSON string of the provenance map.
+    pub fn to_json_string(&self) -> String {
+        serde_json::to_string(self).expect("failed to serialize the provenance map")
+    }
+}
+
+impl Serialize for ProvenanceMap {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.0.iter())
+    }
+}
+
+impl DictValue {
+    /// Merge the provenances of the `key` entry produced with the operation `op`. The
+    /// override operation and the union of the non-config values replace the provenances,
+    /// and the others append them, because all of them contribute to the final value.
+    pub fn merge_provenances(
+        &mut self,
+        key: &str,
+        provenances: &[Provenance],
+        op: &ConfigEntryOperationKind,
+    ) {
+        if provenances.is_empty() {
+            return;
+        }
+        let replace = match op {
+            ConfigEntryOperationKind::Override => true,
+            ConfigEntryOperationKind::Union => {
+                !self.values.get(key).is_some_and(|value| value.is_config())
+            }
+            ConfigEntryOperationKind::Insert => false,
+        };
+        let entry = self.provenances.entry(key.into()).or_default();
+        if replace {
+            entry.clear();
+        }
+        for provenance in provenances {
+            if !entry.contains(provenance) {
+                entry.push(provenance.clone());
+            }
+        }
+    }
+
+    /// Merge the provenances of all the entries in the `delta` dict, which does nothing when
+    /// the provenance map is disabled because no provenances are recorded.
+    pub fn merge_provenances_from(&mut self, delta: &DictValue) {
+        for (key, provenances) in &delta.provenances {
+            let op = delta
+                .ops
+                .get(key)
+                .unwrap_or(&ConfigEntryOperationKind::Union);
+            self.merge_provenances(key, provenances, op);
+        }
+    }
+
+    /// Copy the provenances of the `key` entry from the `other` dict. The provenances are
+    /// only recorded when the provenance map is enabled, so it does nothing otherwise.
+    pub fn copy_provenances(&mut self, other: &DictValue, key: &str) {
+        if other.provenances.is_empty() {
+            return;
+        }
+        if let Some(provenances) = other.provenances.get(key) {
+            self.provenances.insert(key.into(), provenances.clone());
+        }
+    }
+}
+
+impl ValueRef {
+    /// Record the provenance of the config entry `key`, see [`DictValue::merge_provenances`].
+    pub fn dict_add_provenance(
+        &mut self,
+        key: &str,
+        provenance: Provenance,
+        op: &ConfigEntryOperationKind,
+    ) {
+        match &mut *self.rc.borrow_mut() {
+            Value::dict_value(dict) => dict.merge_provenances(key, &[provenance], op),
+            Value::schema_value(schema) => schema.config.merge_provenances(key, &[provenance], op),
+            _ => {}
+        }
+    }
+
+    /// Returns the provenance map of the planned value with the plan options, where the
+    /// `roots` are the provenances of the top-level keys. The values without the recorded
+    /// provenances, e.g., the list elements and the values returned by functions, inherit
+    /// the provenances of their parents.
+    pub fn provenance_map(
+        &self,
+        ctx: &Context,
+        roots: &IndexMap<String, Vec<Provenance>>,
+    ) -> ProvenanceMap {
+        let mut map = ProvenanceMap::default();
+        if self.is_config() {
+            let dict = self.as_dict_ref();
+            for (key, value) in &dict.values {
+                if key.starts_with(KCL_PRIVATE_VAR_PREFIX) && !ctx.plan_opts.show_hidden {
+                    continue;
+                }
+                let provenances = dict
+                    .provenances
+                    .get(key)
+                    .filter(|provenances| !provenances.is_empty())
+                    .or_else(|| roots.get(key.as_str()))
+                    .cloned()
+                    .unwrap_or_default();
+                collect_provenances(ctx, key, value, &provenances, &mut map);
+            }
+        }
+        let opts = &ctx.plan_opts;
+        if !opts.query_paths.is_empty() {
+            map.0.retain(|path, _| {
+                opts.query_paths
+                    .iter()
+                    .any(|query_path| is_path_or_sub_path(path, query_path))
+            });
+        }
+        for override_path in &opts.override_paths {
+            for (path, provenances) in map.0.iter_mut() {
+                if is_path_or_sub_path(path, override_path) {
+                    *provenances = vec![Provenance::cli_override()];
+                }
+            }
+        }
+        map
+    }
+}
+
+/// Collect the provenances of the `value` at `path` and its children.
+fn collect_provenances(
+    ctx: &Context,
+    path: &str,
+    value: &ValueRef,
+    provenances: &[Provenance],
+    map: &mut ProvenanceMap,
+) {
+    if value.is_undefined() || value.is_func() || (value.is_none() && ctx.plan_opts.disable_none) {
+        return;
+    }
+    map.0.insert(path.to_string(), provenances.to_vec());
+    if value.is_config() {
+        let dict = value.as_dict_ref();
+        for (key, value) in &dict.values {
+            if key.starts_with(KCL_PRIVATE_VAR_PREFIX) && !ctx.plan_opts.show_hidden {
+                continue;
+            }
+            let child_provenances = match dict.provenances.get(key) {
+                Some(child_provenances) if !child_provenances.is_empty() => child_provenances,
+                _ => provenances,
+            };
+            collect_provenances(ctx, &format!("{path}.{key}"), value, child_provenances, map);
+        }
+    } else if value.is_list() {
+        for (index, value) in value.as_list_ref().values.iter().enumerate() {
+            collect_provenances(ctx, &format!("{path}[{index}]"), value, provenances, map);
+        }
+    }
+}
+
+/// Whether the `path` is the `parent` path or a sub path of it, e.g., `a.b` and `a.b[0]`
+/// are the sub paths of `a`.
+fn is_path_or_sub_path(path: &str, parent: &str) -> bool {
+    path.strip_prefix(parent)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('.') || rest.starts_with('['))
+}
+
+#[cfg(test)]
+mod test_value_provenance {
+    use crate::*;
+
+    fn provenance(line: u64, op: &ConfigEntryOperationKind) -> Provenance {
+        Provenance::new("main.k", line, 4, op)
+    }
+
+    #[test]
+    fn test_merge_provenances() {
+        let mut dict = DictValue::default();
+        dict.values.insert("a".into(), ValueRef::int(1));
+        dict.values.insert("b".into(), ValueRef::dict(None));
+        dict.values.insert("c".into(), ValueRef::list_int(&[1]));
+        let union = ConfigEntryOperationKind::Union;
+        let insert = ConfigEntryOperationKind::Insert;
+        let override_ = ConfigEntryOperationKind::Override;
+        for (key, op) in [("a", &union), ("b", &union), ("c", &override_)] {
+            dict.merge_provenances(key, &[provenance(1, op)], op);
+            dict.merge_provenances(key, &[provenance(2, op)], op);
+        }
+        dict.merge_provenances("c", &[provenance(3, &insert)], &insert);
+        assert_eq!(dict.provenances["a"], vec![provenance(2, &union)]);
+        assert_eq!(
+            dict.provenances["b"],
+            vec![provenance(1, &union), provenance(2, &union)]
+        );
+        assert_eq!(
+            dict.provenances["c"],
+            vec![provenance(2, &override_), provenance(3, &insert)]
+        );
+    }
+
+    #[test]
+    fn test_provenance_map() {
+        let mut ctx = Context::new();
+        let op = ConfigEntryOperationKind::Override;
+        let mut spec = ValueRef::dict(None);
+        spec.dict_update_key_value("replicas", ValueRef::int(1));
+        spec.dict_update_key_value("ports", ValueRef::list_int(&[80]));
+        spec.dict_add_provenance("replicas", provenance(3, &op), &op);
+        let mut app = ValueRef::dict(None);
+        app.dict_update_key_value("spec", spec);
+        app.dict_update_key_value("_hidden", ValueRef::int(1));
+        let mut value = ValueRef::dict(None);
+        value.dict_update_key_value("app", app);
+        let mut roots = kcl_primitives::IndexMap::default();
+        roots.insert("app".to_string(), vec![provenance(1, &op)]);
+
+        let map = value.provenance_map(&ctx, &roots);
+        assert_eq!(
+            map.0.keys().collect::<Vec<_>>(),
+            vec![
+                "app",
+                "app.spec",
+                "app.spec.replicas",
+                "app.spec.ports",
+                "app.spec.ports[0]"
+            ]
+        );
+        assert_eq!(map.0["app.spec"], vec![provenance(1, &op)]);
+        assert_eq!(map.0["app.spec.replicas"], vec![provenance(3, &op)]);
+        assert_eq!(
+            map.to_json_string(),
+            r#"{"app":[{"filename":"main.k","line":1,"column":4,"op":"="}],"app.spec":[{"filename":"main.k","line":1,"column":4,"op":"="}],"app.spec.replicas":[{"filename":"main.k","line":3,"column":4,"op":"="}],"app.spec.ports":[{"filename":"main.k","line":1,"column":4,"op":"="}],"app.spec.ports[0]":[{"filename":"main.k","line":1,"column":4,"op":"="}]}"#
+        );
+
+        ctx.plan_opts.query_paths = vec!["app.spec".to_string()];
+        ctx.plan_opts.override_paths = vec!["app.spec.replicas".to_string()];
+        let map = value.provenance_map(&ctx, &roots);
+        assert_eq!(map.0.len(), 4);
+        assert_eq!(map.0["app.spec.replicas"], vec![Provenance::cli_override()]);
+    }
+}
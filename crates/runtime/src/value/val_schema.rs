@@ -37,6 +37,10 @@ pub fn schema_config_meta(filename: &str, line: u64, column: u64) -> ValueRef {
 
 pub fn schema_assert(ctx: &mut Context, value: &ValueRef, msg: &str, config_meta: &ValueRef) {
     if !value.is_truthy() {
+        let panic_info = ctx
+            .cfg
+            .collect_check_failures
+            .then(|| ctx.panic_info.clone());
         ctx.set_err_type(&RuntimeErrorType::SchemaCheckFailure);
         if let Some(config_meta_file) = config_meta.get_by_key(CONFIG_META_FILENAME) {
             let config_meta_line = config_meta.get_by_key(CONFIG_META_LINE).unwrap();
@@ -59,6 +63,13 @@ pub fn schema_assert(ctx: &mut Context, value: &ValueRef, msg: &str, config_meta
         );
         ctx.set_kcl_location_info(Some(arg_msg.as_str()), None, None, None);
 
+        // Record the failure and restore the panic information to continue the evaluation.
+        if let Some(panic_info) = panic_info {
+            let mut failure = std::mem::replace(&mut ctx.panic_info, panic_info);
+            failure.message = msg.to_string();
+            ctx.check_failures.push(failure);
+            return;
+        }
         panic!("{}", msg);
     }
 }
@@ -301,6 +312,11 @@ impl ValueRef {
                     insert_indexs.insert(k.clone(), *index);
                 }
             }
+            if !value.config.provenances.is_empty() {
+                for k in value.config.values.keys() {
+                    schema.config.copy_provenances(&value.config, k);
+                }
+            }
         }
     }
 }
@@ -246,6 +246,8 @@ impl ValueRef {
                     }
                 }
             }
+            // Update provenances
+            obj.merge_provenances_from(delta);
         };
 
         // Whether to union schema vars and resolve it and do the check of schema.
@@ -110,6 +110,18 @@ impl SymbolData {
         &self.schemas
     }
 
+    /// Get the schemas whose parent schema, protocol or mixins is the schema.
+    pub fn get_schema_subtypes(&self, schema: SymbolRef) -> Vec<SymbolRef> {
+        self.schemas
+            .iter()
+            .filter(|(_, symbol)| symbol.get_supertypes(self).contains(&schema))
+            .map(|(id, _)| SymbolRef {
+                id,
+                kind: SymbolKind::Schema,
+            })
+            .collect()
+    }
+
     pub fn get_package_symbol(&self, id: SymbolRef) -> Option<&PackageSymbol> {
         if matches!(id.get_kind(), SymbolKind::Package) {
             self.packages.get(id.get_id())
@@ -1274,6 +1286,17 @@ impl SchemaSymbol {
         }
     }
 
+    /// Get the schema definitions of the parent schema, the protocol and the mixins.
+    pub fn get_supertypes(&self, data: &SymbolData) -> Vec<SymbolRef> {
+        self.parent_schema
+            .iter()
+            .chain(self.for_host.iter())
+            .chain(self.mixins.iter())
+            .filter_map(|symbol_ref| data.get_symbol(*symbol_ref)?.get_definition())
+            .filter(|def| def.get_kind() == SymbolKind::Schema)
+            .collect()
+    }
+
     pub fn get_protocol_and_mixin_attrs(
         &self,
         data: &SymbolData,
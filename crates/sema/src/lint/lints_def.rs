@@ -31,12 +31,7 @@ pub static IMPORT_POSITION: &Lint = &Lint {
 declare_lint_pass!(ImportPosition => [IMPORT_POSITION]);
 
 impl LintPass for ImportPosition {
-    fn check_module(
-        &mut self,
-        handler: &mut Handler,
-        _ctx: &mut LintContext,
-        module: &ast::Module,
-    ) {
+    fn check_module(&mut self, handler: &mut Handler, ctx: &mut LintContext, module: &ast::Module) {
         let mut first_non_importstmt = u64::MAX;
         for stmt in &module.body {
             match &stmt.node {
@@ -52,7 +47,9 @@ impl LintPass for ImportPosition {
             if let ast::Stmt::Import(_import_stmt) = &stmt.node
                 && stmt.line > first_non_importstmt
             {
-                handler.add_warning(
+                ctx.report(
+                    handler,
+                    IMPORT_POSITION,
                     WarningKind::ImportPositionWarning,
                     &[Message {
                         range: stmt.get_span_pos(),
@@ -95,14 +92,16 @@ pub static UNUSED_IMPORT: &Lint = &Lint {
 declare_lint_pass!(UnusedImport => [UNUSED_IMPORT]);
 
 impl LintPass for UnusedImport {
-    fn check_scope(&mut self, handler: &mut Handler, _ctx: &mut LintContext, scope: &Scope) {
+    fn check_scope(&mut self, handler: &mut Handler, ctx: &mut LintContext, scope: &Scope) {
         let scope_objs = &scope.elems;
         for (_, scope_obj) in scope_objs {
             let scope_obj = scope_obj.borrow();
             if let ScopeObjectKind::Module(m) = &scope_obj.kind {
                 for (stmt, has_used) in &m.import_stmts {
                     if !has_used {
-                        handler.add_warning(
+                        ctx.report(
+                            handler,
+                            UNUSED_IMPORT,
                             WarningKind::UnusedImportWarning,
                             &[Message {
                                 range: stmt.get_span_pos(),
@@ -144,17 +143,14 @@ pub static REIMPORT: &Lint = &Lint {
 declare_lint_pass!(ReImport => [REIMPORT]);
 
 impl LintPass for ReImport {
-    fn check_module(
-        &mut self,
-        handler: &mut Handler,
-        _ctx: &mut LintContext,
-        module: &ast::Module,
-    ) {
+    fn check_module(&mut self, handler: &mut Handler, ctx: &mut LintContext, module: &ast::Module) {
         let mut import_names = IndexSet::<String>::default();
         for stmt in &module.body {
             if let ast::Stmt::Import(import_stmt) = &stmt.node {
                 if import_names.contains(&import_stmt.path.node) {
-                    handler.add_warning(
+                    ctx.report(
+                        handler,
+                        REIMPORT,
                         WarningKind::ReimportWarning,
                         &[Message {
                             range: stmt.get_span_pos(),
@@ -70,7 +70,11 @@
 //!    }
 //!    ```
 //!
-//! 6. If new `check_*` method was added in step 4, it needs to override the walk_* method in Linter.
+//! 6. Report the lint diagnostics using `LintContext::report` in the `check_*` methods, which applies the
+//!    lint levels configured by users, e.g., in the `[lint]` section of 'kcl.mod', and the inline suppression
+//!    comments, e.g., `# kcl-lint: disable=W0411`.
+//!
+//! 7. If new `check_*` method was added in step 4, it needs to override the walk_* method in Linter.
 //!    In addition to calling the self.pass.check_* function, the original walk method in MutSelfWalker
 //!    should be copied here so that it can continue to traverse the child nodes.
 
@@ -84,7 +88,11 @@ mod types;
 use kcl_ast::ast;
 use kcl_ast::walker::MutSelfWalker;
 
-pub use self::{combinedlintpass::CombinedLintPass, lintpass::LintPass, types::LintContext};
+pub use self::{
+    combinedlintpass::CombinedLintPass,
+    lintpass::LintPass,
+    types::{LINT_SUPPRESSION_PREFIX, LintConfig, LintContext, LintLevel},
+};
 
 /// The struct `Linter` is used to traverse the AST and call the `check_*` method defined in `CombinedLintPass`.
 pub struct Linter<T: LintPass> {
@@ -99,6 +107,8 @@ impl LintContext {
             filename: "".to_string(),
             start_pos: Position::dummy_pos(),
             end_pos: Position::dummy_pos(),
+            config: LintConfig::default(),
+            suppressions: Default::default(),
         }
     }
 }
@@ -117,6 +127,13 @@ impl Linter<CombinedLintPass> {
             ctx: LintContext::dummy_ctx(),
         }
     }
+
+    /// New a linter with the lint levels configured by users.
+    pub fn new_with_config(config: LintConfig) -> Self {
+        let mut linter = Self::new();
+        linter.ctx.config = config;
+        linter
+    }
     pub fn walk_scope(&mut self, scope: &Scope) {
         self.pass
             .check_scope(&mut self.handler, &mut self.ctx, scope);
@@ -127,6 +144,13 @@ impl Resolver<'_> {
     /// Iterate the module and run lint checks, generating diagnostics and save them in `lint.handler`
     pub fn lint_check_module(&mut self, module: &ast::Module) {
         self.linter.ctx.filename = module.filename.clone();
+        self.linter.ctx.add_suppressions(
+            &module.filename,
+            module
+                .comments
+                .iter()
+                .map(|comment| (comment.line, comment.node.text.as_str())),
+        );
         self.linter.walk_module(module);
     }
     /// Recursively iterate the scope and its child scope, run lint checks, generating diagnostics and save them in `lint.handler`
@@ -1,4 +1,13 @@
-use kcl_error::{Level, Position};
+use std::{collections::HashMap, str::FromStr};
+
+use anyhow::{Result, bail};
+use kcl_error::{Diagnostic, DiagnosticId, Handler, Level, Message, Position, WarningKind};
+use kcl_primitives::IndexMap;
+
+use super::CombinedLintPass;
+
+/// The comment prefix to suppress the lints on a statement, e.g., `# kcl-lint: disable=W0411,W0404`.
+pub const LINT_SUPPRESSION_PREFIX: &str = "kcl-lint:";
 
 /// Record the information at `LintContext` when traversing the AST for analysis across AST nodes, e.g., record
 /// used importstmt(used_import_names) when traversing `ast::Identifier` and `ast::SchemaAttr`, and detect unused
@@ -10,6 +19,154 @@ pub struct LintContext {
     pub start_pos: Position,
     /// Are we resolving the ast node end position.
     pub end_pos: Position,
+    /// The lint levels configured by users.
+    pub config: LintConfig,
+    /// The suppressed lint codes or names keyed by the file name and line.
+    pub suppressions: HashMap<String, HashMap<u64, Vec<String>>>,
+}
+
+impl LintContext {
+    /// Report the lint diagnostic with the configured level, and the lint is skipped
+    /// when it is allowed in the config or suppressed on the line of the first message.
+    pub fn report(&self, handler: &mut Handler, lint: &Lint, kind: WarningKind, msgs: &[Message]) {
+        let level = match self.config.level(lint) {
+            Some(level) => level,
+            None => return,
+        };
+        if let Some(msg) = msgs.first()
+            && self.is_suppressed(lint, &msg.range.0)
+        {
+            return;
+        }
+        handler.add_diagnostic(Diagnostic {
+            level,
+            messages: msgs.to_owned(),
+            code: Some(DiagnosticId::Warning(kind)),
+        });
+    }
+
+    /// Record the lint suppression comments of the module. A suppression comment applies to
+    /// the statement on the same line and the statement on the next line.
+    pub fn add_suppressions<'a>(
+        &mut self,
+        filename: &str,
+        comments: impl IntoIterator<Item = (u64, &'a str)>,
+    ) {
+        let mut suppressions: HashMap<u64, Vec<String>> = HashMap::new();
+        for (line, text) in comments {
+            let text = text.trim_start_matches('#').trim();
+            let rules = match text
+                .strip_prefix(LINT_SUPPRESSION_PREFIX)
+                .and_then(|rest| rest.trim().strip_prefix("disable="))
+            {
+                Some(rules) => rules,
+                None => continue,
+            };
+            let rules: Vec<String> = rules
+                .split(',')
+                .map(normalize_rule)
+                .filter(|rule| !rule.is_empty())
+                .collect();
+            for line in [line, line + 1] {
+                suppressions
+                    .entry(line)
+                    .or_default()
+                    .extend(rules.iter().cloned());
+            }
+        }
+        self.suppressions.insert(filename.to_string(), suppressions);
+    }
+
+    fn is_suppressed(&self, lint: &Lint, pos: &Position) -> bool {
+        self.suppressions
+            .get(&pos.filename)
+            .and_then(|lines| lines.get(&pos.line))
+            .map(|rules| rules.iter().any(|rule| rule == "all" || lint.matches(rule)))
+            .unwrap_or_default()
+    }
+}
+
+/// The lint level configured by users.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LintLevel {
+    /// The lint is disabled.
+    Allow,
+    /// The lint is reported as a warning.
+    Warn,
+    /// The lint is reported as an error.
+    Deny,
+}
+
+impl FromStr for LintLevel {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "allow" | "off" => Ok(LintLevel::Allow),
+            "warn" | "warning" => Ok(LintLevel::Warn),
+            "deny" | "error" => Ok(LintLevel::Deny),
+            _ => bail!(
+                "unknown lint level '{}', expected one of allow, warn and deny",
+                s
+            ),
+        }
+    }
+}
+
+/// The lint levels configured by users, e.g., in the `[lint]` section of 'kcl.mod',
+/// which are keyed by the lint code or name, e.g., `W0411` or `unused_import`. The names of
+/// the builtin lints are resolved to their codes, so a lint configured by both the code and the
+/// name uses the level set later.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LintConfig {
+    levels: IndexMap<String, LintLevel>,
+}
+
+impl LintConfig {
+    /// Build the lint config from the rule levels, the later rules override the earlier ones.
+    pub fn from_rules<'a>(rules: impl IntoIterator<Item = (&'a str, &'a str)>) -> Result<Self> {
+        let mut config = LintConfig::default();
+        for (rule, level) in rules {
+            config.set(rule, level.parse()?)?;
+        }
+        Ok(config)
+    }
+
+    /// Set the level of the lint code or name, which overrides the level set before. An error
+    /// is returned when the rule is neither the code nor the name of a builtin lint.
+    pub fn set(&mut self, rule: &str, level: LintLevel) -> Result<()> {
+        let normalized = normalize_rule(rule);
+        let code = match CombinedLintPass::get_lints()
+            .into_iter()
+            .find(|lint| lint.matches(&normalized))
+        {
+            Some(lint) => lint.code.to_lowercase(),
+            None => bail!(
+                "unknown lint '{}', expected a lint code or name, e.g., W0411 or unused_import",
+                rule
+            ),
+        };
+        self.levels.insert(code, level);
+        Ok(())
+    }
+
+    /// Returns the diagnostic level of the lint, or `None` when the lint is allowed.
+    pub fn level(&self, lint: &Lint) -> Option<Level> {
+        match self.levels.get(&lint.code.to_lowercase()) {
+            Some(LintLevel::Allow) => None,
+            Some(LintLevel::Warn) => Some(Level::Warning),
+            Some(LintLevel::Deny) => Some(Level::Error),
+            None => Some(lint.level),
+        }
+    }
+}
+
+/// Normalize the lint code or name to the lowercase snake case.
+fn normalize_rule(rule: &str) -> String {
+    rule.trim()
+        .trim_matches('"')
+        .to_lowercase()
+        .replace('-', "_")
 }
 
 /// Definition of `Lint` struct
@@ -32,6 +189,13 @@ pub struct Lint {
     pub note: Option<&'static str>,
 }
 
+impl Lint {
+    /// Whether the normalized lint code or name refers to the lint.
+    fn matches(&self, rule: &str) -> bool {
+        self.code.to_lowercase() == rule || normalize_rule(self.name) == rule
+    }
+}
+
 pub type LintArray = Vec<&'static Lint>;
 
 /// Declares a static `LintArray` and return it as an expression.
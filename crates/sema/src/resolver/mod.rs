@@ -24,7 +24,7 @@ use kcl_primitives::{IndexMap, IndexSet};
 use std::sync::Arc;
 use std::{cell::RefCell, rc::Rc};
 
-use crate::lint::{CombinedLintPass, Linter};
+use crate::lint::{CombinedLintPass, LintConfig, Linter};
 use crate::pre_process::pre_process_program;
 use crate::resolver::scope::ScopeObject;
 use crate::resolver::ty_alias::type_alias_pass;
@@ -56,6 +56,7 @@ impl<'ctx> Resolver<'ctx> {
     pub fn new(program: &'ctx Program, options: Options) -> Self {
         let builtin_scope = Rc::new(RefCell::new(builtin_scope()));
         let scope = Rc::clone(&builtin_scope);
+        let linter = Linter::<CombinedLintPass>::new_with_config(options.lint_config.clone());
         Resolver {
             program,
             scope_map: IndexMap::default(),
@@ -66,7 +67,7 @@ impl<'ctx> Resolver<'ctx> {
             ctx: Context::default(),
             options,
             handler: Handler::default(),
-            linter: Linter::<CombinedLintPass>::new(),
+            linter,
         }
     }
 
@@ -169,12 +170,14 @@ pub struct Context {
 /// Resolve options.
 /// - lint_check: whether to run lint passes
 /// - resolve_val: whether to resolve and print their AST to value for some nodes.
+/// - lint_config: the lint levels configured by users.
 #[derive(Clone, Debug)]
 pub struct Options {
     pub lint_check: bool,
     pub resolve_val: bool,
     pub merge_program: bool,
     pub type_erasure: bool,
+    pub lint_config: LintConfig,
 }
 
 impl Default for Options {
@@ -184,6 +187,7 @@ impl Default for Options {
             resolve_val: false,
             merge_program: true,
             type_erasure: true,
+            lint_config: LintConfig::default(),
         }
     }
 }
@@ -9,6 +9,8 @@ use std::{
     sync::Arc,
 };
 
+use crate::workspace_symbol::WorkspaceSymbolIndex;
+
 pub type DocumentVersion = i32;
 
 #[derive(Default, Clone)]
@@ -39,4 +41,6 @@ pub struct AnalysisDatabase {
     pub gs: GlobalState,
     pub diags: IndexSet<Diagnostic>,
     pub schema_map: IndexMap<String, Vec<SchemaType>>,
+    /// Top-level symbols of all the packages in the workspace.
+    pub symbol_index: WorkspaceSymbolIndex,
 }
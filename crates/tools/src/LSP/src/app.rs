@@ -1,3 +1,4 @@
+use crate::capabilities::PullDiagnostics;
 use crate::state::LanguageServerState;
 use clap::{Command, builder::Str};
 use lsp_server::Connection;
@@ -7,8 +8,10 @@ use lsp_types::InitializeParams;
 pub fn main_loop(
     connection: Connection,
     initialize_params: InitializeParams,
+    pull_diagnostics: PullDiagnostics,
 ) -> anyhow::Result<()> {
-    LanguageServerState::new(connection.sender, initialize_params).run(connection.receiver)
+    LanguageServerState::new(connection.sender, initialize_params, pull_diagnostics)
+        .run(connection.receiver)
 }
 
 /// Get the kcl language server CLI application.
@@ -17,4 +20,8 @@ pub fn app() -> Command {
         .version(Str::from(kcl_version::get_version_info()))
         .about("KCL language server CLI.")
         .subcommand(Command::new("version").about("Show the KCL language server version"))
+        .subcommand(
+            Command::new("dap")
+                .about("Start the KCL debug adapter using the Debug Adapter Protocol"),
+        )
 }
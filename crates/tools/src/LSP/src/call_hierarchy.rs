@@ -0,0 +1,304 @@
+//! Call hierarchy for KCL
+//! The calls are the lambda function calls and the schema instantiations, e.g.,
+//! `make_service("web")` and `Service {}`. The callers are the top-level statements
+//! which contain the calls, e.g., the configs, lambdas, schemas and rules.
+
+use std::collections::BTreeSet;
+
+use kcl_ast::ast::{self, Program};
+use kcl_ast::pos::{ContainsPos, GetPos};
+use kcl_ast::walker::MutSelfWalker;
+use kcl_error::Position as KCLPos;
+use kcl_primitives::IndexMap;
+use kcl_sema::core::global_state::GlobalState;
+use kcl_sema::core::symbol::{SymbolKind as KCLSymbolKind, SymbolRef};
+use kcl_sema::ty::TypeKind;
+use lsp_types::{
+    CallHierarchyIncomingCall, CallHierarchyItem, CallHierarchyOutgoingCall, Range, SymbolKind,
+};
+
+use crate::document_symbol::symbol_kind_to_document_symbol_kind;
+use crate::from_lsp::{file_path_from_url, kcl_pos};
+use crate::goto_def::find_def;
+use crate::to_lsp::{lsp_location, lsp_pos};
+
+/// Prepares the call hierarchy item of the schema, rule, function or global value at the position.
+pub fn prepare_call_hierarchy(
+    kcl_pos: &KCLPos,
+    gs: &GlobalState,
+) -> Option<Vec<CallHierarchyItem>> {
+    let def = find_def(kcl_pos, gs, true)?;
+    match def.get_kind() {
+        KCLSymbolKind::Schema | KCLSymbolKind::Rule | KCLSymbolKind::Function => {}
+        KCLSymbolKind::Value if gs.get_symbols().get_symbol(def)?.is_global() => {}
+        _ => return None,
+    }
+    Some(vec![hierarchy_item(gs, def)?])
+}
+
+/// Finds the statements which call the item, grouped by the callers.
+pub fn incoming_calls(
+    item: &CallHierarchyItem,
+    gs: &GlobalState,
+    prog: &Program,
+) -> Option<Vec<CallHierarchyIncomingCall>> {
+    let def = item_def(item, gs)?;
+    let symbols = gs.get_symbols();
+    // Only the files which reference the item may call it.
+    let files: BTreeSet<String> = symbols
+        .get_symbol(def)?
+        .get_references()
+        .iter()
+        .filter_map(|symbol_ref| symbols.get_symbol(*symbol_ref))
+        .map(|symbol| symbol.get_range().0.filename)
+        .collect();
+    let mut calls: IndexMap<SymbolRef, Vec<Range>> = IndexMap::default();
+    for file in files {
+        let Ok(Some(module)) = prog.get_module(&file) else {
+            continue;
+        };
+        for stmt in caller_stmts(&module.body) {
+            let from_ranges: Vec<Range> = call_sites(stmt)
+                .into_iter()
+                .filter(|(start, _)| find_def(start, gs, true) == Some(def))
+                .map(|(start, end)| lsp_range(&start, &end))
+                .collect();
+            if from_ranges.is_empty() {
+                continue;
+            }
+            if let Some(caller) = stmt_def(stmt, gs) {
+                calls.entry(caller).or_default().extend(from_ranges);
+            }
+        }
+    }
+    Some(
+        calls
+            .into_iter()
+            .filter_map(|(caller, from_ranges)| {
+                Some(CallHierarchyIncomingCall {
+                    from: hierarchy_item(gs, caller)?,
+                    from_ranges,
+                })
+            })
+            .collect(),
+    )
+}
+
+/// Finds the lambdas and schemas called by the item, grouped by the callees.
+pub fn outgoing_calls(
+    item: &CallHierarchyItem,
+    gs: &GlobalState,
+    prog: &Program,
+) -> Option<Vec<CallHierarchyOutgoingCall>> {
+    let def = item_def(item, gs)?;
+    let (start, _) = gs.get_symbols().get_symbol(def)?.get_range();
+    let module = prog.get_module(&start.filename).ok()??;
+    let stmt = caller_stmts(&module.body)
+        .into_iter()
+        .find(|stmt| stmt.contains_pos(&start) && stmt_def(stmt, gs) == Some(def))?;
+    let mut calls: IndexMap<SymbolRef, Vec<Range>> = IndexMap::default();
+    for (start, end) in call_sites(stmt) {
+        if let Some(callee) = find_def(&start, gs, true) {
+            calls
+                .entry(callee)
+                .or_default()
+                .push(lsp_range(&start, &end));
+        }
+    }
+    Some(
+        calls
+            .into_iter()
+            .filter_map(|(callee, from_ranges)| {
+                Some(CallHierarchyOutgoingCall {
+                    to: hierarchy_item(gs, callee)?,
+                    from_ranges,
+                })
+            })
+            .collect(),
+    )
+}
+
+/// Converts the definition symbol to the call or type hierarchy item. Symbols without
+/// source files, e.g., the builtin functions, have no items.
+pub(crate) fn hierarchy_item(gs: &GlobalState, def: SymbolRef) -> Option<CallHierarchyItem> {
+    let symbol = gs.get_symbols().get_symbol(def)?;
+    let (start, end) = symbol.get_range();
+    let location = lsp_location(start.filename.clone(), &start, &end)?;
+    let ty = symbol.get_sema_info().ty.clone();
+    let kind = match def.get_kind() {
+        KCLSymbolKind::Schema
+            if ty.as_ref().is_some_and(
+                |ty| matches!(&ty.kind, TypeKind::Schema(schema_ty) if schema_ty.is_protocol),
+            ) =>
+        {
+            SymbolKind::INTERFACE
+        }
+        KCLSymbolKind::Value if ty.as_ref().is_some_and(|ty| ty.is_func()) => SymbolKind::FUNCTION,
+        kind => symbol_kind_to_document_symbol_kind(kind)?,
+    };
+    Some(CallHierarchyItem {
+        name: symbol.get_name(),
+        kind,
+        tags: None,
+        detail: ty.map(|ty| ty.ty_str()),
+        uri: location.uri,
+        range: location.range,
+        selection_range: location.range,
+        data: None,
+    })
+}
+
+/// Finds the definition symbol of the hierarchy item from its selection range.
+pub(crate) fn item_def(item: &CallHierarchyItem, gs: &GlobalState) -> Option<SymbolRef> {
+    let file = file_path_from_url(&item.uri).ok()?;
+    find_def(&kcl_pos(&file, item.selection_range.start), gs, true)
+}
+
+/// Returns the top-level statements including the ones in the top-level if statements.
+fn caller_stmts(stmts: &[ast::NodeRef<ast::Stmt>]) -> Vec<&ast::Node<ast::Stmt>> {
+    let mut result = vec![];
+    for stmt in stmts {
+        match &stmt.node {
+            ast::Stmt::If(if_stmt) => {
+                result.extend(caller_stmts(&if_stmt.body));
+                result.extend(caller_stmts(&if_stmt.orelse));
+            }
+            _ => result.push(stmt.as_ref()),
+        }
+    }
+    result
+}
+
+/// The definition symbol of the statement, which is the caller of all the calls in the statement.
+fn stmt_def(stmt: &ast::Node<ast::Stmt>, gs: &GlobalState) -> Option<SymbolRef> {
+    let name_pos = match &stmt.node {
+        ast::Stmt::Assign(assign_stmt) => assign_stmt.targets.first()?.node.name.get_pos(),
+        ast::Stmt::AugAssign(aug_assign_stmt) => aug_assign_stmt.target.node.name.get_pos(),
+        ast::Stmt::Unification(unification_stmt) => {
+            unification_stmt.target.node.names.first()?.get_pos()
+        }
+        ast::Stmt::Schema(schema_stmt) => schema_stmt.name.get_pos(),
+        ast::Stmt::Rule(rule_stmt) => rule_stmt.name.get_pos(),
+        _ => return None,
+    };
+    find_def(&name_pos, gs, true)
+}
+
+/// Returns the name ranges of the callees in the statement.
+fn call_sites(stmt: &ast::Node<ast::Stmt>) -> Vec<(KCLPos, KCLPos)> {
+    let mut collector = CallSiteCollector::default();
+    collector.walk_stmt(&stmt.node);
+    collector.sites
+}
+
+#[derive(Default)]
+struct CallSiteCollector {
+    sites: Vec<(KCLPos, KCLPos)>,
+}
+
+impl MutSelfWalker for CallSiteCollector {
+    fn walk_call_expr(&mut self, call_expr: &ast::CallExpr) {
+        let name = match &call_expr.func.node {
+            ast::Expr::Identifier(identifier) => identifier.names.last(),
+            ast::Expr::Selector(selector_expr) => selector_expr.attr.node.names.last(),
+            _ => None,
+        };
+        if let Some(name) = name {
+            self.sites.push(name.get_span_pos());
+        }
+        self.walk_expr(&call_expr.func.node);
+        for arg in &call_expr.args {
+            self.walk_expr(&arg.node);
+        }
+        for keyword in &call_expr.keywords {
+            self.walk_keyword(&keyword.node);
+        }
+    }
+
+    fn walk_schema_expr(&mut self, schema_expr: &ast::SchemaExpr) {
+        if let Some(name) = schema_expr.name.node.names.last() {
+            self.sites.push(name.get_span_pos());
+        }
+        for arg in &schema_expr.args {
+            self.walk_expr(&arg.node);
+        }
+        for kwarg in &schema_expr.kwargs {
+            self.walk_keyword(&kwarg.node);
+        }
+        self.walk_expr(&schema_expr.config.node);
+    }
+}
+
+#[inline]
+fn lsp_range(start: &KCLPos, end: &KCLPos) -> Range {
+    Range {
+        start: lsp_pos(start),
+        end: lsp_pos(end),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kcl_error::Position as KCLPos;
+    use lsp_types::SymbolKind;
+    use proc_macro_crate::bench_test;
+
+    use super::{incoming_calls, outgoing_calls, prepare_call_hierarchy};
+    use crate::tests::compile_test_file;
+
+    #[test]
+    #[bench_test]
+    fn call_hierarchy_test() {
+        let (file, prog, _, gs, _) = compile_test_file("src/test_data/hierarchy/hierarchy.k");
+        let pos = |line, column| KCLPos {
+            filename: file.clone(),
+            line,
+            column: Some(column),
+        };
+
+        // schema Service
+        let service = prepare_call_hierarchy(&pos(10, 7), &gs).unwrap().remove(0);
+        assert_eq!(service.name, "Service");
+        assert_eq!(service.kind, SymbolKind::STRUCT);
+        let callers: Vec<(String, u32)> = incoming_calls(&service, &gs, &prog)
+            .unwrap()
+            .into_iter()
+            .map(|call| (call.from.name, call.from_ranges[0].start.line))
+            .collect();
+        assert_eq!(
+            callers,
+            vec![("make_service".to_string(), 14), ("web".to_string(), 23)]
+        );
+
+        // make_service = lambda ...
+        let make_service = prepare_call_hierarchy(&pos(14, 0), &gs).unwrap().remove(0);
+        assert_eq!(make_service.kind, SymbolKind::FUNCTION);
+        let callers: Vec<String> = incoming_calls(&make_service, &gs, &prog)
+            .unwrap()
+            .into_iter()
+            .map(|call| call.from.name)
+            .collect();
+        assert_eq!(callers, vec!["make_services", "svc"]);
+        let callees: Vec<String> = outgoing_calls(&make_service, &gs, &prog)
+            .unwrap()
+            .into_iter()
+            .map(|call| call.to.name)
+            .collect();
+        assert_eq!(callees, vec!["Service"]);
+
+        // Builtin functions have no call hierarchy items.
+        let callees: Vec<String> = outgoing_calls(
+            &prepare_call_hierarchy(&pos(23, 0), &gs).unwrap().remove(0),
+            &gs,
+            &prog,
+        )
+        .unwrap()
+        .into_iter()
+        .map(|call| call.to.name)
+        .collect();
+        assert_eq!(callees, vec!["make_services"]);
+
+        // Attributes have no call hierarchy items.
+        assert!(prepare_call_hierarchy(&pos(12, 4), &gs).is_none());
+    }
+}
@@ -1,30 +1,38 @@
 use lsp_types::{
-    ClientCapabilities, CodeActionKind, CodeActionOptions, CodeActionProviderCapability,
-    CompletionOptions, HoverProviderCapability, OneOf, SemanticTokensFullOptions,
-    SemanticTokensLegend, SemanticTokensOptions, ServerCapabilities, SignatureHelpOptions,
-    TextDocumentSyncCapability, TextDocumentSyncKind, WorkDoneProgressOptions,
+    CallHierarchyServerCapability, ClientCapabilities, CodeActionKind, CodeActionOptions,
+    CodeActionProviderCapability, CodeLensOptions, CompletionOptions, DocumentLinkOptions,
+    ExecuteCommandOptions, FoldingRangeProviderCapability, HoverProviderCapability,
+    ImplementationProviderCapability, OneOf, SelectionRangeProviderCapability,
+    SemanticTokensFullOptions, SemanticTokensLegend, SemanticTokensOptions, ServerCapabilities,
+    SignatureHelpOptions, TextDocumentSyncCapability, TextDocumentSyncKind,
+    TypeDefinitionProviderCapability, WorkDoneProgressOptions,
 };
 
-use crate::semantic_token::LEGEND_TYPE;
+use crate::code_lens::SERVER_COMMANDS;
+use crate::refactor::REFACTOR_MOVE;
+use crate::semantic_token::{LEGEND_MODIFIER, LEGEND_TYPE};
 
 /// Returns the capabilities of this LSP server implementation given the capabilities of the client.
 pub fn server_capabilities(client_caps: &ClientCapabilities) -> ServerCapabilities {
     ServerCapabilities {
-        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(
+            TextDocumentSyncKind::INCREMENTAL,
+        )),
         semantic_tokens_provider: Some(
             lsp_types::SemanticTokensServerCapabilities::SemanticTokensOptions(
                 SemanticTokensOptions {
                     work_done_progress_options: WorkDoneProgressOptions::default(),
                     legend: SemanticTokensLegend {
                         token_types: LEGEND_TYPE.into(),
-                        token_modifiers: vec![],
+                        token_modifiers: LEGEND_MODIFIER.into(),
                     },
-                    range: Some(false),
-                    full: Some(SemanticTokensFullOptions::Bool(true)),
+                    range: Some(true),
+                    full: Some(SemanticTokensFullOptions::Delta { delta: Some(true) }),
                 },
             ),
         ),
         document_symbol_provider: Some(OneOf::Left(true)),
+        workspace_symbol_provider: Some(OneOf::Left(true)),
         completion_provider: Some(CompletionOptions {
             resolve_provider: None,
             trigger_characters: Some(vec![
@@ -41,6 +49,8 @@ pub fn server_capabilities(client_caps: &ClientCapabilities) -> ServerCapabiliti
         }),
         hover_provider: Some(HoverProviderCapability::Simple(true)),
         definition_provider: Some(OneOf::Left(true)),
+        type_definition_provider: Some(TypeDefinitionProviderCapability::Simple(true)),
+        implementation_provider: Some(ImplementationProviderCapability::Simple(true)),
         code_action_provider: Some(
             client_caps
                 .text_document
@@ -52,7 +62,13 @@ pub fn server_capabilities(client_caps: &ClientCapabilities) -> ServerCapabiliti
                         // Advertise support for all built-in CodeActionKinds.
                         // Ideally we would base this off of the client capabilities
                         // but the client is supposed to fall back gracefully for unknown values.
-                        code_action_kinds: Some(vec![CodeActionKind::QUICKFIX]),
+                        code_action_kinds: Some(vec![
+                            CodeActionKind::QUICKFIX,
+                            CodeActionKind::REFACTOR_EXTRACT,
+                            CodeActionKind::REFACTOR_INLINE,
+                            CodeActionKind::REFACTOR_REWRITE,
+                            REFACTOR_MOVE,
+                        ]),
                         resolve_provider: None,
                         work_done_progress_options: Default::default(),
                     })
@@ -70,6 +86,59 @@ pub fn server_capabilities(client_caps: &ClientCapabilities) -> ServerCapabiliti
                 work_done_progress: None,
             },
         }),
+        call_hierarchy_provider: Some(CallHierarchyServerCapability::Simple(true)),
+        folding_range_provider: Some(FoldingRangeProviderCapability::Simple(true)),
+        selection_range_provider: Some(SelectionRangeProviderCapability::Simple(true)),
+        document_highlight_provider: Some(OneOf::Left(true)),
+        code_lens_provider: Some(CodeLensOptions {
+            resolve_provider: Some(false),
+        }),
+        document_link_provider: Some(DocumentLinkOptions {
+            resolve_provider: Some(false),
+            work_done_progress_options: Default::default(),
+        }),
+        execute_command_provider: Some(ExecuteCommandOptions {
+            commands: SERVER_COMMANDS.iter().map(|c| c.to_string()).collect(),
+            work_done_progress_options: WorkDoneProgressOptions::default(),
+        }),
         ..Default::default()
     }
 }
+
+/// The pull diagnostics capabilities of the client, which are not provided by lsp-types 0.93
+/// and are read from the raw initialize params.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PullDiagnostics {
+    /// Whether the client pulls the diagnostics, i.e., `textDocument.diagnostic`. The
+    /// diagnostics are not published to the client when it is true.
+    pub enabled: bool,
+    /// Whether the client supports the `workspace/diagnostic/refresh` request.
+    pub refresh: bool,
+}
+
+impl PullDiagnostics {
+    pub fn from_initialize_params(initialize_params: &serde_json::Value) -> Self {
+        let capabilities = &initialize_params["capabilities"];
+        let enabled = capabilities["textDocument"]["diagnostic"].is_object();
+        Self {
+            enabled,
+            refresh: enabled && capabilities["workspace"]["diagnostics"]["refreshSupport"] == true,
+        }
+    }
+}
+
+/// Adds the capabilities which are not provided by lsp-types 0.93 to the serialized
+/// server capabilities, e.g., the type hierarchy provider and the pull diagnostics provider.
+/// The pull diagnostics provider is only advertised when the client pulls the diagnostics.
+pub fn add_unsupported_capabilities(
+    capabilities: &mut serde_json::Value,
+    pull_diagnostics: PullDiagnostics,
+) {
+    capabilities["typeHierarchyProvider"] = serde_json::Value::Bool(true);
+    if pull_diagnostics.enabled {
+        capabilities["diagnosticProvider"] = serde_json::json!({
+            "interFileDependencies": true,
+            "workspaceDiagnostics": true,
+        });
+    }
+}
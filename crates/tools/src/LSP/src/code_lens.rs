@@ -0,0 +1,536 @@
+//! Code lenses of the runnable entries and the test cases
+//!
+//! - Each `test_*` lambda in the `*_test.k` files gets the "Run test" and "Debug test" lenses,
+//!   the test files which are not compiled in the workspace, e.g., the files excluded by the
+//!   `kcl.mod` profile entries, have no test lenses.
+//! - Each schema gets the "N instances / M references" lens.
+//! - Each entry file of the `kcl.mod` profile gets the "Render YAML" lens.
+//!
+//! The "Run test" and "Render YAML" lenses are executed by the server with the
+//! `workspace/executeCommand` request, which streams the output to the client with
+//! the `kcl/commandOutput` notification. The other lenses are executed by the client,
+//! e.g., the "Debug test" lens starts the KCL debugger with the launch configuration.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Result, anyhow};
+use crossbeam_channel::Sender;
+use kcl_ast::ast::{Program, SchemaExpr, Stmt};
+use kcl_ast::pos::GetPos;
+use kcl_ast::walker::MutSelfWalker;
+use kcl_config::modfile::{KCL_MOD_PATH_ENV, get_pkg_root, load_mod_file};
+use kcl_error::Position as KCLPos;
+use kcl_parser::ParseSessionRef;
+use kcl_runner::{ExecProgramArgs, ExecProgramResult, exec_program};
+use kcl_sema::core::global_state::GlobalState;
+use kcl_tools::testing::{
+    TEST_FILE_SUFFIX, TestOptions, TestRun, load_test_cases, load_test_suites,
+};
+use lsp_types::notification::Notification;
+use lsp_types::{CodeLens, Command, Location, Range, Url};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::goto_def::find_def;
+use crate::refactor::MOVE_SCHEMA_TO_COMMAND;
+use crate::state::Task;
+use crate::to_lsp::{lsp_location, lsp_pos};
+use crate::util::from_json;
+
+/// Runs a test case, the argument is [`TestCommandArgs`].
+pub(crate) const RUN_TEST_COMMAND: &str = "kcl.runTest";
+/// Starts the debugger of a test case in the client, the argument is the DAP launch configuration.
+pub(crate) const DEBUG_TEST_COMMAND: &str = "kcl.debugTest";
+/// Renders the YAML of the `kcl.mod` profile entries, the argument is [`RenderCommandArgs`].
+pub(crate) const RENDER_YAML_COMMAND: &str = "kcl.renderYaml";
+/// Shows the references in the client, the arguments are the uri, the position and the locations.
+pub(crate) const SHOW_REFERENCES_COMMAND: &str = "kcl.showReferences";
+
+/// The commands executed by the server.
+pub(crate) const SERVER_COMMANDS: [&str; 3] = [
+    RUN_TEST_COMMAND,
+    RENDER_YAML_COMMAND,
+    MOVE_SCHEMA_TO_COMMAND,
+];
+
+/// The `kcl/commandOutput` notification which streams the output of the executed commands.
+#[derive(Debug)]
+pub enum CommandOutput {}
+
+impl Notification for CommandOutput {
+    type Params = CommandOutputParams;
+    const METHOD: &'static str = "kcl/commandOutput";
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandOutputParams {
+    pub command: String,
+    pub output: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestCommandArgs {
+    /// The test file which contains the test case.
+    pub file: String,
+    /// The test case name, e.g., `test_alice`.
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RenderCommandArgs {
+    /// The entry file of the `kcl.mod` profile.
+    pub file: String,
+}
+
+pub fn code_lens(file: &str, gs: &GlobalState, prog: &Program) -> Vec<CodeLens> {
+    let mut lenses = test_case_lenses(file, prog);
+    lenses.extend(schema_lenses(file, gs, prog));
+    lenses.extend(render_yaml_lens(file));
+    lenses
+}
+
+/// The "Run test" and "Debug test" lenses of the test cases in the compiled test file.
+fn test_case_lenses(file: &str, prog: &Program) -> Vec<CodeLens> {
+    let mut lenses = vec![];
+    if !file.ends_with(TEST_FILE_SUFFIX) {
+        return lenses;
+    }
+    // The debugged program is the package of the test file, and the module may be parsed
+    // but not compiled in the workspace, e.g., when the file is opened.
+    let Some(program) = prog
+        .pkgs
+        .values()
+        .find(|files| files.iter().any(|f| is_same_file(f, file)))
+        .cloned()
+    else {
+        return lenses;
+    };
+    let Ok(Some(module)) = prog.get_module(file) else {
+        return lenses;
+    };
+    for (name, case) in &load_test_cases(&module, "") {
+        let range = line_range(case.line);
+        lenses.push(CodeLens {
+            range,
+            command: Some(Command {
+                title: "Run test".to_string(),
+                command: RUN_TEST_COMMAND.to_string(),
+                arguments: Some(vec![json!(TestCommandArgs {
+                    file: file.to_string(),
+                    name: name.clone(),
+                })]),
+            }),
+            data: None,
+        });
+        lenses.push(CodeLens {
+            range,
+            command: Some(Command {
+                title: "Debug test".to_string(),
+                command: DEBUG_TEST_COMMAND.to_string(),
+                arguments: Some(vec![json!({
+                    "type": "kcl",
+                    "request": "launch",
+                    "name": format!("Debug {name}"),
+                    "program": program,
+                    "testCase": name,
+                })]),
+            }),
+            data: None,
+        });
+    }
+    lenses
+}
+
+/// The "N instances / M references" lenses of the schemas in the file. The instances
+/// are the references in the schema expressions, e.g., `Person {}`.
+fn schema_lenses(file: &str, gs: &GlobalState, prog: &Program) -> Vec<CodeLens> {
+    let mut lenses = vec![];
+    let Ok(Some(module)) = prog.get_module(file) else {
+        return lenses;
+    };
+    let symbols = gs.get_symbols();
+    let mut instances: HashMap<String, HashSet<(u64, Option<u64>)>> = HashMap::new();
+    for stmt in &module.body {
+        let Stmt::Schema(schema_stmt) = &stmt.node else {
+            continue;
+        };
+        let name_pos = schema_stmt.name.get_pos();
+        let Some(def) = find_def(&name_pos, gs, true) else {
+            continue;
+        };
+        let Some(symbol) = symbols.get_symbol(def) else {
+            continue;
+        };
+        let refs: Vec<(KCLPos, KCLPos)> = symbol
+            .get_references()
+            .iter()
+            .filter_map(|symbol_ref| symbols.get_symbol(*symbol_ref))
+            .map(|symbol| symbol.get_range())
+            .collect();
+        for (start, _) in &refs {
+            instances
+                .entry(start.filename.clone())
+                .or_insert_with(|| instance_positions(prog, &start.filename));
+        }
+        let instance_count = refs
+            .iter()
+            .filter(|(start, _)| {
+                instances
+                    .get(&start.filename)
+                    .is_some_and(|positions| positions.contains(&(start.line, start.column)))
+            })
+            .count();
+        let locations: Vec<Location> = refs
+            .iter()
+            .filter_map(|(start, end)| lsp_location(start.filename.clone(), start, end))
+            .collect();
+        let Ok(uri) = Url::from_file_path(file) else {
+            continue;
+        };
+        let title = format!(
+            "{} / {}",
+            plural(instance_count, "instance"),
+            plural(refs.len(), "reference")
+        );
+        lenses.push(CodeLens {
+            range: line_range(stmt.line),
+            command: Some(Command {
+                title,
+                command: SHOW_REFERENCES_COMMAND.to_string(),
+                arguments: Some(vec![
+                    json!(uri),
+                    json!(lsp_pos(&name_pos)),
+                    json!(locations),
+                ]),
+            }),
+            data: None,
+        });
+    }
+    lenses
+}
+
+/// Returns the name positions of the schema expressions in the file.
+fn instance_positions(prog: &Program, file: &str) -> HashSet<(u64, Option<u64>)> {
+    let mut collector = InstanceCollector::default();
+    if let Ok(Some(module)) = prog.get_module(file) {
+        collector.walk_module(&module);
+    }
+    collector.positions
+}
+
+#[derive(Default)]
+struct InstanceCollector {
+    positions: HashSet<(u64, Option<u64>)>,
+}
+
+impl MutSelfWalker for InstanceCollector {
+    fn walk_schema_expr(&mut self, schema_expr: &SchemaExpr) {
+        if let Some(name) = schema_expr.name.node.names.last() {
+            self.positions.insert((name.line, Some(name.column)));
+        }
+        for arg in &schema_expr.args {
+            self.walk_expr_node(arg);
+        }
+        for kwarg in &schema_expr.kwargs {
+            self.walk_keyword(&kwarg.node);
+        }
+        self.walk_expr_node(&schema_expr.config);
+    }
+}
+
+/// The "Render YAML" lens of the `kcl.mod` profile entry file.
+fn render_yaml_lens(file: &str) -> Option<CodeLens> {
+    let (_, entries) = profile_entries(file)?;
+    if !entries.iter().any(|entry| is_same_file(entry, file)) {
+        return None;
+    }
+    Some(CodeLens {
+        range: line_range(1),
+        command: Some(Command {
+            title: "Render YAML".to_string(),
+            command: RENDER_YAML_COMMAND.to_string(),
+            arguments: Some(vec![json!(RenderCommandArgs {
+                file: file.to_string(),
+            })]),
+        }),
+        data: None,
+    })
+}
+
+/// Executes the server command and streams the output to the client.
+pub(crate) fn execute_command(
+    command: &str,
+    arguments: Vec<Value>,
+    sender: &Sender<Task>,
+) -> Result<Value> {
+    let argument = arguments.into_iter().next().unwrap_or_default();
+    let mut output = |output: String| {
+        let _ = sender.send(Task::Notify(lsp_server::Notification::new(
+            CommandOutput::METHOD.to_string(),
+            CommandOutputParams {
+                command: command.to_string(),
+                output,
+            },
+        )));
+    };
+    match command {
+        RUN_TEST_COMMAND => {
+            let args: TestCommandArgs = from_json(RUN_TEST_COMMAND, argument)?;
+            run_test(&args.file, &args.name, &mut output)
+        }
+        RENDER_YAML_COMMAND => {
+            let args: RenderCommandArgs = from_json(RENDER_YAML_COMMAND, argument)?;
+            Ok(serde_json::to_value(render_yaml(&args.file, &mut output)?)?)
+        }
+        _ => Err(anyhow!("unsupported command '{command}'")),
+    }
+}
+
+/// Runs the test case in the package of the test file, and returns the numbers of
+/// the passed and failed test cases.
+pub(crate) fn run_test(file: &str, name: &str, output: &mut impl FnMut(String)) -> Result<Value> {
+    let dir = Path::new(file)
+        .parent()
+        .ok_or_else(|| anyhow!("the test file {file} has no package"))?;
+    let opts = TestOptions {
+        run_regexp: format!("^{}$", regex::escape(name)),
+        ..Default::default()
+    };
+    let (mut passed, mut failed) = (0, 0);
+    for suite in load_test_suites(dir.to_string_lossy(), &opts)? {
+        let result = suite.run(&opts)?;
+        for (name, info) in &result.info {
+            let mut message = String::new();
+            if !info.log_message.is_empty() {
+                message.push_str(&info.log_message);
+            }
+            match &info.error {
+                Some(err) => {
+                    failed += 1;
+                    message.push_str(&format!("--- FAIL: {name} ({:?})\n{err}\n", info.duration));
+                }
+                None => {
+                    passed += 1;
+                    message.push_str(&format!("--- PASS: {name} ({:?})\n", info.duration));
+                }
+            }
+            output(message);
+        }
+    }
+    Ok(json!({ "passed": passed, "failed": failed }))
+}
+
+/// Executes the `kcl.mod` profile entries of the entry file.
+pub(crate) fn render_yaml(
+    file: &str,
+    output: &mut impl FnMut(String),
+) -> Result<ExecProgramResult> {
+    let (root, entries) =
+        profile_entries(file).ok_or_else(|| anyhow!("{file} is not a kcl.mod profile entry"))?;
+    let disable_none = load_mod_file(&root)?
+        .profile
+        .and_then(|profile| profile.disable_none)
+        .unwrap_or_default();
+    let args = ExecProgramArgs {
+        work_dir: Some(root),
+        k_filename_list: entries,
+        disable_none,
+        ..Default::default()
+    };
+    let result = exec_program(ParseSessionRef::default(), &args)?;
+    if !result.log_message.is_empty() {
+        output(result.log_message.clone());
+    }
+    if result.err_message.is_empty() {
+        output(result.yaml_result.clone());
+    } else {
+        output(result.err_message.clone());
+    }
+    Ok(result)
+}
+
+/// Returns the package root and the absolute entry files of the `kcl.mod` profile.
+fn profile_entries(file: &str) -> Option<(String, Vec<String>)> {
+    let root = get_pkg_root(file)?;
+    let entries = load_mod_file(&root).ok()?.get_entries()?;
+    let entries = entries
+        .iter()
+        .map(|entry| {
+            let entry = PathBuf::from(entry.replace(KCL_MOD_PATH_ENV, &root));
+            if entry.is_absolute() {
+                entry
+            } else {
+                Path::new(&root).join(entry)
+            }
+            .to_string_lossy()
+            .to_string()
+        })
+        .collect();
+    Some((root, entries))
+}
+
+fn is_same_file(a: &str, b: &str) -> bool {
+    match (Path::new(a).canonicalize(), Path::new(b).canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// The empty range at the start of the 1-based line.
+#[inline]
+fn line_range(line: u64) -> Range {
+    let pos = lsp_pos(&KCLPos {
+        filename: String::new(),
+        line,
+        column: Some(0),
+    });
+    Range {
+        start: pos,
+        end: pos,
+    }
+}
+
+#[inline]
+fn plural(count: usize, noun: &str) -> String {
+    if count == 1 {
+        format!("{count} {noun}")
+    } else {
+        format!("{count} {noun}s")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use kcl_parser::KCLModuleCache;
+    use kcl_sema::resolver::scope::KCLScopeCache;
+    use proc_macro_crate::bench_test;
+    use serde_json::json;
+
+    use super::{
+        DEBUG_TEST_COMMAND, RENDER_YAML_COMMAND, RUN_TEST_COMMAND, SHOW_REFERENCES_COMMAND,
+        code_lens, execute_command,
+    };
+    use crate::compile::{Params, compile};
+    use crate::state::{KCLGlobalStateCache, KCLVfs, Task};
+    use crate::tests::compile_test_file;
+
+    fn commands(lenses: &[lsp_types::CodeLens]) -> Vec<(u32, String, String)> {
+        lenses
+            .iter()
+            .map(|lens| {
+                let command = lens.command.as_ref().unwrap();
+                (
+                    lens.range.start.line,
+                    command.title.clone(),
+                    command.command.clone(),
+                )
+            })
+            .collect()
+    }
+
+    fn test_file(file: &str) -> String {
+        PathBuf::from(file)
+            .with_file_name("main_test.k")
+            .to_string_lossy()
+            .to_string()
+    }
+
+    #[test]
+    #[bench_test]
+    fn code_lens_test() {
+        let (file, prog, _, gs, _) = compile_test_file("src/test_data/code_lens/main.k");
+        assert_eq!(
+            commands(&code_lens(&file, &gs, &prog)),
+            vec![
+                (
+                    0,
+                    "2 instances / 3 references".to_string(),
+                    SHOW_REFERENCES_COMMAND.to_string()
+                ),
+                (
+                    0,
+                    "Render YAML".to_string(),
+                    RENDER_YAML_COMMAND.to_string()
+                ),
+            ]
+        );
+
+        // The test file is excluded by the profile entries, so it is compiled with the entries.
+        let test_file = test_file(&file);
+        let (_, compile_res) = compile(
+            Params {
+                file: Some(test_file.clone()),
+                module_cache: Some(KCLModuleCache::default()),
+                scope_cache: Some(KCLScopeCache::default()),
+                vfs: Some(KCLVfs::default()),
+                gs_cache: Some(KCLGlobalStateCache::default()),
+            },
+            &mut [file.clone(), test_file.clone()],
+            None,
+        );
+        let (prog, _, gs) = compile_res.unwrap();
+        let lenses = code_lens(&test_file, &gs, &prog);
+        assert_eq!(
+            commands(&lenses),
+            vec![
+                (0, "Run test".to_string(), RUN_TEST_COMMAND.to_string()),
+                (0, "Debug test".to_string(), DEBUG_TEST_COMMAND.to_string()),
+                (3, "Run test".to_string(), RUN_TEST_COMMAND.to_string()),
+                (3, "Debug test".to_string(), DEBUG_TEST_COMMAND.to_string()),
+            ]
+        );
+        let launch = &lenses[1]
+            .command
+            .as_ref()
+            .unwrap()
+            .arguments
+            .as_ref()
+            .unwrap()[0];
+        assert_eq!(launch["testCase"], "test_alice");
+        assert_eq!(launch["program"].as_array().unwrap().len(), 2);
+
+        // The test file which is not compiled has no test lenses.
+        let (_, prog, _, gs, _) = compile_test_file("src/test_data/code_lens/main.k");
+        assert!(code_lens(&test_file, &gs, &prog).is_empty());
+    }
+
+    #[test]
+    #[bench_test]
+    fn execute_command_test() {
+        let (file, _, _, _, _) = compile_test_file("src/test_data/code_lens/main.k");
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        let outputs = || {
+            receiver
+                .try_iter()
+                .map(|task| match task {
+                    Task::Notify(notification) => {
+                        assert_eq!(notification.method, "kcl/commandOutput");
+                        notification.params["output"].as_str().unwrap().to_string()
+                    }
+                    _ => unreachable!(),
+                })
+                .collect::<Vec<String>>()
+        };
+        let result =
+            execute_command(RENDER_YAML_COMMAND, vec![json!({ "file": file })], &sender).unwrap();
+        assert_eq!(result["err_message"], "");
+        let yaml = result["yaml_result"].as_str().unwrap().to_string();
+        assert!(yaml.contains("name: alice"));
+        assert_eq!(outputs(), vec![yaml]);
+
+        let result = execute_command(
+            RUN_TEST_COMMAND,
+            vec![json!({ "file": test_file(&file), "name": "test_bob_age" })],
+            &sender,
+        )
+        .unwrap();
+        assert_eq!(result["passed"], 0);
+        assert_eq!(result["failed"], 1);
+        assert!(outputs()[0].contains("--- FAIL: test_bob_age"));
+
+        assert!(execute_command("kcl.unknown", vec![], &sender).is_err());
+    }
+}
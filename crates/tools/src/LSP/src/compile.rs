@@ -82,13 +82,19 @@ pub fn compile(
         match &mut params.module_cache.clone().unwrap().write() {
             Ok(module_cache) => {
                 let path = PathBuf::from(params.file.clone().unwrap());
-                module_cache.clear(&path);
-                if let Some(vfs) = &params.vfs
-                    && let Ok(code_list) =
-                        load_files_code_from_vfs(&[&params.file.clone().unwrap()], vfs)
-                {
-                    module_cache.source_code.insert(path, code_list[0].clone());
-                };
+                let code = params.vfs.as_ref().and_then(|vfs| {
+                    load_files_code_from_vfs(&[&params.file.clone().unwrap()], vfs)
+                        .ok()
+                        .and_then(|code_list| code_list.into_iter().next())
+                });
+                // Only the changed file is re-parsed, and the AST of the file is kept
+                // if its code is not changed, e.g., the file is just saved.
+                if code.is_none() || module_cache.source_code.get(&path) != code.as_ref() {
+                    module_cache.clear(&path);
+                    if let Some(code) = code {
+                        module_cache.source_code.insert(path, code);
+                    }
+                }
             }
             Err(e) => {
                 return (
@@ -145,6 +151,8 @@ pub fn compile(
         None => &mut default,
     };
 
+    // The symbols are invalidated per package rather than per module, because the package
+    // symbol and the members of a package are shared by all of its modules.
     gs.new_or_invalidate_pkgs = match &params.scope_cache {
         Some(cache) => match cache.try_write() {
             Some(scope) => scope.invalidate_pkgs.clone(),
@@ -0,0 +1,547 @@
+//! Debug Adapter Protocol (DAP) server for stepping through the KCL evaluation,
+//! which is started by the `kcl-language-server dap` subcommand over stdio.
+//!
+//! The server thread handles the DAP requests, and the program is evaluated in
+//! another thread because the [`Evaluator`] is not thread safe. When the evaluation
+//! stops at a breakpoint or a step, the evaluation thread blocks in the debugger hook
+//! and answers the inspection requests such as `stackTrace`, `scopes` and `variables`
+//! until the server resumes it.
+//!
+//! The `launch` request accepts the `program`, `options` and `workDir` arguments, and
+//! the optional `testCase` argument which invokes the named test case lambda instead
+//! of the whole program.
+
+use std::cell::RefCell;
+use std::io::{BufRead, Write};
+use std::path::Path;
+use std::rc::Rc;
+use std::sync::{
+    Arc, Mutex,
+    atomic::{AtomicBool, Ordering},
+};
+use std::time::Duration;
+
+use anyhow::{Result, anyhow};
+use crossbeam_channel::{Receiver, Sender, unbounded};
+use kcl_ast::ast;
+use kcl_evaluator::{
+    Evaluator,
+    debugger::{DebugHook, DebugState, StepMode, StopReason},
+};
+use kcl_parser::ParseSessionRef;
+use kcl_runner::runner::{FastRunner, RunnerOptions};
+use kcl_runner::{ExecProgramArgs, compile_program};
+use kcl_runtime::ValueRef;
+use serde_json::{Value, json};
+
+/// The evaluation is single threaded, so there is only one DAP thread.
+const THREAD_ID: i64 = 1;
+/// The variables reference of the local variables scope.
+const LOCALS_REFERENCE: i64 = 1;
+/// The variables reference of the global variables scope.
+const GLOBALS_REFERENCE: i64 = 2;
+/// The variables reference of the schema config and value scope.
+const SCHEMA_REFERENCE: i64 = 3;
+/// The first variables reference of the config and list values.
+const VALUE_REFERENCE_START: i64 = 1000;
+/// The timeout of the inspection requests answered by the evaluation thread.
+const INSPECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Start the DAP server over stdio.
+pub fn run_dap_server() -> Result<()> {
+    let stdin = std::io::stdin();
+    let mut reader = stdin.lock();
+    let mut server = DapServer::new(Output::new(Box::new(std::io::stdout())));
+    while let Some(request) = read_message(&mut reader)? {
+        if !server.handle(&request)? {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Read a DAP message with the `Content-Length` header, returns [None] at the end of input.
+pub(crate) fn read_message(reader: &mut impl BufRead) -> Result<Option<Value>> {
+    let mut content_length = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some(len) = line.strip_prefix("Content-Length:") {
+            content_length = Some(len.trim().parse::<usize>()?);
+        }
+    }
+    let len = content_length.ok_or_else(|| anyhow!("missing the Content-Length header"))?;
+    let mut buf = vec![0; len];
+    reader.read_exact(&mut buf)?;
+    Ok(Some(serde_json::from_slice(&buf)?))
+}
+
+/// The DAP message writer shared by the server and the evaluation thread.
+#[derive(Clone)]
+pub(crate) struct Output {
+    /// The writer and the last message sequence number.
+    inner: Arc<Mutex<(Box<dyn Write + Send>, i64)>>,
+}
+
+impl Output {
+    pub(crate) fn new(writer: Box<dyn Write + Send>) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new((writer, 0))),
+        }
+    }
+
+    fn send(&self, mut message: Value) -> Result<()> {
+        let mut inner = self.inner.lock().map_err(|e| anyhow!("{e}"))?;
+        inner.1 += 1;
+        message["seq"] = json!(inner.1);
+        let content = serde_json::to_string(&message)?;
+        write!(
+            inner.0,
+            "Content-Length: {}\r\n\r\n{}",
+            content.len(),
+            content
+        )?;
+        inner.0.flush()?;
+        Ok(())
+    }
+
+    fn respond(&self, request: &Value, body: Value) -> Result<()> {
+        self.send(json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "success": true,
+            "command": request["command"],
+            "body": body,
+        }))
+    }
+
+    fn respond_error(&self, request: &Value, message: &str) -> Result<()> {
+        self.send(json!({
+            "type": "response",
+            "request_seq": request["seq"],
+            "success": false,
+            "command": request["command"],
+            "message": message,
+        }))
+    }
+
+    fn event(&self, event: &str, body: Value) -> Result<()> {
+        self.send(json!({
+            "type": "event",
+            "event": event,
+            "body": body,
+        }))
+    }
+}
+
+/// The commands sent from the server to the paused evaluation thread.
+enum DebugCommand {
+    /// Resume the evaluation with the step mode.
+    Resume(StepMode),
+    /// Answer the inspection request with the response body.
+    Inspect(Value, Sender<Value>),
+}
+
+/// The DAP server which handles the requests from the client.
+pub(crate) struct DapServer {
+    output: Output,
+    state: Arc<Mutex<DebugState>>,
+    /// Whether the evaluation is paused in the debugger hook.
+    paused: Arc<AtomicBool>,
+    commands: Option<Sender<DebugCommand>>,
+    launch: Option<Value>,
+    configured: bool,
+}
+
+impl DapServer {
+    pub(crate) fn new(output: Output) -> Self {
+        Self {
+            output,
+            state: Default::default(),
+            paused: Default::default(),
+            commands: None,
+            launch: None,
+            configured: false,
+        }
+    }
+
+    /// Handle the request and return whether the server should keep running.
+    pub(crate) fn handle(&mut self, request: &Value) -> Result<bool> {
+        let args = &request["arguments"];
+        match request["command"].as_str().unwrap_or_default() {
+            "initialize" => {
+                self.output.respond(
+                    request,
+                    json!({
+                        "supportsConfigurationDoneRequest": true,
+                        "supportsTerminateRequest": true,
+                    }),
+                )?;
+                self.output.event("initialized", json!({}))?;
+            }
+            "setBreakpoints" => {
+                let path = args["source"]["path"].as_str().unwrap_or_default();
+                let lines = args["breakpoints"]
+                    .as_array()
+                    .map(|breakpoints| {
+                        breakpoints
+                            .iter()
+                            .filter_map(|b| b["line"].as_u64())
+                            .collect::<Vec<u64>>()
+                    })
+                    .unwrap_or_default();
+                self.lock_state()?.set_breakpoints(path, &lines);
+                let breakpoints = lines
+                    .iter()
+                    .map(|line| json!({"verified": true, "line": line}))
+                    .collect::<Vec<Value>>();
+                self.output
+                    .respond(request, json!({ "breakpoints": breakpoints }))?;
+            }
+            "setExceptionBreakpoints" => {
+                self.output.respond(request, json!({ "breakpoints": [] }))?;
+            }
+            "launch" => {
+                if args["stopOnEntry"].as_bool().unwrap_or_default() {
+                    self.lock_state()?.resume(StepMode::StepIn, 0);
+                }
+                self.launch = Some(args.clone());
+                self.output.respond(request, json!({}))?;
+                self.start()?;
+            }
+            "configurationDone" => {
+                self.configured = true;
+                self.output.respond(request, json!({}))?;
+                self.start()?;
+            }
+            "threads" => {
+                self.output.respond(
+                    request,
+                    json!({ "threads": [{ "id": THREAD_ID, "name": "main" }] }),
+                )?;
+            }
+            "stackTrace" | "scopes" | "variables" => self.inspect(request)?,
+            "continue" => self.resume(request, StepMode::Continue)?,
+            "next" => self.resume(request, StepMode::StepOver)?,
+            "stepIn" => self.resume(request, StepMode::StepIn)?,
+            "stepOut" => self.resume(request, StepMode::StepOut)?,
+            "pause" => {
+                self.lock_state()?.resume(StepMode::Pause, 0);
+                self.output.respond(request, json!({}))?;
+            }
+            "disconnect" | "terminate" => {
+                self.output.respond(request, json!({}))?;
+                return Ok(false);
+            }
+            command => {
+                self.output
+                    .respond_error(request, &format!("unsupported request '{}'", command))?;
+            }
+        }
+        Ok(true)
+    }
+
+    /// Start the evaluation thread when the program is launched and the configuration is done.
+    fn start(&mut self) -> Result<()> {
+        if self.commands.is_some() || !self.configured {
+            return Ok(());
+        }
+        let (args, test_case) = match &self.launch {
+            Some(launch) => (
+                launch_args(launch)?,
+                launch["testCase"].as_str().map(|name| name.to_string()),
+            ),
+            None => return Ok(()),
+        };
+        let (sender, receiver) = unbounded();
+        self.commands = Some(sender);
+        let output = self.output.clone();
+        let state = self.state.clone();
+        let paused = self.paused.clone();
+        std::thread::spawn(move || {
+            let hook = Rc::new(DapHook {
+                state,
+                paused,
+                commands: receiver,
+                output: output.clone(),
+                references: Default::default(),
+            });
+            let exit_code =
+                match compile_program(ParseSessionRef::default(), &args).and_then(|program| {
+                    let runner = FastRunner::new(Some(RunnerOptions {
+                        plugin_agent_ptr: args.plugin_agent,
+                    }));
+                    match &test_case {
+                        // Debug the test case lambda, e.g., from the code lens of the language server.
+                        Some(name) => runner
+                            .run_function_with_debug_hook(&program, &args, name, hook)
+                            .map(|(result, _)| result),
+                        None => runner.run_with_debug_hook(&program, &args, hook),
+                    }
+                }) {
+                    Ok(result) if result.err_message.is_empty() => {
+                        let _ = output.event(
+                            "output",
+                            json!({ "category": "stdout", "output": result.yaml_result }),
+                        );
+                        0
+                    }
+                    Ok(result) => {
+                        let _ = output.event(
+                            "output",
+                            json!({ "category": "stderr", "output": result.err_message }),
+                        );
+                        1
+                    }
+                    Err(err) => {
+                        let _ = output.event(
+                            "output",
+                            json!({ "category": "stderr", "output": err.to_string() }),
+                        );
+                        1
+                    }
+                };
+            let _ = output.event("exited", json!({ "exitCode": exit_code }));
+            let _ = output.event("terminated", json!({}));
+        });
+        Ok(())
+    }
+
+    fn resume(&mut self, request: &Value, mode: StepMode) -> Result<()> {
+        if let Some(commands) = &self.commands
+            && self.paused.load(Ordering::SeqCst)
+        {
+            commands.send(DebugCommand::Resume(mode))?;
+        }
+        self.output
+            .respond(request, json!({ "allThreadsContinued": true }))
+    }
+
+    fn inspect(&mut self, request: &Value) -> Result<()> {
+        let body = match &self.commands {
+            Some(commands) if self.paused.load(Ordering::SeqCst) => {
+                let (sender, receiver) = unbounded();
+                commands.send(DebugCommand::Inspect(request.clone(), sender))?;
+                receiver.recv_timeout(INSPECT_TIMEOUT).ok()
+            }
+            _ => None,
+        };
+        match body {
+            Some(body) => self.output.respond(request, body),
+            None => self
+                .output
+                .respond_error(request, "the evaluation is not paused"),
+        }
+    }
+
+    fn lock_state(&self) -> Result<std::sync::MutexGuard<'_, DebugState>> {
+        self.state.lock().map_err(|e| anyhow!("{e}"))
+    }
+}
+
+/// Get the execution arguments from the launch request arguments, e.g.,
+/// `{"program": "main.k", "workDir": ".", "options": {"env": "prod"}}`.
+fn launch_args(launch: &Value) -> Result<ExecProgramArgs> {
+    let k_filename_list = match &launch["program"] {
+        Value::String(program) => vec![program.clone()],
+        Value::Array(programs) => programs
+            .iter()
+            .filter_map(|p| p.as_str().map(|p| p.to_string()))
+            .collect(),
+        _ => {
+            return Err(anyhow!(
+                "the launch request requires the 'program' argument"
+            ));
+        }
+    };
+    let args = launch["options"]
+        .as_object()
+        .map(|options| {
+            options
+                .iter()
+                .map(|(name, value)| ast::Argument {
+                    name: name.clone(),
+                    // JSON literals are valid KCL literals.
+                    value: value.to_string(),
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    Ok(ExecProgramArgs {
+        k_filename_list,
+        work_dir: launch["workDir"].as_str().map(|w| w.to_string()),
+        args,
+        // The runtime debug mode records the stack frames of the stops.
+        debug: 1,
+        ..Default::default()
+    })
+}
+
+/// The debugger hook running in the evaluation thread.
+struct DapHook {
+    state: Arc<Mutex<DebugState>>,
+    paused: Arc<AtomicBool>,
+    commands: Receiver<DebugCommand>,
+    output: Output,
+    /// The config and list values referenced by the variables references of the current stop.
+    references: RefCell<Vec<ValueRef>>,
+}
+
+impl DebugHook for DapHook {
+    fn on_stmt(&self, evaluator: &Evaluator, stmt: &ast::Node<ast::Stmt>) {
+        let depth = evaluator.debug_stack_depth();
+        let frame_id = evaluator.debug_frame_id();
+        let reason = match self.state.lock() {
+            Ok(mut state) => state.should_stop(&stmt.filename, stmt.line, depth, frame_id),
+            Err(_) => None,
+        };
+        let reason = match reason {
+            Some(StopReason::Breakpoint) => "breakpoint",
+            Some(StopReason::Step) => "step",
+            Some(StopReason::Pause) => "pause",
+            None => return,
+        };
+        self.paused.store(true, Ordering::SeqCst);
+        let _ = self.output.event(
+            "stopped",
+            json!({ "reason": reason, "threadId": THREAD_ID, "allThreadsStopped": true }),
+        );
+        // The evaluation continues when the server is disconnected.
+        while let Ok(command) = self.commands.recv() {
+            match command {
+                DebugCommand::Resume(mode) => {
+                    if let Ok(mut state) = self.state.lock() {
+                        state.resume(mode, depth);
+                    }
+                    break;
+                }
+                DebugCommand::Inspect(request, reply) => {
+                    let _ = reply.send(self.inspect(evaluator, &request));
+                }
+            }
+        }
+        self.references.borrow_mut().clear();
+        self.paused.store(false, Ordering::SeqCst);
+    }
+}
+
+impl DapHook {
+    fn inspect(&self, evaluator: &Evaluator, request: &Value) -> Value {
+        match request["command"].as_str().unwrap_or_default() {
+            "stackTrace" => {
+                let frames = evaluator
+                    .debug_stack_frames()
+                    .iter()
+                    .enumerate()
+                    .map(|(id, frame)| {
+                        json!({
+                            "id": id,
+                            "name": if frame.name.is_empty() { "<module>" } else { frame.name.as_str() },
+                            "source": {
+                                "name": Path::new(&frame.filename)
+                                    .file_name()
+                                    .map(|n| n.to_string_lossy().to_string())
+                                    .unwrap_or_default(),
+                                "path": frame.filename,
+                            },
+                            "line": frame.line,
+                            "column": 1,
+                        })
+                    })
+                    .collect::<Vec<Value>>();
+                json!({ "stackFrames": frames, "totalFrames": frames.len() })
+            }
+            // Only the variables of the innermost frame can be inspected.
+            "scopes" => {
+                let mut scopes = vec![
+                    json!({ "name": "Locals", "variablesReference": LOCALS_REFERENCE, "expensive": false }),
+                    json!({ "name": "Globals", "variablesReference": GLOBALS_REFERENCE, "expensive": false }),
+                ];
+                if evaluator.debug_schema_config_and_value().is_some() {
+                    scopes.push(json!({ "name": "Schema", "variablesReference": SCHEMA_REFERENCE, "expensive": false }));
+                }
+                json!({ "scopes": scopes })
+            }
+            "variables" => {
+                let reference = request["arguments"]["variablesReference"]
+                    .as_i64()
+                    .unwrap_or_default();
+                let variables: Vec<(String, ValueRef)> = match reference {
+                    LOCALS_REFERENCE => evaluator.debug_local_variables().into_iter().collect(),
+                    GLOBALS_REFERENCE => evaluator.debug_global_variables().into_iter().collect(),
+                    SCHEMA_REFERENCE => evaluator
+                        .debug_schema_config_and_value()
+                        .map(|(config, value)| {
+                            vec![("config".to_string(), config), ("value".to_string(), value)]
+                        })
+                        .unwrap_or_default(),
+                    _ => {
+                        let value = self
+                            .references
+                            .borrow()
+                            .get((reference - VALUE_REFERENCE_START).max(0) as usize)
+                            .cloned();
+                        value.map(|v| children(&v)).unwrap_or_default()
+                    }
+                };
+                let variables = variables
+                    .iter()
+                    .map(|(name, value)| self.variable(name, value))
+                    .collect::<Vec<Value>>();
+                json!({ "variables": variables })
+            }
+            _ => json!({}),
+        }
+    }
+
+    fn variable(&self, name: &str, value: &ValueRef) -> Value {
+        let reference = if value.is_config() || value.is_list() {
+            let mut references = self.references.borrow_mut();
+            references.push(value.clone());
+            VALUE_REFERENCE_START + references.len() as i64 - 1
+        } else {
+            0
+        };
+        let display = if value.is_func() {
+            "<function>".to_string()
+        } else if value.is_undefined() {
+            "Undefined".to_string()
+        } else {
+            value.to_json_string()
+        };
+        json!({
+            "name": name,
+            "value": display,
+            "type": value.type_str(),
+            "variablesReference": reference,
+        })
+    }
+}
+
+/// Get the child items of the config or list value.
+fn children(value: &ValueRef) -> Vec<(String, ValueRef)> {
+    if value.is_list() {
+        value
+            .as_list_ref()
+            .values
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (i.to_string(), v.clone()))
+            .collect()
+    } else if value.is_config() {
+        value
+            .as_dict_ref()
+            .values
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.clone()))
+            .collect()
+    } else {
+        vec![]
+    }
+}
@@ -0,0 +1,248 @@
+//! Pull diagnostics of LSP 3.17, i.e., the `textDocument/diagnostic` and the
+//! `workspace/diagnostic` requests. The result id of a document is the hash of its
+//! diagnostics, so the documents whose diagnostics are unchanged since the previous
+//! result are reported as unchanged without the diagnostic items.
+
+use std::collections::BTreeMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+use kcl_error::Diagnostic as KCLDiagnostic;
+use kcl_primitives::IndexSet;
+use lsp_types::request::Request;
+use lsp_types::{
+    Diagnostic, PartialResultParams, TextDocumentIdentifier, Url, WorkDoneProgressParams,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::from_lsp::file_path_from_url;
+use crate::to_lsp::{kcl_diag_to_lsp_diags, kcl_diag_to_lsp_diags_by_file, url_from_path};
+
+/// The `textDocument/diagnostic` request, which is not provided by lsp-types 0.93.
+#[derive(Debug)]
+pub enum DocumentDiagnosticRequest {}
+
+impl Request for DocumentDiagnosticRequest {
+    type Params = DocumentDiagnosticParams;
+    type Result = DocumentDiagnosticReport;
+    const METHOD: &'static str = "textDocument/diagnostic";
+}
+
+/// The `workspace/diagnostic` request, which is not provided by lsp-types 0.93.
+#[derive(Debug)]
+pub enum WorkspaceDiagnosticRequest {}
+
+impl Request for WorkspaceDiagnosticRequest {
+    type Params = WorkspaceDiagnosticParams;
+    type Result = WorkspaceDiagnosticReport;
+    const METHOD: &'static str = "workspace/diagnostic";
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentDiagnosticParams {
+    pub text_document: TextDocumentIdentifier,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub identifier: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub previous_result_id: Option<String>,
+    #[serde(flatten)]
+    pub work_done_progress_params: WorkDoneProgressParams,
+    #[serde(flatten)]
+    pub partial_result_params: PartialResultParams,
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceDiagnosticParams {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub identifier: Option<String>,
+    pub previous_result_ids: Vec<PreviousResultId>,
+    #[serde(flatten)]
+    pub work_done_progress_params: WorkDoneProgressParams,
+    #[serde(flatten)]
+    pub partial_result_params: PartialResultParams,
+}
+
+/// The result id of a document reported by the previous workspace diagnostic request.
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
+pub struct PreviousResultId {
+    pub uri: Url,
+    pub value: String,
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum DocumentDiagnosticReport {
+    Full(FullDocumentDiagnosticReport),
+    Unchanged(UnchangedDocumentDiagnosticReport),
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FullDocumentDiagnosticReport {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result_id: Option<String>,
+    pub items: Vec<Diagnostic>,
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnchangedDocumentDiagnosticReport {
+    pub result_id: String,
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
+pub struct WorkspaceDiagnosticReport {
+    pub items: Vec<WorkspaceDocumentDiagnosticReport>,
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
+pub struct WorkspaceDocumentDiagnosticReport {
+    pub uri: Url,
+    pub version: Option<i32>,
+    #[serde(flatten)]
+    pub report: DocumentDiagnosticReport,
+}
+
+/// Returns the diagnostic report of the file.
+pub fn document_diagnostic(
+    file: &str,
+    diags: &IndexSet<KCLDiagnostic>,
+    previous_result_id: Option<&str>,
+) -> DocumentDiagnosticReport {
+    let items = diags
+        .iter()
+        .flat_map(|diag| kcl_diag_to_lsp_diags_by_file(diag, file))
+        .collect();
+    diagnostic_report(items, previous_result_id)
+}
+
+/// Returns the diagnostic reports of all the files with diagnostics in the workspace, where
+/// the diagnostics of the compile units are merged. The files which had diagnostics in the
+/// previous result but have none now are reported with empty items to clear them.
+pub fn workspace_diagnostic(
+    diags: &IndexSet<KCLDiagnostic>,
+    previous_result_ids: &[PreviousResultId],
+) -> WorkspaceDiagnosticReport {
+    let previous: BTreeMap<String, &str> = previous_result_ids
+        .iter()
+        .filter_map(|id| Some((file_path_from_url(&id.uri).ok()?, id.value.as_str())))
+        .collect();
+    let mut items_map: BTreeMap<String, Vec<Diagnostic>> =
+        previous.keys().map(|file| (file.clone(), vec![])).collect();
+    for diag in diags {
+        for (file, lsp_diags) in kcl_diag_to_lsp_diags(diag) {
+            items_map.entry(file).or_default().extend(lsp_diags);
+        }
+    }
+    WorkspaceDiagnosticReport {
+        items: items_map
+            .into_iter()
+            .filter_map(|(file, items)| {
+                let previous_result_id = previous.get(&file).copied();
+                Some(WorkspaceDocumentDiagnosticReport {
+                    uri: url_from_path(&file).ok()?,
+                    version: None,
+                    report: diagnostic_report(items, previous_result_id),
+                })
+            })
+            .collect(),
+    }
+}
+
+fn diagnostic_report(
+    items: Vec<Diagnostic>,
+    previous_result_id: Option<&str>,
+) -> DocumentDiagnosticReport {
+    let result_id = result_id(&items);
+    if previous_result_id == Some(result_id.as_str()) {
+        DocumentDiagnosticReport::Unchanged(UnchangedDocumentDiagnosticReport { result_id })
+    } else {
+        DocumentDiagnosticReport::Full(FullDocumentDiagnosticReport {
+            result_id: Some(result_id),
+            items,
+        })
+    }
+}
+
+/// The result id is the hash of the serialized diagnostics.
+fn result_id(items: &[Diagnostic]) -> String {
+    let mut hasher = DefaultHasher::new();
+    serde_json::to_string(items)
+        .unwrap_or_default()
+        .hash(&mut hasher);
+    format!("{:x}", hasher.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use kcl_primitives::IndexSet;
+    use lsp_types::Url;
+    use proc_macro_crate::bench_test;
+
+    use super::{
+        DocumentDiagnosticReport, PreviousResultId, WorkspaceDiagnosticReport, document_diagnostic,
+        workspace_diagnostic,
+    };
+    use crate::tests::compile_test_file;
+
+    fn result_id(report: &DocumentDiagnosticReport) -> String {
+        match report {
+            DocumentDiagnosticReport::Full(report) => report.result_id.clone().unwrap(),
+            DocumentDiagnosticReport::Unchanged(report) => report.result_id.clone(),
+        }
+    }
+
+    #[test]
+    #[bench_test]
+    fn document_diagnostic_test() {
+        let (file, _, diags, _, _) = compile_test_file("src/test_data/diagnostic/main.k");
+
+        let report = document_diagnostic(&file, &diags, None);
+        let DocumentDiagnosticReport::Full(full) = &report else {
+            panic!("test failed")
+        };
+        assert_eq!(full.items.len(), 1);
+        assert_eq!(full.items[0].range.start.line, 4);
+
+        let report = document_diagnostic(&file, &diags, Some(&result_id(&report)));
+        assert!(matches!(report, DocumentDiagnosticReport::Unchanged(_)));
+
+        let report = document_diagnostic(&file, &IndexSet::default(), Some(&result_id(&report)));
+        let DocumentDiagnosticReport::Full(full) = &report else {
+            panic!("test failed")
+        };
+        assert!(full.items.is_empty());
+    }
+
+    #[test]
+    #[bench_test]
+    fn workspace_diagnostic_test() {
+        let (file, _, diags, _, _) = compile_test_file("src/test_data/diagnostic/main.k");
+        let uri = Url::from_file_path(&file).unwrap();
+
+        let WorkspaceDiagnosticReport { items } = workspace_diagnostic(&diags, &[]);
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].uri, uri);
+        let previous = vec![PreviousResultId {
+            uri: uri.clone(),
+            value: result_id(&items[0].report),
+        }];
+
+        let WorkspaceDiagnosticReport { items } = workspace_diagnostic(&diags, &previous);
+        assert_eq!(items.len(), 1);
+        assert!(matches!(
+            items[0].report,
+            DocumentDiagnosticReport::Unchanged(_)
+        ));
+
+        // The fixed file is reported with no diagnostics.
+        let WorkspaceDiagnosticReport { items } =
+            workspace_diagnostic(&IndexSet::default(), &previous);
+        assert_eq!(items.len(), 1);
+        match &items[0].report {
+            DocumentDiagnosticReport::Full(full) => assert!(full.items.is_empty()),
+            DocumentDiagnosticReport::Unchanged(_) => panic!("test failed"),
+        }
+    }
+}
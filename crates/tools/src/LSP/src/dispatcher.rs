@@ -5,6 +5,8 @@ use lsp_server::{ExtractError, Request};
 use serde::Serialize;
 use serde::de::DeserializeOwned;
 use std::error::Error;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
 
 use crate::{
     error::LSPError,
@@ -194,6 +196,58 @@ impl<'a> RequestDispatcher<'a> {
         Ok(self)
     }
 
+    /// Try to dispatch the event as the given Request type on the thread pool with a
+    /// cancellation token, which is set when the client cancels the request.
+    pub fn on_cancellable<R>(
+        &mut self,
+        compute_response_fn: fn(
+            LanguageServerSnapshot,
+            R::Params,
+            Sender<Task>,
+            Arc<AtomicBool>,
+        ) -> anyhow::Result<R::Result>,
+    ) -> anyhow::Result<&mut Self>
+    where
+        R: lsp_types::request::Request + 'static,
+        R::Params: DeserializeOwned + 'static + Send,
+        R::Result: Serialize + 'static,
+    {
+        let (req, params) = match self.parse::<R>() {
+            Some(it) => it,
+            None => return Ok(self),
+        };
+
+        let cancelled = Arc::new(AtomicBool::new(false));
+        self.state
+            .request_cancellations
+            .write()
+            .insert(req.id.clone(), cancelled.clone());
+        self.state.thread_pool.execute({
+            let snapshot = self.state.snapshot();
+            let sender = self.state.task_sender.clone();
+            let request_cancellations = self.state.request_cancellations.clone();
+            move || {
+                let result = compute_response_fn(snapshot, params, sender.clone(), cancelled);
+                request_cancellations.write().remove(&req.id);
+                match &result {
+                    Err(e)
+                        if e.downcast_ref::<LSPError>()
+                            .is_some_and(|lsp_err| matches!(lsp_err, LSPError::Retry)) =>
+                    {
+                        sender.send(Task::Retry(req)).unwrap();
+                    }
+                    _ => {
+                        sender
+                            .send(Task::Response(result_to_response::<R>(req.id, result)))
+                            .unwrap();
+                    }
+                }
+            }
+        });
+
+        Ok(self)
+    }
+
     /// Tries to parse the request as the specified type. If the request is of the specified type,
     /// the request is transferred and any subsequent call to this method will return None. If an
     /// error is encountered during parsing of the request parameters an error is send to the
@@ -275,8 +329,7 @@ pub struct Canceled {
 }
 
 impl Canceled {
-    #[allow(unused)]
-    fn new() -> Self {
+    pub(crate) fn new() -> Self {
         Canceled { _private: () }
     }
     #[allow(unused)]
@@ -0,0 +1,163 @@
+//! Document highlights of the symbol at the position, which are the references in
+//! the current file. The definitions and the assignment targets, e.g., the config
+//! keys and the augmented assignment targets, are the write occurrences.
+
+use std::collections::HashSet;
+
+use kcl_ast::ast::{ConfigEntry, Expr, MemberOrIndex, Module, Node, Stmt, Target};
+use kcl_ast::pos::GetPos;
+use kcl_ast::walker::MutSelfWalker;
+use kcl_error::Position as KCLPos;
+use kcl_sema::core::global_state::GlobalState;
+use lsp_types::{DocumentHighlight, DocumentHighlightKind, Position, Url};
+
+use crate::find_refs::find_refs;
+use crate::goto_def::find_def;
+use crate::to_lsp::lsp_pos;
+
+pub fn document_highlight(
+    kcl_pos: &KCLPos,
+    gs: &GlobalState,
+    module: &Module,
+) -> Option<Vec<DocumentHighlight>> {
+    let uri = Url::from_file_path(&kcl_pos.filename).ok()?;
+    let def = find_def(kcl_pos, gs, true)?;
+    let (def_start, _) = gs.get_symbols().get_symbol(def)?.get_range();
+    let mut collector = WriteCollector::default();
+    collector.walk_module(module);
+    let mut writes = collector.writes;
+    writes.insert(position_key(lsp_pos(&def_start)));
+    Some(
+        find_refs(kcl_pos, gs)?
+            .into_iter()
+            .filter(|loc| loc.uri == uri)
+            .map(|loc| DocumentHighlight {
+                range: loc.range,
+                kind: Some(if writes.contains(&position_key(loc.range.start)) {
+                    DocumentHighlightKind::WRITE
+                } else {
+                    DocumentHighlightKind::READ
+                }),
+            })
+            .collect(),
+    )
+}
+
+/// Collects the start positions of the names assigned in the module, keyed by the line
+/// and the character.
+#[derive(Default)]
+struct WriteCollector {
+    writes: HashSet<(u32, u32)>,
+}
+
+impl MutSelfWalker for WriteCollector {
+    fn walk_stmt_node(&mut self, stmt: &Node<Stmt>) {
+        match &stmt.node {
+            Stmt::Assign(assign_stmt) => {
+                for target in &assign_stmt.targets {
+                    self.writes.extend(target_position(&target.node));
+                }
+            }
+            Stmt::AugAssign(aug_assign_stmt) => {
+                self.writes
+                    .extend(target_position(&aug_assign_stmt.target.node));
+            }
+            Stmt::SchemaAttr(schema_attr) => {
+                self.writes
+                    .insert(position_key(lsp_pos(&schema_attr.name.get_pos())));
+            }
+            Stmt::Unification(unification_stmt) => {
+                if let Some(name) = unification_stmt.target.node.names.last() {
+                    self.writes.insert(position_key(lsp_pos(&name.get_pos())));
+                }
+            }
+            _ => {}
+        }
+        self.walk_stmt(&stmt.node);
+    }
+
+    fn walk_config_entry(&mut self, config_entry: &Node<ConfigEntry>) {
+        if let Some(key) = &config_entry.node.key {
+            if let Expr::Identifier(identifier) = &key.node
+                && let Some(name) = identifier.names.last()
+            {
+                self.writes.insert(position_key(lsp_pos(&name.get_pos())));
+            }
+            self.walk_expr_node(key);
+        }
+        self.walk_expr_node(&config_entry.node.value);
+    }
+}
+
+/// The assigned name of the target, e.g., `c` in `a.b.c = 1`.
+fn target_position(target: &Target) -> Option<(u32, u32)> {
+    match target.paths.last() {
+        None => Some(position_key(lsp_pos(&target.name.get_pos()))),
+        Some(MemberOrIndex::Member(member)) => Some(position_key(lsp_pos(&member.get_pos()))),
+        Some(MemberOrIndex::Index(_)) => None,
+    }
+}
+
+/// The position key in the write set, because the LSP positions are not hashable.
+#[inline]
+fn position_key(pos: Position) -> (u32, u32) {
+    (pos.line, pos.character)
+}
+
+#[cfg(test)]
+mod tests {
+    use kcl_error::Position as KCLPos;
+    use lsp_types::DocumentHighlightKind;
+    use proc_macro_crate::bench_test;
+
+    use super::document_highlight;
+    use crate::tests::compile_test_file;
+
+    #[test]
+    #[bench_test]
+    fn document_highlight_test() {
+        let (file, prog, _, gs, _) =
+            compile_test_file("src/test_data/document_highlight/document_highlight.k");
+        let module = prog.get_module(&file).unwrap().unwrap();
+        let pos = |line, column| KCLPos {
+            filename: file.clone(),
+            line,
+            column: Some(column),
+        };
+
+        // The schema attribute `name`
+        let highlights: Vec<(u32, u32, DocumentHighlightKind)> =
+            document_highlight(&pos(2, 4), &gs, &module)
+                .unwrap()
+                .into_iter()
+                .map(|h| (h.range.start.line, h.range.start.character, h.kind.unwrap()))
+                .collect();
+        for highlight in [
+            // name: str
+            (1, 4, DocumentHighlightKind::WRITE),
+            // name = "alice"
+            (5, 4, DocumentHighlightKind::WRITE),
+            // Person {name = alice.name}
+            (7, 14, DocumentHighlightKind::WRITE),
+            (7, 27, DocumentHighlightKind::READ),
+        ] {
+            assert!(highlights.contains(&highlight), "{highlight:?} not found");
+        }
+
+        // The variable `alice`
+        let highlights: Vec<(u32, DocumentHighlightKind)> =
+            document_highlight(&pos(5, 0), &gs, &module)
+                .unwrap()
+                .into_iter()
+                .map(|h| (h.range.start.line, h.kind.unwrap()))
+                .collect();
+        assert_eq!(
+            highlights,
+            vec![
+                (4, DocumentHighlightKind::WRITE),
+                (7, DocumentHighlightKind::READ),
+                (8, DocumentHighlightKind::READ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,297 @@
+//! DocumentLink for KCL
+//! Links the import paths to the packages and the string literals read by `file.read`,
+//! e.g., `yaml.decode(file.read("config.yaml"))` and `template.execute(file.read("a.tmpl"), {})`,
+//! to the files.
+//!
+//! The imports are linked to the files of the packages loaded by the compiler. The imports
+//! which are not loaded, e.g., with compile errors, are resolved from the relative path, the
+//! module root, the `kcl.mod` dependencies and the vendor home.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use kcl_ast::ast;
+use kcl_ast::pos::GetPos;
+use kcl_ast::walker::MutSelfWalker;
+use kcl_config::modfile::{Dependency, KCL_FILE_SUFFIX, KCL_MOD_FILE, get_pkg_root, load_mod_file};
+use kcl_driver::client::ModClient;
+use lsp_types::{DocumentLink, Range, Url};
+
+use crate::to_lsp::lsp_pos;
+
+/// The system module of the file functions.
+const FILE_MODULE: &str = "file";
+/// The file functions whose first argument is a path to link.
+const FILE_PATH_FUNCTIONS: [&str; 1] = ["read"];
+
+/// Returns the links of the import paths and the file paths in the module.
+pub fn document_links(file: &str, program: &ast::Program) -> Vec<DocumentLink> {
+    let Ok(Some(module)) = program.get_module(file) else {
+        return vec![];
+    };
+    let mut links = vec![];
+    // The import names in the module, e.g., `f` in `import file as f`.
+    let mut imports = HashMap::new();
+    for stmt in &module.body {
+        if let ast::Stmt::Import(import_stmt) = &stmt.node {
+            imports.insert(import_stmt.name.clone(), import_stmt.path.node.clone());
+            if let Some(target) = import_target(file, import_stmt, program) {
+                links.push(document_link(&import_stmt.path, target));
+            }
+        }
+    }
+
+    let mut collector = FilePathCollector {
+        imports,
+        paths: vec![],
+    };
+    collector.walk_module(&module);
+    for path in collector.paths {
+        if let Some(target) = file_target(file, &program.root, &path.node) {
+            links.push(document_link(&path, target));
+        }
+    }
+    links
+}
+
+fn document_link<T>(node: &ast::Node<T>, target: PathBuf) -> DocumentLink {
+    let (start, end) = node.get_span_pos();
+    DocumentLink {
+        range: Range {
+            start: lsp_pos(&start),
+            end: lsp_pos(&end),
+        },
+        tooltip: Some(target.display().to_string()),
+        target: Url::from_file_path(target).ok(),
+        data: None,
+    }
+}
+
+/// Returns the first file of the imported package.
+fn import_target(
+    file: &str,
+    import_stmt: &ast::ImportStmt,
+    program: &ast::Program,
+) -> Option<PathBuf> {
+    let pkgpath = &import_stmt.path.node;
+    match program
+        .pkgs
+        .get(pkgpath)
+        .or(program.pkgs_not_imported.get(pkgpath))
+    {
+        Some(files) => files.first().map(PathBuf::from),
+        None => package_entry(&resolve_import_path(
+            file,
+            &import_stmt.rawpath,
+            &program.root,
+        )?),
+    }
+}
+
+/// Resolves the path of the package imported by `rawpath` in `file`, e.g., `.pkg`, `pkg.sub`
+/// and `k8s.api.apps`, which may be a `.k` file or a directory.
+fn resolve_import_path(file: &str, rawpath: &str, root: &str) -> Option<PathBuf> {
+    if rawpath.starts_with('.') {
+        // Each leading dot after the first one refers to the parent directory.
+        let pkgpath = rawpath.trim_start_matches('.');
+        let mut base = Path::new(file).parent()?.to_path_buf();
+        for _ in 1..rawpath.len() - pkgpath.len() {
+            base = base.parent()?.to_path_buf();
+        }
+        return Some(join_pkgpath(base, pkgpath));
+    }
+    let local = join_pkgpath(PathBuf::from(root), rawpath);
+    if !root.is_empty() && (local.exists() || local.with_extension("k").is_file()) {
+        return Some(local);
+    }
+    external_package_path(file, rawpath)
+}
+
+/// Resolves the import path of an external package declared in the `kcl.mod` dependencies,
+/// which is stored in the vendor home. The OCI packages are only resolved from the
+/// `kcl.mod.lock` file, because their paths depend on the locked versions.
+fn external_package_path(file: &str, rawpath: &str) -> Option<PathBuf> {
+    let (pkg_name, pkgpath) = rawpath.split_once('.').unwrap_or((rawpath, ""));
+    let mod_dir = get_pkg_root(file)?;
+    if !Path::new(&mod_dir).join(KCL_MOD_FILE).is_file() {
+        return None;
+    }
+    let dependencies = load_mod_file(&mod_dir).ok()?.dependencies?;
+    let dep = dependencies.get(pkg_name)?;
+    let client = ModClient::new(&mod_dir).ok()?;
+    if let Some(pkg) = client
+        .get_metadata_from_mod_lock_file()
+        .and_then(|mut metadata| metadata.packages.remove(pkg_name))
+        && !pkg.manifest_path.as_os_str().is_empty()
+    {
+        return Some(join_pkgpath(pkg.manifest_path, pkgpath));
+    }
+    if let Dependency::Oci(_) = dep {
+        return None;
+    }
+    // The local dependencies are absolute paths, which replace the vendor path when joined.
+    let pkg_root = client
+        .get_vendor_path()
+        .ok()?
+        .join(client.get_local_path_from_dep(pkg_name, dep));
+    Some(join_pkgpath(pkg_root, pkgpath))
+}
+
+fn join_pkgpath(mut path: PathBuf, pkgpath: &str) -> PathBuf {
+    for name in pkgpath.split('.').filter(|name| !name.is_empty()) {
+        path.push(name);
+    }
+    path
+}
+
+/// Returns the `.k` file of a single file package, or the first `.k` file in the directory of
+/// the package which is not a test file.
+fn package_entry(path: &Path) -> Option<PathBuf> {
+    let file = path.with_extension("k");
+    if file.is_file() {
+        return Some(file);
+    }
+    let mut files: Vec<PathBuf> = std::fs::read_dir(path)
+        .ok()?
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|path| {
+            path.is_file()
+                && path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| {
+                        name.ends_with(KCL_FILE_SUFFIX) && !name.ends_with("_test.k")
+                    })
+        })
+        .collect();
+    files.sort();
+    files.into_iter().next()
+}
+
+/// Resolves a file path read at runtime, which is relative to the working directory. The
+/// directory of the current file is tried first, then the root of the compile unit.
+fn file_target(file: &str, root: &str, path: &str) -> Option<PathBuf> {
+    let path = Path::new(path);
+    if path.is_absolute() {
+        return path.is_file().then(|| path.to_path_buf());
+    }
+    let dir = Path::new(file).parent()?;
+    [dir, Path::new(root)]
+        .into_iter()
+        .filter(|base| !base.as_os_str().is_empty())
+        .map(|base| base.join(path))
+        .find(|path| path.is_file())
+}
+
+/// Collects the string literals passed to the file functions as the path argument.
+struct FilePathCollector {
+    imports: HashMap<String, String>,
+    paths: Vec<ast::Node<String>>,
+}
+
+impl FilePathCollector {
+    fn is_file_path_function(&self, func: &ast::Expr) -> bool {
+        let ast::Expr::Identifier(identifier) = func else {
+            return false;
+        };
+        match identifier.names.as_slice() {
+            [module, name] => {
+                self.imports.get(&module.node).map(|path| path.as_str()) == Some(FILE_MODULE)
+                    && FILE_PATH_FUNCTIONS.contains(&name.node.as_str())
+            }
+            _ => false,
+        }
+    }
+}
+
+impl MutSelfWalker for FilePathCollector {
+    fn walk_call_expr(&mut self, call_expr: &ast::CallExpr) {
+        if self.is_file_path_function(&call_expr.func.node) {
+            let path = call_expr.args.first().map(|arg| arg.as_ref()).or_else(|| {
+                call_expr
+                    .keywords
+                    .iter()
+                    .find(|keyword| keyword.node.arg.node.get_name() == "filepath")
+                    .and_then(|keyword| keyword.node.value.as_deref())
+            });
+            if let Some(path) = path
+                && let ast::Expr::StringLit(string_lit) = &path.node
+            {
+                self.paths.push(ast::Node::new_with_pos(
+                    string_lit.value.clone(),
+                    path.pos(),
+                ));
+            }
+        }
+        self.walk_expr(&call_expr.func.node);
+        for arg in &call_expr.args {
+            self.walk_expr(&arg.node);
+        }
+        for keyword in &call_expr.keywords {
+            self.walk_keyword(&keyword.node);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use proc_macro_crate::bench_test;
+
+    use super::{document_links, resolve_import_path};
+    use crate::tests::compile_test_file;
+
+    #[test]
+    #[bench_test]
+    fn document_link_test() {
+        let (file, program, _, _, _) = compile_test_file("src/test_data/document_link/main.k");
+        let dir = PathBuf::from(&file).parent().unwrap().to_path_buf();
+        let links: Vec<(u32, PathBuf)> = document_links(&file, &program)
+            .into_iter()
+            .map(|link| {
+                (
+                    link.range.start.line,
+                    link.target.unwrap().to_file_path().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            links,
+            vec![
+                (0, dir.join("pkg").join("a.k")),
+                (1, dir.join("single.k")),
+                (8, dir.join("data.yaml")),
+                (9, dir.join("data.yaml")),
+                (10, dir.join("app.tmpl")),
+            ]
+        );
+    }
+
+    #[test]
+    #[bench_test]
+    fn resolve_import_path_test() {
+        let mut root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        root.push("src/test_data/document_link/external");
+        let file = root.join("main.k");
+        let file = file.to_str().unwrap();
+        let root = root.to_str().unwrap();
+
+        assert_eq!(
+            resolve_import_path(file, ".sub", root),
+            Some(PathBuf::from(root).join("sub"))
+        );
+        assert_eq!(
+            resolve_import_path(file, "..single", root),
+            Some(PathBuf::from(root).parent().unwrap().join("single"))
+        );
+        // The local dependency in the kcl.mod
+        assert_eq!(
+            resolve_import_path(file, "dep.sub", root),
+            Some(PathBuf::from(root).join("dep").join("sub"))
+        );
+        // The OCI dependency which is not locked in the kcl.mod.lock
+        assert_eq!(resolve_import_path(file, "oci_dep", root), None);
+        assert_eq!(resolve_import_path(file, "unknown", root), None);
+    }
+}
@@ -95,7 +95,7 @@ fn symbol_to_document_symbol(symbol: &KCLSymbol) -> Option<DocumentSymbol> {
     }
 }
 
-fn symbol_kind_to_document_symbol_kind(kind: KCLSymbolKind) -> Option<SymbolKind> {
+pub(crate) fn symbol_kind_to_document_symbol_kind(kind: KCLSymbolKind) -> Option<SymbolKind> {
     match kind {
         KCLSymbolKind::Schema => Some(SymbolKind::STRUCT),
         KCLSymbolKind::Attribute => Some(SymbolKind::PROPERTY),
@@ -0,0 +1,451 @@
+//! The custom `kcl/evaluate` request, which runs the compile unit of a document with
+//! the in-memory contents and returns the evaluated values of its top-level variables,
+//! e.g., for the clients to preview the computed values in the inlay hints and hover.
+//!
+//! The evaluation is stopped by the debugger hook of the evaluator when it exceeds the
+//! time or the memory budget, or when the request is cancelled by the client. The memory
+//! budget is best-effort: it is only checked on Linux, and it limits the growth of the
+//! resident memory of the whole server process during the evaluation, which includes the
+//! allocations of the other requests handled at the same time.
+
+use std::cell::Cell;
+use std::collections::HashSet;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use kcl_ast::ast;
+use kcl_ast::pos::GetPos;
+use kcl_evaluator::{Evaluator, debugger::DebugHook};
+use kcl_parser::{LoadProgramOptions, ParseSessionRef, load_program};
+use kcl_query::apply_overrides;
+use kcl_runner::ExecProgramArgs;
+use kcl_runner::runner::FastRunner;
+use kcl_sema::resolver::resolve_program;
+use lsp_types::request::Request;
+use lsp_types::{Range, TextDocumentIdentifier};
+use serde::{Deserialize, Serialize};
+
+use crate::dispatcher::Canceled;
+use crate::to_lsp::lsp_pos;
+
+/// The default time budget of an evaluation.
+const DEFAULT_TIMEOUT_MS: u64 = 5000;
+/// The default memory budget of an evaluation.
+const DEFAULT_MEMORY_LIMIT_MB: u64 = 1024;
+/// The memory usage is checked every this many statements and loop iterations, because
+/// reading it is much slower than evaluating a statement.
+const MEMORY_CHECK_INTERVAL: u64 = 1000;
+/// The max length of the value previews.
+const MAX_PREVIEW_LEN: usize = 80;
+
+/// The `kcl/evaluate` request.
+#[derive(Debug)]
+pub enum Evaluate {}
+
+impl Request for Evaluate {
+    type Params = EvaluateParams;
+    type Result = Option<EvaluateResult>;
+    const METHOD: &'static str = "kcl/evaluate";
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EvaluateParams {
+    pub text_document: TextDocumentIdentifier,
+    /// The override specs applied before the evaluation, e.g., `app.replicas=5`.
+    #[serde(default)]
+    pub overrides: Vec<String>,
+    /// The time budget in milliseconds, 5 seconds by default.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+    /// The best-effort memory budget in megabytes, 1024 by default, which is only checked
+    /// on Linux.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memory_limit: Option<u64>,
+    /// Whether to return the source provenance map of the output paths.
+    #[serde(default)]
+    pub provenance: bool,
+}
+
+#[derive(Debug, PartialEq, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EvaluateResult {
+    /// The evaluated top-level variables of the document in the declaration order.
+    pub values: Vec<EvaluatedValue>,
+    /// The error message if the evaluation failed, e.g., a failed check or an exceeded
+    /// budget, in which case the values are empty.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// The map from each output path, e.g., `app.spec.replicas`, to the source positions and
+    /// the operations which produced its final value, if it is requested by the params.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub provenance: Option<serde_json::Value>,
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EvaluatedValue {
+    pub name: String,
+    /// The range of the first declaration of the variable in the document.
+    pub range: Range,
+    pub value: serde_json::Value,
+    /// The single-line JSON of the value, which is truncated when it is too long.
+    pub preview: String,
+}
+
+/// Evaluates the compile unit of `files` and returns the values of the top-level variables
+/// declared in `file`. The code of the files is read from `opts.k_code_list` if set, so the
+/// unsaved contents are evaluated. Returns a [`Canceled`] error if `cancelled` is set.
+pub fn evaluate(
+    file: &str,
+    files: &[String],
+    opts: LoadProgramOptions,
+    params: &EvaluateParams,
+    cancelled: &Arc<AtomicBool>,
+) -> anyhow::Result<EvaluateResult> {
+    let work_dir = opts.work_dir.clone();
+    let sess = ParseSessionRef::default();
+    let paths: Vec<&str> = files.iter().map(|file| file.as_str()).collect();
+    let mut program = load_program(sess.clone(), &paths, Some(opts), None)?.program;
+    apply_overrides(&mut program, &params.overrides, &[], false)?;
+    let scope = resolve_program(&mut program);
+    // The compile errors are reported by the diagnostics.
+    if sess.1.read().has_errors() || scope.handler.has_errors() {
+        return Ok(EvaluateResult {
+            values: vec![],
+            error: Some("the compile unit has compile errors".to_string()),
+            provenance: None,
+        });
+    }
+
+    let args = ExecProgramArgs {
+        work_dir: (!work_dir.is_empty()).then_some(work_dir),
+        k_filename_list: files.to_vec(),
+        show_hidden: true,
+        provenance: params.provenance,
+        // The overrides are applied above, and they are only used to mark the overridden
+        // paths in the provenance map.
+        overrides: params.overrides.clone(),
+        ..Default::default()
+    };
+    let budget = Rc::new(Budget::new(
+        Duration::from_millis(params.timeout.unwrap_or(DEFAULT_TIMEOUT_MS)),
+        params.memory_limit.unwrap_or(DEFAULT_MEMORY_LIMIT_MB) * 1024 * 1024,
+        cancelled.clone(),
+    ));
+    let result = FastRunner::new(None).run_with_debug_hook(&program, &args, budget)?;
+    if cancelled.load(Ordering::Relaxed) {
+        return Err(Canceled::new().into());
+    }
+    if !result.err_message.is_empty() {
+        return Ok(EvaluateResult {
+            values: vec![],
+            error: Some(result.err_message),
+            provenance: None,
+        });
+    }
+
+    let json: serde_json::Value = serde_json::from_str(&result.json_result)?;
+    let mut values = vec![];
+    if let Some(module) = program.get_module(file)? {
+        let mut names = HashSet::new();
+        for (name, range) in top_level_variables(&module.body) {
+            if let Some(value) = json.get(&name)
+                && names.insert(name.clone())
+            {
+                values.push(EvaluatedValue {
+                    name,
+                    range,
+                    value: value.clone(),
+                    preview: preview(value),
+                });
+            }
+        }
+    }
+    let provenance = if params.provenance {
+        Some(serde_json::from_str(&result.provenance_result)?)
+    } else {
+        None
+    };
+    Ok(EvaluateResult {
+        values,
+        error: None,
+        provenance,
+    })
+}
+
+/// Returns the names and the ranges of the variables assigned in the statements, including
+/// the ones in the top-level if statements.
+fn top_level_variables(body: &[ast::NodeRef<ast::Stmt>]) -> Vec<(String, Range)> {
+    let mut variables = vec![];
+    for stmt in body {
+        match &stmt.node {
+            ast::Stmt::Assign(assign_stmt) => {
+                for target in &assign_stmt.targets {
+                    if target.node.paths.is_empty() {
+                        variables.push(variable(&target.node.name));
+                    }
+                }
+            }
+            ast::Stmt::Unification(unification_stmt) => {
+                if let Some(name) = unification_stmt.target.node.names.first() {
+                    variables.push(variable(name));
+                }
+            }
+            ast::Stmt::If(if_stmt) => {
+                variables.extend(top_level_variables(&if_stmt.body));
+                variables.extend(top_level_variables(&if_stmt.orelse));
+            }
+            _ => {}
+        }
+    }
+    variables
+}
+
+fn variable(name: &ast::Node<String>) -> (String, Range) {
+    let (start, end) = name.get_span_pos();
+    (
+        name.node.clone(),
+        Range {
+            start: lsp_pos(&start),
+            end: lsp_pos(&end),
+        },
+    )
+}
+
+fn preview(value: &serde_json::Value) -> String {
+    let preview = value.to_string();
+    match preview.char_indices().nth(MAX_PREVIEW_LEN) {
+        Some((index, _)) => format!("{}...", &preview[..index]),
+        None => preview,
+    }
+}
+
+/// The debugger hook which stops the evaluation by panicking before the next statement or
+/// loop iteration when the budget is exceeded or the evaluation is cancelled. The panic is
+/// caught by the runner and reported as the evaluation error.
+struct Budget {
+    start: Instant,
+    timeout: Duration,
+    memory_limit: u64,
+    /// The resident memory of the process when the evaluation starts, the memory budget
+    /// limits the growth from it.
+    base_memory: Option<u64>,
+    steps: Cell<u64>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl Budget {
+    fn new(timeout: Duration, memory_limit: u64, cancelled: Arc<AtomicBool>) -> Self {
+        Self {
+            start: Instant::now(),
+            timeout,
+            memory_limit,
+            base_memory: resident_memory(),
+            steps: Cell::new(0),
+            cancelled,
+        }
+    }
+
+    fn check(&self) {
+        if self.cancelled.load(Ordering::Relaxed) {
+            panic!("the evaluation is cancelled");
+        }
+        if self.start.elapsed() > self.timeout {
+            panic!(
+                "the evaluation exceeds the time budget of {}ms",
+                self.timeout.as_millis()
+            );
+        }
+        let steps = self.steps.get() + 1;
+        self.steps.set(steps);
+        if steps.is_multiple_of(MEMORY_CHECK_INTERVAL)
+            && let (Some(base), Some(current)) = (self.base_memory, resident_memory())
+            && current.saturating_sub(base) > self.memory_limit
+        {
+            panic!(
+                "the evaluation exceeds the memory budget of {}MB",
+                self.memory_limit / 1024 / 1024
+            );
+        }
+    }
+}
+
+impl DebugHook for Budget {
+    fn on_stmt(&self, _evaluator: &Evaluator, _stmt: &ast::Node<ast::Stmt>) {
+        self.check();
+    }
+
+    fn on_loop(&self, _evaluator: &Evaluator) {
+        self.check();
+    }
+}
+
+/// Returns the resident memory of the whole process in bytes, which is read from the
+/// procfs. The memory budget is not checked on the other platforms.
+#[cfg(target_os = "linux")]
+fn resident_memory() -> Option<u64> {
+    let statm = std::fs::read_to_string("/proc/self/statm").ok()?;
+    let pages: u64 = statm.split_whitespace().nth(1)?.parse().ok()?;
+    let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+    (page_size > 0).then(|| pages * page_size as u64)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn resident_memory() -> Option<u64> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::sync::Arc;
+    use std::sync::atomic::AtomicBool;
+
+    use kcl_parser::LoadProgramOptions;
+    use lsp_types::{TextDocumentIdentifier, Url};
+    use proc_macro_crate::bench_test;
+
+    use super::{EvaluateParams, evaluate};
+    use crate::dispatcher::is_canceled;
+
+    fn test_file() -> String {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("src/test_data/evaluate/main.k");
+        path.to_str().unwrap().to_string()
+    }
+
+    fn params(file: &str, overrides: Vec<String>, timeout: Option<u64>) -> EvaluateParams {
+        EvaluateParams {
+            text_document: TextDocumentIdentifier {
+                uri: Url::from_file_path(file).unwrap(),
+            },
+            overrides,
+            timeout,
+            memory_limit: None,
+            provenance: false,
+        }
+    }
+
+    #[test]
+    #[bench_test]
+    fn evaluate_test() {
+        let file = test_file();
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let result = evaluate(
+            &file,
+            &[file.clone()],
+            LoadProgramOptions::default(),
+            &params(&file, vec!["app.replicas=5".to_string()], None),
+            &cancelled,
+        )
+        .unwrap();
+        assert_eq!(result.error, None);
+        let values: Vec<(&str, String, u32)> = result
+            .values
+            .iter()
+            .map(|v| (v.name.as_str(), v.preview.clone(), v.range.start.line))
+            .collect();
+        assert_eq!(
+            values,
+            vec![
+                ("_base", "2".to_string(), 4),
+                ("app", r#"{"name":"app","replicas":5}"#.to_string(), 5),
+                ("enabled", "true".to_string(), 9),
+            ]
+        );
+        assert_eq!(result.provenance, None);
+    }
+
+    #[test]
+    #[bench_test]
+    fn evaluate_provenance_test() {
+        let file = test_file();
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let mut params = params(&file, vec!["app.replicas=5".to_string()], None);
+        params.provenance = true;
+        let result = evaluate(
+            &file,
+            &[file.clone()],
+            LoadProgramOptions::default(),
+            &params,
+            &cancelled,
+        )
+        .unwrap();
+        let provenance = result.provenance.unwrap();
+        assert_eq!(provenance["app"][0]["line"], 6);
+        assert_eq!(provenance["app"][0]["op"], "=");
+        assert_eq!(provenance["app.name"][0]["line"], 7);
+        assert_eq!(provenance["app.replicas"][0]["op"], "-O");
+    }
+
+    #[test]
+    #[bench_test]
+    fn evaluate_unsaved_code_test() {
+        let file = test_file();
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let opts = LoadProgramOptions {
+            k_code_list: vec!["a = 1 + 2\n".to_string()],
+            ..Default::default()
+        };
+        let result = evaluate(
+            &file,
+            &[file.clone()],
+            opts,
+            &params(&file, vec![], None),
+            &cancelled,
+        )
+        .unwrap();
+        assert_eq!(result.values.len(), 1);
+        assert_eq!(result.values[0].name, "a");
+        assert_eq!(result.values[0].value, serde_json::json!(3));
+    }
+
+    #[test]
+    #[bench_test]
+    fn evaluate_budget_test() {
+        let file = test_file();
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let result = evaluate(
+            &file,
+            &[file.clone()],
+            LoadProgramOptions::default(),
+            &params(&file, vec![], Some(0)),
+            &cancelled,
+        )
+        .unwrap();
+        assert!(result.values.is_empty());
+        assert!(result.error.unwrap().contains("time budget"));
+
+        // The budget is checked in the loops of a single statement.
+        let opts = LoadProgramOptions {
+            k_code_list: vec![
+                "a = [i * j for i in range(10000) for j in range(10000)]\n".to_string(),
+            ],
+            ..Default::default()
+        };
+        let result = evaluate(
+            &file,
+            &[file.clone()],
+            opts,
+            &params(&file, vec![], Some(100)),
+            &cancelled,
+        )
+        .unwrap();
+        assert!(result.values.is_empty());
+        assert!(result.error.unwrap().contains("time budget"));
+
+        let cancelled = Arc::new(AtomicBool::new(true));
+        let err = evaluate(
+            &file,
+            &[file.clone()],
+            LoadProgramOptions::default(),
+            &params(&file, vec![], None),
+            &cancelled,
+        )
+        .unwrap_err();
+        assert!(is_canceled(err.as_ref()));
+    }
+}
@@ -0,0 +1,220 @@
+//! Folding ranges computed from the AST of a module, including the schema and rule
+//! bodies, the config blocks, the list and dict literals, the comprehensions, the lambda
+//! bodies, the if blocks, the docstrings, the import groups and the comment runs.
+
+use std::collections::BTreeMap;
+
+use kcl_ast::ast::{Expr, Module, Node, Stmt};
+use kcl_ast::walker::MutSelfWalker;
+use lsp_types::{FoldingRange, FoldingRangeKind};
+
+use crate::util::schema_end_line;
+
+pub fn folding_range(module: &Module) -> Vec<FoldingRange> {
+    let mut folder = Folder::default();
+    if let Some(doc) = &module.doc {
+        folder.fold(doc.line, doc.end_line, Some(FoldingRangeKind::Comment));
+    }
+    folder.fold_import_groups(module);
+    folder.fold_comment_runs(module);
+    folder.walk_module(module);
+    folder.finish()
+}
+
+/// The folding ranges keyed by the 1-based start lines. Only the largest range
+/// is kept for each start line because clients fold by lines.
+#[derive(Default)]
+struct Folder {
+    ranges: BTreeMap<u64, (u64, Option<FoldingRangeKind>)>,
+}
+
+impl Folder {
+    fn fold(&mut self, start_line: u64, end_line: u64, kind: Option<FoldingRangeKind>) {
+        if end_line <= start_line {
+            return;
+        }
+        match self.ranges.get(&start_line) {
+            Some((end, _)) if *end >= end_line => {}
+            _ => {
+                self.ranges.insert(start_line, (end_line, kind));
+            }
+        }
+    }
+
+    /// Folds the consecutive import statements.
+    fn fold_import_groups(&mut self, module: &Module) {
+        for group in module
+            .body
+            .chunk_by(|a, b| matches!((&a.node, &b.node), (Stmt::Import(_), Stmt::Import(_))))
+        {
+            if let [first, .., last] = group
+                && matches!(first.node, Stmt::Import(_))
+            {
+                self.fold(first.line, last.end_line, Some(FoldingRangeKind::Imports));
+            }
+        }
+    }
+
+    /// Folds the comments on the consecutive lines.
+    fn fold_comment_runs(&mut self, module: &Module) {
+        for run in module.comments.chunk_by(|a, b| b.line == a.end_line + 1) {
+            if let [first, .., last] = run {
+                self.fold(first.line, last.end_line, Some(FoldingRangeKind::Comment));
+            }
+        }
+    }
+
+    /// Converts the ranges to the LSP folding ranges whose lines are 0-based.
+    fn finish(self) -> Vec<FoldingRange> {
+        self.ranges
+            .into_iter()
+            .map(|(start_line, (end_line, kind))| FoldingRange {
+                start_line: start_line.saturating_sub(1) as u32,
+                start_character: None,
+                end_line: end_line.saturating_sub(1) as u32,
+                end_character: None,
+                kind,
+            })
+            .collect()
+    }
+}
+
+impl MutSelfWalker for Folder {
+    fn walk_stmt_node(&mut self, stmt: &Node<Stmt>) {
+        match &stmt.node {
+            Stmt::Schema(schema_stmt) => {
+                // The schema and rule statements end at the following statement, so the
+                // bodies are folded to the last inner nodes.
+                self.fold(stmt.line, schema_end_line(stmt, schema_stmt), None);
+                if let Some(doc) = &schema_stmt.doc {
+                    self.fold(doc.line, doc.end_line, Some(FoldingRangeKind::Comment));
+                }
+            }
+            Stmt::Rule(rule_stmt) => {
+                let end_line = rule_stmt
+                    .checks
+                    .iter()
+                    .map(|check| check.end_line)
+                    .chain(rule_stmt.doc.iter().map(|doc| doc.end_line))
+                    .max();
+                self.fold(stmt.line, end_line.unwrap_or(stmt.line), None);
+                if let Some(doc) = &rule_stmt.doc {
+                    self.fold(doc.line, doc.end_line, Some(FoldingRangeKind::Comment));
+                }
+            }
+            Stmt::If(if_stmt) => {
+                if let Some(last) = if_stmt.body.last() {
+                    self.fold(stmt.line, last.end_line, None);
+                }
+                // The `elif` block is a nested if statement in the `orelse`, which
+                // is folded by itself. The `else` block starts from the `else` line.
+                if let (Some(first), Some(last)) = (if_stmt.orelse.first(), if_stmt.orelse.last())
+                    && !(if_stmt.orelse.len() == 1 && matches!(first.node, Stmt::If(_)))
+                {
+                    self.fold(first.line.saturating_sub(1), last.end_line, None);
+                }
+            }
+            _ => {}
+        }
+        self.walk_stmt(&stmt.node);
+    }
+
+    fn walk_expr_node(&mut self, expr: &Node<Expr>) {
+        // Keep the closing bracket line visible.
+        if let Some(end_line) = inner_end_line(&expr.node) {
+            self.fold(expr.line, end_line, None);
+        }
+        self.walk_expr(&expr.node);
+    }
+}
+
+/// Returns the last end line of the inner nodes of the bracketed expressions and the
+/// lambda expressions, which is the line before the closing bracket.
+fn inner_end_line(expr: &Expr) -> Option<u64> {
+    let end_lines: Vec<u64> = match expr {
+        Expr::Config(config_expr) => config_expr
+            .items
+            .iter()
+            .map(|entry| entry.end_line)
+            .collect(),
+        Expr::List(list_expr) => list_expr.elts.iter().map(|elt| elt.end_line).collect(),
+        Expr::ListComp(list_comp) => list_comp
+            .generators
+            .iter()
+            .map(|generator| generator.end_line)
+            .chain([list_comp.elt.end_line])
+            .collect(),
+        Expr::DictComp(dict_comp) => dict_comp
+            .generators
+            .iter()
+            .map(|generator| generator.end_line)
+            .chain(dict_comp.entry.key.iter().map(|key| key.end_line))
+            .chain([dict_comp.entry.value.end_line])
+            .collect(),
+        Expr::ConfigIfEntry(config_if_entry_expr) => config_if_entry_expr
+            .items
+            .iter()
+            .map(|entry| entry.end_line)
+            .chain(
+                config_if_entry_expr
+                    .orelse
+                    .iter()
+                    .map(|orelse| orelse.end_line),
+            )
+            .chain([config_if_entry_expr.if_cond.end_line])
+            .collect(),
+        Expr::Lambda(lambda_expr) => lambda_expr
+            .body
+            .iter()
+            .map(|stmt| stmt.end_line)
+            .chain(lambda_expr.args.iter().map(|args| args.end_line))
+            .chain(lambda_expr.return_ty.iter().map(|ty| ty.end_line))
+            .collect(),
+        _ => vec![],
+    };
+    end_lines.into_iter().max()
+}
+
+#[cfg(test)]
+mod tests {
+    use lsp_types::FoldingRangeKind;
+    use proc_macro_crate::bench_test;
+
+    use super::folding_range;
+    use crate::tests::compile_test_file;
+
+    #[test]
+    #[bench_test]
+    fn folding_range_test() {
+        let (file, prog, _, _, _) =
+            compile_test_file("src/test_data/folding_range/folding_range.k");
+        let module = prog.get_module(&file).unwrap().unwrap();
+        let ranges: Vec<(u32, u32, Option<FoldingRangeKind>)> = folding_range(&module)
+            .into_iter()
+            .map(|range| (range.start_line, range.end_line, range.kind))
+            .collect();
+        assert_eq!(
+            ranges,
+            vec![
+                // Comment run
+                (0, 1, Some(FoldingRangeKind::Comment)),
+                // Import group
+                (2, 4, Some(FoldingRangeKind::Imports)),
+                // Schema body
+                (6, 17, None),
+                // Schema docstring
+                (7, 9, Some(FoldingRangeKind::Comment)),
+                // List literal in the schema attribute
+                (11, 13, None),
+                // Config block
+                (19, 23, None),
+                // List literal in the config
+                (21, 22, None),
+                // if block
+                (26, 28, None),
+                // else block
+                (29, 31, None),
+            ]
+        );
+    }
+}
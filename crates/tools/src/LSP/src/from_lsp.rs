@@ -24,27 +24,38 @@ pub(crate) fn kcl_pos(file: &str, pos: Position) -> KCLPos {
     }
 }
 
-/// Converts the given lsp range to `Range`
-pub(crate) fn text_range(text: &str, range: lsp_types::Range) -> Range<usize> {
-    let mut lines_length = vec![];
-    let lines_text: Vec<&str> = text.split('\n').collect();
-    let mut pre_total_length = 0;
-    // range line base-zeror
-    for i in 0..range.end.line + 1 {
-        let i = i as usize;
-        if i < lines_text.len() {
-            let line = lines_text.get(i).unwrap();
-            lines_length.push(pre_total_length);
-            pre_total_length += line.len() + "\n".len();
-        } else {
-            lines_length.push(pre_total_length);
+/// Converts the given lsp position to the byte offset in `text`. The lsp character offset is
+/// counted in UTF-16 code units, and the offset past the end of the line is clamped to the
+/// start of the next line, e.g., to delete the whole line with its line break.
+pub(crate) fn text_offset(text: &str, pos: Position) -> usize {
+    let mut line_start = 0;
+    for _ in 0..pos.line {
+        match text[line_start..].find('\n') {
+            Some(i) => line_start += i + 1,
+            None => return text.len(),
         }
     }
+    let line_end = text[line_start..]
+        .find('\n')
+        .map_or(text.len(), |i| line_start + i);
+    let mut utf16_offset = 0;
+    for (i, c) in text[line_start..line_end].char_indices() {
+        if utf16_offset >= pos.character as usize {
+            return line_start + i;
+        }
+        utf16_offset += c.len_utf16();
+    }
+    if utf16_offset < pos.character as usize {
+        (line_end + 1).min(text.len())
+    } else {
+        line_end
+    }
+}
 
-    let start =
-        lines_length.get(range.start.line as usize).unwrap() + range.start.character as usize;
-    let end = lines_length.get(range.end.line as usize).unwrap() + range.end.character as usize;
-
+/// Converts the given lsp range to `Range`
+pub(crate) fn text_range(text: &str, range: lsp_types::Range) -> Range<usize> {
+    let start = text_offset(text, range.start);
+    let end = text_offset(text, range.end).max(start);
     Range { start, end }
 }
 
@@ -75,7 +75,7 @@ pub(crate) fn find_symbol(kcl_pos: &KCLPos, gs: &GlobalState, exact: bool) -> Op
 
 // Convert kcl position to GotoDefinitionResponse. This function will convert to
 // None, Scalar or Array according to the number of positions
-fn positions_to_goto_def_resp(
+pub(crate) fn positions_to_goto_def_resp(
     positions: &IndexSet<(KCLPos, KCLPos)>,
 ) -> Option<GotoDefinitionResponse> {
     match positions.len() {
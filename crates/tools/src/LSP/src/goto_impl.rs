@@ -0,0 +1,78 @@
+//! GotoImplementation for KCL
+//! Navigates from a schema to the schemas which implement it, i.e., the schemas which
+//! inherit from it, mix it in or implement it as a protocol, directly or indirectly.
+
+use kcl_error::Position as KCLPos;
+use kcl_primitives::{DefaultHashBuilder, IndexSet};
+use kcl_sema::core::global_state::GlobalState;
+use kcl_sema::core::symbol::SymbolKind;
+use lsp_types::GotoDefinitionResponse;
+
+use crate::goto_def::{find_def, positions_to_goto_def_resp};
+
+/// Navigates to the implementations of the schema at the position.
+pub fn goto_impl(kcl_pos: &KCLPos, gs: &GlobalState) -> Option<GotoDefinitionResponse> {
+    let def_ref = find_def(kcl_pos, gs, true)?;
+    if def_ref.get_kind() != SymbolKind::Schema {
+        return None;
+    }
+    let symbols = gs.get_symbols();
+    let mut impls = IndexSet::with_hasher(DefaultHashBuilder::default());
+    let mut stack = vec![def_ref];
+    while let Some(schema) = stack.pop() {
+        for subtype in symbols.get_schema_subtypes(schema) {
+            if subtype != def_ref && impls.insert(subtype) {
+                stack.push(subtype);
+            }
+        }
+    }
+    let positions = impls
+        .into_iter()
+        .filter_map(|schema| Some(symbols.get_symbol(schema)?.get_range()))
+        .collect();
+    positions_to_goto_def_resp(&positions)
+}
+
+#[cfg(test)]
+mod tests {
+    use kcl_error::Position as KCLPos;
+    use lsp_types::GotoDefinitionResponse;
+    use proc_macro_crate::bench_test;
+
+    use super::goto_impl;
+    use crate::tests::compile_test_file;
+
+    /// The 0-based start lines of the locations.
+    fn lines(resp: Option<GotoDefinitionResponse>) -> Vec<u32> {
+        match resp {
+            Some(GotoDefinitionResponse::Scalar(loc)) => vec![loc.range.start.line],
+            Some(GotoDefinitionResponse::Array(locs)) => {
+                locs.iter().map(|loc| loc.range.start.line).collect()
+            }
+            Some(GotoDefinitionResponse::Link(_)) => panic!("test failed"),
+            None => vec![],
+        }
+    }
+
+    #[test]
+    #[bench_test]
+    fn goto_impl_test() {
+        let (file, _, _, gs, _) = compile_test_file("src/test_data/hierarchy/hierarchy.k");
+        let pos = |line, column| KCLPos {
+            filename: file.clone(),
+            line,
+            column: Some(column),
+        };
+
+        // schema Base:
+        assert_eq!(lines(goto_impl(&pos(7, 7), &gs)), vec![9]);
+        // The protocol is implemented by the mixin and the schema which mixes it in.
+        assert_eq!(lines(goto_impl(&pos(1, 9), &gs)), vec![3, 9]);
+        // mixin NameMixin for NameProtocol:
+        assert_eq!(lines(goto_impl(&pos(4, 6), &gs)), vec![9]);
+        // schema Service(Base):
+        assert!(goto_impl(&pos(10, 7), &gs).is_none());
+        // Values have no implementations.
+        assert!(goto_impl(&pos(22, 0), &gs).is_none());
+    }
+}
@@ -0,0 +1,122 @@
+//! GotoTypeDefinition for KCL
+//! Navigates from a symbol to the definitions of the schemas and the type aliases in
+//! its resolved type, e.g., from `app` in `app = frontend.Server {...}` to the schema
+//! `Server`. The element types of the list, dict and union types and the return types
+//! of the functions are resolved as well.
+
+use kcl_error::Position as KCLPos;
+use kcl_primitives::{DefaultHashBuilder, IndexSet};
+use kcl_sema::core::global_state::GlobalState;
+use kcl_sema::core::package::ModuleInfo;
+use kcl_sema::core::symbol::{SymbolKind, SymbolRef};
+use kcl_sema::ty::{Type, TypeKind};
+use lsp_types::GotoDefinitionResponse;
+
+use crate::goto_def::{find_def, positions_to_goto_def_resp};
+
+/// Navigates to the type definitions of the symbol at the position.
+pub fn goto_type_def(kcl_pos: &KCLPos, gs: &GlobalState) -> Option<GotoDefinitionResponse> {
+    let def_ref = find_def(kcl_pos, gs, true)?;
+    let mut type_defs = IndexSet::with_hasher(DefaultHashBuilder::default());
+    match def_ref.get_kind() {
+        // The type of a type is itself.
+        SymbolKind::Schema | SymbolKind::TypeAlias => {
+            type_defs.insert(def_ref);
+        }
+        _ => {
+            let ty = gs
+                .get_symbols()
+                .get_symbol(def_ref)?
+                .get_sema_info()
+                .ty
+                .clone()?;
+            let module_info = gs.get_packages().get_module_info(&kcl_pos.filename);
+            collect_type_defs(&ty, gs, module_info, &mut type_defs);
+        }
+    }
+    let positions = type_defs
+        .into_iter()
+        .filter_map(|type_def| Some(gs.get_symbols().get_symbol(type_def)?.get_range()))
+        .collect();
+    positions_to_goto_def_resp(&positions)
+}
+
+fn collect_type_defs(
+    ty: &Type,
+    gs: &GlobalState,
+    module_info: Option<&ModuleInfo>,
+    type_defs: &mut IndexSet<SymbolRef>,
+) {
+    match &ty.kind {
+        TypeKind::Schema(_) | TypeKind::Named(_) => {
+            if let Some(type_def) = gs.get_symbols().get_type_symbol(ty, module_info)
+                && matches!(
+                    type_def.get_kind(),
+                    SymbolKind::Schema | SymbolKind::TypeAlias
+                )
+            {
+                type_defs.insert(type_def);
+            }
+        }
+        TypeKind::List(item_ty) => collect_type_defs(item_ty, gs, module_info, type_defs),
+        TypeKind::Dict(dict_ty) => {
+            collect_type_defs(&dict_ty.key_ty, gs, module_info, type_defs);
+            collect_type_defs(&dict_ty.val_ty, gs, module_info, type_defs);
+        }
+        TypeKind::Union(types) => {
+            for ty in types {
+                collect_type_defs(ty, gs, module_info, type_defs);
+            }
+        }
+        TypeKind::Function(func_ty) => {
+            collect_type_defs(&func_ty.return_ty, gs, module_info, type_defs)
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kcl_error::Position as KCLPos;
+    use lsp_types::GotoDefinitionResponse;
+    use proc_macro_crate::bench_test;
+
+    use super::goto_type_def;
+    use crate::tests::compile_test_file;
+
+    /// The 0-based start lines of the locations.
+    fn lines(resp: Option<GotoDefinitionResponse>) -> Vec<u32> {
+        match resp {
+            Some(GotoDefinitionResponse::Scalar(loc)) => vec![loc.range.start.line],
+            Some(GotoDefinitionResponse::Array(locs)) => {
+                locs.iter().map(|loc| loc.range.start.line).collect()
+            }
+            Some(GotoDefinitionResponse::Link(_)) => panic!("test failed"),
+            None => vec![],
+        }
+    }
+
+    #[test]
+    #[bench_test]
+    fn goto_type_def_test() {
+        let (file, _, _, gs, _) = compile_test_file("src/test_data/goto_type_def/goto_type_def.k");
+        let pos = |line, column| KCLPos {
+            filename: file.clone(),
+            line,
+            column: Some(column),
+        };
+
+        // app = Frontend {name = "app"}
+        assert_eq!(lines(goto_type_def(&pos(9, 0), &gs)), vec![3]);
+        // servers: [Server] = [app]
+        assert_eq!(lines(goto_type_def(&pos(10, 0), &gs)), vec![0]);
+        // make = lambda -> Server {...}
+        assert_eq!(lines(goto_type_def(&pos(11, 0), &gs)), vec![0]);
+        // The type alias `ServerType` in `s: ServerType = app`
+        assert_eq!(lines(goto_type_def(&pos(14, 3), &gs)), vec![6]);
+        // The schema `Frontend` itself
+        assert_eq!(lines(goto_type_def(&pos(4, 7), &gs)), vec![3]);
+        // name: str
+        assert!(goto_type_def(&pos(2, 4), &gs).is_none());
+    }
+}
@@ -1,17 +1,31 @@
 pub mod analysis;
+pub mod call_hierarchy;
 pub mod capabilities;
+pub mod code_lens;
 pub mod completion;
+pub mod dap;
+pub mod diagnostic;
+pub mod document_highlight;
+pub mod document_link;
 pub mod document_symbol;
+pub mod evaluate;
 pub mod find_refs;
+pub mod folding_range;
 pub mod formatting;
 pub mod goto_def;
+pub mod goto_impl;
+pub mod goto_type_def;
 pub mod hover;
 pub mod inlay_hints;
 pub mod quick_fix;
+pub mod refactor;
 pub mod rename;
 pub mod request;
+pub mod selection_range;
 pub mod semantic_token;
 pub mod signature_help;
+pub mod type_hierarchy;
+pub mod workspace_symbol;
 
 pub mod app;
 pub mod compile;
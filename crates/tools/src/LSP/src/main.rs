@@ -1,26 +1,40 @@
 mod analysis;
 mod app;
+mod call_hierarchy;
 mod capabilities;
+mod code_lens;
 mod compile;
 mod completion;
+mod dap;
+mod diagnostic;
 mod dispatcher;
+mod document_highlight;
+mod document_link;
 mod document_symbol;
 mod error;
+mod evaluate;
 mod find_refs;
+mod folding_range;
 mod formatting;
 mod from_lsp;
 mod goto_def;
+mod goto_impl;
+mod goto_type_def;
 mod hover;
 mod inlay_hints;
 mod notification;
 mod quick_fix;
+mod refactor;
 mod request;
+mod selection_range;
 mod semantic_token;
 mod signature_help;
 mod state;
 mod to_lsp;
+mod type_hierarchy;
 mod util;
 mod word_index;
+mod workspace_symbol;
 
 #[cfg(test)]
 mod tests;
@@ -39,6 +53,7 @@ fn main() -> Result<(), anyhow::Error> {
                 println!("{}", kcl_version::get_version_info());
                 Ok(())
             }
+            Some(("dap", _)) => dap::run_dap_server(),
             Some((subcommand, _)) => Err(anyhow::anyhow!("unknown subcommand: {}", subcommand)),
             None => {
                 let status: Result<ExitStatus, anyhow::Error> = {
@@ -64,6 +79,8 @@ fn run_server() -> anyhow::Result<()> {
     // Wait for a client to connect
     let (initialize_id, initialize_params) = connection.initialize_start()?;
 
+    let pull_diagnostics =
+        capabilities::PullDiagnostics::from_initialize_params(&initialize_params);
     let initialize_params =
         util::from_json::<lsp_types::InitializeParams>("InitializeParams", initialize_params)?;
 
@@ -78,11 +95,15 @@ fn run_server() -> anyhow::Result<()> {
         offset_encoding: None,
     };
 
-    let initialize_result = serde_json::to_value(initialize_result)
+    let mut initialize_result = serde_json::to_value(initialize_result)
         .map_err(|_| anyhow::anyhow!("Initialize result error"))?;
+    capabilities::add_unsupported_capabilities(
+        &mut initialize_result["capabilities"],
+        pull_diagnostics,
+    );
 
     connection.initialize_finish(initialize_id, initialize_result)?;
-    main_loop(connection, initialize_params)?;
+    main_loop(connection, initialize_params, pull_diagnostics)?;
     io_threads.join()?;
     Ok(())
 }
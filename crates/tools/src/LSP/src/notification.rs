@@ -3,11 +3,12 @@ use lsp_types::notification::{
     DidOpenTextDocument, DidSaveTextDocument,
 };
 use std::collections::HashSet;
+use std::sync::atomic::Ordering;
 
 use crate::util::apply_document_changes;
 use crate::{
     analysis::OpenFileInfo, dispatcher::NotificationDispatcher, from_lsp,
-    state::LanguageServerState,
+    from_lsp::file_path_from_url, state::LanguageServerState,
 };
 
 impl LanguageServerState {
@@ -31,6 +32,10 @@ impl LanguageServerState {
             lsp_types::NumberOrString::Number(id) => id.into(),
             lsp_types::NumberOrString::String(id) => id.into(),
         };
+        // Stop the running cancellable request, e.g., an evaluation.
+        if let Some(cancelled) = self.request_cancellations.read().get(&id) {
+            cancelled.store(true, Ordering::Relaxed);
+        }
         self.request_queue.incoming.complete(&id);
         Ok(())
     }
@@ -115,6 +120,9 @@ impl LanguageServerState {
         if let Some(id) = self.vfs.read().file_id(&path.clone().into()) {
             self.opened_files.write().remove(&id);
         }
+        if let Ok(file) = file_path_from_url(&params.text_document.uri) {
+            self.semantic_tokens_cache.write().remove(&file);
+        }
 
         // Update vfs
         let vfs = &mut *self.vfs.write();
@@ -131,6 +139,7 @@ impl LanguageServerState {
     ) -> anyhow::Result<()> {
         for change in params.changes {
             let path = from_lsp::abs_path(&change.uri)?;
+            self.invalidate_workspace_diags(path.as_ref());
             self.loader.handle.invalidate(path.clone());
         }
 
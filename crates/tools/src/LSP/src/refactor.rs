@@ -0,0 +1,1467 @@
+//! Refactoring code actions built on the AST, which are provided for the selected range
+//! besides the quick fixes of the diagnostics.
+//!
+//! - Extract a dict literal into a new schema whose attribute types are inferred from the values.
+//! - Convert a dict literal into a schema instance when its keys match a known schema.
+//! - Inline a top-level variable which is only referenced in its file.
+//! - Move a schema to another package of the workspace and fix up the references and imports.
+//!   The client picks the target package from the candidates of the action, and the edits
+//!   are computed when the server executes the move command.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail};
+
+use kcl_ast::MAIN_PKG;
+use kcl_ast::ast::{
+    AssignStmt, ConfigEntry, Expr, Module, NameConstant, Node, NodeRef, NumberLitValue, Program,
+    SchemaExpr, SchemaStmt, Stmt,
+};
+use kcl_ast::pos::{ContainsPos, GetPos};
+use kcl_ast::walker::MutSelfWalker;
+use kcl_ast_pretty::{ASTNode, print_ast_node};
+use kcl_error::Position as KCLPos;
+use kcl_error::diagnostic::Range as KCLRange;
+use kcl_primitives::{IndexMap, IndexSet};
+use kcl_sema::core::global_state::GlobalState;
+use kcl_sema::core::symbol::{SymbolKind as KCLSymbolKind, SymbolRef};
+use kcl_sema::info::is_valid_kcl_name;
+use kcl_sema::ty::{
+    ANY_TYPE_STR, BOOL_TYPE_STR, FLOAT_TYPE_STR, INT_TYPE_STR, STR_TYPE_STR, SchemaType, Type,
+    TypeKind,
+};
+use lsp_types::{
+    CodeAction, CodeActionKind, CodeActionOrCommand, Command, CreateFile, CreateFileOptions,
+    DocumentChangeOperation, DocumentChanges, OneOf, OptionalVersionedTextDocumentIdentifier,
+    Position, Range, ResourceOp, TextDocumentEdit, TextEdit, Url, WorkspaceEdit,
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::from_lsp::kcl_pos;
+use crate::goto_def::find_def;
+use crate::to_lsp::lsp_pos;
+use crate::util::schema_end_line;
+
+/// The kind of the code actions which move the code to another file, which is not
+/// provided by lsp-types 0.93.
+pub(crate) const REFACTOR_MOVE: CodeActionKind = CodeActionKind::new("refactor.move");
+
+/// Picks the target package of the moved schema in the client, the argument is
+/// [`MoveSchemaArgs`], and the client executes the [`MOVE_SCHEMA_TO_COMMAND`] with the picked
+/// package.
+pub(crate) const MOVE_SCHEMA_COMMAND: &str = "kcl.moveSchema";
+/// Moves the schema to the target package, the argument is [`MoveSchemaToArgs`]. The server
+/// applies the edits with the `workspace/applyEdit` request.
+pub(crate) const MOVE_SCHEMA_TO_COMMAND: &str = "kcl.moveSchemaTo";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MoveSchemaArgs {
+    /// The file which defines the schema.
+    pub file: String,
+    /// The position of the schema name.
+    pub position: Position,
+    /// The package paths which the schema can be moved to.
+    pub packages: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MoveSchemaToArgs {
+    /// The file which defines the schema.
+    pub file: String,
+    /// The position of the schema name.
+    pub position: Position,
+    /// The package path which the schema is moved to.
+    pub target: String,
+}
+
+pub fn refactor(
+    file: &str,
+    range: &KCLRange,
+    prog: &Program,
+    gs: &GlobalState,
+    schema_map: &IndexMap<String, Vec<SchemaType>>,
+) -> Vec<CodeActionOrCommand> {
+    let mut actions = vec![];
+    let Ok(Some(module)) = prog.get_module(file) else {
+        return vec![];
+    };
+    let Ok(uri) = Url::from_file_path(file) else {
+        return vec![];
+    };
+    let (start, end) = range;
+    let stmt = module.body.iter().find(|stmt| stmt.contains_pos(start));
+    if let Some(stmt) = stmt
+        && let Some(dict) = dict_literal(stmt, start, end)
+    {
+        actions.extend(extract_schema(&uri, file, &module, stmt, &dict, prog, gs));
+        actions.extend(convert_to_schema(
+            &uri,
+            file,
+            &module,
+            &dict.config,
+            prog,
+            schema_map,
+        ));
+    }
+    actions.extend(inline_variable(&uri, file, start, &module, gs));
+    if let Some(stmt) = stmt {
+        actions.extend(move_schema(file, start, &module, stmt, prog, gs));
+    }
+    actions
+        .into_iter()
+        .map(CodeActionOrCommand::CodeAction)
+        .collect()
+}
+
+/// A dict literal and the name of the variable or the config key it is assigned to.
+struct DictLiteral {
+    config: Node<Expr>,
+    name: Option<String>,
+}
+
+/// Returns the innermost dict literal in the statement containing the selection, which is
+/// not the config of a schema expression.
+fn dict_literal(stmt: &Node<Stmt>, start: &KCLPos, end: &KCLPos) -> Option<DictLiteral> {
+    let mut finder = DictLiteralFinder {
+        start,
+        end,
+        name: None,
+        dict: None,
+    };
+    finder.walk_stmt_node(stmt);
+    finder.dict
+}
+
+/// Walks into the expressions containing the selection start, and keeps the innermost dict
+/// literal which also contains the selection end. The dict literal is cloned because the
+/// walker can't keep the references of the AST nodes.
+struct DictLiteralFinder<'a> {
+    start: &'a KCLPos,
+    end: &'a KCLPos,
+    /// The name which the next walked expression is assigned to.
+    name: Option<String>,
+    dict: Option<DictLiteral>,
+}
+
+impl MutSelfWalker for DictLiteralFinder<'_> {
+    fn walk_assign_stmt(&mut self, assign_stmt: &AssignStmt) {
+        for target in &assign_stmt.targets {
+            self.walk_target(&target.node);
+        }
+        self.name = assign_stmt
+            .targets
+            .first()
+            .map(|target| target.node.get_name().to_string());
+        self.walk_expr_node(&assign_stmt.value);
+    }
+
+    fn walk_config_entry(&mut self, config_entry: &Node<ConfigEntry>) {
+        if let Some(key) = &config_entry.node.key {
+            self.walk_expr_node(key);
+        }
+        self.name = entry_key_name(&config_entry.node);
+        self.walk_expr_node(&config_entry.node.value);
+    }
+
+    fn walk_schema_expr(&mut self, schema_expr: &SchemaExpr) {
+        for arg in &schema_expr.args {
+            self.walk_expr_node(arg);
+        }
+        for kwarg in &schema_expr.kwargs {
+            self.walk_keyword(&kwarg.node);
+        }
+        // The config of a schema expression is not a dict literal, but its values may be.
+        self.walk_expr(&schema_expr.config.node);
+    }
+
+    fn walk_expr_node(&mut self, expr: &Node<Expr>) {
+        let name = self.name.take();
+        if !expr.contains_pos(self.start) {
+            return;
+        }
+        if matches!(expr.node, Expr::Config(_)) && expr.contains_pos(self.end) {
+            self.dict = Some(DictLiteral {
+                config: expr.clone(),
+                name,
+            });
+        }
+        self.walk_expr(&expr.node);
+    }
+}
+
+/// Extracts the dict literal into a new schema which is inserted before the top-level
+/// statement, e.g., `web_server = {port = 80}` to `web_server = WebServer {port = 80}`.
+fn extract_schema(
+    uri: &Url,
+    file: &str,
+    module: &Module,
+    stmt: &Node<Stmt>,
+    dict: &DictLiteral,
+    prog: &Program,
+    gs: &GlobalState,
+) -> Option<CodeAction> {
+    let config = &dict.config;
+    let Expr::Config(config_expr) = &config.node else {
+        return None;
+    };
+    let mut keys = HashSet::new();
+    let mut attrs = vec![];
+    for entry in &config_expr.items {
+        let key = entry_key_name(&entry.node)?;
+        if keys.insert(key.clone()) {
+            attrs.push(format!(
+                "    {}: {}",
+                key,
+                infer_type(&entry.node.value, gs)
+            ));
+        }
+    }
+    if attrs.is_empty() {
+        return None;
+    }
+    let names = package_names(prog, file, module);
+    let hint = schema_name_hint(dict.name.as_deref());
+    let name = (1..)
+        .map(|i| {
+            if i == 1 {
+                hint.clone()
+            } else {
+                format!("{hint}{i}")
+            }
+        })
+        .find(|name| !names.contains(name))?;
+    let stmt_start = Position::new(stmt.line.saturating_sub(1) as u32, 0);
+    Some(code_action(
+        format!("Extract schema `{name}`"),
+        CodeActionKind::REFACTOR_EXTRACT,
+        uri,
+        vec![
+            insert(
+                stmt_start,
+                format!("schema {name}:\n{}\n\n", attrs.join("\n")),
+            ),
+            insert(lsp_pos(&config.get_pos()), format!("{name} ")),
+        ],
+    ))
+}
+
+/// Converts the dict literal into the instances of the schemas whose attributes match
+/// the keys, e.g., `{name = "alice"}` to `Person {name = "alice"}`.
+fn convert_to_schema(
+    uri: &Url,
+    file: &str,
+    module: &Module,
+    config: &Node<Expr>,
+    prog: &Program,
+    schema_map: &IndexMap<String, Vec<SchemaType>>,
+) -> Vec<CodeAction> {
+    let mut actions = vec![];
+    let Expr::Config(config_expr) = &config.node else {
+        return actions;
+    };
+    let Some(keys) = config_expr
+        .items
+        .iter()
+        .map(|entry| entry_key_name(&entry.node))
+        .collect::<Option<HashSet<String>>>()
+        .filter(|keys| !keys.is_empty())
+    else {
+        return actions;
+    };
+    let Some(pkgpath) = file_pkgpath(prog, file) else {
+        return actions;
+    };
+    for schema in schema_map.values().flatten() {
+        if schema.is_mixin
+            || schema.is_protocol
+            || schema.is_rule
+            || !schema.func.params.is_empty()
+            || schema.index_signature.is_some()
+        {
+            continue;
+        }
+        if !keys.iter().all(|key| schema.get_obj_of_attr(key).is_some())
+            || !required_attrs(schema)
+                .iter()
+                .all(|attr| keys.contains(attr))
+        {
+            continue;
+        }
+        let Some((name, import)) = qualified_schema_name(schema, &pkgpath, module) else {
+            continue;
+        };
+        let mut edits = vec![insert(lsp_pos(&config.get_pos()), format!("{name} "))];
+        edits.extend(import);
+        actions.push(code_action(
+            format!("Convert to `{name}` instance"),
+            CodeActionKind::REFACTOR_REWRITE,
+            uri,
+            edits,
+        ));
+    }
+    actions
+}
+
+/// Inlines the top-level variable at the position, which is assigned once and only
+/// referenced in its file, and removes the assignment.
+fn inline_variable(
+    uri: &Url,
+    file: &str,
+    pos: &KCLPos,
+    module: &Module,
+    gs: &GlobalState,
+) -> Option<CodeAction> {
+    let def = find_def(pos, gs, true)?;
+    if def.get_kind() != KCLSymbolKind::Value {
+        return None;
+    }
+    let symbols = gs.get_symbols();
+    let symbol = symbols.get_symbol(def)?;
+    if symbol.get_owner()?.get_kind() != KCLSymbolKind::Package {
+        return None;
+    }
+    let name = symbol.get_name();
+    let mut assigns = vec![];
+    collect_assigns(&module.body, &name, &mut assigns);
+    let [stmt] = assigns.as_slice() else {
+        return None;
+    };
+    let Stmt::Assign(assign_stmt) = &stmt.node else {
+        return None;
+    };
+    if assign_stmt.targets.len() != 1 || !assign_stmt.targets[0].node.paths.is_empty() {
+        return None;
+    }
+    let mut refs: Vec<KCLRange> = symbol
+        .get_references()
+        .iter()
+        .filter_map(|r| symbols.get_symbol(*r))
+        .map(|r| r.get_range())
+        .filter(|(start, _)| !stmt.contains_pos(start))
+        .collect();
+    refs.sort_by_key(|(start, _)| (start.filename.clone(), start.line, start.column));
+    if refs.is_empty() || refs.iter().any(|(start, _)| start.filename != file) {
+        return None;
+    }
+    let value = print_ast_node(ASTNode::Expr(&assign_stmt.value));
+    let value = value.trim();
+    let value = if needs_parens(&assign_stmt.value.node) {
+        format!("({value})")
+    } else {
+        value.to_string()
+    };
+    let mut edits: Vec<TextEdit> = refs
+        .iter()
+        .map(|(start, end)| TextEdit {
+            range: Range {
+                start: lsp_pos(start),
+                end: lsp_pos(end),
+            },
+            new_text: value.clone(),
+        })
+        .collect();
+    edits.push(delete_lines(stmt.line, stmt.end_line));
+    Some(code_action(
+        format!("Inline variable `{name}`"),
+        CodeActionKind::REFACTOR_INLINE,
+        uri,
+        edits,
+    ))
+}
+
+/// Moves the schema whose name is at the position to a new file in another package of the
+/// workspace, e.g., `models/person.k` for the schema `Person`. The action only carries the
+/// candidate packages, and the client picks the target package and executes the
+/// [`MOVE_SCHEMA_TO_COMMAND`], whose edits are computed by [`move_schema_edit`].
+fn move_schema(
+    file: &str,
+    pos: &KCLPos,
+    module: &Module,
+    stmt: &Node<Stmt>,
+    prog: &Program,
+    gs: &GlobalState,
+) -> Option<CodeAction> {
+    let schema_move = SchemaMove::new(file, pos, module, stmt, gs)?;
+    let packages = schema_move.target_packages(prog);
+    if packages.is_empty() {
+        return None;
+    }
+    let title = format!("Move schema `{}` to another package", schema_move.name);
+    Some(CodeAction {
+        title: title.clone(),
+        kind: Some(REFACTOR_MOVE),
+        command: Some(Command {
+            title,
+            command: MOVE_SCHEMA_COMMAND.to_string(),
+            arguments: Some(vec![json!(MoveSchemaArgs {
+                file: file.to_string(),
+                position: lsp_pos(&schema_move.schema_stmt.name.get_pos()),
+                packages,
+            })]),
+        }),
+        ..Default::default()
+    })
+}
+
+/// Returns the workspace edit which moves the schema whose name is at the position to the
+/// target package, see [`move_schema`].
+pub(crate) fn move_schema_edit(
+    file: &str,
+    src: &str,
+    position: Position,
+    target_pkgpath: &str,
+    prog: &Program,
+    gs: &GlobalState,
+) -> anyhow::Result<WorkspaceEdit> {
+    let pos = kcl_pos(file, position);
+    let module = prog
+        .get_module(file)?
+        .ok_or_else(|| anyhow!("the file {file} is not compiled"))?;
+    let schema_move = module
+        .body
+        .iter()
+        .find(|stmt| stmt.contains_pos(&pos))
+        .and_then(|stmt| SchemaMove::new(file, &pos, &module, stmt, gs))
+        .ok_or_else(|| anyhow!("no schema can be moved at {file}:{}", pos.line))?;
+    if !schema_move
+        .target_packages(prog)
+        .iter()
+        .any(|pkgpath| pkgpath == target_pkgpath)
+    {
+        bail!(
+            "the schema `{}` can't be moved to the package `{target_pkgpath}`, which is not a \
+             package of the workspace or forms an import cycle",
+            schema_move.name
+        );
+    }
+    schema_move
+        .edit(src, target_pkgpath, prog, gs)
+        .ok_or_else(|| anyhow!("failed to move the schema `{}`", schema_move.name))
+}
+
+/// The schema to move and the references to fix up.
+struct SchemaMove<'a> {
+    file: &'a str,
+    module: &'a Module,
+    stmt: &'a Node<Stmt>,
+    schema_stmt: &'a SchemaStmt,
+    name: String,
+    origin_dir: &'a Path,
+    imports: Vec<SchemaImport>,
+    /// The references outside the schema sorted by the positions.
+    refs: Vec<KCLRange>,
+}
+
+impl<'a> SchemaMove<'a> {
+    fn new(
+        file: &'a str,
+        pos: &KCLPos,
+        module: &'a Module,
+        stmt: &'a Node<Stmt>,
+        gs: &GlobalState,
+    ) -> Option<Self> {
+        let Stmt::Schema(schema_stmt) = &stmt.node else {
+            return None;
+        };
+        if !schema_stmt.name.contains_pos(pos) {
+            return None;
+        }
+        let origin_dir = Path::new(file).parent()?;
+        let def = find_def(&schema_stmt.name.get_pos(), gs, true)?;
+        let imports = schema_imports(file, stmt, def, origin_dir, module, gs)?;
+        let symbols = gs.get_symbols();
+        let symbol = symbols.get_symbol(def)?;
+        let mut refs: Vec<KCLRange> = symbol
+            .get_references()
+            .iter()
+            .filter_map(|r| symbols.get_symbol(*r))
+            .map(|r| r.get_range())
+            .filter(|(start, _)| !stmt.contains_pos(start))
+            .collect();
+        refs.sort_by_key(|(start, _)| (start.filename.clone(), start.line, start.column));
+        Some(Self {
+            file,
+            module,
+            stmt,
+            schema_stmt,
+            name: symbol.get_name(),
+            origin_dir,
+            imports,
+            refs,
+        })
+    }
+
+    /// The packages which the schema can be moved to. The packages imported by the schema,
+    /// the packages in the directory of the origin package and the packages which would form
+    /// an import cycle are excluded, e.g., the target package imports a package referencing
+    /// the schema, which imports the target package after the schema is moved.
+    fn target_packages(&self, prog: &Program) -> Vec<String> {
+        let Some(origin_pkgpath) = file_pkgpath(prog, self.file) else {
+            return vec![];
+        };
+        let graph = package_imports(prog, self.file, self.module);
+        // The main package is imported with the path of its directory in the workspace.
+        let origin_import_path = self
+            .origin_dir
+            .strip_prefix(&prog.root)
+            .ok()
+            .map(|dir| {
+                dir.components()
+                    .map(|component| component.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join(".")
+            })
+            .filter(|path| !path.is_empty());
+        // The packages which import the target package after the schema is moved. The
+        // references only cover the compiled files, so the packages importing the origin
+        // package are included as well.
+        let importers: HashSet<String> = self
+            .refs
+            .iter()
+            .filter_map(|(start, _)| file_pkgpath(prog, &start.filename))
+            .chain(
+                graph
+                    .iter()
+                    .filter(|(_, imports)| {
+                        imports.contains(&origin_pkgpath)
+                            || origin_import_path
+                                .as_ref()
+                                .is_some_and(|path| imports.contains(path))
+                    })
+                    .map(|(pkgpath, _)| pkgpath.clone()),
+            )
+            .collect();
+        let mut targets: Vec<String> = prog
+            .pkgs
+            .iter()
+            .chain(prog.pkgs_not_imported.iter())
+            .filter(|(pkgpath, files)| {
+                *pkgpath != &origin_pkgpath
+                    && *pkgpath != MAIN_PKG
+                    && !pkgpath.is_empty()
+                    && package_dir(files).is_some_and(|dir| {
+                        dir != self.origin_dir
+                            && dir.starts_with(&prog.root)
+                            && !self.new_file(dir).exists()
+                    })
+                    && !self
+                        .imports
+                        .iter()
+                        .any(|import| imports_package(&graph, &import.path, pkgpath))
+                    && !importers.iter().any(|importer| {
+                        importer != *pkgpath && imports_package(&graph, pkgpath, importer)
+                    })
+            })
+            .map(|(pkgpath, _)| pkgpath.clone())
+            .collect();
+        targets.sort();
+        targets.dedup();
+        targets
+    }
+
+    /// The new file of the schema in the target package directory.
+    fn new_file(&self, target_dir: &Path) -> PathBuf {
+        target_dir.join(format!("{}.k", snake_case(&self.name)))
+    }
+
+    /// Returns the workspace edit which creates the new file of the schema in the target
+    /// package, removes the schema from the origin file and fixes up the references.
+    fn edit(
+        &self,
+        src: &str,
+        target_pkgpath: &str,
+        prog: &Program,
+        gs: &GlobalState,
+    ) -> Option<WorkspaceEdit> {
+        let (file, module, stmt, name) = (self.file, self.module, self.stmt, &self.name);
+        let target_dir = prog
+            .pkgs
+            .get(target_pkgpath)
+            .or(prog.pkgs_not_imported.get(target_pkgpath))
+            .and_then(|files| package_dir(files))?;
+        // The schema source with its decorators.
+        let start_line = self
+            .schema_stmt
+            .decorators
+            .iter()
+            .map(|decorator| decorator.line)
+            .chain([stmt.line])
+            .min()
+            .unwrap_or(stmt.line);
+        let lines: Vec<&str> = src.lines().collect();
+        // Skip the blank lines between the body and the following statement.
+        let mut schema_end_line = schema_end_line(stmt, self.schema_stmt);
+        while schema_end_line > stmt.line
+            && lines
+                .get(schema_end_line as usize - 1)
+                .is_some_and(|line| line.trim().is_empty())
+        {
+            schema_end_line -= 1;
+        }
+        let schema_src = lines.get(start_line as usize - 1..schema_end_line as usize)?;
+        // Remove the blank line after the schema as well.
+        let end_line = match lines.get(schema_end_line as usize) {
+            Some(line) if line.trim().is_empty() => schema_end_line + 1,
+            _ => schema_end_line,
+        };
+        let new_uri = Url::from_file_path(self.new_file(target_dir)).ok()?;
+        let mut content = String::new();
+        for import in &self.imports {
+            let path = import.target_path(target_pkgpath);
+            match &import.asname {
+                Some(asname) => content.push_str(&format!("import {path} as {asname}\n")),
+                None => content.push_str(&format!("import {path}\n")),
+            }
+        }
+        if !self.imports.is_empty() {
+            content.push('\n');
+        }
+        content.push_str(&schema_src.join("\n"));
+        content.push('\n');
+
+        let mut changes: IndexMap<String, Vec<TextEdit>> = IndexMap::default();
+        let origin_edits = changes.entry(file.to_string()).or_default();
+        origin_edits.push(delete_lines(start_line, end_line));
+        // Remove the imports which are only used in the moved schema.
+        for import in self.imports.iter().filter(|import| !import.used_elsewhere) {
+            origin_edits.push(delete_lines(import.line, import.end_line));
+        }
+        let mut aliases: HashMap<String, String> = HashMap::new();
+        for (start, end) in &self.refs {
+            let ref_dir = Path::new(&start.filename).parent();
+            let (start, new_text) = if ref_dir == Some(self.origin_dir) {
+                (start.clone(), None)
+            } else {
+                let Some(qualifier) = qualifier_start(gs, start) else {
+                    continue;
+                };
+                if ref_dir == Some(target_dir) {
+                    (qualifier, Some(name.clone()))
+                } else {
+                    (qualifier, None)
+                }
+            };
+            let new_text = match new_text {
+                Some(new_text) => new_text,
+                None => {
+                    let alias = match aliases.get(&start.filename) {
+                        Some(alias) => alias.clone(),
+                        None => {
+                            let (alias, import) = if start.filename == file {
+                                import_alias(module, target_pkgpath)
+                            } else {
+                                let Ok(Some(ref_module)) = prog.get_module(&start.filename) else {
+                                    continue;
+                                };
+                                import_alias(&ref_module, target_pkgpath)
+                            };
+                            changes
+                                .entry(start.filename.clone())
+                                .or_default()
+                                .extend(import);
+                            aliases.insert(start.filename.clone(), alias.clone());
+                            alias
+                        }
+                    };
+                    format!("{alias}.{name}")
+                }
+            };
+            changes
+                .entry(start.filename.clone())
+                .or_default()
+                .push(TextEdit {
+                    range: Range {
+                        start: lsp_pos(&start),
+                        end: lsp_pos(end),
+                    },
+                    new_text,
+                });
+        }
+
+        let mut operations = vec![
+            DocumentChangeOperation::Op(ResourceOp::Create(CreateFile {
+                uri: new_uri.clone(),
+                options: Some(CreateFileOptions {
+                    overwrite: Some(false),
+                    ignore_if_exists: Some(false),
+                }),
+                annotation_id: None,
+            })),
+            text_document_edit(new_uri, vec![insert(Position::new(0, 0), content)]),
+        ];
+        for (file, edits) in changes {
+            if let Ok(uri) = Url::from_file_path(&file) {
+                operations.push(text_document_edit(uri, edits));
+            }
+        }
+        Some(WorkspaceEdit {
+            document_changes: Some(DocumentChanges::Operations(operations)),
+            ..Default::default()
+        })
+    }
+}
+
+/// The directory of the package files.
+fn package_dir(files: &[String]) -> Option<&Path> {
+    files.first().and_then(|file| Path::new(file).parent())
+}
+
+/// The packages imported by each package of the workspace, where `module` is the AST of
+/// `file` which is already locked.
+fn package_imports(
+    prog: &Program,
+    file: &str,
+    module: &Module,
+) -> HashMap<String, HashSet<String>> {
+    let mut graph: HashMap<String, HashSet<String>> = HashMap::new();
+    for (pkgpath, files) in prog.pkgs.iter().chain(prog.pkgs_not_imported.iter()) {
+        let imports = graph.entry(pkgpath.clone()).or_default();
+        for pkg_file in files {
+            let mut add_imports = |module: &Module| {
+                for stmt in &module.body {
+                    if let Stmt::Import(import_stmt) = &stmt.node {
+                        imports.insert(import_stmt.path.node.clone());
+                    }
+                }
+            };
+            if pkg_file == file {
+                add_imports(module);
+            } else if let Ok(Some(module)) = prog.get_module(pkg_file) {
+                add_imports(&module);
+            }
+        }
+    }
+    graph
+}
+
+/// Whether the package `from` is the package `to` or imports it transitively.
+fn imports_package(graph: &HashMap<String, HashSet<String>>, from: &str, to: &str) -> bool {
+    let mut visited = HashSet::new();
+    let mut stack = vec![from];
+    while let Some(pkgpath) = stack.pop() {
+        if pkgpath == to {
+            return true;
+        }
+        if visited.insert(pkgpath)
+            && let Some(imports) = graph.get(pkgpath)
+        {
+            stack.extend(imports.iter().map(|import| import.as_str()));
+        }
+    }
+    false
+}
+
+/// An import statement of the origin file which is used in the moved schema.
+struct SchemaImport {
+    /// The absolute package path, which is fixed by the parser for the relative imports.
+    path: String,
+    asname: Option<String>,
+    /// Whether the import path is relative, e.g., `.models`.
+    relative: bool,
+    /// Whether the package is used outside the moved schema in the origin file.
+    used_elsewhere: bool,
+    line: u64,
+    end_line: u64,
+}
+
+impl SchemaImport {
+    /// The import path in the target package, where the relative import of a sub package
+    /// of the target package is still relative, e.g., `.common` for `models.common` in the
+    /// package `models`, and the other relative imports are absolute.
+    fn target_path(&self, target_pkgpath: &str) -> String {
+        if self.relative
+            && let Some(rest) = self
+                .path
+                .strip_prefix(target_pkgpath)
+                .and_then(|rest| rest.strip_prefix('.'))
+        {
+            format!(".{rest}")
+        } else {
+            self.path.clone()
+        }
+    }
+}
+
+/// Returns the imports used in the schema statement, or `None` when the schema uses the
+/// other top-level symbols of its package, which are not visible after it is moved.
+fn schema_imports(
+    file: &str,
+    stmt: &Node<Stmt>,
+    def: SymbolRef,
+    origin_dir: &Path,
+    module: &Module,
+    gs: &GlobalState,
+) -> Option<Vec<SchemaImport>> {
+    let symbols = gs.get_symbols();
+    let mut names = HashSet::new();
+    let mut names_elsewhere = HashSet::new();
+    for symbol_ref in gs.get_sema_db().get_file_sema(file)?.get_symbols() {
+        let Some(symbol) = symbols.get_symbol(*symbol_ref) else {
+            continue;
+        };
+        let Some(symbol_def) = symbol.get_definition() else {
+            continue;
+        };
+        let (start, _) = symbol.get_range();
+        if !stmt.contains_pos(&start) {
+            // The package names in the import statements are not the uses of the packages.
+            let in_import = module
+                .body
+                .iter()
+                .any(|stmt| matches!(stmt.node, Stmt::Import(_)) && stmt.contains_pos(&start));
+            if !in_import && let KCLSymbolKind::Package = symbol_def.get_kind() {
+                names_elsewhere.insert(symbol.get_name());
+            }
+            continue;
+        }
+        match symbol_def.get_kind() {
+            KCLSymbolKind::Package => {
+                names.insert(symbol.get_name());
+            }
+            KCLSymbolKind::Schema
+            | KCLSymbolKind::Rule
+            | KCLSymbolKind::TypeAlias
+            | KCLSymbolKind::Function
+            | KCLSymbolKind::Value => {
+                let def_symbol = symbols.get_symbol(symbol_def)?;
+                let (def_start, _) = def_symbol.get_range();
+                if symbol_def != def
+                    && def_symbol.is_global()
+                    && !stmt.contains_pos(&def_start)
+                    && Path::new(&def_start.filename).parent() == Some(origin_dir)
+                {
+                    return None;
+                }
+            }
+            _ => {}
+        }
+    }
+    Some(
+        module
+            .body
+            .iter()
+            .filter_map(|import| match &import.node {
+                Stmt::Import(import_stmt) if names.contains(&import_stmt.name) => {
+                    Some(SchemaImport {
+                        path: import_stmt.path.node.clone(),
+                        asname: import_stmt
+                            .asname
+                            .as_ref()
+                            .map(|asname| asname.node.clone()),
+                        relative: import_stmt.rawpath.starts_with('.'),
+                        used_elsewhere: names_elsewhere.contains(&import_stmt.name),
+                        line: import.line,
+                        end_line: import.end_line,
+                    })
+                }
+                _ => None,
+            })
+            .collect(),
+    )
+}
+
+/// The start of the package qualifier before the name, e.g., `pkg` of `pkg.Name`.
+fn qualifier_start(gs: &GlobalState, name_start: &KCLPos) -> Option<KCLPos> {
+    let dot = KCLPos {
+        column: Some(name_start.column?.checked_sub(1)?),
+        ..name_start.clone()
+    };
+    let qualifier = gs.look_up_exact_symbol(&dot)?;
+    let (start, end) = gs.get_symbols().get_symbol(qualifier)?.get_range();
+    (end == dot).then_some(start)
+}
+
+/// The attribute name of the config entry key, e.g., `name` of `name = "alice"` and
+/// `"name": "alice"`. The unpacking entries and the `if` entries have no attribute names.
+fn entry_key_name(entry: &ConfigEntry) -> Option<String> {
+    match &entry.key.as_ref()?.node {
+        Expr::Identifier(identifier) if identifier.names.len() == 1 => {
+            Some(identifier.names[0].node.clone())
+        }
+        Expr::StringLit(string_lit) if is_valid_kcl_name(&string_lit.value) => {
+            Some(string_lit.value.clone())
+        }
+        _ => None,
+    }
+}
+
+/// Infers the attribute type of the value from the literal or the type of the variable.
+fn infer_type(expr: &Node<Expr>, gs: &GlobalState) -> String {
+    match &expr.node {
+        Expr::StringLit(_) | Expr::JoinedString(_) => STR_TYPE_STR.to_string(),
+        Expr::NumberLit(number_lit) => match number_lit.value {
+            NumberLitValue::Int(_) => INT_TYPE_STR.to_string(),
+            NumberLitValue::Float(_) => FLOAT_TYPE_STR.to_string(),
+        },
+        Expr::NameConstantLit(name_constant_lit) => match name_constant_lit.value {
+            NameConstant::True | NameConstant::False => BOOL_TYPE_STR.to_string(),
+            NameConstant::None | NameConstant::Undefined => ANY_TYPE_STR.to_string(),
+        },
+        Expr::List(list_expr) => format!(
+            "[{}]",
+            common_type(list_expr.elts.iter().map(|elt| infer_type(elt, gs)))
+        ),
+        Expr::Config(config_expr) => format!(
+            "{{str:{}}}",
+            common_type(
+                config_expr
+                    .items
+                    .iter()
+                    .map(|entry| infer_type(&entry.node.value, gs))
+            )
+        ),
+        Expr::Schema(schema_expr) => schema_expr.name.node.get_name(),
+        Expr::Paren(paren_expr) => infer_type(&paren_expr.expr, gs),
+        Expr::Identifier(identifier) => identifier
+            .names
+            .last()
+            .and_then(|name| find_def(&name.get_pos(), gs, true))
+            .and_then(|def| gs.get_symbols().get_symbol(def))
+            .and_then(|symbol| symbol.get_sema_info().ty.clone())
+            .map(|ty| type_annotation(&ty))
+            .unwrap_or_else(|| ANY_TYPE_STR.to_string()),
+        _ => ANY_TYPE_STR.to_string(),
+    }
+}
+
+/// The common type of the items, or `any` for the empty or mixed items.
+fn common_type(types: impl Iterator<Item = String>) -> String {
+    let types: IndexSet<String> = types.collect();
+    match types.len() {
+        1 => types.into_iter().next().unwrap_or_default(),
+        _ => ANY_TYPE_STR.to_string(),
+    }
+}
+
+/// The type annotation of the resolved type, where the literal types are widened,
+/// e.g., `str` of `str(alice)`.
+fn type_annotation(ty: &Type) -> String {
+    match &ty.kind {
+        TypeKind::BoolLit(_) => BOOL_TYPE_STR.to_string(),
+        TypeKind::IntLit(_) => INT_TYPE_STR.to_string(),
+        TypeKind::FloatLit(_) => FLOAT_TYPE_STR.to_string(),
+        TypeKind::StrLit(_) => STR_TYPE_STR.to_string(),
+        TypeKind::List(item_ty) => format!("[{}]", type_annotation(item_ty)),
+        TypeKind::Dict(dict_ty) => format!(
+            "{{{}:{}}}",
+            type_annotation(&dict_ty.key_ty),
+            type_annotation(&dict_ty.val_ty)
+        ),
+        TypeKind::Union(types) => types
+            .iter()
+            .map(|ty| type_annotation(ty))
+            .collect::<IndexSet<String>>()
+            .into_iter()
+            .collect::<Vec<String>>()
+            .join(" | "),
+        TypeKind::None | TypeKind::Void | TypeKind::Module(_) => ANY_TYPE_STR.to_string(),
+        _ => ty.ty_str(),
+    }
+}
+
+/// The schema name of the dict literal derived from the assigned variable or the config
+/// key, e.g., `WebServer` of `web_server = {...}`.
+fn schema_name_hint(name: Option<&str>) -> String {
+    let name: String = name
+        .unwrap_or_default()
+        .split('_')
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            chars
+                .next()
+                .map(|c| c.to_uppercase().chain(chars).collect::<String>())
+                .unwrap_or_default()
+        })
+        .collect();
+    if is_valid_kcl_name(&name) {
+        name
+    } else {
+        "NewSchema".to_string()
+    }
+}
+
+/// The file name of the schema, e.g., `web_server` of `WebServer`.
+fn snake_case(name: &str) -> String {
+    let mut snake = String::new();
+    for (i, c) in name.chars().enumerate() {
+        if c.is_uppercase() {
+            if i > 0 {
+                snake.push('_');
+            }
+            snake.extend(c.to_lowercase());
+        } else {
+            snake.push(c);
+        }
+    }
+    snake
+}
+
+/// The attributes which are not optional and have no default values, including the
+/// attributes of the base schemas.
+fn required_attrs(schema: &SchemaType) -> Vec<String> {
+    let mut attrs: Vec<String> = schema
+        .attrs
+        .iter()
+        .filter(|(_, attr)| !attr.is_optional && !attr.has_default)
+        .map(|(name, _)| name.clone())
+        .collect();
+    if let Some(base) = &schema.base {
+        attrs.extend(required_attrs(base));
+    }
+    attrs
+}
+
+/// The schema name used in the module and the import edit when the schema package is
+/// not imported yet. The schemas of the main package can't be imported.
+fn qualified_schema_name(
+    schema: &SchemaType,
+    pkgpath: &str,
+    module: &Module,
+) -> Option<(String, Option<TextEdit>)> {
+    if schema.pkgpath == pkgpath {
+        return Some((schema.name.clone(), None));
+    }
+    if schema.pkgpath == MAIN_PKG {
+        return None;
+    }
+    let (alias, import) = import_alias(module, &schema.pkgpath);
+    Some((format!("{}.{}", alias, schema.name), import))
+}
+
+/// The name of the package imported in the module, and the import edit when the package
+/// is not imported yet.
+fn import_alias(module: &Module, pkgpath: &str) -> (String, Option<TextEdit>) {
+    let imports: Vec<&Node<Stmt>> = module
+        .body
+        .iter()
+        .map(|stmt| stmt.as_ref())
+        .filter(|stmt| matches!(stmt.node, Stmt::Import(_)))
+        .collect();
+    for stmt in &imports {
+        if let Stmt::Import(import_stmt) = &stmt.node
+            && import_stmt.path.node == pkgpath
+        {
+            return (import_stmt.name.clone(), None);
+        }
+    }
+    let alias = pkgpath.rsplit('.').next().unwrap_or(pkgpath).to_string();
+    // Insert after the last import statement, or before the first statement.
+    let edit = match imports.last() {
+        Some(last) => insert(
+            Position::new(last.end_line as u32, 0),
+            format!("import {pkgpath}\n"),
+        ),
+        None => {
+            let line = match (&module.doc, module.body.first()) {
+                (Some(doc), _) => doc.end_line,
+                (None, Some(first)) => first.line.saturating_sub(1),
+                (None, None) => 0,
+            };
+            insert(
+                Position::new(line as u32, 0),
+                format!("import {pkgpath}\n\n"),
+            )
+        }
+    };
+    (alias, Some(edit))
+}
+
+/// Returns the package path of the file in the program.
+fn file_pkgpath(prog: &Program, file: &str) -> Option<String> {
+    prog.pkgs
+        .iter()
+        .chain(prog.pkgs_not_imported.iter())
+        .find(|(_, files)| files.iter().any(|f| f == file))
+        .map(|(pkgpath, _)| pkgpath.clone())
+}
+
+/// Returns the top-level names of the package of the file, where `module` is the AST of
+/// the file which is already locked.
+fn package_names(prog: &Program, file: &str, module: &Module) -> HashSet<String> {
+    let mut names = HashSet::new();
+    top_level_names(module, &mut names);
+    if let Some(pkgpath) = file_pkgpath(prog, file) {
+        let files = prog
+            .pkgs
+            .get(&pkgpath)
+            .or(prog.pkgs_not_imported.get(&pkgpath));
+        for pkg_file in files.into_iter().flatten() {
+            if pkg_file != file
+                && let Ok(Some(pkg_module)) = prog.get_module(pkg_file)
+            {
+                top_level_names(&pkg_module, &mut names);
+            }
+        }
+    }
+    names
+}
+
+fn top_level_names(module: &Module, names: &mut HashSet<String>) {
+    for stmt in &module.body {
+        match &stmt.node {
+            Stmt::Assign(assign_stmt) => names.extend(
+                assign_stmt
+                    .targets
+                    .iter()
+                    .map(|target| target.node.get_name().to_string()),
+            ),
+            Stmt::Schema(schema_stmt) => {
+                names.insert(schema_stmt.name.node.clone());
+            }
+            Stmt::Rule(rule_stmt) => {
+                names.insert(rule_stmt.name.node.clone());
+            }
+            Stmt::TypeAlias(type_alias_stmt) => {
+                names.insert(type_alias_stmt.type_name.node.get_name());
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Collects the statements which assign the top-level name, including the ones in the
+/// `if` blocks.
+fn collect_assigns<'a>(body: &'a [NodeRef<Stmt>], name: &str, assigns: &mut Vec<&'a Node<Stmt>>) {
+    for stmt in body {
+        match &stmt.node {
+            Stmt::Assign(assign_stmt)
+                if assign_stmt
+                    .targets
+                    .iter()
+                    .any(|target| target.node.get_name() == name) =>
+            {
+                assigns.push(stmt);
+            }
+            Stmt::AugAssign(aug_assign_stmt) if aug_assign_stmt.target.node.get_name() == name => {
+                assigns.push(stmt);
+            }
+            Stmt::Unification(unification_stmt)
+                if unification_stmt.target.node.get_name() == name =>
+            {
+                assigns.push(stmt);
+            }
+            Stmt::If(if_stmt) => {
+                collect_assigns(&if_stmt.body, name, assigns);
+                collect_assigns(&if_stmt.orelse, name, assigns);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Whether the inlined expression needs the parentheses to keep the precedence.
+fn needs_parens(expr: &Expr) -> bool {
+    matches!(
+        expr,
+        Expr::Unary(_)
+            | Expr::Binary(_)
+            | Expr::Compare(_)
+            | Expr::If(_)
+            | Expr::Lambda(_)
+            | Expr::Quant(_)
+    )
+}
+
+/// Deletes the 1-based lines from `start_line` to `end_line`.
+fn delete_lines(start_line: u64, end_line: u64) -> TextEdit {
+    TextEdit {
+        range: Range {
+            start: Position::new(start_line.saturating_sub(1) as u32, 0),
+            end: Position::new(end_line as u32, 0),
+        },
+        new_text: String::new(),
+    }
+}
+
+#[inline]
+fn insert(pos: Position, new_text: String) -> TextEdit {
+    TextEdit {
+        range: Range {
+            start: pos,
+            end: pos,
+        },
+        new_text,
+    }
+}
+
+fn code_action(title: String, kind: CodeActionKind, uri: &Url, edits: Vec<TextEdit>) -> CodeAction {
+    let mut changes = HashMap::new();
+    changes.insert(uri.clone(), edits);
+    CodeAction {
+        title,
+        kind: Some(kind),
+        edit: Some(WorkspaceEdit {
+            changes: Some(changes),
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+}
+
+fn text_document_edit(uri: Url, edits: Vec<TextEdit>) -> DocumentChangeOperation {
+    DocumentChangeOperation::Edit(TextDocumentEdit {
+        text_document: OptionalVersionedTextDocumentIdentifier { uri, version: None },
+        edits: edits.into_iter().map(OneOf::Left).collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use kcl_error::Position as KCLPos;
+    use lsp_types::{
+        CodeAction, CodeActionOrCommand, DocumentChangeOperation, DocumentChanges, OneOf, Position,
+        Range, ResourceOp, TextEdit, WorkspaceEdit,
+    };
+    use proc_macro_crate::bench_test;
+
+    use super::{MoveSchemaArgs, move_schema_edit, refactor};
+    use crate::tests::compile_test_file;
+
+    fn code_actions(line: u64, column: u64) -> Vec<CodeAction> {
+        code_actions_in("main.k", line, column)
+    }
+
+    fn code_actions_in(file: &str, line: u64, column: u64) -> Vec<CodeAction> {
+        let (file, prog, _, gs, schema_map) =
+            compile_test_file(&format!("src/test_data/code_action/refactor/{file}"));
+        let pos = KCLPos {
+            filename: file.clone(),
+            line,
+            column: Some(column),
+        };
+        refactor(&file, &(pos.clone(), pos), &prog, &gs, &schema_map)
+            .into_iter()
+            .map(|action| match action {
+                CodeActionOrCommand::CodeAction(action) => action,
+                CodeActionOrCommand::Command(_) => panic!("unexpected command"),
+            })
+            .collect()
+    }
+
+    fn find<'a>(actions: &'a [CodeAction], title: &str) -> &'a CodeAction {
+        actions
+            .iter()
+            .find(|action| action.title == title)
+            .unwrap_or_else(|| panic!("{title} not found"))
+    }
+
+    fn edits(action: &CodeAction) -> Vec<TextEdit> {
+        let changes = action.edit.as_ref().unwrap().changes.as_ref().unwrap();
+        changes.values().next().unwrap().clone()
+    }
+
+    fn edit(start: (u32, u32), end: (u32, u32), new_text: &str) -> TextEdit {
+        TextEdit {
+            range: Range {
+                start: Position::new(start.0, start.1),
+                end: Position::new(end.0, end.1),
+            },
+            new_text: new_text.to_string(),
+        }
+    }
+
+    #[test]
+    #[bench_test]
+    fn extract_schema_test() {
+        // server = {
+        let actions = code_actions(10, 9);
+        assert_eq!(
+            edits(find(&actions, "Extract schema `Server`")),
+            vec![
+                edit(
+                    (9, 0),
+                    (9, 0),
+                    "schema Server:\n    host: str\n    port: int\n    tags: [str]\n    enabled: bool\n\n"
+                ),
+                edit((9, 9), (9, 9), "Server "),
+            ]
+        );
+        assert!(
+            !actions
+                .iter()
+                .any(|action| action.title.starts_with("Convert"))
+        );
+    }
+
+    #[test]
+    #[bench_test]
+    fn convert_to_schema_test() {
+        // alice = {name = "alice", age = 1}
+        let actions = code_actions(8, 8);
+        assert_eq!(
+            edits(find(&actions, "Convert to `Person` instance")),
+            vec![edit((7, 8), (7, 8), "Person ")]
+        );
+        assert!(
+            !actions
+                .iter()
+                .any(|action| action.title == "Convert to `models.Service` instance")
+        );
+
+        // svc = {name = "web", ports = [80]}
+        let actions = code_actions(9, 6);
+        assert_eq!(
+            edits(find(&actions, "Convert to `models.Service` instance")),
+            vec![edit((8, 6), (8, 6), "models.Service ")]
+        );
+    }
+
+    #[test]
+    #[bench_test]
+    fn inline_variable_test() {
+        // _port = 8080
+        let actions = code_actions(7, 0);
+        assert_eq!(
+            edits(find(&actions, "Inline variable `_port`")),
+            vec![edit((11, 11), (11, 16), "8080"), edit((6, 0), (7, 0), "")]
+        );
+        // The variables without references are not inlined.
+        let actions = code_actions(8, 0);
+        assert!(
+            !actions
+                .iter()
+                .any(|action| action.title.starts_with("Inline"))
+        );
+    }
+
+    /// Returns the candidate packages of the move schema action at the position, and the
+    /// created file and the edits of moving the schema to the target package.
+    fn move_schema(
+        file: &str,
+        line: u64,
+        column: u64,
+        title: &str,
+        target: &str,
+    ) -> (Vec<String>, anyhow::Result<MovedSchema>) {
+        let (file, prog, _, gs, schema_map) =
+            compile_test_file(&format!("src/test_data/code_action/refactor/{file}"));
+        let pos = KCLPos {
+            filename: file.clone(),
+            line,
+            column: Some(column),
+        };
+        let actions: Vec<CodeAction> =
+            refactor(&file, &(pos.clone(), pos), &prog, &gs, &schema_map)
+                .into_iter()
+                .filter_map(|action| match action {
+                    CodeActionOrCommand::CodeAction(action) => Some(action),
+                    CodeActionOrCommand::Command(_) => None,
+                })
+                .collect();
+        let command = find(&actions, title).command.clone().unwrap();
+        let args: MoveSchemaArgs =
+            serde_json::from_value(command.arguments.unwrap()[0].clone()).unwrap();
+        let src = std::fs::read_to_string(&file).unwrap();
+        let edit = move_schema_edit(&file, &src, args.position, target, &prog, &gs)
+            .map(|edit| document_changes(&edit));
+        (args.packages, edit)
+    }
+
+    /// The path of the new file and the edits of each changed file.
+    type MovedSchema = (String, Vec<Vec<TextEdit>>);
+
+    fn document_changes(edit: &WorkspaceEdit) -> MovedSchema {
+        let Some(DocumentChanges::Operations(operations)) = &edit.document_changes else {
+            panic!("unexpected document changes");
+        };
+        let DocumentChangeOperation::Op(ResourceOp::Create(create)) = &operations[0] else {
+            panic!("unexpected operation");
+        };
+        let edits = operations[1..]
+            .iter()
+            .map(|operation| match operation {
+                DocumentChangeOperation::Edit(edit) => edit
+                    .edits
+                    .iter()
+                    .map(|edit| match edit {
+                        OneOf::Left(edit) => edit.clone(),
+                        OneOf::Right(edit) => edit.text_edit.clone(),
+                    })
+                    .collect(),
+                DocumentChangeOperation::Op(_) => panic!("unexpected operation"),
+            })
+            .collect();
+        (create.uri.path().to_string(), edits)
+    }
+
+    #[test]
+    #[bench_test]
+    fn move_schema_test() {
+        // schema Person:
+        let (packages, moved) = move_schema(
+            "main.k",
+            3,
+            7,
+            "Move schema `Person` to another package",
+            "models",
+        );
+        assert_eq!(packages, vec!["app", "models", "models.common"]);
+        let (path, edits) = moved.unwrap();
+        assert!(path.ends_with("refactor/models/person.k"));
+        assert_eq!(
+            edits,
+            vec![
+                vec![edit(
+                    (0, 0),
+                    (0, 0),
+                    "schema Person:\n    name: str\n    age?: int\n"
+                )],
+                vec![
+                    edit((2, 0), (6, 0), ""),
+                    edit((15, 6), (15, 12), "models.Person"),
+                ],
+            ]
+        );
+    }
+
+    #[test]
+    #[bench_test]
+    fn move_schema_with_relative_import_test() {
+        // schema Base:
+        let (packages, moved) = move_schema(
+            "base.k",
+            3,
+            7,
+            "Move schema `Base` to another package",
+            "models",
+        );
+        // The imported package is not a target package.
+        assert_eq!(packages, vec!["app", "models"]);
+        let (path, edits) = moved.unwrap();
+        assert!(path.ends_with("refactor/models/base.k"));
+        // The relative import is rewritten against the target package, and it is removed
+        // from the origin file because it is only used in the moved schema.
+        assert_eq!(
+            edits,
+            vec![
+                vec![edit(
+                    (0, 0),
+                    (0, 0),
+                    "import .common\n\nschema Base:\n    labels: common.Labels\n"
+                )],
+                vec![edit((2, 0), (4, 0), ""), edit((0, 0), (1, 0), "")],
+            ]
+        );
+    }
+
+    #[test]
+    #[bench_test]
+    fn move_schema_import_cycle_test() {
+        // schema Labels:
+        let (packages, moved) = move_schema(
+            "models/common/labels.k",
+            1,
+            7,
+            "Move schema `Labels` to another package",
+            "models",
+        );
+        // The package `models` imports the package `app` which references the schema, so
+        // moving the schema to `models` forms an import cycle.
+        assert_eq!(packages, vec!["app"]);
+        assert!(moved.is_err());
+    }
+}
@@ -1,30 +1,52 @@
 use anyhow::anyhow;
 use crossbeam_channel::Sender;
 
-use kcl_driver::WorkSpaceKind;
+use kcl_driver::{CompileUnitOptions, WorkSpaceKind, lookup_compile_workspaces};
+use kcl_error::Diagnostic as KCLDiagnostic;
+use kcl_parser::entry::get_normalized_k_files_from_paths;
+use kcl_primitives::IndexSet;
 use kcl_sema::info::is_valid_kcl_name;
-use lsp_types::{Location, SemanticTokensResult, TextEdit};
+use lsp_types::{
+    Location, SemanticTokensFullDeltaResult, SemanticTokensRangeResult, SemanticTokensResult,
+    TextEdit,
+};
 use ra_ap_vfs::VfsPath;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
 use std::time::Instant;
 
 use crate::{
     analysis::{AnalysisDatabase, DBState},
+    call_hierarchy,
+    code_lens::{code_lens, execute_command},
+    compile::{Params, compile},
     completion::completion,
+    diagnostic,
     dispatcher::RequestDispatcher,
+    document_highlight::document_highlight,
+    document_link::document_links,
     document_symbol::document_symbol,
     error::LSPError,
+    evaluate,
     find_refs::find_refs,
+    folding_range::folding_range,
     formatting::format,
     from_lsp::{self, file_path_from_url, kcl_pos},
     goto_def::goto_def,
+    goto_impl::goto_impl,
+    goto_type_def::goto_type_def,
     hover,
     inlay_hints::inlay_hints,
     quick_fix,
-    semantic_token::semantic_tokens_full,
+    refactor::{MOVE_SCHEMA_TO_COMMAND, MoveSchemaToArgs, move_schema_edit, refactor},
+    selection_range::selection_range,
+    semantic_token::{semantic_tokens_full, semantic_tokens_range},
     signature_help::signature_help,
     state::{LanguageServerSnapshot, LanguageServerState, Task, log_message},
+    type_hierarchy,
+    util::{from_json, load_files_code_from_vfs},
+    workspace_symbol::workspace_symbol,
 };
 
 impl LanguageServerState {
@@ -53,16 +75,38 @@ impl LanguageServerState {
                 Ok(())
             })?
             .on::<lsp_types::request::GotoDefinition>(handle_goto_definition)?
+            .on::<lsp_types::request::GotoTypeDefinition>(handle_goto_type_definition)?
+            .on::<lsp_types::request::GotoImplementation>(handle_goto_implementation)?
             .on::<lsp_types::request::References>(handle_reference)?
             .on::<lsp_types::request::HoverRequest>(handle_hover)?
             .on::<lsp_types::request::DocumentSymbolRequest>(handle_document_symbol)?
+            .on::<lsp_types::request::WorkspaceSymbol>(handle_workspace_symbol)?
             .on::<lsp_types::request::CodeActionRequest>(handle_code_action)?
             .on::<lsp_types::request::Formatting>(handle_formatting)?
             .on::<lsp_types::request::RangeFormatting>(handle_range_formatting)?
             .on::<lsp_types::request::Rename>(handle_rename)?
             .on::<lsp_types::request::SemanticTokensFullRequest>(handle_semantic_tokens_full)?
+            .on::<lsp_types::request::SemanticTokensFullDeltaRequest>(
+                handle_semantic_tokens_full_delta,
+            )?
+            .on::<lsp_types::request::SemanticTokensRangeRequest>(handle_semantic_tokens_range)?
             .on::<lsp_types::request::InlayHintRequest>(handle_inlay_hint)?
             .on::<lsp_types::request::SignatureHelpRequest>(handle_signature_help)?
+            .on::<lsp_types::request::CallHierarchyPrepare>(handle_prepare_call_hierarchy)?
+            .on::<lsp_types::request::CallHierarchyIncomingCalls>(handle_incoming_calls)?
+            .on::<lsp_types::request::CallHierarchyOutgoingCalls>(handle_outgoing_calls)?
+            .on::<type_hierarchy::TypeHierarchyPrepare>(handle_prepare_type_hierarchy)?
+            .on::<type_hierarchy::TypeHierarchySupertypes>(handle_type_hierarchy_supertypes)?
+            .on::<type_hierarchy::TypeHierarchySubtypes>(handle_type_hierarchy_subtypes)?
+            .on::<lsp_types::request::FoldingRangeRequest>(handle_folding_range)?
+            .on::<lsp_types::request::SelectionRangeRequest>(handle_selection_range)?
+            .on::<lsp_types::request::DocumentHighlightRequest>(handle_document_highlight)?
+            .on::<lsp_types::request::CodeLensRequest>(handle_code_lens)?
+            .on::<lsp_types::request::DocumentLinkRequest>(handle_document_link)?
+            .on::<lsp_types::request::ExecuteCommand>(handle_execute_command)?
+            .on::<diagnostic::DocumentDiagnosticRequest>(handle_document_diagnostic)?
+            .on::<diagnostic::WorkspaceDiagnosticRequest>(handle_workspace_diagnostic)?
+            .on_cancellable::<evaluate::Evaluate>(handle_evaluate)?
             .on_maybe_retry::<lsp_types::request::Completion>(handle_completion)?
             .finish();
 
@@ -174,6 +218,44 @@ impl LanguageServerSnapshot {
             None => Ok(None),
         }
     }
+
+    /// Returns the diagnostics of the compile unit. The compile units which have not been
+    /// compiled by the language server, e.g., the ones without opened files, are compiled
+    /// here, and their diagnostics are cached until any file of them is changed.
+    pub(crate) fn compile_unit_diags(
+        &self,
+        workspace: &WorkSpaceKind,
+        opts: CompileUnitOptions,
+    ) -> IndexSet<KCLDiagnostic> {
+        if let Some(DBState::Ready(db) | DBState::Compiling(db)) =
+            self.workspaces.read().get(workspace)
+        {
+            return db.diags.clone();
+        }
+        if let Some((_, diags)) = self.workspace_diags_cache.read().get(workspace) {
+            return diags.clone();
+        }
+        let (mut files, opts, _) = opts;
+        let (diags, compile_res) = compile(
+            Params {
+                file: None,
+                module_cache: Some(self.module_cache.clone()),
+                scope_cache: Some(self.scope_cache.clone()),
+                vfs: Some(self.vfs.clone()),
+                gs_cache: None,
+            },
+            &mut files,
+            opts,
+        );
+        let mut unit_files: HashSet<String> = files.into_iter().collect();
+        if let Ok((prog, _, _)) = &compile_res {
+            unit_files.extend(prog.pkgs.values().flatten().cloned());
+        }
+        self.workspace_diags_cache
+            .write()
+            .insert(workspace.clone(), (unit_files, diags.clone()));
+        diags
+    }
 }
 
 pub(crate) fn handle_semantic_tokens_full(
@@ -190,7 +272,62 @@ pub(crate) fn handle_semantic_tokens_full(
         },
         Err(_) => return Ok(None),
     };
-    let res = semantic_tokens_full(&file, &db.gs);
+    let res = match semantic_tokens_full(&file, &db.gs) {
+        Some(SemanticTokensResult::Tokens(tokens)) => Some(SemanticTokensResult::Tokens(
+            snapshot
+                .semantic_tokens_cache
+                .write()
+                .insert(&file, tokens.data),
+        )),
+        res => res,
+    };
+
+    Ok(res)
+}
+
+pub(crate) fn handle_semantic_tokens_full_delta(
+    snapshot: LanguageServerSnapshot,
+    params: lsp_types::SemanticTokensDeltaParams,
+    sender: Sender<Task>,
+) -> anyhow::Result<Option<SemanticTokensFullDeltaResult>> {
+    let file = file_path_from_url(&params.text_document.uri)?;
+    let path: VfsPath = from_lsp::abs_path(&params.text_document.uri)?.into();
+    let db = match snapshot.try_get_db(&path, &sender) {
+        Ok(option_db) => match option_db {
+            Some(db) => db,
+            None => return Err(anyhow!(LSPError::Retry)),
+        },
+        Err(_) => return Ok(None),
+    };
+    let res = match semantic_tokens_full(&file, &db.gs) {
+        Some(SemanticTokensResult::Tokens(tokens)) => {
+            Some(snapshot.semantic_tokens_cache.write().delta(
+                &file,
+                &params.previous_result_id,
+                tokens.data,
+            ))
+        }
+        _ => None,
+    };
+
+    Ok(res)
+}
+
+pub(crate) fn handle_semantic_tokens_range(
+    snapshot: LanguageServerSnapshot,
+    params: lsp_types::SemanticTokensRangeParams,
+    sender: Sender<Task>,
+) -> anyhow::Result<Option<SemanticTokensRangeResult>> {
+    let file = file_path_from_url(&params.text_document.uri)?;
+    let path: VfsPath = from_lsp::abs_path(&params.text_document.uri)?.into();
+    let db = match snapshot.try_get_db(&path, &sender) {
+        Ok(option_db) => match option_db {
+            Some(db) => db,
+            None => return Err(anyhow!(LSPError::Retry)),
+        },
+        Err(_) => return Ok(None),
+    };
+    let res = semantic_tokens_range(&file, &db.gs, &params.range);
 
     Ok(res)
 }
@@ -238,15 +375,26 @@ pub(crate) fn handle_range_formatting(
 
 /// Called when a `textDocument/codeAction` request was received.
 pub(crate) fn handle_code_action(
-    _snapshot: LanguageServerSnapshot,
+    snapshot: LanguageServerSnapshot,
     params: lsp_types::CodeActionParams,
-    _sender: Sender<Task>,
+    sender: Sender<Task>,
 ) -> anyhow::Result<Option<lsp_types::CodeActionResponse>> {
     let mut code_actions: Vec<lsp_types::CodeActionOrCommand> = vec![];
     code_actions.extend(quick_fix::quick_fix(
         &params.text_document.uri,
         &params.context.diagnostics,
     ));
+    let file = file_path_from_url(&params.text_document.uri)?;
+    let path = from_lsp::abs_path(&params.text_document.uri)?;
+    // The quick fixes only need the diagnostics, and the refactorings are provided
+    // when the file is compiled.
+    if let Ok(Some(db)) = snapshot.try_get_db(&path.into(), &sender) {
+        let range = (
+            kcl_pos(&file, params.range.start),
+            kcl_pos(&file, params.range.end),
+        );
+        code_actions.extend(refactor(&file, &range, &db.prog, &db.gs, &db.schema_map));
+    }
     Ok(Some(code_actions))
 }
 
@@ -276,6 +424,58 @@ pub(crate) fn handle_goto_definition(
     Ok(res)
 }
 
+/// Called when a `textDocument/typeDefinition` request was received
+pub(crate) fn handle_goto_type_definition(
+    snapshot: LanguageServerSnapshot,
+    params: lsp_types::request::GotoTypeDefinitionParams,
+    sender: Sender<Task>,
+) -> anyhow::Result<Option<lsp_types::request::GotoTypeDefinitionResponse>> {
+    let file = file_path_from_url(&params.text_document_position_params.text_document.uri)?;
+    let path = from_lsp::abs_path(&params.text_document_position_params.text_document.uri)?;
+    if !snapshot.verify_request_path(&path.clone().into(), &sender) {
+        return Ok(None);
+    };
+    let db = match snapshot.try_get_db(&path.clone().into(), &sender) {
+        Ok(option_db) => match option_db {
+            Some(db) => db,
+            None => return Err(anyhow!(LSPError::Retry)),
+        },
+        Err(_) => return Ok(None),
+    };
+    let kcl_pos = kcl_pos(&file, params.text_document_position_params.position);
+    let res = goto_type_def(&kcl_pos, &db.gs);
+    if res.is_none() {
+        log_message("Type definition item not found".to_string(), &sender)?;
+    }
+    Ok(res)
+}
+
+/// Called when a `textDocument/implementation` request was received
+pub(crate) fn handle_goto_implementation(
+    snapshot: LanguageServerSnapshot,
+    params: lsp_types::request::GotoImplementationParams,
+    sender: Sender<Task>,
+) -> anyhow::Result<Option<lsp_types::request::GotoImplementationResponse>> {
+    let file = file_path_from_url(&params.text_document_position_params.text_document.uri)?;
+    let path = from_lsp::abs_path(&params.text_document_position_params.text_document.uri)?;
+    if !snapshot.verify_request_path(&path.clone().into(), &sender) {
+        return Ok(None);
+    };
+    let db = match snapshot.try_get_db(&path.clone().into(), &sender) {
+        Ok(option_db) => match option_db {
+            Some(db) => db,
+            None => return Err(anyhow!(LSPError::Retry)),
+        },
+        Err(_) => return Ok(None),
+    };
+    let kcl_pos = kcl_pos(&file, params.text_document_position_params.position);
+    let res = goto_impl(&kcl_pos, &db.gs);
+    if res.is_none() {
+        log_message("Implementation item not found".to_string(), &sender)?;
+    }
+    Ok(res)
+}
+
 /// Called when a `textDocument/references` request was received
 pub(crate) fn handle_reference(
     snapshot: LanguageServerSnapshot,
@@ -411,6 +611,22 @@ pub(crate) fn handle_document_symbol(
     Ok(res)
 }
 
+/// Called when a `workspace/symbol` request was received.
+pub(crate) fn handle_workspace_symbol(
+    snapshot: LanguageServerSnapshot,
+    params: lsp_types::WorkspaceSymbolParams,
+    _sender: Sender<Task>,
+) -> anyhow::Result<Option<Vec<lsp_types::SymbolInformation>>> {
+    // The workspaces in compiling answer with the symbols of the last compilation.
+    let workspaces = snapshot.workspaces.read();
+    let indexes = workspaces.values().filter_map(|state| match state {
+        DBState::Ready(db) | DBState::Compiling(db) => Some(&db.symbol_index),
+        DBState::Init | DBState::Failed(_) => None,
+    });
+    let symbols = workspace_symbol(&params.query, indexes);
+    Ok(Some(symbols))
+}
+
 /// Called when a `textDocument/rename` request was received.
 pub(crate) fn handle_rename(
     snapshot: LanguageServerSnapshot,
@@ -495,3 +711,342 @@ pub(crate) fn handle_signature_help(
 
     Ok(res)
 }
+
+/// Called when a `textDocument/prepareCallHierarchy` request was received.
+pub(crate) fn handle_prepare_call_hierarchy(
+    snapshot: LanguageServerSnapshot,
+    params: lsp_types::CallHierarchyPrepareParams,
+    sender: Sender<Task>,
+) -> anyhow::Result<Option<Vec<lsp_types::CallHierarchyItem>>> {
+    let file = file_path_from_url(&params.text_document_position_params.text_document.uri)?;
+    let path = from_lsp::abs_path(&params.text_document_position_params.text_document.uri)?;
+    let db = match snapshot.try_get_db(&path.clone().into(), &sender) {
+        Ok(option_db) => match option_db {
+            Some(db) => db,
+            None => return Err(anyhow!(LSPError::Retry)),
+        },
+        Err(_) => return Ok(None),
+    };
+    let kcl_pos = kcl_pos(&file, params.text_document_position_params.position);
+    Ok(call_hierarchy::prepare_call_hierarchy(&kcl_pos, &db.gs))
+}
+
+/// Called when a `callHierarchy/incomingCalls` request was received.
+pub(crate) fn handle_incoming_calls(
+    snapshot: LanguageServerSnapshot,
+    params: lsp_types::CallHierarchyIncomingCallsParams,
+    sender: Sender<Task>,
+) -> anyhow::Result<Option<Vec<lsp_types::CallHierarchyIncomingCall>>> {
+    let path = from_lsp::abs_path(&params.item.uri)?;
+    let db = match snapshot.try_get_db(&path.clone().into(), &sender) {
+        Ok(option_db) => match option_db {
+            Some(db) => db,
+            None => return Err(anyhow!(LSPError::Retry)),
+        },
+        Err(_) => return Ok(None),
+    };
+    Ok(call_hierarchy::incoming_calls(
+        &params.item,
+        &db.gs,
+        &db.prog,
+    ))
+}
+
+/// Called when a `callHierarchy/outgoingCalls` request was received.
+pub(crate) fn handle_outgoing_calls(
+    snapshot: LanguageServerSnapshot,
+    params: lsp_types::CallHierarchyOutgoingCallsParams,
+    sender: Sender<Task>,
+) -> anyhow::Result<Option<Vec<lsp_types::CallHierarchyOutgoingCall>>> {
+    let path = from_lsp::abs_path(&params.item.uri)?;
+    let db = match snapshot.try_get_db(&path.clone().into(), &sender) {
+        Ok(option_db) => match option_db {
+            Some(db) => db,
+            None => return Err(anyhow!(LSPError::Retry)),
+        },
+        Err(_) => return Ok(None),
+    };
+    Ok(call_hierarchy::outgoing_calls(
+        &params.item,
+        &db.gs,
+        &db.prog,
+    ))
+}
+
+/// Called when a `textDocument/prepareTypeHierarchy` request was received.
+pub(crate) fn handle_prepare_type_hierarchy(
+    snapshot: LanguageServerSnapshot,
+    params: type_hierarchy::TypeHierarchyPrepareParams,
+    sender: Sender<Task>,
+) -> anyhow::Result<Option<Vec<type_hierarchy::TypeHierarchyItem>>> {
+    let file = file_path_from_url(&params.text_document_position_params.text_document.uri)?;
+    let path = from_lsp::abs_path(&params.text_document_position_params.text_document.uri)?;
+    let db = match snapshot.try_get_db(&path.clone().into(), &sender) {
+        Ok(option_db) => match option_db {
+            Some(db) => db,
+            None => return Err(anyhow!(LSPError::Retry)),
+        },
+        Err(_) => return Ok(None),
+    };
+    let kcl_pos = kcl_pos(&file, params.text_document_position_params.position);
+    Ok(type_hierarchy::prepare_type_hierarchy(&kcl_pos, &db.gs))
+}
+
+/// Called when a `typeHierarchy/supertypes` request was received.
+pub(crate) fn handle_type_hierarchy_supertypes(
+    snapshot: LanguageServerSnapshot,
+    params: type_hierarchy::TypeHierarchyItemParams,
+    sender: Sender<Task>,
+) -> anyhow::Result<Option<Vec<type_hierarchy::TypeHierarchyItem>>> {
+    let path = from_lsp::abs_path(&params.item.uri)?;
+    let db = match snapshot.try_get_db(&path.clone().into(), &sender) {
+        Ok(option_db) => match option_db {
+            Some(db) => db,
+            None => return Err(anyhow!(LSPError::Retry)),
+        },
+        Err(_) => return Ok(None),
+    };
+    Ok(type_hierarchy::supertypes(&params.item, &db.gs))
+}
+
+/// Called when a `typeHierarchy/subtypes` request was received.
+pub(crate) fn handle_type_hierarchy_subtypes(
+    snapshot: LanguageServerSnapshot,
+    params: type_hierarchy::TypeHierarchyItemParams,
+    sender: Sender<Task>,
+) -> anyhow::Result<Option<Vec<type_hierarchy::TypeHierarchyItem>>> {
+    let path = from_lsp::abs_path(&params.item.uri)?;
+    let db = match snapshot.try_get_db(&path.clone().into(), &sender) {
+        Ok(option_db) => match option_db {
+            Some(db) => db,
+            None => return Err(anyhow!(LSPError::Retry)),
+        },
+        Err(_) => return Ok(None),
+    };
+    Ok(type_hierarchy::subtypes(&params.item, &db.gs))
+}
+
+/// Called when a `textDocument/foldingRange` request was received.
+pub(crate) fn handle_folding_range(
+    snapshot: LanguageServerSnapshot,
+    params: lsp_types::FoldingRangeParams,
+    sender: Sender<Task>,
+) -> anyhow::Result<Option<Vec<lsp_types::FoldingRange>>> {
+    let file = file_path_from_url(&params.text_document.uri)?;
+    let path = from_lsp::abs_path(&params.text_document.uri)?;
+    let db = match snapshot.try_get_db(&path.clone().into(), &sender) {
+        Ok(option_db) => match option_db {
+            Some(db) => db,
+            None => return Err(anyhow!(LSPError::Retry)),
+        },
+        Err(_) => return Ok(None),
+    };
+    let Some(module) = db.prog.get_module(&file)? else {
+        return Ok(None);
+    };
+    Ok(Some(folding_range(&module)))
+}
+
+/// Called when a `textDocument/selectionRange` request was received.
+pub(crate) fn handle_selection_range(
+    snapshot: LanguageServerSnapshot,
+    params: lsp_types::SelectionRangeParams,
+    sender: Sender<Task>,
+) -> anyhow::Result<Option<Vec<lsp_types::SelectionRange>>> {
+    let file = file_path_from_url(&params.text_document.uri)?;
+    let path = from_lsp::abs_path(&params.text_document.uri)?;
+    let db = match snapshot.try_get_db(&path.clone().into(), &sender) {
+        Ok(option_db) => match option_db {
+            Some(db) => db,
+            None => return Err(anyhow!(LSPError::Retry)),
+        },
+        Err(_) => return Ok(None),
+    };
+    let Some(module) = db.prog.get_module(&file)? else {
+        return Ok(None);
+    };
+    let positions: Vec<_> = params
+        .positions
+        .into_iter()
+        .map(|pos| kcl_pos(&file, pos))
+        .collect();
+    Ok(Some(selection_range(&module, &positions)))
+}
+
+/// Called when a `textDocument/documentHighlight` request was received.
+pub(crate) fn handle_document_highlight(
+    snapshot: LanguageServerSnapshot,
+    params: lsp_types::DocumentHighlightParams,
+    sender: Sender<Task>,
+) -> anyhow::Result<Option<Vec<lsp_types::DocumentHighlight>>> {
+    let file = file_path_from_url(&params.text_document_position_params.text_document.uri)?;
+    let path = from_lsp::abs_path(&params.text_document_position_params.text_document.uri)?;
+    let db = match snapshot.try_get_db(&path.clone().into(), &sender) {
+        Ok(option_db) => match option_db {
+            Some(db) => db,
+            None => return Err(anyhow!(LSPError::Retry)),
+        },
+        Err(_) => return Ok(None),
+    };
+    let Some(module) = db.prog.get_module(&file)? else {
+        return Ok(None);
+    };
+    let kcl_pos = kcl_pos(&file, params.text_document_position_params.position);
+    Ok(document_highlight(&kcl_pos, &db.gs, &module))
+}
+
+/// Called when a `textDocument/codeLens` request was received.
+pub(crate) fn handle_code_lens(
+    snapshot: LanguageServerSnapshot,
+    params: lsp_types::CodeLensParams,
+    sender: Sender<Task>,
+) -> anyhow::Result<Option<Vec<lsp_types::CodeLens>>> {
+    let file = file_path_from_url(&params.text_document.uri)?;
+    let path = from_lsp::abs_path(&params.text_document.uri)?;
+    let db = match snapshot.try_get_db(&path.clone().into(), &sender) {
+        Ok(option_db) => match option_db {
+            Some(db) => db,
+            None => return Err(anyhow!(LSPError::Retry)),
+        },
+        Err(_) => return Ok(None),
+    };
+    Ok(Some(code_lens(&file, &db.gs, &db.prog)))
+}
+
+/// Called when a `textDocument/documentLink` request was received.
+pub(crate) fn handle_document_link(
+    snapshot: LanguageServerSnapshot,
+    params: lsp_types::DocumentLinkParams,
+    sender: Sender<Task>,
+) -> anyhow::Result<Option<Vec<lsp_types::DocumentLink>>> {
+    let file = file_path_from_url(&params.text_document.uri)?;
+    let path = from_lsp::abs_path(&params.text_document.uri)?;
+    let db = match snapshot.try_get_db(&path.clone().into(), &sender) {
+        Ok(option_db) => match option_db {
+            Some(db) => db,
+            None => return Err(anyhow!(LSPError::Retry)),
+        },
+        Err(_) => return Ok(None),
+    };
+    Ok(Some(document_links(&file, &db.prog)))
+}
+
+/// Called when a `workspace/executeCommand` request was received.
+pub(crate) fn handle_execute_command(
+    snapshot: LanguageServerSnapshot,
+    params: lsp_types::ExecuteCommandParams,
+    sender: Sender<Task>,
+) -> anyhow::Result<Option<serde_json::Value>> {
+    // The schema is moved with the compiled workspace, and the other commands run by themselves.
+    if params.command == MOVE_SCHEMA_TO_COMMAND {
+        let argument = params.arguments.into_iter().next().unwrap_or_default();
+        let args: MoveSchemaToArgs = from_json(MOVE_SCHEMA_TO_COMMAND, argument)?;
+        let uri = lsp_types::Url::from_file_path(&args.file)
+            .map_err(|_| anyhow!("invalid file path {}", args.file))?;
+        let path: VfsPath = from_lsp::abs_path(&uri)?.into();
+        let db = match snapshot.try_get_db(&path, &sender) {
+            Ok(Some(db)) => db,
+            Ok(None) => return Err(anyhow!(LSPError::Retry)),
+            Err(err) => return Err(err),
+        };
+        let src = {
+            let vfs = snapshot.vfs.read();
+            vfs.file_id(&path)
+                .map(|file_id| String::from_utf8_lossy(vfs.file_contents(file_id)).to_string())
+        }
+        .ok_or_else(|| anyhow!("the file {} is not opened", args.file))?;
+        let edit = move_schema_edit(
+            &args.file,
+            &src,
+            args.position,
+            &args.target,
+            &db.prog,
+            &db.gs,
+        )?;
+        sender.send(Task::ApplyEdit(edit))?;
+        return Ok(None);
+    }
+    Ok(Some(execute_command(
+        &params.command,
+        params.arguments,
+        &sender,
+    )?))
+}
+
+pub(crate) fn handle_document_diagnostic(
+    snapshot: LanguageServerSnapshot,
+    params: diagnostic::DocumentDiagnosticParams,
+    sender: Sender<Task>,
+) -> anyhow::Result<diagnostic::DocumentDiagnosticReport> {
+    let file = file_path_from_url(&params.text_document.uri)?;
+    let path: VfsPath = from_lsp::abs_path(&params.text_document.uri)?.into();
+    let diags = match snapshot.try_get_db(&path, &sender) {
+        Ok(option_db) => match option_db {
+            Some(db) => db.diags.clone(),
+            None => return Err(anyhow!(LSPError::Retry)),
+        },
+        Err(_) => IndexSet::default(),
+    };
+    Ok(diagnostic::document_diagnostic(
+        &file,
+        &diags,
+        params.previous_result_id.as_deref(),
+    ))
+}
+
+/// Reports the diagnostics of all the compile units in the workspace folders, including the
+/// compile units defined by the `kcl.work` files.
+pub(crate) fn handle_workspace_diagnostic(
+    snapshot: LanguageServerSnapshot,
+    params: diagnostic::WorkspaceDiagnosticParams,
+    sender: Sender<Task>,
+) -> anyhow::Result<diagnostic::WorkspaceDiagnosticReport> {
+    let mut diags = IndexSet::default();
+    for folder in snapshot.workspace_folders.iter().flatten() {
+        let path = file_path_from_url(&folder.uri)?;
+        let (workspaces, failed) = lookup_compile_workspaces(&*snapshot.tool.read(), &path, true);
+        for (key, err) in failed.into_iter().flatten() {
+            log_message(format!("parse kcl.work failed: {}: {}", key, err), &sender)?;
+        }
+        for (workspace, opts) in workspaces {
+            diags.extend(snapshot.compile_unit_diags(&workspace, opts));
+        }
+    }
+    Ok(diagnostic::workspace_diagnostic(
+        &diags,
+        &params.previous_result_ids,
+    ))
+}
+
+/// Called when a `kcl/evaluate` request was received. The compile unit of the document is
+/// evaluated with the contents in the VFS, including the unsaved changes.
+pub(crate) fn handle_evaluate(
+    snapshot: LanguageServerSnapshot,
+    params: evaluate::EvaluateParams,
+    _sender: Sender<Task>,
+    cancelled: Arc<AtomicBool>,
+) -> anyhow::Result<Option<evaluate::EvaluateResult>> {
+    let file = file_path_from_url(&params.text_document.uri)?;
+    let path: VfsPath = from_lsp::abs_path(&params.text_document.uri)?.into();
+    let workspace = match snapshot.try_get_db_state(&path) {
+        Ok(Some((workspace, _))) => workspace,
+        Ok(None) => return Err(anyhow!(LSPError::Retry)),
+        Err(_) => return Ok(None),
+    };
+    let Some((files, opts, _)) = snapshot
+        .workspace_config_cache
+        .read()
+        .get(&workspace)
+        .cloned()
+    else {
+        return Ok(None);
+    };
+    let mut opts = opts.unwrap_or_default();
+    opts.preserve_symlink_paths = true;
+    let files = get_normalized_k_files_from_paths(&files, &opts)?;
+    let paths: Vec<&str> = files.iter().map(|file| file.as_str()).collect();
+    opts.k_code_list = load_files_code_from_vfs(&paths, &snapshot.vfs)?;
+    Ok(Some(evaluate::evaluate(
+        &file, &files, opts, &params, &cancelled,
+    )?))
+}
@@ -0,0 +1,161 @@
+//! Selection ranges which expand from the innermost AST node at the position to
+//! the top-level statement along the AST node chain.
+
+use kcl_ast::ast::{ConfigEntry, Expr, Module, Node, Stmt};
+use kcl_ast::pos::{ContainsPos, GetPos};
+use kcl_ast::walker::MutSelfWalker;
+use kcl_error::Position as KCLPos;
+use kcl_error::diagnostic::Range as KCLRange;
+use lsp_types::{Range, SelectionRange};
+
+use crate::to_lsp::lsp_pos;
+
+pub fn selection_range(module: &Module, positions: &[KCLPos]) -> Vec<SelectionRange> {
+    positions
+        .iter()
+        .map(|pos| {
+            let mut collector = RangeCollector {
+                pos,
+                ranges: vec![],
+            };
+            collector.walk_module(module);
+            let mut ranges: Vec<Range> = collector
+                .ranges
+                .iter()
+                .map(|(start, end)| Range {
+                    start: lsp_pos(start),
+                    end: lsp_pos(end),
+                })
+                .collect();
+            // The nodes with the same range, e.g., an expression statement and its
+            // expression, are one selection.
+            ranges.dedup();
+            // The position itself is selected when it is not in any node.
+            if ranges.is_empty() {
+                let pos = lsp_pos(pos);
+                ranges.push(Range {
+                    start: pos,
+                    end: pos,
+                });
+            }
+            ranges
+                .into_iter()
+                .fold(None, |parent, range| {
+                    Some(SelectionRange {
+                        range,
+                        parent: parent.map(Box::new),
+                    })
+                })
+                .unwrap()
+        })
+        .collect()
+}
+
+/// Collects the ranges of the AST nodes containing the position from the top-level
+/// statement to the innermost node, and only walks into the nodes containing it.
+struct RangeCollector<'a> {
+    pos: &'a KCLPos,
+    ranges: Vec<KCLRange>,
+}
+
+impl RangeCollector<'_> {
+    /// Adds the range of the node if it contains the position and is inside the last
+    /// range, which skips the sibling nodes sharing a boundary with the last one.
+    fn enter(&mut self, range: KCLRange) -> bool {
+        if !range.contains_pos(self.pos) {
+            return false;
+        }
+        if let Some((start, end)) = self.ranges.last()
+            && !(start.less_equal(&range.0) && range.1.less_equal(end))
+        {
+            return false;
+        }
+        self.ranges.push(range);
+        true
+    }
+}
+
+impl MutSelfWalker for RangeCollector<'_> {
+    fn walk_stmt_node(&mut self, stmt: &Node<Stmt>) {
+        if self.enter(stmt.get_span_pos()) {
+            self.walk_stmt(&stmt.node);
+        }
+    }
+
+    fn walk_expr_node(&mut self, expr: &Node<Expr>) {
+        if self.enter(expr.get_span_pos()) {
+            self.walk_expr(&expr.node);
+        }
+    }
+
+    fn walk_config_entry(&mut self, config_entry: &Node<ConfigEntry>) {
+        if self.enter(config_entry.get_span_pos()) {
+            if let Some(key) = &config_entry.node.key {
+                self.walk_expr_node(key);
+            }
+            self.walk_expr_node(&config_entry.node.value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kcl_error::Position as KCLPos;
+    use lsp_types::{Position, Range, SelectionRange};
+    use proc_macro_crate::bench_test;
+
+    use super::selection_range;
+    use crate::tests::compile_test_file;
+
+    fn ranges(selection: &SelectionRange) -> Vec<Range> {
+        let mut ranges = vec![selection.range];
+        let mut parent = &selection.parent;
+        while let Some(selection) = parent {
+            ranges.push(selection.range);
+            parent = &selection.parent;
+        }
+        ranges
+    }
+
+    fn range(start: (u32, u32), end: (u32, u32)) -> Range {
+        Range {
+            start: Position::new(start.0, start.1),
+            end: Position::new(end.0, end.1),
+        }
+    }
+
+    #[test]
+    #[bench_test]
+    fn selection_range_test() {
+        let (file, prog, _, _, _) =
+            compile_test_file("src/test_data/folding_range/folding_range.k");
+        let module = prog.get_module(&file).unwrap().unwrap();
+        let pos = |line, column| KCLPos {
+            filename: file.clone(),
+            line,
+            column: Some(column),
+        };
+
+        let res = selection_range(&module, &[pos(23, 9), pos(6, 0)]);
+        assert_eq!(res.len(), 2);
+        assert_eq!(
+            ranges(&res[0]),
+            vec![
+                // 8080
+                range((22, 8), (22, 12)),
+                // [8080]
+                range((21, 12), (23, 5)),
+                // ports = [8080]
+                range((21, 4), (23, 5)),
+                // {...}
+                range((19, 14), (24, 1)),
+                // Service {...}
+                range((19, 6), (24, 1)),
+                // web = Service {...}
+                range((19, 0), (24, 1)),
+            ]
+        );
+        // Blank lines
+        assert_eq!(ranges(&res[1]), vec![range((5, 0), (5, 0))]);
+    }
+}
@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::vec;
 
 use kcl_error::Position;
@@ -6,7 +7,13 @@ use kcl_sema::core::{
     symbol::{KCLSymbol, SymbolKind, SymbolRef},
 };
 use kcl_sema::ty::TypeKind;
-use lsp_types::{SemanticToken, SemanticTokenType, SemanticTokens, SemanticTokensResult};
+use lsp_types::{
+    Range, SemanticToken, SemanticTokenModifier, SemanticTokenType, SemanticTokens,
+    SemanticTokensDelta, SemanticTokensEdit, SemanticTokensFullDeltaResult,
+    SemanticTokensRangeResult, SemanticTokensResult,
+};
+
+use crate::to_lsp::lsp_pos;
 
 pub const LEGEND_TYPE: &[SemanticTokenType] = &[
     SemanticTokenType::VARIABLE,
@@ -20,13 +27,97 @@ pub const LEGEND_TYPE: &[SemanticTokenType] = &[
     SemanticTokenType::FUNCTION,
 ];
 
+pub const LEGEND_MODIFIER: &[SemanticTokenModifier] = &[
+    SemanticTokenModifier::DECLARATION,
+    SemanticTokenModifier::READONLY,
+    SemanticTokenModifier::DEPRECATED,
+    SemanticTokenModifier::DEFAULT_LIBRARY,
+];
+
+const DEPRECATED_DECORATOR: &str = "deprecated";
+
 pub(crate) struct KCLSemanticToken {
     pub start: Position,
     pub kind: u32,
+    pub modifiers: u32,
     pub length: u32,
 }
 
+/// The last semantic tokens sent to the client for each file, which are the base of
+/// the `textDocument/semanticTokens/full/delta` requests.
+#[derive(Default)]
+pub(crate) struct SemanticTokensCache {
+    next_result_id: u64,
+    tokens: HashMap<String, SemanticTokens>,
+}
+
+impl SemanticTokensCache {
+    /// Caches the tokens of the file with a new result id.
+    pub(crate) fn insert(&mut self, file: &str, data: Vec<SemanticToken>) -> SemanticTokens {
+        self.next_result_id += 1;
+        let tokens = SemanticTokens {
+            result_id: Some(self.next_result_id.to_string()),
+            data,
+        };
+        self.tokens.insert(file.to_string(), tokens.clone());
+        tokens
+    }
+
+    /// Caches the tokens of the file and returns the edits from the previous result. All the
+    /// tokens are returned when the previous result is not in the cache.
+    pub(crate) fn delta(
+        &mut self,
+        file: &str,
+        previous_result_id: &str,
+        data: Vec<SemanticToken>,
+    ) -> SemanticTokensFullDeltaResult {
+        let previous = self
+            .tokens
+            .remove(file)
+            .filter(|tokens| tokens.result_id.as_deref() == Some(previous_result_id));
+        let tokens = self.insert(file, data);
+        match previous {
+            Some(previous) => SemanticTokensFullDeltaResult::TokensDelta(SemanticTokensDelta {
+                result_id: tokens.result_id,
+                edits: semantic_tokens_edits(&previous.data, &tokens.data),
+            }),
+            None => SemanticTokensFullDeltaResult::Tokens(tokens),
+        }
+    }
+
+    pub(crate) fn remove(&mut self, file: &str) {
+        self.tokens.remove(file);
+    }
+}
+
 pub fn semantic_tokens_full(file: &str, gs: &GlobalState) -> Option<SemanticTokensResult> {
+    Some(SemanticTokensResult::Tokens(SemanticTokens {
+        result_id: None,
+        data: kcl_semantic_tokens_to_semantic_tokens(&mut kcl_semantic_tokens(file, gs)),
+    }))
+}
+
+/// Returns the semantic tokens which start in the range. The positions are still relative
+/// to the start of the document.
+pub fn semantic_tokens_range(
+    file: &str,
+    gs: &GlobalState,
+    range: &Range,
+) -> Option<SemanticTokensRangeResult> {
+    let mut kcl_tokens: Vec<KCLSemanticToken> = kcl_semantic_tokens(file, gs)
+        .into_iter()
+        .filter(|token| {
+            let start = lsp_pos(&token.start);
+            range.start <= start && start < range.end
+        })
+        .collect();
+    Some(SemanticTokensRangeResult::Tokens(SemanticTokens {
+        result_id: None,
+        data: kcl_semantic_tokens_to_semantic_tokens(&mut kcl_tokens),
+    }))
+}
+
+pub(crate) fn kcl_semantic_tokens(file: &str, gs: &GlobalState) -> Vec<KCLSemanticToken> {
     let mut kcl_tokens: Vec<KCLSemanticToken> = vec![];
     let sema_db = gs.get_sema_db();
     if let Some(file_sema) = sema_db.get_file_sema(file) {
@@ -39,6 +130,7 @@ pub fn semantic_tokens_full(file: &str, gs: &GlobalState) -> Option<SemanticToke
                         kcl_tokens.push(KCLSemanticToken {
                             start: start.clone(),
                             kind,
+                            modifiers: get_modifiers(*symbol_ref, symbol, gs),
                             length: if start.line == end.line {
                                 (end.column.unwrap_or(0) - start.column.unwrap_or(0)) as u32
                             } else {
@@ -51,11 +143,7 @@ pub fn semantic_tokens_full(file: &str, gs: &GlobalState) -> Option<SemanticToke
             }
         }
     }
-
-    Some(SemanticTokensResult::Tokens(SemanticTokens {
-        result_id: None,
-        data: kcl_semantic_tokens_to_semantic_tokens(&mut kcl_tokens),
-    }))
+    kcl_tokens
 }
 
 pub(crate) fn get_kind(symbol_ref: SymbolRef, symbol: &KCLSymbol, gs: &GlobalState) -> Option<u32> {
@@ -100,6 +188,103 @@ pub(crate) fn type_index(ty: SemanticTokenType) -> u32 {
     LEGEND_TYPE.iter().position(|it| *it == ty).unwrap() as u32
 }
 
+/// Returns the modifier bitset of the symbol. The definitions are declarations and the
+/// references share the other modifiers of their definitions:
+///
+/// + `readonly`: the global variables whose names do not start with `_`, which are immutable.
+/// + `deprecated`: the schema attributes with the `@deprecated` decorator.
+/// + `defaultLibrary`: the builtin functions and the system module functions.
+pub(crate) fn get_modifiers(symbol_ref: SymbolRef, symbol: &KCLSymbol, gs: &GlobalState) -> u32 {
+    let mut modifiers = 0;
+    let def_ref = match symbol_ref.get_kind() {
+        SymbolKind::Unresolved => match symbol.get_definition() {
+            Some(def_ref) => def_ref,
+            None => return modifiers,
+        },
+        SymbolKind::Decorator | SymbolKind::Expression | SymbolKind::Comment => {
+            return modifiers;
+        }
+        _ => {
+            modifiers |= modifier_bit(SemanticTokenModifier::DECLARATION);
+            symbol_ref
+        }
+    };
+    let Some(def) = gs.get_symbols().get_symbol(def_ref) else {
+        return modifiers;
+    };
+    match def_ref.get_kind() {
+        SymbolKind::Value if def.is_global() && !def.get_name().starts_with('_') => {
+            modifiers |= modifier_bit(SemanticTokenModifier::READONLY);
+        }
+        SymbolKind::Attribute if is_deprecated_attr(def, gs) => {
+            modifiers |= modifier_bit(SemanticTokenModifier::DEPRECATED);
+        }
+        // The function symbols are only allocated for the builtin functions, the system
+        // module functions and the string member functions.
+        SymbolKind::Function => {
+            modifiers |= modifier_bit(SemanticTokenModifier::DEFAULT_LIBRARY);
+        }
+        _ => {}
+    }
+    modifiers
+}
+
+fn is_deprecated_attr(attr: &KCLSymbol, gs: &GlobalState) -> bool {
+    let Some(schema) = attr
+        .get_owner()
+        .and_then(|owner| gs.get_symbols().get_symbol(owner))
+    else {
+        return false;
+    };
+    match schema.get_sema_info().ty.as_ref().map(|ty| &ty.kind) {
+        Some(TypeKind::Schema(schema_ty)) => schema_ty
+            .get_obj_of_attr(&attr.get_name())
+            .is_some_and(|attr| {
+                attr.decorators
+                    .iter()
+                    .any(|decorator| decorator.name == DEPRECATED_DECORATOR)
+            }),
+        _ => false,
+    }
+}
+
+pub(crate) fn modifier_bit(modifier: SemanticTokenModifier) -> u32 {
+    1 << LEGEND_MODIFIER
+        .iter()
+        .position(|it| *it == modifier)
+        .unwrap()
+}
+
+/// Returns the edit which replaces the tokens between the common prefix and the common
+/// suffix of the previous and the current tokens. The edit offsets count the integers of
+/// the encoded tokens, i.e., five integers per token.
+pub(crate) fn semantic_tokens_edits(
+    previous: &[SemanticToken],
+    current: &[SemanticToken],
+) -> Vec<SemanticTokensEdit> {
+    let prefix = previous
+        .iter()
+        .zip(current)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let suffix = previous[prefix..]
+        .iter()
+        .rev()
+        .zip(current[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let deleted = previous.len() - prefix - suffix;
+    let inserted = &current[prefix..current.len() - suffix];
+    if deleted == 0 && inserted.is_empty() {
+        return vec![];
+    }
+    vec![SemanticTokensEdit {
+        start: 5 * prefix as u32,
+        delete_count: 5 * deleted as u32,
+        data: Some(inserted.to_vec()),
+    }]
+}
+
 pub(crate) fn kcl_semantic_tokens_to_semantic_tokens(
     tokens: &mut [KCLSemanticToken],
 ) -> Vec<SemanticToken> {
@@ -132,7 +317,7 @@ pub(crate) fn kcl_semantic_tokens_to_semantic_tokens(
         delta_start: (first_token.start.column.unwrap_or(0)) as u32,
         length: first_token.length,
         token_type: first_token.kind,
-        token_modifiers_bitset: 0,
+        token_modifiers_bitset: first_token.modifiers,
     });
 
     for token_tuple in tokens.windows(2) {
@@ -150,7 +335,7 @@ pub(crate) fn kcl_semantic_tokens_to_semantic_tokens(
                 delta_start,
                 length: second_token.length,
                 token_type: second_token.kind,
-                token_modifiers_bitset: 0,
+                token_modifiers_bitset: second_token.modifiers,
             });
         }
     }
@@ -160,9 +345,42 @@ pub(crate) fn kcl_semantic_tokens_to_semantic_tokens(
 #[cfg(test)]
 mod tests {
     use crate::tests::compile_test_file;
+    use lsp_types::{
+        Position, Range, SemanticToken, SemanticTokenModifier, SemanticTokenType,
+        SemanticTokensFullDeltaResult, SemanticTokensRangeResult, SemanticTokensResult,
+    };
     use proc_macro_crate::bench_test;
 
-    use super::semantic_tokens_full;
+    use super::{
+        SemanticTokensCache, modifier_bit, semantic_tokens_edits, semantic_tokens_full,
+        semantic_tokens_range, type_index,
+    };
+
+    /// Decodes the relative token positions to (line, col, kind, modifiers).
+    fn decode(tokens: &[SemanticToken]) -> Vec<(u32, u32, u32, u32)> {
+        let (mut line, mut col) = (0, 0);
+        tokens
+            .iter()
+            .map(|token| {
+                if token.delta_line != 0 {
+                    col = 0;
+                }
+                line += token.delta_line;
+                col += token.delta_start;
+                (line, col, token.token_type, token.token_modifiers_bitset)
+            })
+            .collect()
+    }
+
+    fn token(delta_line: u32, delta_start: u32, length: u32) -> SemanticToken {
+        SemanticToken {
+            delta_line,
+            delta_start,
+            length,
+            token_type: 0,
+            token_modifiers_bitset: 0,
+        }
+    }
 
     #[test]
     #[bench_test]
@@ -220,4 +438,106 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    #[bench_test]
+    fn semantic_tokens_modifiers_test() {
+        let (file, _, _, gs, _) = compile_test_file("src/test_data/sema_token/modifiers.k");
+        let Some(SemanticTokensResult::Tokens(tokens)) = semantic_tokens_full(&file, &gs) else {
+            panic!("test failed")
+        };
+        let tokens = decode(&tokens.data);
+        let declaration = modifier_bit(SemanticTokenModifier::DECLARATION);
+        let readonly = modifier_bit(SemanticTokenModifier::READONLY);
+        let deprecated = modifier_bit(SemanticTokenModifier::DEPRECATED);
+        let default_library = modifier_bit(SemanticTokenModifier::DEFAULT_LIBRARY);
+        let property = type_index(SemanticTokenType::PROPERTY);
+        let variable = type_index(SemanticTokenType::VARIABLE);
+        let function = type_index(SemanticTokenType::FUNCTION);
+        for expected in [
+            // schema Person
+            (0, 7, type_index(SemanticTokenType::STRUCT), declaration),
+            // nickname?: str
+            (2, 4, property, declaration | deprecated),
+            // name: str
+            (3, 4, property, declaration),
+            // _suffix = "!"
+            (5, 0, variable, declaration),
+            // alice = Person {
+            (6, 0, variable, declaration | readonly),
+            (6, 8, type_index(SemanticTokenType::STRUCT), 0),
+            // name = "alice" + _suffix
+            (7, 4, property, 0),
+            (7, 21, variable, 0),
+            // nickname = "ally"
+            (8, 4, property, deprecated),
+            // n = len(alice.name)
+            (10, 0, variable, declaration | readonly),
+            (10, 4, function, default_library),
+            (10, 8, variable, readonly),
+        ] {
+            assert!(tokens.contains(&expected), "{expected:?} not found");
+        }
+    }
+
+    #[test]
+    #[bench_test]
+    fn semantic_tokens_range_test() {
+        let (file, _, _, gs, _) = compile_test_file("src/test_data/sema_token/modifiers.k");
+        let range = Range::new(Position::new(6, 0), Position::new(9, 0));
+        let Some(SemanticTokensRangeResult::Tokens(tokens)) =
+            semantic_tokens_range(&file, &gs, &range)
+        else {
+            panic!("test failed")
+        };
+        let lines: Vec<(u32, u32)> = decode(&tokens.data)
+            .into_iter()
+            .map(|(line, col, _, _)| (line, col))
+            .collect();
+        assert_eq!(lines, vec![(6, 0), (6, 8), (7, 4), (7, 21), (8, 4)]);
+    }
+
+    #[test]
+    fn semantic_tokens_edits_test() {
+        let previous = vec![token(0, 0, 1), token(1, 0, 2), token(1, 0, 3)];
+        assert!(semantic_tokens_edits(&previous, &previous).is_empty());
+
+        let current = vec![
+            token(0, 0, 1),
+            token(1, 4, 2),
+            token(0, 3, 5),
+            token(1, 0, 3),
+        ];
+        let edits = semantic_tokens_edits(&previous, &current);
+        assert_eq!(edits.len(), 1);
+        assert_eq!(edits[0].start, 5);
+        assert_eq!(edits[0].delete_count, 5);
+        assert_eq!(edits[0].data, Some(vec![token(1, 4, 2), token(0, 3, 5)]));
+
+        let edits = semantic_tokens_edits(&previous, &previous[..1]);
+        assert_eq!(edits[0].start, 5);
+        assert_eq!(edits[0].delete_count, 10);
+        assert_eq!(edits[0].data, Some(vec![]));
+    }
+
+    #[test]
+    fn semantic_tokens_cache_test() {
+        let mut cache = SemanticTokensCache::default();
+        let tokens = cache.insert("main.k", vec![token(0, 0, 1)]);
+        let result_id = tokens.result_id.unwrap();
+
+        match cache.delta("main.k", &result_id, vec![token(0, 0, 1), token(1, 0, 2)]) {
+            SemanticTokensFullDeltaResult::TokensDelta(delta) => {
+                assert_ne!(delta.result_id, Some(result_id.clone()));
+                assert_eq!(delta.edits.len(), 1);
+                assert_eq!(delta.edits[0].start, 5);
+            }
+            _ => panic!("test failed"),
+        }
+        // The previous result is outdated.
+        assert!(matches!(
+            cache.delta("main.k", &result_id, vec![]),
+            SemanticTokensFullDeltaResult::Tokens(_)
+        ));
+    }
 }
@@ -1,28 +1,35 @@
 use crate::analysis::{Analysis, AnalysisDatabase, DBState, OpenFileInfo};
+use crate::capabilities::PullDiagnostics;
 use crate::compile::{Params, compile};
 use crate::from_lsp::file_path_from_url;
+use crate::semantic_token::SemanticTokensCache;
 use crate::to_lsp::{kcl_diag_to_lsp_diags, url_from_path};
 use crate::util::{filter_kcl_config_file, get_file_name, to_json};
+use crate::workspace_symbol::WorkspaceSymbolIndex;
 use crossbeam_channel::{Receiver, Sender, select, unbounded};
 use kcl_driver::toolchain::{self, Toolchain};
 use kcl_driver::{
     CompileUnitOptions, WorkSpaceKind, lookup_compile_workspace, lookup_compile_workspaces,
 };
+use kcl_error::Diagnostic as KCLDiagnostic;
 use kcl_parser::KCLModuleCache;
+use kcl_primitives::IndexSet;
 use kcl_sema::core::global_state::GlobalState;
 use kcl_sema::resolver::scope::KCLScopeCache;
 use lsp_server::RequestId;
 use lsp_server::{ReqQueue, Request, Response};
 use lsp_types::{
-    InitializeParams, PublishDiagnosticsParams, WorkspaceFolder,
+    ApplyWorkspaceEditParams, InitializeParams, PublishDiagnosticsParams, WorkspaceFolder,
     notification::{Notification, PublishDiagnostics},
+    request::{ApplyWorkspaceEdit, Request as _},
 };
 use notify::{RecommendedWatcher, RecursiveMode, Watcher};
 use parking_lot::RwLock;
 use ra_ap_vfs::{ChangeKind, ChangedFile, FileId, Vfs};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::sync::Mutex;
+use std::sync::atomic::AtomicBool;
 use std::thread;
 use std::time::Duration;
 use std::{sync::Arc, sync::mpsc, time::Instant};
@@ -36,6 +43,11 @@ pub(crate) type RequestHandler = fn(&mut LanguageServerState, lsp_server::Respon
 pub(crate) enum Task {
     Response(Response),
     Notify(lsp_server::Notification),
+    /// Applies the workspace edit with the `workspace/applyEdit` request to the client.
+    ApplyEdit(lsp_types::WorkspaceEdit),
+    /// Asks the client to pull the diagnostics again with the `workspace/diagnostic/refresh`
+    /// request after a compile unit is compiled.
+    RefreshDiagnostics,
     Retry(Request),
     ChangedFile(FileId, ChangeKind),
     ReOpenFile(FileId, ChangeKind),
@@ -65,6 +77,15 @@ pub(crate) type KCLVfs = Arc<RwLock<Vfs>>;
 
 pub(crate) type KCLWorkSpaceConfigCache = Arc<RwLock<HashMap<WorkSpaceKind, CompileUnitOptions>>>;
 
+/// The diagnostics of the compile units which are compiled for the workspace diagnostics
+/// without opening any file, and the files of each compile unit to invalidate them.
+pub(crate) type KCLWorkspaceDiagsCache =
+    Arc<RwLock<HashMap<WorkSpaceKind, (HashSet<String>, IndexSet<KCLDiagnostic>)>>>;
+
+/// The method of the `workspace/diagnostic/refresh` request, which is not provided by
+/// lsp-types 0.93.
+const WORKSPACE_DIAGNOSTIC_REFRESH: &str = "workspace/diagnostic/refresh";
+
 pub(crate) type KCLToolChain = Arc<RwLock<dyn Toolchain>>;
 pub(crate) type KCLGlobalStateCache = Arc<Mutex<GlobalState>>;
 pub(crate) type FSEventWatcher = Handle<
@@ -112,6 +133,14 @@ pub(crate) struct LanguageServerState {
     /// Actively monitor file system changes. These changes will not be notified through lsp,
     /// e.g., execute `kcl mod add xxx`, `kcl fmt xxx`
     pub fs_event_watcher: Option<FSEventWatcher>,
+    /// The last semantic tokens sent to the client for each file
+    pub semantic_tokens_cache: Arc<RwLock<SemanticTokensCache>>,
+    /// The cancellation tokens of the running cancellable requests
+    pub request_cancellations: Arc<RwLock<HashMap<RequestId, Arc<AtomicBool>>>>,
+    /// The pull diagnostics capabilities of the client
+    pub pull_diagnostics: PullDiagnostics,
+    /// The diagnostics of the compile units which are not compiled for the opened files
+    pub workspace_diags_cache: KCLWorkspaceDiagsCache,
 }
 
 /// A snapshot of the state of the language server
@@ -135,11 +164,21 @@ pub(crate) struct LanguageServerSnapshot {
     pub temporary_workspace: Arc<RwLock<HashMap<FileId, Option<WorkSpaceKind>>>>,
     /// Compile config cache
     pub workspace_config_cache: KCLWorkSpaceConfigCache,
+    /// The last semantic tokens sent to the client for each file
+    pub semantic_tokens_cache: Arc<RwLock<SemanticTokensCache>>,
+    /// The workspace folders of the client
+    pub workspace_folders: Option<Vec<WorkspaceFolder>>,
+    /// The diagnostics of the compile units which are not compiled for the opened files
+    pub workspace_diags_cache: KCLWorkspaceDiagsCache,
 }
 
 #[allow(unused)]
 impl LanguageServerState {
-    pub fn new(sender: Sender<lsp_server::Message>, initialize_params: InitializeParams) -> Self {
+    pub fn new(
+        sender: Sender<lsp_server::Message>,
+        initialize_params: InitializeParams,
+        pull_diagnostics: PullDiagnostics,
+    ) -> Self {
         let (task_sender, task_receiver) = unbounded::<Task>();
 
         let loader = {
@@ -190,6 +229,10 @@ impl LanguageServerState {
             temporary_workspace: Arc::new(RwLock::new(HashMap::new())),
             workspace_folders: initialize_params.workspace_folders.clone(),
             fs_event_watcher,
+            semantic_tokens_cache: Arc::new(RwLock::new(SemanticTokensCache::default())),
+            request_cancellations: Arc::new(RwLock::new(HashMap::new())),
+            pull_diagnostics,
+            workspace_diags_cache: Arc::new(RwLock::new(HashMap::new())),
         };
 
         state.init_workspaces();
@@ -266,14 +309,11 @@ impl LanguageServerState {
         // 1. Process the incoming event
         match event {
             Event::Task(task) => self.handle_task(task, start_time)?,
-            Event::Lsp(msg) => {
-                match msg {
-                    lsp_server::Message::Request(req) => self.on_request(req, start_time)?,
-                    lsp_server::Message::Notification(not) => self.on_notification(not)?,
-                    // lsp_server::Message::Response(resp) => self.complete_request(resp),
-                    _ => {}
-                }
-            }
+            Event::Lsp(msg) => match msg {
+                lsp_server::Message::Request(req) => self.on_request(req, start_time)?,
+                lsp_server::Message::Notification(not) => self.on_notification(not)?,
+                lsp_server::Message::Response(resp) => self.complete_request(resp),
+            },
             Event::FileWatcher(file_watcher_event) => {
                 self.handle_file_watcher_event(file_watcher_event)?
             }
@@ -300,11 +340,27 @@ impl LanguageServerState {
 
         // Construct an AnalysisChange to apply to the analysis
         for file in changed_files {
+            if let Ok(filename) = get_file_name(self.vfs.read(), file.file_id) {
+                self.invalidate_workspace_diags(Path::new(&filename));
+            }
             self.process_changed_file(file);
         }
         true
     }
 
+    /// Removes the cached workspace diagnostics of the compile units which contain the
+    /// changed file, or whose packages are in the directory of the file, e.g., a new file
+    /// or a `kcl.mod` file of the package.
+    pub(crate) fn invalidate_workspace_diags(&self, path: &Path) {
+        let dir = path.parent();
+        self.workspace_diags_cache.write().retain(|_, (files, _)| {
+            !files.iter().any(|file| {
+                let file = Path::new(file);
+                file == path || file.parent() == dir
+            })
+        });
+    }
+
     /// Process vfs changed file. Update db cache when create(did_open_file), modify(did_change) or delete(did_close_file) vfs files.
     fn process_changed_file(&mut self, file: ChangedFile) {
         match file.change_kind {
@@ -481,6 +537,22 @@ impl LanguageServerState {
                 self.send(notification.into());
             }
             Task::Response(response) => self.respond(response)?,
+            Task::ApplyEdit(edit) => {
+                let request = self.request_queue.outgoing.register(
+                    ApplyWorkspaceEdit::METHOD.to_string(),
+                    ApplyWorkspaceEditParams { label: None, edit },
+                    |_, _| {},
+                );
+                self.send(request.into())?;
+            }
+            Task::RefreshDiagnostics => {
+                let request = self.request_queue.outgoing.register(
+                    WORKSPACE_DIAGNOSTIC_REFRESH.to_string(),
+                    (),
+                    |_, _| {},
+                );
+                self.send(request.into())?;
+            }
             Task::Retry(req) if !self.is_completed(&req) => {
                 thread::sleep(Duration::from_millis(20));
                 self.on_request(req, request_received)?
@@ -504,6 +576,12 @@ impl LanguageServerState {
     /// Handles a task sent by another async task
     #[allow(clippy::unnecessary_wraps)]
     fn handle_file_watcher_event(&mut self, event: FileWatcherEvent) -> anyhow::Result<()> {
+        let (FileWatcherEvent::Changed(paths)
+        | FileWatcherEvent::Create(paths)
+        | FileWatcherEvent::Removed(paths)) = &event;
+        for path in paths {
+            self.invalidate_workspace_diags(path);
+        }
         match event {
             FileWatcherEvent::Changed(paths) => self.handle_changed_confg_file(&paths),
             FileWatcherEvent::Create(paths) => self.handle_create_confg_file(&paths),
@@ -527,6 +605,13 @@ impl LanguageServerState {
         Ok(())
     }
 
+    /// Handles the response of a request sent to the client.
+    fn complete_request(&mut self, response: lsp_server::Response) {
+        if let Some(handler) = self.request_queue.outgoing.complete(response.id.clone()) {
+            handler(self, response);
+        }
+    }
+
     /// Sends a message to the client
     pub(crate) fn send(&self, message: lsp_server::Message) -> anyhow::Result<()> {
         self.sender.send(message)?;
@@ -558,6 +643,9 @@ impl LanguageServerState {
             workspaces: self.analysis.workspaces.clone(),
             temporary_workspace: self.temporary_workspace.clone(),
             workspace_config_cache: self.workspace_config_cache.clone(),
+            semantic_tokens_cache: self.semantic_tokens_cache.clone(),
+            workspace_folders: self.workspace_folders.clone(),
+            workspace_diags_cache: self.workspace_diags_cache.clone(),
         }
     }
 
@@ -628,6 +716,8 @@ impl LanguageServerState {
             let tool = Arc::clone(&self.tool);
             let gs_cache = Arc::clone(&self.gs_cache);
 
+            let pull_diagnostics = self.pull_diagnostics;
+
             let mut files = opts.0.clone();
             move || {
                 let old_diags = {
@@ -680,55 +770,59 @@ impl LanguageServerState {
                     &sender,
                 );
 
-                let mut old_diags_maps = HashMap::new();
-                for diag in &old_diags {
-                    let lsp_diag = kcl_diag_to_lsp_diags(diag);
-                    for (key, value) in lsp_diag {
-                        old_diags_maps.entry(key).or_insert(vec![]).extend(value);
+                // The diagnostics are published when the client does not pull them.
+                if !pull_diagnostics.enabled {
+                    let mut old_diags_maps = HashMap::new();
+                    for diag in &old_diags {
+                        let lsp_diag = kcl_diag_to_lsp_diags(diag);
+                        for (key, value) in lsp_diag {
+                            old_diags_maps.entry(key).or_insert(vec![]).extend(value);
+                        }
                     }
-                }
 
-                // publish diags
-                let mut new_diags_maps = HashMap::new();
+                    // publish diags
+                    let mut new_diags_maps = HashMap::new();
+
+                    for diag in &diags {
+                        let lsp_diag = kcl_diag_to_lsp_diags(diag);
+                        for (key, value) in lsp_diag {
+                            new_diags_maps.entry(key).or_insert(vec![]).extend(value);
+                        }
+                    }
 
-                for diag in &diags {
-                    let lsp_diag = kcl_diag_to_lsp_diags(diag);
-                    for (key, value) in lsp_diag {
-                        new_diags_maps.entry(key).or_insert(vec![]).extend(value);
+                    for (file, diags) in old_diags_maps {
+                        if !new_diags_maps.contains_key(&file)
+                            && let Ok(uri) = url_from_path(file) {
+                                sender.send(Task::Notify(lsp_server::Notification {
+                                    method: PublishDiagnostics::METHOD.to_owned(),
+                                    params: to_json(PublishDiagnosticsParams {
+                                        uri: uri.clone(),
+                                        diagnostics: vec![],
+                                        version: None,
+                                    })
+                                    .unwrap(),
+                                }));
+                            }
                     }
-                }
 
-                for (file, diags) in old_diags_maps {
-                    if !new_diags_maps.contains_key(&file)
-                        && let Ok(uri) = url_from_path(file) {
+                    for (filename, diagnostics) in new_diags_maps {
+                        if let Ok(uri) = url_from_path(filename) {
                             sender.send(Task::Notify(lsp_server::Notification {
                                 method: PublishDiagnostics::METHOD.to_owned(),
                                 params: to_json(PublishDiagnosticsParams {
                                     uri: uri.clone(),
-                                    diagnostics: vec![],
+                                    diagnostics,
                                     version: None,
                                 })
                                 .unwrap(),
                             }));
                         }
-                }
-
-                for (filename, diagnostics) in new_diags_maps {
-                    if let Ok(uri) = url_from_path(filename) {
-                        sender.send(Task::Notify(lsp_server::Notification {
-                            method: PublishDiagnostics::METHOD.to_owned(),
-                            params: to_json(PublishDiagnosticsParams {
-                                uri: uri.clone(),
-                                diagnostics,
-                                version: None,
-                            })
-                            .unwrap(),
-                        }));
                     }
                 }
 
                 match compile_res {
                     Ok((prog, schema_map, gs)) => {
+                        let symbol_index = WorkspaceSymbolIndex::new(&gs);
                         let mut workspaces = snapshot.workspaces.write();
                         log_message(
                             format!(
@@ -738,7 +832,7 @@ impl LanguageServerState {
                         );
                         workspaces.insert(
                             workspace.clone(),
-                            DBState::Ready(Arc::new(AnalysisDatabase { prog, gs, diags,schema_map })),
+                            DBState::Ready(Arc::new(AnalysisDatabase { prog, gs, diags, schema_map, symbol_index })),
                         );
                         drop(workspaces);
                         if temp && let Some(changed_file_id) = changed_file_id {
@@ -777,6 +871,9 @@ impl LanguageServerState {
                         }
                     }
                 }
+                if pull_diagnostics.refresh {
+                    let _ = sender.send(Task::RefreshDiagnostics);
+                }
             }
         })
     }
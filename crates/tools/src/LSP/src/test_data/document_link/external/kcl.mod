@@ -0,0 +1,7 @@
+[package]
+name = "external"
+version = "0.0.1"
+
+[dependencies]
+dep = { path = "dep" }
+oci_dep = { oci = "oci://ghcr.io/kcl-lang/helloworld", tag = "0.1.0" }
@@ -77,7 +77,9 @@ use crate::compile::compile_with_params;
 use crate::from_lsp::file_path_from_url;
 
 use crate::app::main_loop;
+use crate::capabilities::{PullDiagnostics, add_unsupported_capabilities};
 use crate::compile::Params;
+use crate::diagnostic::{WorkspaceDiagnosticParams, WorkspaceDiagnosticReport};
 use crate::state::KCLGlobalStateCache;
 use crate::state::KCLVfs;
 use crate::to_lsp::kcl_diag_to_lsp_diags_by_file;
@@ -399,14 +401,23 @@ fn test_apply_document_changes() {
     apply_document_changes(&mut text, change![0, 0; 0, 0 => "a"]);
     assert_eq!(text, "a❤️");
 
-    // todo: Non-ASCII char
-    // text = String::from("a\nb");
-    // apply_document_changes(&mut text, change![0, 1; 1, 0 => "\nțc", 0, 1; 1, 1 => "d"]);
-    // assert_eq!(text, "adcb");
+    // Non-ASCII char
+    text = String::from("a\nb");
+    apply_document_changes(&mut text, change![0, 1; 1, 0 => "\nțc", 0, 1; 1, 1 => "d"]);
+    assert_eq!(text, "adcb");
 
-    // text = String::from("a\nb");
-    // apply_document_changes(&mut text, change![0, 1; 1, 0 => "ț\nc", 0, 2; 0, 2 => "c"]);
-    // assert_eq!(text, "ațc\ncb");
+    text = String::from("a\nb");
+    apply_document_changes(&mut text, change![0, 1; 1, 0 => "ț\nc", 0, 2; 0, 2 => "c"]);
+    assert_eq!(text, "ațc\ncb");
+
+    // The character offsets are counted in UTF-16 code units.
+    text = String::from("a = \"😀\"\nb = 1");
+    apply_document_changes(&mut text, change![0, 8; 0, 8 => "!"]);
+    assert_eq!(text, "a = \"😀\"!\nb = 1");
+
+    // Out of range positions are clamped to the start of the next line or the end of the text.
+    apply_document_changes(&mut text, change![1, 100; 5, 0 => "0"]);
+    assert_eq!(text, "a = \"😀\"!\nb = 10");
 }
 
 #[test]
@@ -452,7 +463,7 @@ impl Server {
         let (connection, client) = Connection::memory();
 
         let worker = std::thread::spawn(move || {
-            main_loop(connection, initialize_params).unwrap();
+            main_loop(connection, initialize_params, PullDiagnostics::default()).unwrap();
         });
 
         Self {
@@ -1360,6 +1371,72 @@ fn lsp_invalid_subcommand_test() {
     }
 }
 
+#[test]
+fn workspace_diagnostic_test() {
+    let root = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("src")
+        .join("test_data")
+        .join("diagnostic")
+        .join("workspace");
+    let initialize_params = InitializeParams {
+        workspace_folders: Some(vec![WorkspaceFolder {
+            uri: Url::from_file_path(root.clone()).unwrap(),
+            name: "test".to_string(),
+        }]),
+        ..Default::default()
+    };
+    let server = Project {}.server(initialize_params);
+
+    let id = server.next_request_id.get();
+    server.next_request_id.set(id.wrapping_add(1));
+
+    // The compile units in `kcl.work` are reported without opening any file.
+    let r: Request = Request::new(
+        id.into(),
+        "workspace/diagnostic".to_string(),
+        WorkspaceDiagnosticParams {
+            identifier: None,
+            previous_result_ids: vec![],
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        },
+    );
+
+    let res = server.send_and_receive(r);
+    let report: WorkspaceDiagnosticReport = serde_json::from_value(res.result.unwrap()).unwrap();
+    assert_eq!(report.items.len(), 1);
+    assert!(report.items[0].uri.path().ends_with("a/main.k"));
+}
+
+#[test]
+fn pull_diagnostics_capabilities_test() {
+    let params = serde_json::json!({
+        "capabilities": {
+            "textDocument": { "diagnostic": { "dynamicRegistration": false } },
+            "workspace": { "diagnostics": { "refreshSupport": true } },
+        }
+    });
+    let pull_diagnostics = PullDiagnostics::from_initialize_params(&params);
+    assert_eq!(
+        pull_diagnostics,
+        PullDiagnostics {
+            enabled: true,
+            refresh: true,
+        }
+    );
+    let mut capabilities = serde_json::json!({});
+    add_unsupported_capabilities(&mut capabilities, pull_diagnostics);
+    assert!(capabilities["diagnosticProvider"].is_object());
+
+    // The diagnostics are only pushed to the clients which do not pull them.
+    let params = serde_json::json!({ "capabilities": {} });
+    let pull_diagnostics = PullDiagnostics::from_initialize_params(&params);
+    assert_eq!(pull_diagnostics, PullDiagnostics::default());
+    let mut capabilities = serde_json::json!({});
+    add_unsupported_capabilities(&mut capabilities, pull_diagnostics);
+    assert!(capabilities["diagnosticProvider"].is_null());
+}
+
 #[test]
 fn find_refs_test() {
     let root = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
@@ -1943,3 +2020,196 @@ fn aug_assign_without_define() {
         compile_test_file("src/test_data/error_code/aug_assign/aug_assign.k");
     assert_eq!(diags.len(), 1);
 }
+
+/// An in-memory writer for the DAP server output.
+#[derive(Clone, Default)]
+struct DapOutputBuffer(Arc<std::sync::Mutex<Vec<u8>>>);
+
+impl std::io::Write for DapOutputBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl DapOutputBuffer {
+    /// Get all the DAP messages written into the buffer.
+    fn messages(&self) -> Vec<serde_json::Value> {
+        let content = self.0.lock().unwrap().clone();
+        let mut reader = std::io::BufReader::new(content.as_slice());
+        let mut messages = vec![];
+        while let Some(message) = crate::dap::read_message(&mut reader).unwrap() {
+            messages.push(message);
+        }
+        messages
+    }
+
+    /// Wait for the event and return the count of the event.
+    fn wait_event(&self, event: &str, count: usize) {
+        for _ in 0..100 {
+            if self
+                .messages()
+                .iter()
+                .filter(|m| m["event"] == event)
+                .count()
+                >= count
+            {
+                return;
+            }
+            thread::sleep(Duration::from_millis(50));
+        }
+        panic!("wait for the DAP event {event} timeout");
+    }
+
+    /// Get the last response of the command.
+    fn response(&self, command: &str) -> serde_json::Value {
+        self.messages()
+            .into_iter()
+            .rev()
+            .find(|m| m["type"] == "response" && m["command"] == command)
+            .unwrap()
+    }
+}
+
+#[test]
+fn dap_breakpoint_and_step_test() {
+    use crate::dap::{DapServer, Output};
+    use serde_json::json;
+
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("src/test_data/dap/main.k")
+        .to_string_lossy()
+        .to_string();
+    let buffer = DapOutputBuffer::default();
+    let mut server = DapServer::new(Output::new(Box::new(buffer.clone())));
+    let mut seq = 0;
+    let mut request = |server: &mut DapServer, command: &str, arguments: serde_json::Value| {
+        seq += 1;
+        server
+            .handle(
+                &json!({"seq": seq, "type": "request", "command": command, "arguments": arguments}),
+            )
+            .unwrap()
+    };
+    request(&mut server, "initialize", json!({}));
+    request(
+        &mut server,
+        "setBreakpoints",
+        json!({"source": {"path": path}, "breakpoints": [{"line": 6}]}),
+    );
+    request(&mut server, "launch", json!({"program": path}));
+    request(&mut server, "configurationDone", json!({}));
+
+    // Stop at the breakpoint in the lambda body.
+    buffer.wait_event("stopped", 1);
+    request(&mut server, "stackTrace", json!({"threadId": 1}));
+    let frames = &buffer.response("stackTrace")["body"]["stackFrames"];
+    assert_eq!(frames[0]["line"], 6);
+    assert_eq!(frames[1]["line"], 9);
+    request(&mut server, "variables", json!({"variablesReference": 1}));
+    let variables = buffer.response("variables")["body"]["variables"].clone();
+    let x = variables
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|v| v["name"] == "x")
+        .unwrap();
+    assert_eq!(x["value"], "1");
+
+    // Step out of the lambda.
+    request(&mut server, "stepOut", json!({"threadId": 1}));
+    buffer.wait_event("stopped", 2);
+    request(&mut server, "stackTrace", json!({"threadId": 1}));
+    let frames = &buffer.response("stackTrace")["body"]["stackFrames"];
+    assert_eq!(frames[0]["line"], 10);
+
+    // Step into the schema body and inspect the schema config.
+    request(&mut server, "stepIn", json!({"threadId": 1}));
+    buffer.wait_event("stopped", 3);
+    request(&mut server, "scopes", json!({"frameId": 0}));
+    let scopes = &buffer.response("scopes")["body"]["scopes"];
+    assert_eq!(scopes[2]["name"], "Schema");
+    request(&mut server, "variables", json!({"variablesReference": 3}));
+    let variables = &buffer.response("variables")["body"]["variables"];
+    assert_eq!(variables[0]["name"], "config");
+    let config_reference = variables[0]["variablesReference"].clone();
+    request(
+        &mut server,
+        "variables",
+        json!({"variablesReference": config_reference}),
+    );
+    let variables = &buffer.response("variables")["body"]["variables"];
+    assert_eq!(variables[0]["name"], "name");
+    assert_eq!(variables[0]["value"], "\"Alice\"");
+
+    request(&mut server, "continue", json!({"threadId": 1}));
+    buffer.wait_event("terminated", 1);
+    let exited = buffer
+        .messages()
+        .into_iter()
+        .find(|m| m["event"] == "exited")
+        .unwrap();
+    assert_eq!(exited["body"]["exitCode"], 0);
+    assert!(!request(&mut server, "disconnect", json!({})));
+}
+
+#[test]
+fn dap_test_case_test() {
+    use crate::dap::{DapServer, Output};
+    use serde_json::json;
+
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("src/test_data/code_lens");
+    let main = dir.join("main.k").to_string_lossy().to_string();
+    let test = dir.join("main_test.k").to_string_lossy().to_string();
+    let buffer = DapOutputBuffer::default();
+    let mut server = DapServer::new(Output::new(Box::new(buffer.clone())));
+    let mut seq = 0;
+    let mut request = |server: &mut DapServer, command: &str, arguments: serde_json::Value| {
+        seq += 1;
+        server
+            .handle(
+                &json!({"seq": seq, "type": "request", "command": command, "arguments": arguments}),
+            )
+            .unwrap()
+    };
+    request(&mut server, "initialize", json!({}));
+    request(
+        &mut server,
+        "setBreakpoints",
+        json!({"source": {"path": test}, "breakpoints": [{"line": 5}]}),
+    );
+    request(
+        &mut server,
+        "launch",
+        json!({"program": [main, test], "testCase": "test_bob_age"}),
+    );
+    request(&mut server, "configurationDone", json!({}));
+
+    // Stop at the assertion in the test case lambda.
+    buffer.wait_event("stopped", 1);
+    request(&mut server, "stackTrace", json!({"threadId": 1}));
+    let frames = &buffer.response("stackTrace")["body"]["stackFrames"];
+    assert_eq!(frames[0]["line"], 5);
+
+    // The test case fails with the assertion message.
+    request(&mut server, "continue", json!({"threadId": 1}));
+    buffer.wait_event("terminated", 1);
+    let messages = buffer.messages();
+    let stderr = messages
+        .iter()
+        .find(|m| m["event"] == "output" && m["body"]["category"] == "stderr")
+        .unwrap();
+    assert!(
+        stderr["body"]["output"]
+            .as_str()
+            .unwrap()
+            .contains("bob is 1")
+    );
+    let exited = messages.iter().find(|m| m["event"] == "exited").unwrap();
+    assert_eq!(exited["body"]["exitCode"], 1);
+    assert!(!request(&mut server, "disconnect", json!({})));
+}
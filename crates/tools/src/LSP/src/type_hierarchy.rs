@@ -0,0 +1,139 @@
+//! Type hierarchy for KCL schemas
+//! The supertypes of a schema are its parent schema, protocol and mixins, and the
+//! subtypes are the schemas which inherit it, mix it in or implement it as a protocol.
+
+use kcl_error::Position as KCLPos;
+use kcl_sema::core::global_state::GlobalState;
+use kcl_sema::core::symbol::SymbolKind as KCLSymbolKind;
+use lsp_types::request::Request;
+use lsp_types::{CallHierarchyIncomingCallsParams, CallHierarchyItem, CallHierarchyPrepareParams};
+
+use crate::call_hierarchy::{hierarchy_item, item_def};
+use crate::goto_def::find_def;
+
+/// The type hierarchy item has the same fields as the call hierarchy item.
+pub type TypeHierarchyItem = CallHierarchyItem;
+
+/// The type hierarchy prepare params have the same fields as the call hierarchy ones.
+pub type TypeHierarchyPrepareParams = CallHierarchyPrepareParams;
+
+/// The supertypes and subtypes params have the same fields as the call hierarchy calls params.
+pub type TypeHierarchyItemParams = CallHierarchyIncomingCallsParams;
+
+/// The `textDocument/prepareTypeHierarchy` request, which is not provided by lsp-types 0.93.
+#[derive(Debug)]
+pub enum TypeHierarchyPrepare {}
+
+impl Request for TypeHierarchyPrepare {
+    type Params = TypeHierarchyPrepareParams;
+    type Result = Option<Vec<TypeHierarchyItem>>;
+    const METHOD: &'static str = "textDocument/prepareTypeHierarchy";
+}
+
+/// The `typeHierarchy/supertypes` request.
+#[derive(Debug)]
+pub enum TypeHierarchySupertypes {}
+
+impl Request for TypeHierarchySupertypes {
+    type Params = TypeHierarchyItemParams;
+    type Result = Option<Vec<TypeHierarchyItem>>;
+    const METHOD: &'static str = "typeHierarchy/supertypes";
+}
+
+/// The `typeHierarchy/subtypes` request.
+#[derive(Debug)]
+pub enum TypeHierarchySubtypes {}
+
+impl Request for TypeHierarchySubtypes {
+    type Params = TypeHierarchyItemParams;
+    type Result = Option<Vec<TypeHierarchyItem>>;
+    const METHOD: &'static str = "typeHierarchy/subtypes";
+}
+
+/// Prepares the type hierarchy item of the schema at the position.
+pub fn prepare_type_hierarchy(
+    kcl_pos: &KCLPos,
+    gs: &GlobalState,
+) -> Option<Vec<TypeHierarchyItem>> {
+    let def = find_def(kcl_pos, gs, true)?;
+    if def.get_kind() != KCLSymbolKind::Schema {
+        return None;
+    }
+    Some(vec![hierarchy_item(gs, def)?])
+}
+
+/// Returns the parent schema, protocol and mixins of the schema item.
+pub fn supertypes(item: &TypeHierarchyItem, gs: &GlobalState) -> Option<Vec<TypeHierarchyItem>> {
+    let def = item_def(item, gs)?;
+    let schema = gs.get_symbols().get_schema_symbol(def)?;
+    Some(
+        schema
+            .get_supertypes(gs.get_symbols())
+            .into_iter()
+            .filter_map(|supertype| hierarchy_item(gs, supertype))
+            .collect(),
+    )
+}
+
+/// Returns the schemas whose parent schema, protocol or mixins is the schema item.
+pub fn subtypes(item: &TypeHierarchyItem, gs: &GlobalState) -> Option<Vec<TypeHierarchyItem>> {
+    let def = item_def(item, gs)?;
+    if def.get_kind() != KCLSymbolKind::Schema {
+        return None;
+    }
+    Some(
+        gs.get_symbols()
+            .get_schema_subtypes(def)
+            .into_iter()
+            .filter_map(|subtype| hierarchy_item(gs, subtype))
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use kcl_error::Position as KCLPos;
+    use lsp_types::SymbolKind;
+    use proc_macro_crate::bench_test;
+
+    use super::{TypeHierarchyItem, prepare_type_hierarchy, subtypes, supertypes};
+    use crate::tests::compile_test_file;
+
+    fn names(items: Option<Vec<TypeHierarchyItem>>) -> Vec<String> {
+        items.unwrap().into_iter().map(|item| item.name).collect()
+    }
+
+    #[test]
+    #[bench_test]
+    fn type_hierarchy_test() {
+        let (file, _, _, gs, _) = compile_test_file("src/test_data/hierarchy/hierarchy.k");
+        let pos = |line, column| KCLPos {
+            filename: file.clone(),
+            line,
+            column: Some(column),
+        };
+
+        // schema Service(Base):
+        let service = prepare_type_hierarchy(&pos(10, 7), &gs).unwrap().remove(0);
+        assert_eq!(service.name, "Service");
+        assert_eq!(names(supertypes(&service, &gs)), vec!["Base", "NameMixin"]);
+        assert!(names(subtypes(&service, &gs)).is_empty());
+
+        // The parent schema name in `schema Service(Base)`
+        let base = prepare_type_hierarchy(&pos(10, 15), &gs).unwrap().remove(0);
+        assert_eq!(base.name, "Base");
+        assert_eq!(names(subtypes(&base, &gs)), vec!["Service"]);
+
+        // mixin NameMixin for NameProtocol:
+        let mixin = prepare_type_hierarchy(&pos(4, 6), &gs).unwrap().remove(0);
+        assert_eq!(names(supertypes(&mixin, &gs)), vec!["NameProtocol"]);
+        assert_eq!(names(subtypes(&mixin, &gs)), vec!["Service"]);
+
+        let protocol = prepare_type_hierarchy(&pos(1, 9), &gs).unwrap().remove(0);
+        assert_eq!(protocol.kind, SymbolKind::INTERFACE);
+        assert_eq!(names(subtypes(&protocol, &gs)), vec!["NameMixin"]);
+
+        // Values are not types.
+        assert!(prepare_type_hierarchy(&pos(22, 0), &gs).is_none());
+    }
+}
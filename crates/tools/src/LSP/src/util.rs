@@ -565,6 +565,43 @@ fn inner_most_expr_in_config_entry(
     }
 }
 
+/// Returns the last line of the schema body. The schema statement ends at the following
+/// statement, so the end line is the last one of the inner nodes. The last attribute may
+/// also end at the first column of the following statement, which is not part of the body.
+pub(crate) fn schema_end_line(stmt: &Node<Stmt>, schema_stmt: &SchemaStmt) -> u64 {
+    let end_line = |line: u64, end_line: u64, end_column: u64| {
+        if end_column == 0 && end_line > line {
+            end_line - 1
+        } else {
+            end_line
+        }
+    };
+    schema_stmt
+        .body
+        .iter()
+        .map(|stmt| end_line(stmt.line, stmt.end_line, stmt.end_column))
+        .chain(
+            schema_stmt
+                .checks
+                .iter()
+                .map(|check| end_line(check.line, check.end_line, check.end_column)),
+        )
+        .chain(
+            schema_stmt
+                .index_signature
+                .iter()
+                .map(|sig| end_line(sig.line, sig.end_line, sig.end_column)),
+        )
+        .chain(
+            schema_stmt
+                .doc
+                .iter()
+                .map(|doc| end_line(doc.line, doc.end_line, doc.end_column)),
+        )
+        .max()
+        .unwrap_or(stmt.line)
+}
+
 pub(crate) fn is_in_docstring(
     program: &Program,
     pos: &KCLPos,
@@ -0,0 +1,231 @@
+use std::collections::HashSet;
+
+use kcl_sema::core::global_state::GlobalState;
+use kcl_sema::core::symbol::SymbolKind as KCLSymbolKind;
+use kcl_sema::namer::BUILTIN_SYMBOL_PKG_PATH;
+use lsp_types::SymbolInformation;
+
+use crate::document_symbol::symbol_kind_to_document_symbol_kind;
+use crate::to_lsp::lsp_location;
+
+/// The max number of the symbols returned by a workspace symbol request.
+pub(crate) const MAX_WORKSPACE_SYMBOLS: usize = 128;
+
+/// The index of the top-level schemas, rules, type aliases, functions and values in all
+/// the packages of a compiled workspace, including the packages not opened in the editor.
+/// It is built in the compile thread after the workspace is compiled.
+#[derive(Debug, Default, Clone)]
+pub struct WorkspaceSymbolIndex {
+    symbols: Vec<SymbolInformation>,
+}
+
+impl WorkspaceSymbolIndex {
+    pub fn new(gs: &GlobalState) -> Self {
+        let symbols_data = gs.get_symbols();
+        let mut symbols = vec![];
+        for symbol_ref in symbols_data.get_fully_qualified_name_map().values() {
+            if !matches!(
+                symbol_ref.get_kind(),
+                KCLSymbolKind::Schema
+                    | KCLSymbolKind::Rule
+                    | KCLSymbolKind::TypeAlias
+                    | KCLSymbolKind::Function
+                    | KCLSymbolKind::Value
+            ) {
+                continue;
+            }
+            // The name map may contain the symbols of the invalidated packages.
+            let Some(symbol) = symbols_data.get_symbol(*symbol_ref) else {
+                continue;
+            };
+            let Some(owner) = symbol.get_owner() else {
+                continue;
+            };
+            if !symbol.is_global() || owner.get_kind() != KCLSymbolKind::Package {
+                continue;
+            }
+            let Some(pkg) = symbols_data.get_symbol(owner).map(|pkg| pkg.get_name()) else {
+                continue;
+            };
+            let (start, end) = symbol.get_range();
+            // Builtin and system package symbols have no source file.
+            if pkg == BUILTIN_SYMBOL_PKG_PATH || start.filename.is_empty() {
+                continue;
+            }
+            let Some(kind) = symbol_kind_to_document_symbol_kind(symbol_ref.get_kind()) else {
+                continue;
+            };
+            let Some(location) = lsp_location(start.filename.clone(), &start, &end) else {
+                continue;
+            };
+            #[allow(deprecated)]
+            symbols.push(SymbolInformation {
+                name: symbol.get_name(),
+                kind,
+                tags: None,
+                deprecated: None,
+                location,
+                container_name: Some(pkg),
+            });
+        }
+        Self { symbols }
+    }
+}
+
+/// Search the symbols matching the query in the workspace symbol indexes. The symbols
+/// shared by several workspaces are returned once, and the best matches are returned first.
+pub(crate) fn workspace_symbol<'a>(
+    query: &str,
+    indexes: impl IntoIterator<Item = &'a WorkspaceSymbolIndex>,
+) -> Vec<SymbolInformation> {
+    let mut seen = HashSet::new();
+    let mut matches = vec![];
+    for index in indexes {
+        for symbol in &index.symbols {
+            if let Some(score) = fuzzy_match(query, &symbol.name)
+                && seen.insert((
+                    symbol.name.as_str(),
+                    symbol.location.uri.as_str(),
+                    symbol.location.range.start.line,
+                    symbol.location.range.start.character,
+                ))
+            {
+                matches.push((score, symbol));
+            }
+        }
+    }
+    matches.sort_by(|(a_score, a), (b_score, b)| {
+        b_score
+            .cmp(a_score)
+            .then_with(|| a.name.len().cmp(&b.name.len()))
+            .then_with(|| a.name.cmp(&b.name))
+            .then_with(|| a.location.uri.as_str().cmp(b.location.uri.as_str()))
+    });
+    matches
+        .into_iter()
+        .take(MAX_WORKSPACE_SYMBOLS)
+        .map(|(_, symbol)| symbol.clone())
+        .collect()
+}
+
+/// Returns the match score if all the query characters appear in the name in order, ignoring
+/// the case and the whitespaces. Matches at the start of words and consecutive matches score
+/// higher, e.g., the query `appcfg` matches `appConfig` better than `application_config`.
+pub(crate) fn fuzzy_match(query: &str, name: &str) -> Option<i64> {
+    let query: Vec<char> = query.chars().filter(|c| !c.is_whitespace()).collect();
+    if query.is_empty() {
+        return Some(0);
+    }
+    let name: Vec<char> = name.chars().collect();
+    let mut score = 0;
+    let mut next = 0;
+    let mut last_matched: Option<usize> = None;
+    for q in &query {
+        let i = (next..name.len()).find(|&i| eq_ignore_case(*q, name[i]))?;
+        score += 1;
+        if name[i] == *q {
+            score += 1;
+        }
+        if is_word_start(&name, i) {
+            score += 8;
+        }
+        if last_matched == Some(i.wrapping_sub(1)) {
+            score += 4;
+        }
+        last_matched = Some(i);
+        next = i + 1;
+    }
+    if query.len() == name.len() {
+        // The whole name matches.
+        score += 64;
+    } else if last_matched == Some(query.len() - 1) {
+        // The name starts with the query.
+        score += 32;
+    }
+    Some(score)
+}
+
+#[inline]
+fn eq_ignore_case(a: char, b: char) -> bool {
+    a == b || a.to_lowercase().eq(b.to_lowercase())
+}
+
+/// Whether the character at `i` starts a word in the name, e.g., `a` and `C` in `appConfig`
+/// and `c` in `app_config`.
+fn is_word_start(name: &[char], i: usize) -> bool {
+    match i.checked_sub(1).map(|prev| name[prev]) {
+        None => true,
+        Some(prev) => !prev.is_alphanumeric() || (prev.is_lowercase() && name[i].is_uppercase()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lsp_types::SymbolKind;
+    use proc_macro_crate::bench_test;
+
+    use super::{WorkspaceSymbolIndex, fuzzy_match, workspace_symbol};
+    use crate::tests::compile_test_file;
+
+    #[test]
+    #[bench_test]
+    fn fuzzy_match_test() {
+        assert!(fuzzy_match("appcfg", "appConfig").is_some());
+        assert!(fuzzy_match("Deploy", "Deployment").is_some());
+        assert!(fuzzy_match("dpl", "Deployment").is_some());
+        assert!(fuzzy_match("cfgapp", "appConfig").is_none());
+        assert!(fuzzy_match("appConfigs", "appConfig").is_none());
+        assert_eq!(fuzzy_match("", "appConfig"), Some(0));
+
+        assert!(fuzzy_match("appcfg", "appConfig") > fuzzy_match("appcfg", "application_config"));
+        assert!(fuzzy_match("Deployment", "Deployment") > fuzzy_match("Deployment", "Deployments"));
+        assert!(fuzzy_match("Deploy", "Deployment") > fuzzy_match("Deploy", "MyDeployment"));
+    }
+
+    #[test]
+    #[bench_test]
+    fn workspace_symbol_test() {
+        let (_, _, _, gs, _) =
+            compile_test_file("src/test_data/workspace_symbol/workspace_symbol.k");
+        let index = WorkspaceSymbolIndex::new(&gs);
+
+        // Symbols in the imported package.
+        let res = workspace_symbol("Deploy", [&index]);
+        assert_eq!(res[0].name, "Deployment");
+        assert_eq!(res[0].kind, SymbolKind::STRUCT);
+        assert_eq!(res[0].container_name.as_deref(), Some("apps"));
+        assert!(res[0].location.uri.path().ends_with("apps/deployment.k"));
+        assert_eq!(res[0].location.range.start.line, 0);
+
+        // Symbols in the main package.
+        let res = workspace_symbol("appcfg", [&index]);
+        assert_eq!(res[0].name, "appConfig");
+        assert_eq!(res[0].kind, SymbolKind::VARIABLE);
+
+        let names: Vec<String> = workspace_symbol("", [&index])
+            .into_iter()
+            .map(|symbol| symbol.name)
+            .collect();
+        for name in [
+            "Deployment",
+            "Replicas",
+            "is_prod",
+            "CheckReplicas",
+            "appConfig",
+        ] {
+            assert!(names.contains(&name.to_string()), "{name} not found");
+        }
+        // Schema attributes and local variables are not workspace symbols.
+        assert!(!names.contains(&"replicas".to_string()));
+        assert!(!names.contains(&"x".to_string()));
+
+        // The same symbols in several workspaces are returned once.
+        assert_eq!(
+            workspace_symbol("Deployment", [&index, &index])
+                .iter()
+                .filter(|symbol| symbol.name == "Deployment")
+                .count(),
+            1
+        );
+    }
+}
@@ -1,9 +1,14 @@
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
-use kcl_error::{Diagnostic, Handler};
+use anyhow::Result;
+use kcl_config::modfile::{KCL_MOD_FILE, LintRules, get_pkg_root, load_mod_file};
+use kcl_config::settings::{DEFAULT_SETTING_FILE, load_file};
+use kcl_error::{Diagnostic, Handler, sarif};
 use kcl_parser::{LoadProgramOptions, ParseSession, load_program};
 use kcl_primitives::IndexSet;
 use kcl_runtime::PanicInfo;
+use kcl_sema::lint::LintConfig;
 use kcl_sema::resolver::resolve_program_with_opts;
 #[cfg(test)]
 mod tests;
@@ -65,10 +70,30 @@ mod tests;
 ///     }
 /// ]
 /// ```
-#[allow(clippy::arc_with_non_send_sync)]
+///
+/// The lint levels in the `[lint]` section of the 'kcl.mod' file and the 'kcl.yaml' settings
+/// file of the package are used, and the lints can be suppressed using the comments, e.g.,
+/// `# kcl-lint: disable=W0411`. The lints configured as `deny` are returned as errors.
 pub fn lint_files(
     files: &[&str],
     opts: Option<LoadProgramOptions>,
+) -> (IndexSet<Diagnostic>, IndexSet<Diagnostic>) {
+    let settings_file = default_settings_file(files);
+    let settings_files: Vec<&str> = settings_file.iter().map(|file| file.as_str()).collect();
+    match load_lint_config(files, &settings_files) {
+        Ok(config) => lint_files_with_config(files, opts, config),
+        Err(err) => Handler::default()
+            .add_panic_info(&PanicInfo::from(err.to_string()))
+            .classification(),
+    }
+}
+
+/// KCL Lint tools API like [lint_files] using the lint levels in `config`.
+#[allow(clippy::arc_with_non_send_sync)]
+pub fn lint_files_with_config(
+    files: &[&str],
+    opts: Option<LoadProgramOptions>,
+    config: LintConfig,
 ) -> (IndexSet<Diagnostic>, IndexSet<Diagnostic>) {
     // Parse AST program.
     let sess = Arc::new(ParseSession::default());
@@ -87,6 +112,7 @@ pub fn lint_files(
             &mut program,
             kcl_sema::resolver::Options {
                 merge_program: false,
+                lint_config: config,
                 ..Default::default()
             },
             None,
@@ -96,3 +122,57 @@ pub fn lint_files(
     )
     .classification()
 }
+
+/// Load the lint levels in the `[lint]` section of the 'kcl.mod' file of the package which
+/// the first file belongs to, and the `kcl_lint_configs` of the settings files, the latter
+/// settings files override the former ones and the 'kcl.mod' file.
+pub fn load_lint_config(files: &[&str], settings_files: &[&str]) -> Result<LintConfig> {
+    let mut rules = LintRules::new();
+    if let Some(root) = files.first().and_then(|file| get_pkg_root(file))
+        && Path::new(&root).join(KCL_MOD_FILE).is_file()
+    {
+        let mod_file = load_mod_file(&root)?;
+        rules.extend(mod_file.lint.unwrap_or_default());
+    }
+    for settings_file in settings_files {
+        let settings = load_file(settings_file)?;
+        rules.extend(settings.kcl_lint_configs.unwrap_or_default());
+    }
+    LintConfig::from_rules(
+        rules
+            .iter()
+            .map(|(rule, level)| (rule.as_str(), level.as_str())),
+    )
+}
+
+/// Returns the default 'kcl.yaml' settings file in the package root of the first file, or in
+/// the directory of the first file when it does not belong to any package.
+fn default_settings_file(files: &[&str]) -> Option<String> {
+    let file = files.first()?;
+    let root = get_pkg_root(file).map(PathBuf::from).or_else(|| {
+        let path = Path::new(file);
+        if path.is_dir() {
+            Some(path.to_path_buf())
+        } else {
+            path.parent().map(|parent| parent.to_path_buf())
+        }
+    })?;
+    let settings_file = root.join(DEFAULT_SETTING_FILE);
+    settings_file
+        .is_file()
+        .then(|| settings_file.to_string_lossy().to_string())
+}
+
+/// KCL Lint tools API like [lint_files], which returns the error and warning diagnostics
+/// in a SARIF 2.1.0 log string that can be uploaded to the code scanning services.
+///
+/// # Examples
+///
+/// ```no_run
+/// use kcl_tools::lint::lint_files_to_sarif;
+/// let sarif = lint_files_to_sarif(&["test.k"], None).unwrap();
+/// ```
+pub fn lint_files_to_sarif(files: &[&str], opts: Option<LoadProgramOptions>) -> Result<String> {
+    let (errs, warnings) = lint_files(files, opts);
+    sarif::to_sarif_string(errs.iter().chain(warnings.iter()))
+}
@@ -0,0 +1,8 @@
+[package]
+name = "lint_config"
+edition = "v0.9.0"
+version = "0.0.1"
+
+[lint]
+W0411 = "deny"
+reimport = "allow"
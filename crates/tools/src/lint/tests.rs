@@ -1,4 +1,5 @@
-use super::lint_files;
+use super::{lint_files, lint_files_to_sarif, lint_files_with_config, load_lint_config};
+use kcl_sema::lint::LintConfig;
 use std::path::PathBuf;
 
 #[test]
@@ -68,3 +69,96 @@ fn test_unused_check_for_each_file() {
         path.to_str().unwrap().to_string()
     );
 }
+
+#[test]
+fn test_lint_to_sarif() {
+    let (errors, warnings) = lint_files(&["./src/lint/test_data/lint.k"], None);
+    let sarif = lint_files_to_sarif(&["./src/lint/test_data/lint.k"], None).unwrap();
+    let log: serde_json::Value = serde_json::from_str(&sarif).unwrap();
+    assert_eq!(log["version"], "2.1.0");
+    let run = &log["runs"][0];
+    let rules = run["tool"]["driver"]["rules"].as_array().unwrap();
+    let results = run["results"].as_array().unwrap();
+    assert_eq!(results.len(), errors.len() + warnings.len());
+    // Each result refers to the rule of its diagnostic code.
+    for result in results {
+        if let Some(index) = result["ruleIndex"].as_u64() {
+            assert_eq!(rules[index as usize]["id"], result["ruleId"]);
+        }
+    }
+    // The note of the message is appended to the text.
+    let warning = results
+        .iter()
+        .find(|result| {
+            result["message"]["text"]
+                == "Module 'abc' imported but unused\nnote: Consider removing this statement"
+        })
+        .unwrap();
+    assert_eq!(warning["level"], "warning");
+    assert_eq!(warning["ruleId"], "W1001");
+    let rule = &rules[warning["ruleIndex"].as_u64().unwrap() as usize];
+    assert_eq!(rule["name"], "CompilerWarning");
+    assert!(!rule["help"]["markdown"].as_str().unwrap().is_empty());
+    let location = &warning["locations"][0]["physicalLocation"];
+    assert!(
+        location["artifactLocation"]["uri"]
+            .as_str()
+            .unwrap()
+            .ends_with("src/lint/test_data/lint.k")
+    );
+    assert_eq!(location["region"]["startLine"], 3);
+}
+
+#[test]
+fn test_lint_with_config() {
+    let file = "./src/lint/test_data/lint_config/main.k";
+    // The unused imports are denied in the kcl.mod file, and the reimports are allowed.
+    let config = load_lint_config(&[file], &[]).unwrap();
+    let (errors, warnings) = lint_files_with_config(&[file], None, config);
+    assert_eq!(warnings.len(), 0);
+    assert_eq!(
+        errors
+            .iter()
+            .map(|e| e.messages[0].message.clone())
+            .collect::<Vec<String>>(),
+        vec![
+            "Module 'math' imported but unused",
+            "Module 'math' imported but unused"
+        ]
+    );
+    // The settings file overrides the kcl.mod file, even though the lint is configured by
+    // the code `W0411` in the kcl.mod file and by the name `unused-import` in the settings file.
+    let config = load_lint_config(&[file], &["./src/lint/test_data/lint_config/kcl.yaml"]).unwrap();
+    let (errors, warnings) = lint_files_with_config(&[file], None, config);
+    assert_eq!(errors.len(), 0);
+    assert_eq!(warnings.len(), 2);
+    // The kcl.yaml settings file in the package root is loaded by default.
+    let (errors, warnings) = lint_files(&[file], None);
+    assert_eq!(errors.len(), 0);
+    assert_eq!(warnings.len(), 2);
+}
+
+#[test]
+fn test_lint_config_later_rules_override() {
+    let config = LintConfig::from_rules([
+        ("reimport", "allow"),
+        ("unused-import", "warn"),
+        ("W0411", "deny"),
+    ])
+    .unwrap();
+    let (errors, warnings) =
+        lint_files_with_config(&["./src/lint/test_data/lint_config/main.k"], None, config);
+    assert_eq!(errors.len(), 2);
+    assert_eq!(warnings.len(), 0);
+}
+
+#[test]
+fn test_lint_config_unknown_rule() {
+    let err = LintConfig::from_rules([("unused-import", "warn"), ("unused-imports", "deny")])
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "unknown lint 'unused-imports', expected a lint code or name, e.g., W0411 or unused_import"
+    );
+    assert!(LintConfig::from_rules([("W9999", "allow")]).is_err());
+}
@@ -4,16 +4,23 @@
 //! that have the suffix "_test.k" and do not start with "_". These test files will be regard
 //! as test suites. Within these files, any lambda literals starting with "test_" will be
 //! considered as test cases, but these lambda functions should not have any parameters.
-//! To perform the testing, the tool compiles the test suite file and its dependencies into an
-//! [kcl_runner::Artifact], which is regard as a new compilation entry point. Then,
-//! it executes each test case separately and collects information about the test cases,
-//! such as the execution time and whether the test passes or fails.
-pub use crate::testing::suite::{TestSuite, load_test_suites};
+//! To perform the testing, the tool parses and resolves the test suite files and their
+//! dependencies only once. Then, it invokes each test case lambda on the resolved program
+//! in parallel threads and collects information about the test cases, such as the execution
+//! time and whether the test passes or fails.
+pub use crate::testing::report::{ReportFormat, render_report, write_report};
+pub use crate::testing::suite::{
+    DEFAULT_TEST_CASE_TIMEOUT, TEST_FILE_SUFFIX, TestCase, TestSuite, load_test_cases,
+    load_test_suites,
+};
 use anyhow::{Error, Result};
 use kcl_primitives::IndexMap;
 use kcl_runner::ExecProgramArgs;
+use kcl_runtime::PanicInfo;
+use serde::Serialize;
 use std::time::Duration;
 
+mod report;
 mod suite;
 
 #[cfg(test)]
@@ -44,6 +51,43 @@ pub struct TestCaseInfo {
     pub error: Option<Error>,
     /// This field stores the duration of the test case.
     pub duration: Duration,
+    /// This field stores the filename of the test case lambda.
+    pub filename: String,
+    /// This field stores the line of the test case lambda.
+    pub line: u64,
+    /// This field stores the column of the test case lambda.
+    pub column: u64,
+    /// This field stores the structured failure of the test case, if any.
+    pub failure: Option<TestFailure>,
+}
+
+/// Represents the structured failure of a test case, which is taken from the runtime [PanicInfo].
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct TestFailure {
+    /// This field stores the failure message, e.g., the assertion message.
+    pub message: String,
+    /// This field stores the filename where the failure occurs.
+    pub filename: String,
+    /// This field stores the line where the failure occurs.
+    pub line: u64,
+    /// This field stores the column where the failure occurs.
+    pub column: u64,
+}
+
+impl From<&PanicInfo> for TestFailure {
+    fn from(panic_info: &PanicInfo) -> Self {
+        let message = if panic_info.kcl_arg_msg.is_empty() {
+            &panic_info.message
+        } else {
+            &panic_info.kcl_arg_msg
+        };
+        Self {
+            message: message.clone(),
+            filename: panic_info.kcl_file.clone(),
+            line: panic_info.kcl_line.max(0) as u64,
+            column: panic_info.kcl_col.max(0) as u64,
+        }
+    }
 }
 
 /// Represents options for running tests.
@@ -55,4 +99,26 @@ pub struct TestOptions {
     pub run_regexp: String,
     /// This field determines whether the test run should stop on the first failure.
     pub fail_fast: bool,
+    /// This field stores the number of test cases that run at the same time,
+    /// 0 denotes using the available parallelism of the machine.
+    pub parallel: usize,
+    /// This field stores the timeout of each test case, [DEFAULT_TEST_CASE_TIMEOUT] is used when it is [None].
+    pub timeout: Option<Duration>,
+    /// This field stores the format of the test report, no report is written when it is [None].
+    pub report_format: Option<ReportFormat>,
+    /// This field stores the output file path of the test report.
+    pub report_output: Option<String>,
+}
+
+impl TestOptions {
+    /// Get the thread count used to run the test cases.
+    pub fn thread_count(&self) -> usize {
+        if self.parallel > 0 {
+            self.parallel
+        } else {
+            std::thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(1)
+        }
+    }
 }
@@ -0,0 +1,247 @@
+use std::{fmt::Write, str::FromStr};
+
+use crate::testing::{TestCaseInfo, TestFailure, TestOptions, TestResult};
+use anyhow::{Result, bail};
+use serde::Serialize;
+
+/// Represents the machine-readable test report formats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    /// JSON report which contains all the test case information.
+    Json,
+    /// JUnit XML report which can be consumed by the CI systems, e.g., GitLab and Jenkins.
+    JUnit,
+    /// Test Anything Protocol (TAP) version 13 report.
+    Tap,
+}
+
+impl FromStr for ReportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "json" => Ok(ReportFormat::Json),
+            "junit" | "xml" => Ok(ReportFormat::JUnit),
+            "tap" => Ok(ReportFormat::Tap),
+            _ => bail!(
+                "unknown test report format '{}', expected one of json, junit and tap",
+                s
+            ),
+        }
+    }
+}
+
+/// Render the test results of the test suites into the report. Each result is paired
+/// with the package path of its test suite.
+pub fn render_report(format: ReportFormat, results: &[(&str, &TestResult)]) -> Result<String> {
+    match format {
+        ReportFormat::Json => render_json(results),
+        ReportFormat::JUnit => render_junit(results),
+        ReportFormat::Tap => render_tap(results),
+    }
+}
+
+/// Render the test report using the report options in [TestOptions] and write it into
+/// the report output file if set. The rendered report is returned, and [None] is
+/// returned when the report format is not set.
+pub fn write_report(opts: &TestOptions, results: &[(&str, &TestResult)]) -> Result<Option<String>> {
+    let format = match opts.report_format {
+        Some(format) => format,
+        None => return Ok(None),
+    };
+    let report = render_report(format, results)?;
+    if let Some(output) = &opts.report_output
+        && !output.is_empty()
+    {
+        std::fs::write(output, &report)?;
+    }
+    Ok(Some(report))
+}
+
+#[derive(Serialize)]
+struct JsonReport<'a> {
+    suites: Vec<JsonSuite<'a>>,
+}
+
+#[derive(Serialize)]
+struct JsonSuite<'a> {
+    pkg: &'a str,
+    cases: Vec<JsonCase<'a>>,
+}
+
+#[derive(Serialize)]
+struct JsonCase<'a> {
+    name: &'a str,
+    filename: &'a str,
+    line: u64,
+    column: u64,
+    passed: bool,
+    /// Duration of the test case in microseconds.
+    duration: u64,
+    log_message: &'a str,
+    failure: Option<TestFailure>,
+}
+
+fn render_json(results: &[(&str, &TestResult)]) -> Result<String> {
+    let report = JsonReport {
+        suites: results
+            .iter()
+            .map(|(pkg, result)| JsonSuite {
+                pkg,
+                cases: result
+                    .info
+                    .iter()
+                    .map(|(name, info)| JsonCase {
+                        name,
+                        filename: &info.filename,
+                        line: info.line,
+                        column: info.column,
+                        passed: info.error.is_none(),
+                        duration: info.duration.as_micros() as u64,
+                        log_message: &info.log_message,
+                        failure: failure(info),
+                    })
+                    .collect(),
+            })
+            .collect(),
+    };
+    Ok(serde_json::to_string_pretty(&report)?)
+}
+
+fn render_junit(results: &[(&str, &TestResult)]) -> Result<String> {
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let (tests, failures, time) = results.iter().fold((0, 0, 0.0), |acc, (_, result)| {
+        let (tests, failures, time) = summary(result);
+        (acc.0 + tests, acc.1 + failures, acc.2 + time)
+    });
+    writeln!(
+        out,
+        "<testsuites name=\"kcl test\" tests=\"{tests}\" failures=\"{failures}\" time=\"{time:.6}\">"
+    )?;
+    for (pkg, result) in results {
+        let (tests, failures, time) = summary(result);
+        writeln!(
+            out,
+            "  <testsuite name=\"{}\" tests=\"{tests}\" failures=\"{failures}\" time=\"{time:.6}\">",
+            xml_escape(pkg)
+        )?;
+        for (name, info) in &result.info {
+            write!(
+                out,
+                "    <testcase name=\"{}\" classname=\"{}\" file=\"{}\" line=\"{}\" time=\"{:.6}\"",
+                xml_escape(name),
+                xml_escape(pkg),
+                xml_escape(&info.filename),
+                info.line,
+                info.duration.as_secs_f64()
+            )?;
+            let failure = failure(info);
+            if failure.is_none() && info.log_message.is_empty() {
+                out.push_str("/>\n");
+                continue;
+            }
+            out.push_str(">\n");
+            if let Some(failure) = failure {
+                writeln!(
+                    out,
+                    "      <failure message=\"{}\">{}:{}:{}: {}</failure>",
+                    xml_escape(&failure.message),
+                    xml_escape(&failure.filename),
+                    failure.line,
+                    failure.column,
+                    xml_escape(&failure.message)
+                )?;
+            }
+            if !info.log_message.is_empty() {
+                writeln!(
+                    out,
+                    "      <system-out>{}</system-out>",
+                    xml_escape(&info.log_message)
+                )?;
+            }
+            out.push_str("    </testcase>\n");
+        }
+        out.push_str("  </testsuite>\n");
+    }
+    out.push_str("</testsuites>\n");
+    Ok(out)
+}
+
+fn render_tap(results: &[(&str, &TestResult)]) -> Result<String> {
+    let mut out = String::from("TAP version 13\n");
+    let total: usize = results.iter().map(|(_, result)| result.info.len()).sum();
+    writeln!(out, "1..{total}")?;
+    let mut index = 0;
+    for (pkg, result) in results {
+        for (name, info) in &result.info {
+            index += 1;
+            match failure(info) {
+                None => writeln!(out, "ok {index} - {pkg} {name}")?,
+                Some(failure) => {
+                    writeln!(out, "not ok {index} - {pkg} {name}")?;
+                    // The YAML diagnostic block, strings are quoted using JSON which is valid YAML.
+                    out.push_str("  ---\n");
+                    writeln!(
+                        out,
+                        "  message: {}",
+                        serde_json::to_string(&failure.message)?
+                    )?;
+                    out.push_str("  at:\n");
+                    writeln!(
+                        out,
+                        "    file: {}",
+                        serde_json::to_string(&failure.filename)?
+                    )?;
+                    writeln!(out, "    line: {}", failure.line)?;
+                    writeln!(out, "    column: {}", failure.column)?;
+                    writeln!(out, "  duration_ms: {}", info.duration.as_millis())?;
+                    out.push_str("  ...\n");
+                }
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// Get the failure of the test case, the error message is used when the structured
+/// failure is not available.
+fn failure(info: &TestCaseInfo) -> Option<TestFailure> {
+    match (&info.failure, &info.error) {
+        (Some(failure), _) => Some(failure.clone()),
+        (None, Some(err)) => Some(TestFailure {
+            message: err.to_string(),
+            filename: info.filename.clone(),
+            line: info.line,
+            column: info.column,
+        }),
+        (None, None) => None,
+    }
+}
+
+/// Return the test count, failure count and total seconds of the test result.
+fn summary(result: &TestResult) -> (usize, usize, f64) {
+    result.info.values().fold((0, 0, 0.0), |acc, info| {
+        (
+            acc.0 + 1,
+            acc.1 + info.error.is_some() as usize,
+            acc.2 + info.duration.as_secs_f64(),
+        )
+    })
+}
+
+fn xml_escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            // Control characters are not allowed in XML 1.0 documents.
+            c if c.is_control() && !matches!(c, '\n' | '\r' | '\t') => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -1,29 +1,35 @@
-use std::{fs::remove_file, path::Path};
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    path::Path,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+        mpsc::Sender,
+    },
+    time::{Duration, Instant},
+};
 
-use crate::testing::{TestCaseInfo, TestOptions, TestResult, TestRun};
+use crate::testing::{TestCaseInfo, TestFailure, TestOptions, TestResult, TestRun};
 use anyhow::{Result, anyhow};
+use compiler_base_parallel::{
+    executor::{Executor, timeout::TimeoutExecutor},
+    task::{FinishedTask, Task, TaskInfo, TaskStatus, event::TaskEventType},
+};
 use kcl_ast::ast;
 use kcl_driver::get_pkg_list;
 use kcl_parser::get_kcl_files;
 use kcl_parser::{ParseSessionRef, parse_file_force_errors};
 use kcl_primitives::{DefaultHashBuilder, IndexMap};
-use kcl_runner::ExecProgramArgs;
-use kcl_runner::exec_program;
-use std::time::Instant;
+use kcl_runner::runner::{FastRunner, RunnerOptions};
+use kcl_runner::{ExecProgramArgs, compile_program};
 
 /// File suffix for test files.
 pub const TEST_FILE_SUFFIX: &str = "_test.k";
 /// Prefix for test suite names.
 pub const TEST_SUITE_PREFIX: &str = "test_";
-
-const TEST_MAIN_FILE: &str = "_kcl_test.k";
-const TEST_CASE_RUN_OPTION: &str = "_kcl_test_case_run";
-const TEST_MAIN_FILE_PREFIX: &str = r#"
-# Auto generated by the kcl test tool; DO NOT EDIT!
-
-_kcl_test_case_run = option("_kcl_test_case_run", type="str", default="")
-
-"#;
+/// Default timeout for each test case.
+pub const DEFAULT_TEST_CASE_TIMEOUT: Duration = Duration::from_secs(60);
 
 pub struct TestSuite {
     /// Package path of the test suite. e.g. ./path/to/pkg
@@ -49,85 +55,206 @@ impl TestRun for TestSuite {
         if self.skip || self.cases.is_empty() {
             return Ok(result);
         }
-        // Generate the test main entry file.
-        let main_file = self.gen_test_main_file()?;
         // Set up execution arguments.
-        let mut args = ExecProgramArgs {
-            k_filename_list: self.get_input_files(&main_file),
-            overrides: vec![],
+        let args = Arc::new(ExecProgramArgs {
+            k_filename_list: self.get_input_files(),
             disable_yaml_result: true,
             ..opts.exec_args.clone()
-        };
-        // Save the user argument options.
-        let user_args = args.args;
-        // Test every case in the suite.
-        for (name, _) in &self.cases {
-            args.args = vec![ast::Argument {
-                name: TEST_CASE_RUN_OPTION.into(),
-                value: format!("{:?}", name),
-            }];
-            args.args.append(&mut user_args.clone());
-            let start = Instant::now();
-            // Check if is the fast eval mode.
-            let exec_result = exec_program(ParseSessionRef::default(), &args)?;
-            // Check if there was an error.
-            let error = if exec_result.err_message.is_empty() {
-                None
-            } else {
-                Some(anyhow!("{}", exec_result.err_message))
-            };
-            // Check if the fail_fast option is enabled and there was an error.
-            let fail_fast = error.is_some() && opts.fail_fast;
-            // Add test case information to the result.
-            result.info.insert(
-                name.clone(),
-                TestCaseInfo {
-                    log_message: exec_result.log_message.clone(),
-                    duration: Instant::now() - start,
-                    error,
-                },
-            );
-            if fail_fast {
-                break;
+        });
+        // Parse and resolve the test suite only once, all the test cases share the program.
+        let program = Arc::new(compile_program(ParseSessionRef::default(), &args)?);
+        let infos = Arc::new(Mutex::new(HashMap::new()));
+        let stopped = Arc::new(AtomicBool::new(false));
+        let tasks = self
+            .cases
+            .iter()
+            .enumerate()
+            .map(|(id, (name, case))| TestCaseTask {
+                id,
+                name: name.clone(),
+                case: case.clone(),
+                program: program.clone(),
+                args: args.clone(),
+                infos: infos.clone(),
+                stopped: stopped.clone(),
+                cancel_flag: Arc::new(AtomicBool::new(false)),
+            })
+            .collect::<Vec<TestCaseTask>>();
+        let cancel_flags: HashMap<_, _> = tasks
+            .iter()
+            .map(|task| (task.info().tid(), task.cancel_flag.clone()))
+            .collect();
+        let timeout = opts.timeout.unwrap_or(DEFAULT_TEST_CASE_TIMEOUT);
+        let executor =
+            TimeoutExecutor::new_with_thread_count_and_task_timeout(opts.thread_count(), timeout);
+        let timed_out = RefCell::new(HashSet::new());
+        executor.run_all_tasks(&tasks, |event| {
+            match event.ty() {
+                TaskEventType::Timeout(_) => {
+                    let tid = event.tinfo().tid();
+                    // Interrupt the timed out test case, otherwise the executor waits for
+                    // it forever, e.g., a test case that never terminates.
+                    if let Some(cancel_flag) = cancel_flags.get(&tid) {
+                        cancel_flag.store(true, Ordering::SeqCst);
+                    }
+                    timed_out.borrow_mut().insert(tid);
+                    if opts.fail_fast {
+                        stopped.store(true, Ordering::SeqCst);
+                    }
+                }
+                TaskEventType::Finished(task)
+                    if opts.fail_fast && matches!(task.status(), TaskStatus::Failed(_)) =>
+                {
+                    stopped.store(true, Ordering::SeqCst);
+                }
+                _ => {}
+            }
+            Ok(())
+        })?;
+        // Collect test case information in the order of the test cases, the cases
+        // skipped by the fail_fast option are not included.
+        let mut infos = infos.lock().map_err(|e| anyhow!("{e}"))?;
+        let timed_out = timed_out.borrow();
+        for task in &tasks {
+            if let Some(mut info) = infos.remove(&task.name) {
+                if timed_out.contains(&task.info().tid()) {
+                    let message = format!("test case {} timed out after {:?}", task.name, timeout);
+                    info.failure = Some(TestFailure {
+                        message: message.clone(),
+                        filename: task.case.filename.clone(),
+                        line: task.case.line,
+                        column: task.case.column,
+                    });
+                    info.error = Some(anyhow!(message));
+                }
+                result.info.insert(task.name.clone(), info);
             }
-        }
-        // Remove the temp test main file
-        if opts.exec_args.debug == 0 {
-            remove_file(main_file)?;
         }
         Ok(result)
     }
 }
 
 impl TestSuite {
-    fn gen_test_main_file(&self) -> Result<String> {
-        let test_codes = self
-            .cases
-            .keys()
-            .map(|c| format!("if {} == '{}': {}()", TEST_CASE_RUN_OPTION, c, c))
-            .collect::<Vec<String>>();
-        let code = format!("{}{}", TEST_MAIN_FILE_PREFIX, test_codes.join("\n"));
-        let path = Path::new(&self.pkg).join(TEST_MAIN_FILE);
-        let test_main_file = path
-            .to_str()
-            .ok_or(anyhow!("{} is not found", TEST_MAIN_FILE))?;
-        std::fs::write(test_main_file, code)?;
-        Ok(test_main_file.into())
-    }
-
-    fn get_input_files(&self, main_file: &str) -> Vec<String> {
+    fn get_input_files(&self) -> Vec<String> {
         // Construct test package files.
         let mut files = vec![];
         let mut normal_files = self.normal_files.clone();
         let mut test_files = self.test_files.clone();
         files.append(&mut normal_files);
         files.append(&mut test_files);
-        files.push(main_file.into());
         files
     }
 }
 
-pub struct TestCase;
+/// [`TestCaseTask`] runs a test case lambda on the resolved program shared by
+/// all the test cases in the test suite.
+///
+/// Each test case evaluates the main package in its own evaluator before invoking the
+/// lambda, because the evaluator holds the single threaded runtime context and can't be
+/// shared by the test cases running in parallel threads. It also keeps the test cases
+/// isolated, e.g., the runtime context of a failed test case is not reused by the other
+/// ones.
+#[derive(Clone)]
+struct TestCaseTask {
+    id: usize,
+    name: String,
+    case: TestCase,
+    program: Arc<ast::Program>,
+    args: Arc<ExecProgramArgs>,
+    infos: Arc<Mutex<HashMap<String, TestCaseInfo>>>,
+    stopped: Arc<AtomicBool>,
+    /// The flag to interrupt the test case when it times out.
+    cancel_flag: Arc<AtomicBool>,
+}
+
+impl Task for TestCaseTask {
+    fn run(&self, ch: Sender<FinishedTask>) {
+        // The test case is skipped when a previous test case failed with the fail_fast option.
+        if self.stopped.load(Ordering::SeqCst) {
+            let _ = ch.send(FinishedTask::new(
+                self.info(),
+                vec![],
+                vec![],
+                TaskStatus::Waiting,
+            ));
+            return;
+        }
+        let start = Instant::now();
+        let args = ExecProgramArgs {
+            cancel_flag: Some(self.cancel_flag.clone()),
+            ..self.args.as_ref().clone()
+        };
+        let exec_result = FastRunner::new(Some(RunnerOptions {
+            plugin_agent_ptr: self.args.plugin_agent,
+        }))
+        .run_function(&self.program, &args, &self.name);
+        let duration = Instant::now() - start;
+        let (log_message, error, failure) = match exec_result {
+            Ok((exec_result, _)) if exec_result.err_message.is_empty() => {
+                (exec_result.log_message, None, None)
+            }
+            Ok((exec_result, panic_info)) => (
+                exec_result.log_message,
+                Some(exec_result.err_message.clone()),
+                panic_info.as_ref().map(TestFailure::from),
+            ),
+            Err(err) => (String::new(), Some(err.to_string()), None),
+        };
+        let failure = error.as_ref().map(|err| {
+            // Fall back to the test case position when the runtime error has no position.
+            let mut failure = failure.unwrap_or_else(|| TestFailure {
+                message: err.clone(),
+                ..Default::default()
+            });
+            if failure.filename.is_empty() {
+                failure.filename = self.case.filename.clone();
+                failure.line = self.case.line;
+                failure.column = self.case.column;
+            }
+            failure
+        });
+        let status = match &error {
+            Some(err) => TaskStatus::Failed(err.clone()),
+            None => TaskStatus::Finished,
+        };
+        if let Ok(mut infos) = self.infos.lock() {
+            infos.insert(
+                self.name.clone(),
+                TestCaseInfo {
+                    log_message: log_message.clone(),
+                    error: error.clone().map(|e| anyhow!("{}", e)),
+                    duration,
+                    filename: self.case.filename.clone(),
+                    line: self.case.line,
+                    column: self.case.column,
+                    failure,
+                },
+            );
+        }
+        // Always notify the executor, otherwise the executor will wait forever.
+        let _ = ch.send(FinishedTask::new(
+            self.info(),
+            log_message.into_bytes(),
+            error.unwrap_or_default().into_bytes(),
+            status,
+        ));
+    }
+
+    fn info(&self) -> TaskInfo {
+        TaskInfo::new(self.id.into(), self.name.clone().into())
+    }
+}
+
+/// Represents a test case lambda in the test suite.
+#[derive(Debug, Default, Clone)]
+pub struct TestCase {
+    /// The filename of the test case lambda.
+    pub filename: String,
+    /// The line of the test case lambda.
+    pub line: u64,
+    /// The column of the test case lambda.
+    pub column: u64,
+}
 
 /// Load test suite from path
 pub fn load_test_suites<P: AsRef<str>>(path: P, opts: &TestOptions) -> Result<Vec<TestSuite>> {
@@ -138,18 +265,7 @@ pub fn load_test_suites<P: AsRef<str>>(path: P, opts: &TestOptions) -> Result<Ve
         let mut cases = IndexMap::with_hasher(DefaultHashBuilder::default());
         for file in &test_files {
             let module = parse_file_force_errors(file, None)?;
-            for stmt in &module.body {
-                if let ast::Stmt::Assign(assign_stmt) = &stmt.node
-                    && let ast::Expr::Lambda(_lambda_expr) = &assign_stmt.value.node
-                {
-                    for target in &assign_stmt.targets {
-                        let func_name = target.node.get_name();
-                        if is_test_suite(func_name) && should_run(&opts.run_regexp, func_name) {
-                            cases.insert(func_name.to_string(), TestCase {});
-                        }
-                    }
-                }
-            }
+            cases.extend(load_test_cases(&module, &opts.run_regexp));
         }
         suites.push(TestSuite {
             pkg: pkg.clone(),
@@ -162,6 +278,31 @@ pub fn load_test_suites<P: AsRef<str>>(path: P, opts: &TestOptions) -> Result<Ve
     Ok(suites)
 }
 
+/// Load the test case lambdas in the test file module whose names match the `run_regexp`.
+pub fn load_test_cases(module: &ast::Module, run_regexp: &str) -> IndexMap<String, TestCase> {
+    let mut cases = IndexMap::with_hasher(DefaultHashBuilder::default());
+    for stmt in &module.body {
+        if let ast::Stmt::Assign(assign_stmt) = &stmt.node
+            && let ast::Expr::Lambda(_lambda_expr) = &assign_stmt.value.node
+        {
+            for target in &assign_stmt.targets {
+                let func_name = target.node.get_name();
+                if is_test_suite(func_name) && should_run(run_regexp, func_name) {
+                    cases.insert(
+                        func_name.to_string(),
+                        TestCase {
+                            filename: assign_stmt.value.filename.clone(),
+                            line: assign_stmt.value.line,
+                            column: assign_stmt.value.column,
+                        },
+                    );
+                }
+            }
+        }
+    }
+    cases
+}
+
 #[inline]
 fn get_test_files<P: AsRef<Path>>(pkg: P) -> Result<(Vec<String>, Vec<String>)> {
     let files = get_kcl_files(pkg, false)?;
@@ -3,8 +3,9 @@ use kcl_runner::ExecProgramArgs;
 
 use crate::testing::TestRun;
 
-use super::{TestOptions, load_test_suites};
+use super::{ReportFormat, TestOptions, load_test_suites, render_report};
 use std::path::Path;
+use std::time::Duration;
 
 #[test]
 fn test_load_test_suites_and_run() {
@@ -49,3 +50,96 @@ fn test_load_test_suites_and_run() {
         test_result.info[2].error
     );
 }
+
+#[test]
+fn test_run_test_suites_with_fail_fast() {
+    let opts = TestOptions {
+        fail_fast: true,
+        parallel: 1,
+        ..Default::default()
+    };
+    let suites = load_test_suites(
+        Path::new(".")
+            .join("src")
+            .join("testing")
+            .join("test_data")
+            .join("module")
+            .join("pkg")
+            .to_str()
+            .unwrap(),
+        &opts,
+    )
+    .unwrap();
+    let test_result = suites[0].run(&opts).unwrap();
+    // The last test case is skipped after the failed case.
+    assert_eq!(test_result.info.len(), 2);
+    assert!(test_result.info[0].error.is_none());
+    assert!(test_result.info[1].error.is_some());
+}
+
+#[test]
+fn test_render_test_reports() {
+    let opts = TestOptions {
+        parallel: 1,
+        ..Default::default()
+    };
+    let pkg = Path::new(".")
+        .join("src")
+        .join("testing")
+        .join("test_data")
+        .join("module")
+        .join("pkg");
+    let pkg = pkg.to_str().unwrap();
+    let suites = load_test_suites(pkg, &opts).unwrap();
+    let test_result = suites[0].run(&opts).unwrap();
+    // Test case positions and structured failures.
+    let info = &test_result.info["test_func_1"];
+    assert!(info.filename.ends_with("func_test.k"));
+    assert_eq!(info.line, 5);
+    let failure = info.failure.as_ref().unwrap();
+    assert!(failure.filename.ends_with("func_test.k"));
+    assert_eq!(failure.line, 6);
+    assert!(test_result.info["test_func_0"].failure.is_none());
+
+    let results = [(pkg, &test_result)];
+    let junit = render_report(ReportFormat::JUnit, &results).unwrap();
+    assert!(junit.contains("<testsuites name=\"kcl test\" tests=\"3\" failures=\"2\""));
+    assert!(junit.contains("<testcase name=\"test_func_1\""));
+    assert_eq!(junit.matches("<failure ").count(), 2);
+
+    let tap = render_report(ReportFormat::Tap, &results).unwrap();
+    assert!(tap.starts_with("TAP version 13\n1..3\n"));
+    assert!(tap.contains("ok 1 - "));
+    assert!(tap.contains("not ok 2 - "));
+
+    let json = render_report(ReportFormat::Json, &results).unwrap();
+    let json: serde_json::Value = serde_json::from_str(&json).unwrap();
+    let cases = &json["suites"][0]["cases"];
+    assert_eq!(cases[0]["passed"], true);
+    assert_eq!(cases[1]["passed"], false);
+    assert_eq!(cases[1]["failure"]["line"], 6);
+}
+
+#[test]
+fn test_run_test_suites_with_timeout() {
+    let opts = TestOptions {
+        timeout: Some(Duration::from_millis(100)),
+        ..Default::default()
+    };
+    let suites = load_test_suites(
+        Path::new(".")
+            .join("src")
+            .join("testing")
+            .join("test_data")
+            .join("timeout")
+            .to_str()
+            .unwrap(),
+        &opts,
+    )
+    .unwrap();
+    // The test case never terminates and it is interrupted when it times out.
+    let test_result = suites[0].run(&opts).unwrap();
+    assert_eq!(test_result.info.len(), 1);
+    let error = test_result.info[0].error.as_ref().unwrap().to_string();
+    assert!(error.contains("timed out"), "{error}");
+}
@@ -10,12 +10,15 @@ pub(crate) trait Loader<T> {
     fn load(&self) -> Result<T>;
 }
 
-/// Types of verifiable files currently supported by KCL-Vet,
-/// currently only YAML files and Json files are supported.
+/// Types of verifiable files currently supported by KCL-Vet, including YAML
+/// streams, Json files, TOML files and JSON Lines files.
 #[derive(Clone, Copy)]
 pub enum LoaderKind {
     YAML,
     JSON,
+    TOML,
+    /// JSON Lines, each non-empty line is a Json document.
+    JSONL,
 }
 
 /// DataLoader for Json or Yaml
@@ -121,9 +124,49 @@ impl Loader<spanned::Value> for DataLoader {
     }
 }
 
+/// Load the JSON Lines documents into Json values with span. Each document is paired with
+/// the byte offset of its line, because the span of the value is relative to the line.
+impl Loader<Vec<(usize, spanned::Value)>> for DataLoader {
+    fn load(&self) -> Result<Vec<(usize, spanned::Value)>> {
+        match self.kind {
+            LoaderKind::JSONL => {
+                let mut docs = vec![];
+                let mut offset = 0;
+                for (i, line) in self.get_data().split_inclusive('\n').enumerate() {
+                    if !line.trim().is_empty() {
+                        let v = jsv::from_str(line).with_context(|| {
+                            format!(
+                                "Failed to String '{}' to Json at line {}",
+                                line.trim_end(),
+                                i + 1
+                            )
+                        })?;
+                        docs.push((offset, v));
+                    }
+                    offset += line.len();
+                }
+                Ok(docs)
+            }
+            _ => {
+                bail!("Failed to String to Json Lines Value")
+            }
+        }
+    }
+}
+
 /// Load data into Json value with span.
 impl Loader<located_yaml::Yaml> for DataLoader {
     fn load(&self) -> Result<located_yaml::Yaml> {
+        <DataLoader as Loader<Vec<located_yaml::Yaml>>>::load(self)?
+            .into_iter()
+            .next()
+            .map_or_else(|| bail!("Failed to Load YAML"), Ok)
+    }
+}
+
+/// Load all the documents in the YAML stream with span.
+impl Loader<Vec<located_yaml::Yaml>> for DataLoader {
+    fn load(&self) -> Result<Vec<located_yaml::Yaml>> {
         let v = match self.kind {
             LoaderKind::YAML => YamlLoader::load_from_str(self.get_data())
                 .with_context(|| format!("Failed to String '{}' to Yaml", self.get_data()))?,
@@ -132,9 +175,22 @@ impl Loader<located_yaml::Yaml> for DataLoader {
             }
         };
 
-        v.docs
-            .first()
-            .map_or_else(|| bail!("Failed to Load YAML"), |res| Ok(res.clone()))
+        Ok(v.docs)
+    }
+}
+
+impl Loader<toml::Value> for DataLoader {
+    /// Load data into TOML value.
+    fn load(&self) -> Result<toml::Value> {
+        let v = match self.kind {
+            LoaderKind::TOML => toml::from_str(self.get_data())
+                .with_context(|| format!("Failed to String '{}' to TOML", self.get_data()))?,
+            _ => {
+                bail!("Failed to String to TOML Value")
+            }
+        };
+
+        Ok(v)
     }
 }
 
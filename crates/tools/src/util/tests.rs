@@ -167,6 +167,40 @@ websites:
             assert_eq!(got_json, expect_json);
         }
 
+        #[test]
+        fn test_load_documents() {
+            let yaml_loader = data_loader_from_str(LoaderKind::YAML, "a: 1\n---\nb: 2\n");
+            let docs = <DataLoader as Loader<Vec<located_yaml::Yaml>>>::load(&yaml_loader).unwrap();
+            assert_eq!(docs.len(), 2);
+
+            let jsonl_loader =
+                data_loader_from_str(LoaderKind::JSONL, "{\"a\": 1}\n\n{\"b\": 2}\n");
+            let docs = <DataLoader as Loader<
+                Vec<(
+                    usize,
+                    json_spanned_value::Spanned<json_spanned_value::Value>,
+                )>,
+            >>::load(&jsonl_loader)
+            .unwrap();
+            let offsets: Vec<usize> = docs.iter().map(|(offset, _)| *offset).collect();
+            assert_eq!(offsets, vec![0, 10]);
+
+            let jsonl_loader = data_loader_from_str(LoaderKind::JSONL, "{\"a\": 1}\n{\"b\": \n");
+            let err = <DataLoader as Loader<
+                Vec<(
+                    usize,
+                    json_spanned_value::Spanned<json_spanned_value::Value>,
+                )>,
+            >>::load(&jsonl_loader)
+            .unwrap_err();
+            assert!(err.to_string().ends_with("at line 2"), "{err}");
+
+            let toml_loader =
+                data_loader_from_str(LoaderKind::TOML, "name = \"Alice\"\n[owner]\nage = 18\n");
+            let got_toml = <DataLoader as Loader<toml::Value>>::load(&toml_loader).unwrap();
+            assert_eq!(got_toml["owner"]["age"].as_integer(), Some(18));
+        }
+
         #[test]
         fn test_load_invalid() {
             let yaml_loader = data_loader_from_file(
@@ -1,8 +1,11 @@
+use std::cell::Cell;
+
 use compiler_base_span::span::new_byte_pos;
 use kcl_ast::{
     ast::{
         ConfigEntry, ConfigEntryOperation, ConfigExpr, Expr, ExprContext, Identifier, ListExpr,
-        NameConstant, NameConstantLit, Node, NodeRef, NumberLit, NumberLitValue, SchemaExpr,
+        NameConstant, NameConstantLit, Node, NodeRef, NumberLit, NumberLitValue, PosTuple,
+        SchemaExpr,
     },
     node_ref,
 };
@@ -17,11 +20,14 @@ trait ExprGenerator<T> {
     fn generate(&self, value: &T, schema_name: &Option<String>) -> Result<NodeRef<Expr>>;
 }
 
-/// `ExprBuilder` will generate ast expr from Json/Yaml.
-/// `Object` in Json and `Mapping` in Yaml is mapped to `Schema Expr`.
+/// `ExprBuilder` will generate ast expr from Json/Yaml/TOML.
+/// `Object` in Json, `Mapping` in Yaml and `Table` in TOML is mapped to `Schema Expr`.
 /// You should set `schema_name` for `Schema Expr` before using `ExprBuilder`.
 pub(crate) struct ExprBuilder {
     loader: DataLoader,
+    /// The byte offset of the Json document being generated in the file,
+    /// which is not zero for the documents in JSON Lines files.
+    json_offset: Cell<usize>,
 }
 
 impl ExprBuilder {
@@ -29,7 +35,10 @@ impl ExprBuilder {
         let loader = DataLoader::new_with_file_path(kind, &file_path)
             .with_context(|| format!("Failed to Load '{}'", file_path))?;
 
-        Ok(Self { loader })
+        Ok(Self {
+            loader,
+            json_offset: Cell::new(0),
+        })
     }
 
     #[allow(dead_code)]
@@ -37,10 +46,14 @@ impl ExprBuilder {
         let loader = DataLoader::new_with_str(kind, &content)
             .with_context(|| format!("Failed to Parse String '{}'", content))?;
 
-        Ok(Self { loader })
+        Ok(Self {
+            loader,
+            json_offset: Cell::new(0),
+        })
     }
 
-    /// Generate ast expr from Json/Yaml depends on `LoaderKind`.
+    /// Generate ast expr from Json/Yaml/TOML depends on `LoaderKind`.
+    /// Only the first document is used for the YAML streams and JSON Lines files.
     pub(crate) fn build(&self, schema_name: Option<String>) -> Result<NodeRef<Expr>> {
         match self.loader.get_kind() {
             LoaderKind::JSON => {
@@ -59,8 +72,76 @@ impl ExprBuilder {
                     .generate(&value, &schema_name)
                     .with_context(|| "Failed to Load YAML".to_string())?)
             }
+            LoaderKind::TOML | LoaderKind::JSONL => self
+                .build_documents(schema_name)?
+                .into_iter()
+                .next()
+                .with_context(|| FAIL_LOAD_VALIDATED_ERR_MSG),
         }
     }
+
+    /// Generate ast exprs for all the documents in the YAML streams and JSON Lines files,
+    /// and the Json and TOML files have only one document.
+    pub(crate) fn build_documents(
+        &self,
+        schema_name: Option<String>,
+    ) -> Result<Vec<NodeRef<Expr>>> {
+        match self.loader.get_kind() {
+            LoaderKind::JSON => Ok(vec![self.build(schema_name)?]),
+            LoaderKind::YAML => {
+                let docs = <DataLoader as Loader<Vec<located_yaml::Yaml>>>::load(&self.loader)
+                    .with_context(|| "Failed to Load YAML".to_string())?;
+                if docs.is_empty() {
+                    bail!("Failed to Load YAML, no documents are found");
+                }
+                docs.iter()
+                    .map(|doc| {
+                        self.generate(doc, &schema_name)
+                            .with_context(|| "Failed to Load YAML".to_string())
+                    })
+                    .collect()
+            }
+            LoaderKind::TOML => {
+                let value = <DataLoader as Loader<toml::Value>>::load(&self.loader)
+                    .with_context(|| "Failed to Load TOML".to_string())?;
+                Ok(vec![
+                    self.generate(&value, &schema_name)
+                        .with_context(|| "Failed to Load TOML".to_string())?,
+                ])
+            }
+            LoaderKind::JSONL => {
+                let docs = <DataLoader as Loader<
+                    Vec<(
+                        usize,
+                        json_spanned_value::Spanned<json_spanned_value::Value>,
+                    )>,
+                >>::load(&self.loader)
+                .with_context(|| "Failed to Load JSON Lines".to_string())?;
+                if docs.is_empty() {
+                    bail!("Failed to Load JSON Lines, no documents are found");
+                }
+                let exprs = docs
+                    .iter()
+                    .map(|(offset, doc)| {
+                        self.json_offset.set(*offset);
+                        self.generate(doc, &schema_name)
+                            .with_context(|| "Failed to Load JSON Lines".to_string())
+                    })
+                    .collect();
+                self.json_offset.set(0);
+                exprs
+            }
+        }
+    }
+
+    /// Convert the span of the Json value to the position in the file.
+    fn json_loc(&self, span: (usize, usize)) -> PosTuple {
+        let offset = self.json_offset.get();
+        self.loader.byte_pos_to_pos_in_sourcemap(
+            new_byte_pos((span.0 + offset) as u32),
+            new_byte_pos((span.1 + offset) as u32),
+        )
+    }
 }
 
 impl ExprGenerator<serde_yaml::Value> for ExprBuilder {
@@ -338,10 +419,7 @@ impl ExprGenerator<json_spanned_value::Spanned<json_spanned_value::Value>> for E
         value: &json_spanned_value::Spanned<json_spanned_value::Value>,
         schema_name: &Option<String>,
     ) -> Result<NodeRef<Expr>> {
-        let loc = self.loader.byte_pos_to_pos_in_sourcemap(
-            new_byte_pos(value.span().0 as u32),
-            new_byte_pos(value.span().1 as u32),
-        );
+        let loc = self.json_loc(value.span());
         match value.get_ref() {
             json_spanned_value::Value::Null => Ok(node_ref!(
                 Expr::NameConstantLit(NameConstantLit {
@@ -430,13 +508,7 @@ impl ExprGenerator<json_spanned_value::Spanned<json_spanned_value::Value>> for E
 
                     let config_entry = node_ref!(
                         ConfigEntry {
-                            key: Some(node_ref!(
-                                Expr::StringLit(k),
-                                self.loader.byte_pos_to_pos_in_sourcemap(
-                                    new_byte_pos(k_span.0 as u32),
-                                    new_byte_pos(k_span.1 as u32)
-                                )
-                            )),
+                            key: Some(node_ref!(Expr::StringLit(k), self.json_loc(k_span))),
                             value: v,
                             operation: ConfigEntryOperation::Union,
                         },
@@ -596,3 +668,94 @@ impl ExprGenerator<serde_json::Value> for ExprBuilder {
         }
     }
 }
+
+/// The TOML parser does not record the positions of the values, so the generated exprs have
+/// no positions and the violations in the TOML files are not located in the validated file.
+impl ExprGenerator<toml::Value> for ExprBuilder {
+    fn generate(&self, value: &toml::Value, schema_name: &Option<String>) -> Result<NodeRef<Expr>> {
+        match value {
+            toml::Value::Boolean(t_bool) => {
+                let name_const = match NameConstant::try_from(*t_bool) {
+                    Ok(nc) => nc,
+                    Err(err) => {
+                        bail!("{FAIL_LOAD_VALIDATED_ERR_MSG}, {err}")
+                    }
+                };
+
+                Ok(node_ref!(Expr::NameConstantLit(NameConstantLit {
+                    value: name_const
+                })))
+            }
+            toml::Value::Integer(t_int) => Ok(node_ref!(Expr::NumberLit(NumberLit {
+                binary_suffix: None,
+                value: NumberLitValue::Int(*t_int)
+            }))),
+            toml::Value::Float(t_float) => Ok(node_ref!(Expr::NumberLit(NumberLit {
+                binary_suffix: None,
+                value: NumberLitValue::Float(*t_float)
+            }))),
+            toml::Value::String(t_string) => {
+                let str_lit = From::from(t_string.to_string());
+
+                Ok(node_ref!(Expr::StringLit(str_lit)))
+            }
+            // TOML date and time values are validated as strings.
+            toml::Value::Datetime(t_datetime) => {
+                let str_lit = From::from(t_datetime.to_string());
+
+                Ok(node_ref!(Expr::StringLit(str_lit)))
+            }
+            toml::Value::Array(t_arr) => {
+                let mut t_arr_ast_nodes: Vec<NodeRef<Expr>> = Vec::new();
+                for t_arr_item in t_arr {
+                    t_arr_ast_nodes.push(
+                        self.generate(t_arr_item, schema_name)
+                            .with_context(|| FAIL_LOAD_VALIDATED_ERR_MSG)?,
+                    );
+                }
+                Ok(node_ref!(Expr::List(ListExpr {
+                    ctx: ExprContext::Load,
+                    elts: t_arr_ast_nodes
+                })))
+            }
+            toml::Value::Table(t_map) => {
+                let mut config_entries: Vec<NodeRef<ConfigEntry>> = Vec::new();
+
+                for (k, v) in t_map.iter() {
+                    let k = From::from(k.to_string());
+                    let v = self
+                        .generate(v, &None)
+                        .with_context(|| FAIL_LOAD_VALIDATED_ERR_MSG)?;
+
+                    let config_entry = node_ref!(ConfigEntry {
+                        key: Some(node_ref!(Expr::StringLit(k))),
+                        value: v,
+                        operation: ConfigEntryOperation::Union,
+                    });
+                    config_entries.push(config_entry);
+                }
+
+                let config_expr = node_ref!(Expr::Config(ConfigExpr {
+                    items: config_entries
+                }));
+
+                match schema_name {
+                    Some(s_name) => {
+                        let iden = node_ref!(Identifier {
+                            names: vec![Node::dummy_node(s_name.to_string())],
+                            pkgpath: String::new(),
+                            ctx: ExprContext::Load
+                        });
+                        Ok(node_ref!(Expr::Schema(SchemaExpr {
+                            name: iden,
+                            config: config_expr,
+                            args: vec![],
+                            kwargs: vec![]
+                        })))
+                    }
+                    None => Ok(config_expr),
+                }
+            }
+        }
+    }
+}
@@ -324,7 +324,7 @@ mod test_validater {
         util::loader::LoaderKind,
         vet::{
             tests::deal_windows_filepath,
-            validator::{ValidateOption, validate},
+            validator::{ValidateOption, validate, validate_all},
         },
     };
 
@@ -586,6 +586,252 @@ mod test_validater {
         }
     }
 
+    #[test]
+    fn test_validate_all() {
+        let kcl_path = construct_full_path(
+            &Path::new("validate_all_cases")
+                .join("deployment.k")
+                .display()
+                .to_string(),
+        )
+        .unwrap();
+        for (i, file_suffix) in VALIDATED_FILE_TYPE.iter().enumerate() {
+            let validated_file_path = construct_full_path(&format!(
+                "{}.{}",
+                Path::new("validate_all_cases")
+                    .join("deployment.k")
+                    .display(),
+                file_suffix
+            ))
+            .unwrap();
+            let opt = ValidateOption::new(
+                None,
+                "value".to_string(),
+                validated_file_path.clone(),
+                *LOADER_KIND[i],
+                Some(kcl_path.clone()),
+                None,
+                Default::default(),
+            );
+            let mut violations = validate_all(opt).unwrap();
+            violations.sort_by(|a, b| a.path.cmp(&b.path));
+            let paths: Vec<&str> = violations.iter().map(|v| v.path.as_str()).collect();
+            assert_eq!(
+                paths,
+                vec!["spec", "spec.containers[1]", "spec.containers[2]"],
+                "{violations:?}"
+            );
+            assert_eq!(
+                violations[0].message,
+                "Check failed on the condition: replicas must be positive"
+            );
+            assert_eq!(
+                violations[1].message,
+                "Check failed on the condition: the latest image tag is not allowed"
+            );
+            assert!(violations[1].line < violations[2].line, "{violations:?}");
+            for violation in &violations {
+                assert!(
+                    violation
+                        .filename
+                        .ends_with(&format!("deployment.k.{file_suffix}")),
+                    "{violation:?}"
+                );
+                assert!(
+                    violation.kcl_filename.ends_with("deployment.k"),
+                    "{violation:?}"
+                );
+            }
+            // The default validation stops at the first failure.
+            let opt = ValidateOption::new(
+                None,
+                "value".to_string(),
+                validated_file_path,
+                *LOADER_KIND[i],
+                Some(kcl_path.clone()),
+                None,
+                Default::default(),
+            );
+            assert!(validate(opt).is_err());
+        }
+    }
+
+    #[test]
+    fn test_validate_documents_with_discriminator() {
+        let kcl_path = construct_full_path(
+            &Path::new("validate_stream_cases")
+                .join("stream.k")
+                .display()
+                .to_string(),
+        )
+        .unwrap();
+        for (kind, file_suffix) in [(LoaderKind::YAML, "yaml"), (LoaderKind::JSONL, "jsonl")] {
+            let validated_file_path = construct_full_path(&format!(
+                "{}.{}",
+                Path::new("validate_stream_cases")
+                    .join("stream.k")
+                    .display(),
+                file_suffix
+            ))
+            .unwrap();
+            let opt = ValidateOption::new(
+                None,
+                "value".to_string(),
+                validated_file_path.clone(),
+                kind,
+                Some(kcl_path.clone()),
+                None,
+                Default::default(),
+            )
+            .with_discriminator(Some("kind".to_string()));
+            let violations = validate_all(opt).unwrap();
+            let documents: Vec<usize> = violations.iter().map(|v| v.document).collect();
+            assert_eq!(documents, vec![1, 2], "{violations:?}");
+            assert_eq!(
+                violations[0].message,
+                "Check failed on the condition: port must be in the range (0, 65536)"
+            );
+            assert_eq!(
+                violations[1].message,
+                "Check failed on the condition: replicas must be positive"
+            );
+            assert!(violations[0].line < violations[1].line, "{violations:?}");
+            // Without the discriminator, all the documents are validated using the first schema.
+            let opt = ValidateOption::new(
+                None,
+                "value".to_string(),
+                validated_file_path,
+                kind,
+                Some(kcl_path.clone()),
+                None,
+                Default::default(),
+            );
+            assert!(validate(opt).is_err());
+        }
+    }
+
+    #[test]
+    fn test_validate_documents_with_unmatched_discriminator() {
+        let kcl_path = construct_full_path(
+            &Path::new("validate_stream_cases")
+                .join("stream.k")
+                .display()
+                .to_string(),
+        )
+        .unwrap();
+        let validated_file_path = construct_full_path(
+            &Path::new("validate_stream_cases")
+                .join("unmatched.k.yaml")
+                .display()
+                .to_string(),
+        )
+        .unwrap();
+        let new_opt = || {
+            ValidateOption::new(
+                None,
+                "value".to_string(),
+                validated_file_path.clone(),
+                LoaderKind::YAML,
+                Some(kcl_path.clone()),
+                None,
+                Default::default(),
+            )
+            .with_discriminator(Some("kind".to_string()))
+        };
+        let violations = validate_all(new_opt()).unwrap();
+        let messages: Vec<(usize, &str)> = violations
+            .iter()
+            .map(|v| (v.document, v.message.as_str()))
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                (1, "no schema named Ingress for discriminator kind"),
+                (2, "no discriminator kind in the document"),
+            ],
+            "{violations:?}"
+        );
+        assert_eq!(violations[0].line, 5, "{violations:?}");
+        assert_eq!(
+            validate(new_opt()).unwrap_err().to_string(),
+            "no schema named Ingress for discriminator kind"
+        );
+        // The unmatched documents fall back to the specified schema, which rejects the kind.
+        let opt = ValidateOption::new(
+            Some("Deployment".to_string()),
+            "value".to_string(),
+            validated_file_path,
+            LoaderKind::YAML,
+            Some(kcl_path),
+            None,
+            Default::default(),
+        )
+        .with_discriminator(Some("kind".to_string()));
+        let err = validate_all(opt).unwrap_err().to_string();
+        assert!(
+            err.contains("expected str(Deployment), got str(Ingress)"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_validate_toml() {
+        let case = Path::new("validate_stream_cases").join("server.k");
+        let opt = ValidateOption::new(
+            None,
+            "value".to_string(),
+            construct_full_path(&format!("{}.toml", case.display())).unwrap(),
+            LoaderKind::TOML,
+            Some(construct_full_path(&case.display().to_string()).unwrap()),
+            None,
+            Default::default(),
+        );
+        assert!(validate(opt).unwrap());
+        let opt = ValidateOption::new(
+            Some("Owner".to_string()),
+            "value".to_string(),
+            construct_full_path(&format!("{}.toml", case.display())).unwrap(),
+            LoaderKind::TOML,
+            Some(construct_full_path(&case.display().to_string()).unwrap()),
+            None,
+            Default::default(),
+        );
+        assert!(validate(opt).is_err());
+    }
+
+    #[test]
+    fn test_validate_empty_documents() {
+        let kcl_path = construct_full_path(
+            &Path::new("validate_stream_cases")
+                .join("stream.k")
+                .display()
+                .to_string(),
+        )
+        .unwrap();
+        for (kind, file_suffix) in [(LoaderKind::YAML, "yaml"), (LoaderKind::JSONL, "jsonl")] {
+            let validated_file_path = construct_full_path(&format!(
+                "{}.{}",
+                Path::new("validate_stream_cases").join("empty.k").display(),
+                file_suffix
+            ))
+            .unwrap();
+            let opt = ValidateOption::new(
+                None,
+                "value".to_string(),
+                validated_file_path,
+                kind,
+                Some(kcl_path.clone()),
+                None,
+                Default::default(),
+            );
+            let err = validate(opt).unwrap_err();
+            assert!(
+                format!("{err:?}").contains("no documents are found"),
+                "{err:?}"
+            );
+        }
+    }
+
     fn test_validate_with_invalid_kcl_path() {
         let opt = ValidateOption::new(
             None,
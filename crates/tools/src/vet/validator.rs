@@ -1,11 +1,14 @@
-//! KCL-Vet can use KCL to validate the content of json or yaml files.
+//! KCL-Vet can use KCL to validate the content of json, yaml or toml files.
 //!
 //! The entry point of KCL-Vet is method `validate`, for more information, see doc above method `validate`.
 //!
 //! The main principle consists of three parts:
 //!
 //! - Validation rules for validating file contents are defined in KCL statment.
-//! - Convert the json or yaml file to be verified into a KCL assign expression.
+//! - Convert the json or yaml file to be verified into a KCL assign expression. The documents
+//!   of a yaml stream or a json lines file are converted into a list expression.
+//!   The values of a toml file have no positions, so its violations have no data paths
+//!   and source positions.
 //! - Combine KCL statment and KCL expression into a KCL program,
 //!   and the KCL program is checked by the KCL compiler.
 //!
@@ -71,11 +74,16 @@ use super::expr_builder::ExprBuilder;
 pub use crate::util::loader::LoaderKind;
 use anyhow::Result;
 use kcl_ast::{
-    ast::{AssignStmt, Expr, Node, NodeRef, Program, SchemaStmt, Stmt, Target},
+    ast::{
+        AssignStmt, ConfigExpr, Expr, ExprContext, Identifier, ListExpr, MissingExpr, Node,
+        NodeRef, Program, SchemaExpr, SchemaStmt, Stmt, Target, Type,
+    },
     node_ref,
 };
 use kcl_parser::{LoadProgramOptions, ParseSessionRef};
-use kcl_runner::{ExecProgramArgs, MapErrorResult, execute};
+use kcl_runner::{ExecProgramArgs, MapErrorResult, execute, execute_and_collect_check_failures};
+use kcl_runtime::{PanicInfo, RuntimeErrorType};
+use serde::Serialize;
 
 const TMP_FILE: &str = "validationTempKCLCode.k";
 
@@ -176,6 +184,145 @@ const TMP_FILE: &str = "validationTempKCLCode.k";
 /// }
 /// ```
 pub fn validate(val_opt: ValidateOption) -> Result<bool> {
+    let (program, unmatched) = build_validation_program(val_opt, None)?;
+    if let Some(violation) = unmatched.first() {
+        return Err(anyhow::anyhow!("{}", violation.message));
+    }
+    execute(
+        ParseSessionRef::default(),
+        program,
+        &ExecProgramArgs::default(),
+    )
+    .map_err_to_result()
+    .map(|_| true)
+}
+
+/// Validate the data file using the schema code like [`validate`], but keep evaluating after
+/// a check failure and return every violation instead of raising the first one. An empty list
+/// is returned when the data conforms to the schema rules.
+///
+/// The configs of the schema typed attributes in the data are instantiated using the attribute
+/// schemas, and each violation carries the data path of the failed schema instance, e.g.,
+/// `spec.containers[2]`, and its position in the validated file.
+///
+/// The evaluation still stops at the first error which is not a check failure, e.g., a type
+/// error, and the error is the last violation.
+pub fn validate_all(val_opt: ValidateOption) -> Result<Vec<Violation>> {
+    let mut data_paths = DataPaths::default();
+    let (program, mut violations) = build_validation_program(val_opt, Some(&mut data_paths))?;
+    let (_, failures) = execute_and_collect_check_failures(
+        ParseSessionRef::default(),
+        program,
+        &ExecProgramArgs::default(),
+    )?;
+    violations.extend(failures.iter().map(|failure| data_paths.violation(failure)));
+    Ok(violations)
+}
+
+/// A violation of the schema rules found in the validated file.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Violation {
+    /// The index of the document in the validated file, which is not zero only for
+    /// the YAML streams and JSON Lines files.
+    pub document: usize,
+    /// The data path of the violated value, e.g., `spec.containers[2]`, which is
+    /// empty for the root value or when the value is not located, e.g., in TOML files.
+    pub path: String,
+    /// The violation message, e.g., `Check failed on the condition: name is required`.
+    pub message: String,
+    /// The validated file name of the violated value.
+    pub filename: String,
+    /// The line of the violated value in the validated file.
+    pub line: u64,
+    /// The column of the violated value in the validated file.
+    pub column: u64,
+    /// The KCL file name of the failed check condition.
+    pub kcl_filename: String,
+    /// The line of the failed check condition in the KCL file.
+    pub kcl_line: u64,
+}
+
+/// Data paths of the values in the validated file keyed by their positions.
+#[derive(Debug, Default)]
+struct DataPaths {
+    /// The index of the document being recorded.
+    document: usize,
+    positions: HashMap<(String, u64, u64), (usize, String)>,
+    lines: HashMap<(String, u64), (usize, String)>,
+}
+
+impl DataPaths {
+    /// Record the data path of the value, the outer value is preferred
+    /// when values are at the same position. The values without positions,
+    /// e.g., the values in TOML files, are not recorded.
+    fn insert(&mut self, node: &Node<Expr>, path: &str) {
+        if node.filename.is_empty() {
+            return;
+        }
+        self.positions
+            .entry((node.filename.clone(), node.line, node.column))
+            .or_insert_with(|| (self.document, path.to_string()));
+        self.lines
+            .entry((node.filename.clone(), node.line))
+            .or_insert_with(|| (self.document, path.to_string()));
+    }
+
+    fn get(&self, filename: &str, line: u64, column: u64) -> Option<&(usize, String)> {
+        self.positions
+            .get(&(filename.to_string(), line, column))
+            .or_else(|| self.lines.get(&(filename.to_string(), line)))
+    }
+
+    /// Map the runtime failure back to the value in the validated file. Check failures are
+    /// located by the schema instance position, and other errors by the error position.
+    fn violation(&self, failure: &PanicInfo) -> Violation {
+        let is_check_failure = failure.err_type_code == RuntimeErrorType::SchemaCheckFailure as i32;
+        let (filename, line, column) =
+            if is_check_failure && !failure.kcl_config_meta_file.is_empty() {
+                (
+                    failure.kcl_config_meta_file.clone(),
+                    failure.kcl_config_meta_line.max(0) as u64,
+                    failure.kcl_config_meta_col.max(0) as u64,
+                )
+            } else {
+                (
+                    failure.kcl_file.clone(),
+                    failure.kcl_line.max(0) as u64,
+                    failure.kcl_col.max(0) as u64,
+                )
+            };
+        let message = if is_check_failure && !failure.kcl_arg_msg.is_empty() {
+            failure.kcl_arg_msg.clone()
+        } else {
+            failure.message.clone()
+        };
+        let (document, path) = self
+            .get(&filename, line, column)
+            .cloned()
+            .unwrap_or_default();
+        Violation {
+            document,
+            path,
+            message,
+            filename,
+            line,
+            column,
+            kcl_filename: failure.kcl_file.clone(),
+            kcl_line: failure.kcl_line.max(0) as u64,
+        }
+    }
+}
+
+/// Load the KCL program and append the assignment of the validated data expression to the main
+/// module. When `data_paths` is set, the nested schema configs are expanded into schema expressions
+/// and the data paths are recorded.
+///
+/// When no schema is specified, the documents whose discriminator values match no schema are
+/// left as plain configs and returned as the violations.
+fn build_validation_program(
+    val_opt: ValidateOption,
+    data_paths: Option<&mut DataPaths>,
+) -> Result<(Program, Vec<Violation>)> {
     let k_path = val_opt.kcl_path.unwrap_or_else(|| TMP_FILE.to_string());
     let k_code = val_opt.kcl_code.map_or_else(Vec::new, |code| vec![code]);
 
@@ -197,16 +344,72 @@ pub fn validate(val_opt: ValidateOption) -> Result<bool> {
     )?;
 
     let schemas = filter_schema_stmt_from_prog(&compile_res.program);
-    let schema_name = match val_opt.schema_name {
-        Some(name) => Some(name),
-        None => schemas.first().map(|schema| schema.name.node.clone()),
+    let schema_name = match (&val_opt.schema_name, &val_opt.discriminator) {
+        (Some(name), _) => Some(name.clone()),
+        // The document schemas are selected by the discriminator field.
+        (None, Some(_)) => None,
+        (None, None) => schemas.first().map(|schema| schema.name.node.clone()),
     };
 
     let expr_builder =
         ExprBuilder::new_with_file_path(val_opt.validated_file_kind, val_opt.validated_file_path)?;
 
-    let validated_expr = expr_builder.build(schema_name)?;
+    let mut unmatched = vec![];
+    let mut documents = match &val_opt.discriminator {
+        Some(discriminator) => {
+            let mut documents = expr_builder.build_documents(None)?;
+            for (i, document) in documents.iter_mut().enumerate() {
+                let value = discriminator_schema_name(document, discriminator);
+                let name = value
+                    .as_ref()
+                    .filter(|name| schemas.iter().any(|schema| &schema.name.node == *name))
+                    .cloned()
+                    .or_else(|| schema_name.clone());
+                match name {
+                    Some(name) => wrap_schema_expr(document, &name),
+                    None => {
+                        let message = match value {
+                            Some(value) => {
+                                format!("no schema named {value} for discriminator {discriminator}")
+                            }
+                            None => format!("no discriminator {discriminator} in the document"),
+                        };
+                        unmatched.push(Violation {
+                            document: i,
+                            message,
+                            filename: document.filename.clone(),
+                            line: document.line,
+                            column: document.column,
+                            ..Default::default()
+                        });
+                    }
+                }
+            }
+            documents
+        }
+        None => expr_builder.build_documents(schema_name)?,
+    };
+
+    if let Some(data_paths) = data_paths {
+        let schemas: HashMap<String, &SchemaStmt> = schemas
+            .iter()
+            .map(|schema| (schema.name.node.clone(), schema))
+            .collect();
+        for (i, document) in documents.iter_mut().enumerate() {
+            data_paths.document = i;
+            expand_schema_exprs(document, None, "", &schemas, data_paths);
+        }
+    }
 
+    // A single document is validated as it is, and the documents of a stream as a list.
+    let validated_expr = if documents.len() == 1 {
+        documents.remove(0)
+    } else {
+        node_ref!(Expr::List(ListExpr {
+            elts: documents,
+            ctx: ExprContext::Load,
+        }))
+    };
     let assign_stmt = build_assign(&val_opt.attribute_name, validated_expr);
 
     match compile_res.program.pkgs.get(kcl_ast::MAIN_PKG) {
@@ -226,14 +429,186 @@ pub fn validate(val_opt: ValidateOption) -> Result<bool> {
             return Err(anyhow::anyhow!("No main package found"));
         }
     }
+    Ok((compile_res.program, unmatched))
+}
 
-    execute(
-        ParseSessionRef::default(),
-        compile_res.program,
-        &ExecProgramArgs::default(),
-    )
-    .map_err_to_result()
-    .map(|_| true)
+/// Expand the configs of the schema typed attributes into schema expressions, which makes the
+/// check failures of the nested schema instances located in the validated file, and record the
+/// data paths of the values.
+fn expand_schema_exprs(
+    expr: &mut NodeRef<Expr>,
+    ty: Option<&Type>,
+    path: &str,
+    schemas: &HashMap<String, &SchemaStmt>,
+    data_paths: &mut DataPaths,
+) {
+    data_paths.insert(expr, path);
+    let schema_name = match &expr.node {
+        Expr::Schema(schema_expr) => Some(schema_expr.name.node.get_name()),
+        Expr::Config(_) => match ty {
+            Some(Type::Named(identifier))
+                if identifier.pkgpath.is_empty()
+                    && schemas.contains_key(&identifier.get_name()) =>
+            {
+                Some(identifier.get_name())
+            }
+            _ => None,
+        },
+        _ => None,
+    };
+    let is_config = matches!(expr.node, Expr::Config(_));
+    if is_config && let Some(name) = &schema_name {
+        wrap_schema_expr(expr, name);
+    }
+    match &mut expr.node {
+        Expr::Schema(schema_expr) => {
+            // The schema config is at the same position of the schema expression.
+            if let Expr::Config(config_expr) = &mut schema_expr.config.node {
+                expand_config_entries(
+                    config_expr,
+                    schema_name.as_deref(),
+                    None,
+                    path,
+                    schemas,
+                    data_paths,
+                );
+            }
+        }
+        Expr::Config(config_expr) => {
+            let value_ty = match ty {
+                Some(Type::Dict(dict_ty)) => dict_ty.value_type.as_ref().map(|ty| &ty.node),
+                _ => None,
+            };
+            expand_config_entries(config_expr, None, value_ty, path, schemas, data_paths);
+        }
+        Expr::List(list_expr) => {
+            let inner_ty = match ty {
+                Some(Type::List(list_ty)) => list_ty.inner_type.as_ref().map(|ty| &ty.node),
+                _ => None,
+            };
+            for (i, elt) in list_expr.elts.iter_mut().enumerate() {
+                expand_schema_exprs(elt, inner_ty, &format!("{path}[{i}]"), schemas, data_paths);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Wrap the config expression into the schema expression named `name` at the same position.
+fn wrap_schema_expr(expr: &mut NodeRef<Expr>, name: &str) {
+    if !matches!(expr.node, Expr::Config(_)) {
+        return;
+    }
+    let pos = expr.pos();
+    let config = std::mem::replace(expr, node_ref!(Expr::Missing(MissingExpr), pos.clone()));
+    *expr = node_ref!(
+        Expr::Schema(SchemaExpr {
+            name: node_ref!(
+                Identifier {
+                    names: vec![Node::new_with_pos(name.to_string(), pos.clone())],
+                    pkgpath: String::new(),
+                    ctx: ExprContext::Load,
+                },
+                pos.clone()
+            ),
+            config,
+            args: vec![],
+            kwargs: vec![],
+        }),
+        pos
+    );
+}
+
+/// Get the string value of the discriminator field in the document config, e.g.,
+/// `Deployment` for the document `{"kind": "Deployment"}` and the discriminator `kind`.
+fn discriminator_schema_name(document: &NodeRef<Expr>, discriminator: &str) -> Option<String> {
+    let Expr::Config(config_expr) = &document.node else {
+        return None;
+    };
+    config_expr.items.iter().find_map(|item| {
+        match (
+            item.node.key.as_ref().map(|key| &key.node),
+            &item.node.value.node,
+        ) {
+            (Some(Expr::StringLit(key)), Expr::StringLit(value)) if key.value == discriminator => {
+                Some(value.value.clone())
+            }
+            _ => None,
+        }
+    })
+}
+
+fn expand_config_entries(
+    config_expr: &mut ConfigExpr,
+    schema_name: Option<&str>,
+    value_ty: Option<&Type>,
+    path: &str,
+    schemas: &HashMap<String, &SchemaStmt>,
+    data_paths: &mut DataPaths,
+) {
+    for item in config_expr.items.iter_mut() {
+        let key = match item.node.key.as_ref().map(|key| &key.node) {
+            Some(Expr::StringLit(string_lit)) => string_lit.value.clone(),
+            _ => continue,
+        };
+        let ty = match schema_name {
+            Some(schema_name) => schema_attr_type(schemas, schema_name, &key),
+            None => value_ty,
+        };
+        expand_schema_exprs(
+            &mut item.node.value,
+            ty,
+            &join_data_path(path, &key),
+            schemas,
+            data_paths,
+        );
+    }
+}
+
+/// Get the type of the schema attribute including the inherited attributes, and the index
+/// signature value type is returned when the attribute is not defined.
+fn schema_attr_type<'a>(
+    schemas: &HashMap<String, &'a SchemaStmt>,
+    schema_name: &str,
+    attr: &str,
+) -> Option<&'a Type> {
+    let mut index_signature_ty = None;
+    let mut current = schemas.get(schema_name);
+    while let Some(schema) = current {
+        for stmt in &schema.body {
+            if let Stmt::SchemaAttr(schema_attr) = &stmt.node
+                && schema_attr.name.node == attr
+            {
+                return Some(&schema_attr.ty.node);
+            }
+        }
+        if index_signature_ty.is_none() {
+            index_signature_ty = schema
+                .index_signature
+                .as_ref()
+                .map(|index_signature| &index_signature.node.value_ty.node);
+        }
+        current = schema
+            .parent_name
+            .as_ref()
+            .and_then(|parent_name| schemas.get(&parent_name.node.get_name()));
+    }
+    index_signature_ty
+}
+
+fn join_data_path(path: &str, key: &str) -> String {
+    let is_identifier = key
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_alphabetic() || c == '_')
+        && key
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '-');
+    match (path.is_empty(), is_identifier) {
+        (true, true) => key.to_string(),
+        (false, true) => format!("{path}.{key}"),
+        (_, false) => format!("{path}[{key:?}]"),
+    }
 }
 
 fn build_assign(attr_name: &str, node: NodeRef<Expr>) -> NodeRef<Stmt> {
@@ -275,6 +650,7 @@ pub struct ValidateOption {
     kcl_path: Option<String>,
     kcl_code: Option<String>,
     package_maps: HashMap<String, String>,
+    discriminator: Option<String>,
 }
 
 impl ValidateOption {
@@ -295,6 +671,15 @@ impl ValidateOption {
             kcl_path,
             kcl_code,
             package_maps,
+            discriminator: None,
         }
     }
+
+    /// Select the schema of each document by the value of the discriminator field, e.g., `kind`.
+    /// The schema name is used for the documents whose discriminator value is not a schema name
+    /// in the KCL code, and the documents are not validated using a schema without the schema name.
+    pub fn with_discriminator(mut self, discriminator: Option<String>) -> Self {
+        self.discriminator = discriminator;
+        self
+    }
 }